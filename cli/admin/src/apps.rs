@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use auth::UserRole;
+use errors::TicketsResult;
+use sdk::client::{SdkCallWithBody, SdkCallWithPathAndBody, SdkInvoke, SignedTicketClient};
+use sdk::routes::staff::{
+    AppPath, CreateApp, CreateAppBody, Login, ToggleGateway, ToggleGatewayBody,
+};
+
+use crate::config::AdminConfig;
+
+/// The gateways an app can accept tickets through, named as they send `x-gateway`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gateway {
+    Discord,
+    Webhook,
+    Email,
+    Matrix,
+}
+
+impl Gateway {
+    pub const ALL: [Gateway; 4] = [
+        Gateway::Discord,
+        Gateway::Webhook,
+        Gateway::Email,
+        Gateway::Matrix,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Gateway::Discord => "discord",
+            Gateway::Webhook => "webhook",
+            Gateway::Email => "email",
+            Gateway::Matrix => "matrix",
+        }
+    }
+
+    pub fn system_accessor(&self) -> JwtAccessor {
+        match self {
+            Gateway::Discord => JwtAccessor::DiscordSystem,
+            Gateway::Webhook => JwtAccessor::WebhookSystem,
+            Gateway::Email => JwtAccessor::EmailSystem,
+            Gateway::Matrix => JwtAccessor::MatrixSystem,
+        }
+    }
+}
+
+/// A client acting as the staff member `user_id`, sending `gateway` as the calling gateway.
+pub fn management_client(
+    config: &AdminConfig,
+    gateway: Gateway,
+    user_id: u64,
+) -> TicketsResult<SignedTicketClient> {
+    config.client(
+        gateway,
+        JwtAccessor::DiscordStaffMember {
+            user_id,
+            authorized_apps: HashSet::new(),
+            // the collector checks the role the staff member holds in the app on every request
+            role: UserRole::Management,
+        },
+    )
+}
+
+/// Creates an app owned by `user_id` through the collector, enabling `gateways` on it.
+pub async fn create_app(
+    config: &AdminConfig,
+    user_id: u64,
+    app_name: String,
+    gateways: &[Gateway],
+) -> TicketsResult<Uuid> {
+    let client = management_client(config, Gateway::Discord, user_id)?;
+
+    // staff sign in through Discord, provisions the owner under that identity
+    Login::invoke(&client).await?;
+
+    let app = CreateApp::call_with_body(&client, CreateAppBody { app_name }).await?;
+
+    for gateway in gateways {
+        set_gateway(config, user_id, app.app_id, *gateway, true).await?;
+    }
+
+    Ok(app.app_id)
+}
+
+pub async fn set_gateway(
+    config: &AdminConfig,
+    user_id: u64,
+    app_id: Uuid,
+    gateway: Gateway,
+    enabled: bool,
+) -> TicketsResult<()> {
+    // the collector toggles the gateway named by the request's `x-gateway` header
+    ToggleGateway::call_with_path_and_body(
+        &management_client(config, gateway, user_id)?,
+        AppPath { app_id },
+        ToggleGatewayBody { enabled },
+    )
+    .await?;
+
+    Ok(())
+}
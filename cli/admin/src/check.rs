@@ -0,0 +1,86 @@
+use std::future::Future;
+use std::time::Duration;
+
+use errors::{MiscError, TicketsResult};
+use events::adapter::Adapter;
+
+use crate::config::AdminConfig;
+use crate::databases::{self, Database};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs `check`, printing whether it passed. Hanging checks fail after [`TIMEOUT`].
+async fn report<F: Future<Output = TicketsResult<()>>>(name: String, check: F) -> bool {
+    let result = match tokio::time::timeout(TIMEOUT, check).await {
+        Ok(result) => result.map_err(|err| err.to_string()),
+        Err(_) => Err(format!("timed out after {} seconds", TIMEOUT.as_secs())),
+    };
+
+    match result {
+        Ok(()) => {
+            println!("{name:<20}  ok");
+            true
+        }
+        Err(err) => {
+            println!("{name:<20}  failed: {err}");
+            false
+        }
+    }
+}
+
+async fn check_postgres(url: String) -> TicketsResult<()> {
+    let pool = databases::connect(&url).await?;
+    sqlx::query("SELECT 1").execute(&pool).await?;
+    pool.close().await;
+
+    Ok(())
+}
+
+async fn check_redis(url: String) -> TicketsResult<()> {
+    let client = redis::Client::open(url)?;
+    let mut connection = client.get_multiplexed_tokio_connection().await?;
+    redis::cmd("PING")
+        .query_async::<_, String>(&mut connection)
+        .await?;
+
+    Ok(())
+}
+
+/// Checks that every configured Postgres database and the Redis adapter are reachable.
+pub async fn check() -> TicketsResult<()> {
+    let config = AdminConfig::load()?;
+    let mut failed = 0;
+
+    for database in Database::ALL {
+        let name = format!("postgres ({database})");
+        let passed = match database.url() {
+            Some(url) => report(name, check_postgres(url)).await,
+            None => {
+                println!(
+                    "{name:<20}  skipped, {} is not set",
+                    database.url_variable()
+                );
+                true
+            }
+        };
+        failed += usize::from(!passed);
+    }
+
+    match config.adapter_config.adapter_type {
+        Adapter::Redis => {
+            let passed = match &config.adapter_config.redis {
+                Some(redis) => report("redis".to_string(), check_redis(redis.url.clone())).await,
+                None => {
+                    println!("{:<20}  failed: no redis config", "redis");
+                    false
+                }
+            };
+            failed += usize::from(!passed);
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(MiscError::ConnectivityChecksFailed { failed })?,
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtKeyPathsConfig};
+use errors::TicketsResult;
+use events::adapter::AdapterConfig;
+use sdk::client::{InternalSdk, SignedTicketClient};
+
+use crate::apps::Gateway;
+
+/// The part of `config.json` shared with the services which the admin commands read.
+#[derive(serde::Deserialize)]
+pub struct AdminConfig {
+    pub collector_url: String,
+    #[serde(rename = "adapter")]
+    pub adapter_config: AdapterConfig,
+    pub jwt: JwtKeyPathsConfig,
+}
+
+impl AdminConfig {
+    pub fn load() -> TicketsResult<Self> {
+        dry::config::load_config()
+    }
+
+    pub fn jwt_config(&self) -> TicketsResult<Arc<JwtConfig>> {
+        Ok(Arc::new(JwtConfig::from_key_paths(
+            &self.jwt.public_key_location,
+            &self.jwt.private_key_location,
+        )?))
+    }
+
+    /// A collector client calling as `accessor` through `gateway`.
+    pub fn client(
+        &self,
+        gateway: Gateway,
+        accessor: JwtAccessor,
+    ) -> TicketsResult<SignedTicketClient> {
+        let sdk: InternalSdk = (
+            self.collector_url.clone(),
+            self.jwt_config()?,
+            gateway.name(),
+        )
+            .try_into()?;
+
+        sdk.sign_client(accessor, InternalSdk::DEFAULT_TTL)
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use sqlx::migrate::{MigrateDatabase, Migrator};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgPool, Postgres};
+
+use errors::TicketsResult;
+
+/// The services owning a database, each migrated from its own `migrations` directory.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Database {
+    Collector,
+    Discord,
+    Email,
+    Matrix,
+}
+
+impl Database {
+    pub const ALL: [Database; 4] = [
+        Database::Collector,
+        Database::Discord,
+        Database::Email,
+        Database::Matrix,
+    ];
+
+    /// The variable holding the database's url, as set in `.env`.
+    pub fn url_variable(&self) -> &'static str {
+        match self {
+            Database::Collector => "COLLECTOR_DATABASE_URL",
+            Database::Discord => "DISCORD_DATABASE_URL",
+            Database::Email => "EMAIL_DATABASE_URL",
+            Database::Matrix => "MATRIX_DATABASE_URL",
+        }
+    }
+
+    pub fn url(&self) -> Option<String> {
+        std::env::var(self.url_variable())
+            .ok()
+            .filter(|url| !url.is_empty())
+    }
+
+    fn migrator(&self) -> Migrator {
+        match self {
+            Database::Collector => sqlx::migrate!("../../collector/migrations"),
+            Database::Discord => sqlx::migrate!("../../gateways/discord/migrations"),
+            Database::Email => sqlx::migrate!("../../gateways/email/migrations"),
+            Database::Matrix => sqlx::migrate!("../../gateways/matrix/migrations"),
+        }
+    }
+}
+
+impl Display for Database {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Database::Collector => write!(f, "collector"),
+            Database::Discord => write!(f, "discord"),
+            Database::Email => write!(f, "email"),
+            Database::Matrix => write!(f, "matrix"),
+        }
+    }
+}
+
+pub async fn connect(url: &str) -> TicketsResult<PgPool> {
+    Ok(PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(5))
+        .connect(url)
+        .await?)
+}
+
+/// Creates the databases which do not exist yet and applies their pending migrations,
+/// `reset` drops them first. Databases without a url are skipped.
+pub async fn migrate(databases: Vec<Database>, reset: bool) -> TicketsResult<()> {
+    let databases = if databases.is_empty() {
+        Database::ALL.to_vec()
+    } else {
+        databases
+    };
+
+    for database in databases {
+        let Some(url) = database.url() else {
+            println!(
+                "{database}: skipped, {} is not set",
+                database.url_variable()
+            );
+            continue;
+        };
+
+        if reset && Postgres::database_exists(&url).await? {
+            Postgres::drop_database(&url).await?;
+            println!("{database}: dropped");
+        }
+
+        if !Postgres::database_exists(&url).await? {
+            Postgres::create_database(&url).await?;
+            println!("{database}: created");
+        }
+
+        let pool = connect(&url).await?;
+        // the services migrate on startup without locking as CockroachDB lacks advisory locks
+        database.migrator().set_locking(false).run(&pool).await?;
+        pool.close().await;
+
+        println!("{database}: migrated");
+    }
+
+    Ok(())
+}
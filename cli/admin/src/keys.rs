@@ -0,0 +1,79 @@
+use std::fmt::Display;
+use std::io::Write;
+use std::path::Path;
+
+use rsa::pkcs1::{DecodeRsaPrivateKey, EncodeRsaPrivateKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePublicKey, LineEnding};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+
+use errors::{MiscError, TicketsResult};
+
+fn key_error(err: impl Display) -> MiscError {
+    MiscError::KeyGeneration(err.to_string())
+}
+
+/// Writes the RS256 key pair signing the services' tokens. An existing private key is kept
+/// and only its public key derived, so running this twice never invalidates issued tokens. The
+/// public key is rewritten whenever it does not belong to the private key, as every token would
+/// fail verification otherwise.
+pub fn generate(private_key_path: &Path, public_key_path: &Path, bits: usize) -> TicketsResult<()> {
+    let private_key = if private_key_path.exists() {
+        let pem = std::fs::read_to_string(private_key_path)?;
+        println!("Keeping private key {}", private_key_path.display());
+
+        RsaPrivateKey::from_pkcs1_pem(&pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs8_pem(&pem))
+            .map_err(key_error)?
+    } else {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), bits).map_err(key_error)?;
+        let pem = private_key
+            .to_pkcs1_pem(LineEnding::LF)
+            .map_err(key_error)?;
+
+        write_private(private_key_path, pem.as_bytes())?;
+        println!("Private key generated at {}", private_key_path.display());
+
+        private_key
+    };
+
+    let public_key = RsaPublicKey::from(&private_key);
+
+    let existing = match std::fs::read_to_string(public_key_path) {
+        Ok(pem) => Some(RsaPublicKey::from_public_key_pem(&pem).ok()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    match existing {
+        Some(Some(existing)) if existing == public_key => {
+            println!("Keeping public key {}", public_key_path.display());
+        }
+        existing => {
+            let pem = public_key
+                .to_public_key_pem(LineEnding::LF)
+                .map_err(key_error)?;
+
+            std::fs::write(public_key_path, pem)?;
+            match existing {
+                Some(_) => println!(
+                    "Public key {} did not match the private key and was rewritten",
+                    public_key_path.display()
+                ),
+                None => println!("Public key generated at {}", public_key_path.display()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_private(path: &Path, contents: &[u8]) -> TicketsResult<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    // readable by the owner only, as ssh-keygen leaves it
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    Ok(options.open(path)?.write_all(contents)?)
+}
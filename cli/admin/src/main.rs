@@ -0,0 +1,137 @@
+//! `admin`, setting up and inspecting a local deployment: databases, keys, tokens and apps
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use tracing_subscriber::filter::LevelFilter;
+use uuid::Uuid;
+
+use errors::TicketsResult;
+
+use crate::apps::Gateway;
+use crate::config::AdminConfig;
+use crate::databases::Database;
+use crate::tokens::AccessorArgs;
+
+mod apps;
+mod check;
+mod config;
+mod databases;
+mod keys;
+mod seed;
+mod tokens;
+
+#[derive(Parser)]
+#[command(name = "admin", about = "Set up and inspect a tickets deployment")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates missing databases and applies their migrations, urls are read from
+    /// `COLLECTOR_DATABASE_URL`, `DISCORD_DATABASE_URL` and so on.
+    Migrate {
+        /// Only these databases, all of them when omitted.
+        databases: Vec<Database>,
+        /// Drops the databases first, deleting all of their data.
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Generates the key pair signing tokens, an existing private key is kept.
+    GenKeys {
+        #[arg(long, default_value = "private.key")]
+        private_key: PathBuf,
+        #[arg(long, default_value = "public.key")]
+        public_key: PathBuf,
+        #[arg(long, default_value_t = 4096)]
+        bits: usize,
+    },
+    /// Prints a token for the given accessor, signed with the keys named in `config.json`.
+    Token {
+        /// Seconds until the token expires.
+        #[arg(long, default_value_t = 3600)]
+        ttl: u64,
+        #[command(subcommand)]
+        accessor: AccessorArgs,
+    },
+    /// Creates an app through the collector, owned by the given staff member.
+    CreateApp {
+        app_name: String,
+        /// Discord user id of the app's owner.
+        #[arg(long)]
+        user_id: u64,
+        /// Gateways to enable on the app.
+        #[arg(long = "gateway")]
+        gateways: Vec<Gateway>,
+    },
+    /// Enables or disables a gateway on an app, acting as one of its management members.
+    Gateway {
+        app_id: Uuid,
+        gateway: Gateway,
+        #[arg(long)]
+        user_id: u64,
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Creates a demo app owned by the given staff member and fills it with tickets.
+    Seed {
+        #[arg(long)]
+        user_id: u64,
+        #[arg(long, default_value_t = 5)]
+        tickets: usize,
+    },
+    /// Checks that Postgres and Redis are reachable.
+    Check,
+}
+
+async fn run(cli: Cli) -> TicketsResult<()> {
+    match cli.command {
+        Command::Migrate { databases, reset } => databases::migrate(databases, reset).await,
+        Command::GenKeys {
+            private_key,
+            public_key,
+            bits,
+        } => keys::generate(&private_key, &public_key, bits),
+        Command::Token { ttl, accessor } => tokens::mint(accessor, Duration::from_secs(ttl)),
+        Command::CreateApp {
+            app_name,
+            user_id,
+            gateways,
+        } => {
+            let app_id =
+                apps::create_app(&AdminConfig::load()?, user_id, app_name, &gateways).await?;
+            println!("{app_id}");
+            Ok(())
+        }
+        Command::Gateway {
+            app_id,
+            gateway,
+            user_id,
+            disable,
+        } => {
+            apps::set_gateway(&AdminConfig::load()?, user_id, app_id, gateway, !disable).await?;
+
+            let state = if disable { "disabled" } else { "enabled" };
+            println!("{} is {state} on app {app_id}", gateway.name());
+            Ok(())
+        }
+        Command::Seed { user_id, tickets } => seed::seed(user_id, tickets).await,
+        Command::Check => check::check().await,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(LevelFilter::WARN)
+        .init();
+
+    if let Err(err) = run(Cli::parse()).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
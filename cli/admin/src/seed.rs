@@ -0,0 +1,145 @@
+use errors::TicketsResult;
+use sdk::client::{SdkCallWithBody, SdkCallWithPathAndBody};
+use sdk::routes::consumer::{
+    CustomerMessageBody, SendCustomerMessage, SubmitTicket, SubmitTicketBody, Submitter,
+};
+use sdk::routes::staff::{
+    AddTicketNote, AssignTicket, AssignTicketBody, ReplyToTicket, SetTicketStatus,
+    SetTicketStatusBody, TicketMessageBody, TicketPath, TicketStatus,
+};
+
+use crate::apps::{self, Gateway};
+use crate::config::AdminConfig;
+
+struct DemoTicket {
+    customer: &'static str,
+    message: &'static str,
+    follow_up: Option<&'static str>,
+    reply: Option<&'static str>,
+}
+
+const DEMO_TICKETS: [DemoTicket; 5] = [
+    DemoTicket {
+        customer: "Ada",
+        message: "I cannot log in since resetting my password.",
+        follow_up: Some("The reset mail says the link expired."),
+        reply: Some("Sorry about that, I've sent you a fresh reset link."),
+    },
+    DemoTicket {
+        customer: "Grace",
+        message: "Is there a way to export my data as CSV?",
+        follow_up: None,
+        reply: Some("Yes, head to Settings → Export and pick CSV."),
+    },
+    DemoTicket {
+        customer: "Linus",
+        message: "The app crashes when I upload a photo larger than 10 MB.",
+        follow_up: Some("Happens on both Android and iOS."),
+        reply: None,
+    },
+    DemoTicket {
+        customer: "Margaret",
+        message: "Could you add a dark mode?",
+        follow_up: None,
+        reply: None,
+    },
+    DemoTicket {
+        customer: "Alan",
+        message: "I was charged twice this month.",
+        follow_up: Some("Both charges show the same date."),
+        reply: Some("Thanks for flagging, the duplicate charge has been refunded."),
+    },
+];
+
+/// Creates a demo app owned by `user_id` and fills it with `count` tickets in various states,
+/// submitted through the Discord gateway so they show up like real ones.
+pub async fn seed(user_id: u64, count: usize) -> TicketsResult<()> {
+    let config = AdminConfig::load()?;
+
+    let app_id = apps::create_app(&config, user_id, "Demo App".to_string(), &Gateway::ALL).await?;
+    println!("Created app {app_id}");
+
+    let gateway = config.client(Gateway::Discord, Gateway::Discord.system_accessor())?;
+    let staff = apps::management_client(&config, Gateway::Discord, user_id)?;
+
+    for (index, demo) in DEMO_TICKETS.iter().cycle().take(count).enumerate() {
+        let external_id = format!("demo-customer-{}", index % DEMO_TICKETS.len());
+
+        let ticket = SubmitTicket::call_with_body(
+            &gateway,
+            SubmitTicketBody {
+                app_id,
+                message: demo.message.to_string(),
+                submitter: Submitter {
+                    external_id: external_id.clone(),
+                    display_name: Some(demo.customer.to_string()),
+                },
+            },
+        )
+        .await?;
+
+        let path = || TicketPath {
+            app_id,
+            ticket_id: ticket.ticket_id,
+        };
+
+        if let Some(follow_up) = demo.follow_up {
+            SendCustomerMessage::call_with_path_and_body(
+                &gateway,
+                path(),
+                CustomerMessageBody {
+                    external_id,
+                    body: follow_up.to_string(),
+                },
+            )
+            .await?;
+        }
+
+        match demo.reply {
+            Some(reply) => {
+                ReplyToTicket::call_with_path_and_body(
+                    &staff,
+                    path(),
+                    TicketMessageBody {
+                        body: reply.to_string(),
+                    },
+                )
+                .await?;
+
+                SetTicketStatus::call_with_path_and_body(
+                    &staff,
+                    path(),
+                    SetTicketStatusBody {
+                        status: TicketStatus::Closed,
+                    },
+                )
+                .await?;
+            }
+            // unanswered tickets are left open, every other one taken by the owner
+            None if index % 2 == 0 => {
+                AssignTicket::call_with_path_and_body(
+                    &staff,
+                    path(),
+                    AssignTicketBody {
+                        assignee_id: Some(user_id),
+                    },
+                )
+                .await?;
+
+                AddTicketNote::call_with_path_and_body(
+                    &staff,
+                    path(),
+                    TicketMessageBody {
+                        body: "Looking into this.".to_string(),
+                    },
+                )
+                .await?;
+            }
+            None => {}
+        }
+    }
+
+    println!("Seeded {count} tickets");
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use clap::Subcommand;
+use uuid::Uuid;
+
+use auth::jwt::{JwtAccessor, JwtData};
+use auth::UserRole;
+use errors::{ParsingError, TicketsResult};
+
+use crate::config::AdminConfig;
+
+/// Who a minted token speaks for, one subcommand per [`JwtAccessor`].
+#[derive(Subcommand)]
+pub enum AccessorArgs {
+    DiscordSystem,
+    WebhookSystem,
+    EmailSystem,
+    MatrixSystem,
+    /// A staff member, identified by their Discord user id.
+    Staff {
+        #[arg(long)]
+        user_id: u64,
+        #[arg(long, value_parser = parse_role, default_value = "staff")]
+        role: UserRole,
+        /// Apps the token is authorized for, trusted without a membership check only when
+        /// `--ttl` is at most 300 seconds.
+        #[arg(long = "app")]
+        apps: Vec<Uuid>,
+    },
+}
+
+fn parse_role(role: &str) -> Result<UserRole, ParsingError> {
+    UserRole::try_from(role.to_string())
+}
+
+impl From<AccessorArgs> for JwtAccessor {
+    fn from(accessor: AccessorArgs) -> Self {
+        match accessor {
+            AccessorArgs::DiscordSystem => JwtAccessor::DiscordSystem,
+            AccessorArgs::WebhookSystem => JwtAccessor::WebhookSystem,
+            AccessorArgs::EmailSystem => JwtAccessor::EmailSystem,
+            AccessorArgs::MatrixSystem => JwtAccessor::MatrixSystem,
+            AccessorArgs::Staff {
+                user_id,
+                role,
+                apps,
+            } => JwtAccessor::DiscordStaffMember {
+                user_id,
+                authorized_apps: HashSet::from_iter(apps),
+                role,
+            },
+        }
+    }
+}
+
+/// Prints a token signed with the configured private key, for calling the collector by hand.
+pub fn mint(accessor: AccessorArgs, ttl: Duration) -> TicketsResult<()> {
+    let config = AdminConfig::load()?;
+
+    let (token, claim) = config.jwt_config()?.generate(
+        JwtData {
+            accessor: accessor.into(),
+        },
+        ttl,
+    )?;
+
+    // the token alone goes to stdout so it can be captured by scripts
+    eprintln!("Expires at unix timestamp {}", claim.exp);
+    println!("{token}");
+
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use uuid::Uuid;
+
+use errors::TicketsResult;
+use events::{TicketEvent, TicketUpdatedEvent};
+use sdk::client::{
+    PageOptions, SdkCall, SdkCallWithBody, SdkCallWithPath, SdkCallWithPathAndBody,
+    SdkCallWithPathAndParams, SdkInvoke, SdkPaginateWithPath,
+};
+use sdk::routes::staff::{
+    AppPath, AssignTicket, AssignTicketBody, AuthorizeApps, AuthorizeAppsBody, GetProfile,
+    GetTicketThread, ListTickets, Login, ReplyToTicket, SetTicketStatus, SetTicketStatusBody,
+    TicketMessageBody, TicketPath, TicketStatus, TicketsQuery,
+};
+use sdk::routes::Page;
+use socketio_client::{TicketNamespace, TicketSocketConfig, TicketsWebsocketClientExt};
+
+use crate::output::{self, format_timestamp, OutputFormat};
+use crate::session::{Session, TtConfig};
+use crate::ListArgs;
+
+// `--all` refuses to list more, narrow the listing with filters instead
+const MAX_LISTED_TICKETS: usize = 10_000;
+
+pub async fn login(format: OutputFormat, code: String, app_id: Option<Uuid>) -> TicketsResult<()> {
+    let config: TtConfig = dry::config::load_config()?;
+    let session = Session::exchange(config, code, app_id).await?;
+    let client = session.client()?;
+
+    Login::invoke(&client).await?;
+
+    // fails unless the staff member belongs to the app
+    if let Some(app_id) = app_id {
+        AuthorizeApps::call_with_body(
+            &client,
+            AuthorizeAppsBody {
+                app_ids: [app_id].into(),
+            },
+        )
+        .await?;
+    }
+
+    let profile = GetProfile::call(&client).await?;
+    session.save()?;
+
+    output::print(format, &profile)
+}
+
+pub async fn list(
+    format: OutputFormat,
+    app_id: Option<Uuid>,
+    args: ListArgs,
+    search: Option<String>,
+) -> TicketsResult<()> {
+    let session = Session::load()?;
+    let app_id = session.app_id(app_id)?;
+
+    let assignee_id = if args.mine {
+        Some(session.user_id)
+    } else {
+        args.assignee
+    };
+
+    let client = session.client()?;
+    let query = TicketsQuery {
+        cursor: args.cursor,
+        limit: args.limit,
+        status: args.status,
+        assignee_id,
+        search,
+    };
+
+    let page = if args.all {
+        let items = ListTickets::collect_all_with_path_and_query(
+            &client,
+            AppPath { app_id },
+            query,
+            PageOptions::default().with_page_size(args.limit),
+            MAX_LISTED_TICKETS,
+        )
+        .await?;
+
+        Page {
+            items,
+            next_cursor: None,
+        }
+    } else {
+        ListTickets::call_with_path_and_query(&client, AppPath { app_id }, query).await?
+    };
+
+    output::print(format, &page)
+}
+
+pub async fn show(
+    format: OutputFormat,
+    app_id: Option<Uuid>,
+    ticket_id: Uuid,
+) -> TicketsResult<()> {
+    let session = Session::load()?;
+    let app_id = session.app_id(app_id)?;
+
+    let thread =
+        GetTicketThread::call_with_path(&session.client()?, TicketPath { app_id, ticket_id })
+            .await?;
+
+    output::print(format, &thread)
+}
+
+pub async fn reply(
+    format: OutputFormat,
+    app_id: Option<Uuid>,
+    ticket_id: Uuid,
+    body: String,
+) -> TicketsResult<()> {
+    let session = Session::load()?;
+    let app_id = session.app_id(app_id)?;
+
+    let message = ReplyToTicket::call_with_path_and_body(
+        &session.client()?,
+        TicketPath { app_id, ticket_id },
+        TicketMessageBody { body },
+    )
+    .await?;
+
+    output::print(format, &message)
+}
+
+pub async fn assign(
+    format: OutputFormat,
+    app_id: Option<Uuid>,
+    ticket_id: Uuid,
+    assignee_id: Option<u64>,
+    unassign: bool,
+) -> TicketsResult<()> {
+    let session = Session::load()?;
+    let app_id = session.app_id(app_id)?;
+
+    // the ticket goes to whoever runs the command unless someone else is named
+    let assignee_id = (!unassign).then(|| assignee_id.unwrap_or(session.user_id));
+
+    let ticket = AssignTicket::call_with_path_and_body(
+        &session.client()?,
+        TicketPath { app_id, ticket_id },
+        AssignTicketBody { assignee_id },
+    )
+    .await?;
+
+    output::print(format, &ticket)
+}
+
+pub async fn set_status(
+    format: OutputFormat,
+    app_id: Option<Uuid>,
+    ticket_id: Uuid,
+    status: TicketStatus,
+) -> TicketsResult<()> {
+    let session = Session::load()?;
+    let app_id = session.app_id(app_id)?;
+
+    let change = SetTicketStatus::call_with_path_and_body(
+        &session.client()?,
+        TicketPath { app_id, ticket_id },
+        SetTicketStatusBody { status },
+    )
+    .await?;
+
+    output::print(format, &change)
+}
+
+/// Prints the app's ticket events as they happen, one per line, until interrupted.
+pub async fn tail(format: OutputFormat, app_id: Option<Uuid>) -> TicketsResult<()> {
+    let session = Session::load()?;
+    let app_id = session.app_id(app_id)?;
+
+    // the websocket server only lets staff listen to apps named in a collector minted token
+    let authorized = AuthorizeApps::call_with_body(
+        &session.client()?,
+        AuthorizeAppsBody {
+            app_ids: [app_id].into(),
+        },
+    )
+    .await?;
+
+    let socket_config = TicketSocketConfig {
+        server_url: session.realtime_events_url.clone(),
+        token: session.token().to_string(),
+    };
+
+    let (client, mut events) = socketio_client::connect::<TicketNamespace>(&socket_config).await?;
+    client.listen_to(app_id, Some(authorized.token)).await?;
+
+    eprintln!("Listening to app {app_id}, press Ctrl+C to stop.");
+
+    while let Some((app_id, event)) = events.recv().await {
+        match format {
+            OutputFormat::Table => println!("{}", describe(&event)),
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&serde_json::json!({ "app_id": app_id, "event": event }))?
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(event: &TicketUpdatedEvent) -> String {
+    let detail = match event {
+        TicketUpdatedEvent::TicketSubmitted(event) => output::excerpt(&event.message),
+        TicketUpdatedEvent::AttachmentAdded(event) => {
+            format!("{}  {}", event.ticket_id, event.file_name)
+        }
+        TicketUpdatedEvent::MessageAdded(event) => {
+            let author = if event.from_staff {
+                "staff"
+            } else {
+                "customer"
+            };
+            format!(
+                "{}  {author}: {}",
+                event.ticket_id,
+                output::excerpt(&event.body)
+            )
+        }
+        TicketUpdatedEvent::TicketClosed(event) => {
+            format!("{}  by {}", event.ticket_id, event.closed_by)
+        }
+        TicketUpdatedEvent::TicketReopened(event) => {
+            format!("{}  by {}", event.ticket_id, event.reopened_by)
+        }
+    };
+
+    format!(
+        "{}  {:<16}  {detail}",
+        format_timestamp(chrono::Utc::now().timestamp()),
+        TicketEvent::from(event.clone()).event_type()
+    )
+}
@@ -0,0 +1,154 @@
+//! `tt`, triaging an app's tickets from the shell as a staff member
+
+use clap::{Args, Parser, Subcommand};
+use tracing_subscriber::filter::LevelFilter;
+use uuid::Uuid;
+
+use errors::{AuthorizationError, MiscError, ParsingError, TicketsError, TicketsResult};
+use sdk::routes::staff::TicketStatus;
+
+use crate::output::OutputFormat;
+use crate::session::Session;
+
+mod commands;
+mod output;
+mod session;
+
+#[derive(Parser)]
+#[command(name = "tt", about = "Triage tickets from the shell")]
+struct Cli {
+    /// How results are printed.
+    #[arg(long, short, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// The app to act on, the app chosen at login when omitted.
+    #[arg(long, global = true)]
+    app: Option<Uuid>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Signs in to the collector named in `config.json` of the working directory, `--app`
+    /// becomes the default app.
+    Login {
+        /// A code from `/cli-login` in your Discord, valid once.
+        #[arg(long)]
+        code: String,
+    },
+    Logout,
+    /// Lists the app's tickets, newest first.
+    List(ListArgs),
+    /// Lists tickets whose message or replies contain `text`.
+    Search {
+        text: String,
+        #[command(flatten)]
+        filters: ListArgs,
+    },
+    /// Shows a ticket with its messages, notes and status changes.
+    Show {
+        ticket_id: Uuid,
+    },
+    /// Replies to the ticket's customer.
+    Reply {
+        ticket_id: Uuid,
+        body: String,
+    },
+    /// Assigns the ticket, to yourself unless an assignee is given.
+    Assign {
+        ticket_id: Uuid,
+        /// User id of the staff member taking the ticket.
+        assignee_id: Option<u64>,
+        #[arg(long, conflicts_with = "assignee_id")]
+        unassign: bool,
+    },
+    Close {
+        ticket_id: Uuid,
+    },
+    Reopen {
+        ticket_id: Uuid,
+    },
+    /// Prints the app's ticket events as they happen.
+    Tail,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    #[arg(long, value_parser = parse_status)]
+    status: Option<TicketStatus>,
+    /// Only tickets assigned to this staff member.
+    #[arg(long, conflicts_with = "mine")]
+    assignee: Option<u64>,
+    /// Only tickets assigned to you.
+    #[arg(long)]
+    mine: bool,
+    #[arg(long, default_value_t = 20)]
+    limit: u32,
+    /// Continues a previous listing.
+    #[arg(long)]
+    cursor: Option<String>,
+    /// Lists every matching ticket instead of a single page, `--limit` tickets per request.
+    #[arg(long, conflicts_with = "cursor")]
+    all: bool,
+}
+
+fn parse_status(status: &str) -> Result<TicketStatus, ParsingError> {
+    TicketStatus::try_from(status.to_string())
+}
+
+async fn run(cli: Cli) -> TicketsResult<()> {
+    let Cli {
+        output: format,
+        app,
+        command,
+    } = cli;
+
+    match command {
+        Command::Login { code } => commands::login(format, code, app).await,
+        Command::Logout => Session::remove(),
+        Command::List(args) => commands::list(format, app, args, None).await,
+        Command::Search { text, filters } => commands::list(format, app, filters, Some(text)).await,
+        Command::Show { ticket_id } => commands::show(format, app, ticket_id).await,
+        Command::Reply { ticket_id, body } => commands::reply(format, app, ticket_id, body).await,
+        Command::Assign {
+            ticket_id,
+            assignee_id,
+            unassign,
+        } => commands::assign(format, app, ticket_id, assignee_id, unassign).await,
+        Command::Close { ticket_id } => {
+            commands::set_status(format, app, ticket_id, TicketStatus::Closed).await
+        }
+        Command::Reopen { ticket_id } => {
+            commands::set_status(format, app, ticket_id, TicketStatus::Open).await
+        }
+        Command::Tail => commands::tail(format, app).await,
+    }
+}
+
+/// What to run next for errors the user fixes by logging in.
+fn hint(err: &TicketsError) -> Option<&'static str> {
+    match err {
+        TicketsError::Misc(MiscError::NotLoggedIn) => Some("run `tt login` first"),
+        TicketsError::Authorization(AuthorizationError::LoginExpired) => {
+            Some("run `tt login` again")
+        }
+        _ => None,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    // stdout carries the output, logs of the sdk and socket client stay out of it
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(LevelFilter::WARN)
+        .init();
+
+    if let Err(err) = run(Cli::parse()).await {
+        eprintln!("error: {err}");
+        if let Some(hint) = hint(&err) {
+            eprintln!("hint: {hint}");
+        }
+        std::process::exit(1);
+    }
+}
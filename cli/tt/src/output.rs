@@ -0,0 +1,240 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use errors::TicketsResult;
+use sdk::routes::consumer::TicketMessage;
+use sdk::routes::staff::{TicketOverview, TicketStatusChange, TicketThread, UserProfile};
+use sdk::routes::Page;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// A command's result, printed as text for people or as JSON for scripts.
+pub trait Render: Serialize {
+    fn render(&self) -> String;
+}
+
+pub fn print<T: Render>(format: OutputFormat, value: &T) -> TicketsResult<()> {
+    match format {
+        OutputFormat::Table => println!("{}", value.render()),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+    Ok(())
+}
+
+/// Columns padded to their widest cell.
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: Vec<&'static str>) -> Self {
+        Self {
+            headers,
+            rows: vec![],
+        }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn render(&self) -> String {
+        let mut widths = self
+            .headers
+            .iter()
+            .map(|header| header.chars().count())
+            .collect::<Vec<usize>>();
+
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: Vec<&str>| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        std::iter::once(line(self.headers.clone()))
+            .chain(
+                self.rows
+                    .iter()
+                    .map(|row| line(row.iter().map(String::as_str).collect())),
+            )
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// The first line of `text`, cut to fit a table cell.
+pub fn excerpt(text: &str) -> String {
+    const MAX_CHARS: usize = 60;
+
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() > MAX_CHARS || line.len() < text.trim_end().len() {
+        let cut = line.chars().take(MAX_CHARS - 1).collect::<String>();
+        format!("{}…", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+impl Render for UserProfile {
+    fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "Logged in as {} ({})",
+            self.display_name
+                .as_deref()
+                .unwrap_or("unnamed staff member"),
+            self.user_id
+        )];
+
+        for identity in &self.identities {
+            lines.push(format!(
+                "  {}: {}",
+                identity.gateway,
+                identity
+                    .display_name
+                    .as_deref()
+                    .unwrap_or(&identity.external_id)
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl Render for Page<TicketOverview> {
+    fn render(&self) -> String {
+        if self.items.is_empty() {
+            return "No tickets found.".to_string();
+        }
+
+        let mut table = Table::new(vec![
+            "TICKET", "STATUS", "GATEWAY", "ASSIGNEE", "CREATED", "MESSAGE",
+        ]);
+
+        for ticket in &self.items {
+            table.row(vec![
+                ticket.ticket_id.to_string(),
+                ticket.status.to_string(),
+                ticket.gateway.clone(),
+                optional(ticket.assignee_id),
+                format_timestamp(ticket.created_at),
+                excerpt(&ticket.message),
+            ]);
+        }
+
+        match &self.next_cursor {
+            Some(cursor) => format!(
+                "{}\n\nMore tickets follow, pass `--cursor {cursor}`.",
+                table.render()
+            ),
+            None => table.render(),
+        }
+    }
+}
+
+impl Render for TicketOverview {
+    fn render(&self) -> String {
+        [
+            format!("Ticket    {}", self.ticket_id),
+            format!("Status    {}", self.status),
+            format!("Gateway   {}", self.gateway),
+            format!("Customer  {}", optional(self.customer_id)),
+            format!("Assignee  {}", optional(self.assignee_id)),
+            format!("Created   {}", format_timestamp(self.created_at)),
+        ]
+        .join("\n")
+    }
+}
+
+impl Render for TicketThread {
+    fn render(&self) -> String {
+        // messages, notes and status changes are merged into one timeline
+        let mut entries = vec![(
+            self.ticket.created_at,
+            "customer".to_string(),
+            self.ticket.message.clone(),
+        )];
+
+        entries.extend(self.messages.iter().map(|message| {
+            let author = match message.author_id {
+                Some(user_id) => format!("staff {user_id}"),
+                None => "customer".to_string(),
+            };
+            (message.created_at, author, message.body.clone())
+        }));
+
+        entries.extend(self.notes.iter().map(|note| {
+            (
+                note.created_at,
+                format!("note by {}", note.author_id),
+                note.body.clone(),
+            )
+        }));
+
+        entries.extend(self.status_changes.iter().map(|change| {
+            (
+                change.created_at,
+                format!("staff {}", change.changed_by),
+                format!("{} → {}", change.from_status, change.to_status),
+            )
+        }));
+
+        // stable, entries of the same second keep the order they were listed in
+        entries.sort_by_key(|(created_at, ..)| *created_at);
+
+        let timeline = entries
+            .into_iter()
+            .map(|(created_at, author, body)| {
+                let body = body.lines().collect::<Vec<&str>>().join("\n    ");
+                format!("[{}] {author}\n    {body}", format_timestamp(created_at))
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n");
+
+        format!("{}\n\n{timeline}", self.ticket.render())
+    }
+}
+
+impl Render for TicketMessage {
+    fn render(&self) -> String {
+        format!(
+            "Replied to ticket {} at {}.",
+            self.ticket_id,
+            format_timestamp(self.created_at)
+        )
+    }
+}
+
+impl Render for TicketStatusChange {
+    fn render(&self) -> String {
+        format!(
+            "Ticket {} is now {}, it was {}.",
+            self.ticket_id, self.to_status, self.from_status
+        )
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+use auth::jwt::{JwtAccessor, JwtData};
+use auth::UserRole;
+use errors::{AuthorizationError, MiscError, ParsingError, TicketsResult};
+use sdk::client::SignedTicketClient;
+
+/// Staff sign in with their Discord user id, the CLI acts under the identity they have there.
+pub const GATEWAY_NAME: &str = "discord";
+
+/// The part of `config.json` the CLI reads when logging in.
+#[derive(serde::Deserialize)]
+pub struct TtConfig {
+    pub collector_url: String,
+    pub realtime_events_url: Option<String>,
+}
+
+/// Who is logged in and where the collector is, kept between invocations so commands work
+/// outside of the directory holding `config.json`.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    pub user_id: u64,
+    /// Commands act on this app unless another is passed with `--app`.
+    pub app_id: Option<Uuid>,
+    pub collector_url: String,
+    pub realtime_events_url: String,
+    /// Issued by the collector for the login code, the CLI never holds the keys to sign one.
+    token: String,
+    /// Unix timestamp in seconds, after which the staff member logs in again.
+    expiration: i64,
+}
+
+impl Session {
+    /// Exchanges `code`, created through the Discord gateway's `/cli-login`, with the collector.
+    pub async fn exchange(
+        config: TtConfig,
+        code: String,
+        app_id: Option<Uuid>,
+    ) -> TicketsResult<Self> {
+        let issued =
+            sdk::client::exchange_login_code(&config.collector_url, GATEWAY_NAME, code).await?;
+
+        Ok(Self {
+            user_id: issued.user_id,
+            app_id,
+            realtime_events_url: config
+                .realtime_events_url
+                .unwrap_or_else(|| config.collector_url.clone()),
+            collector_url: config.collector_url,
+            token: issued.token,
+            expiration: issued.expiration,
+        })
+    }
+
+    /// `$XDG_CONFIG_HOME/tt/session.json`, falling back to `~/.config`.
+    fn path() -> TicketsResult<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .ok_or(MiscError::NotLoggedIn)?;
+
+        Ok(config_dir.join("tt").join("session.json"))
+    }
+
+    pub fn load() -> TicketsResult<Self> {
+        let session = match std::fs::read_to_string(Self::path()?) {
+            Ok(session) => session,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(MiscError::NotLoggedIn)?
+            }
+            Err(err) => return Err(err)?,
+        };
+
+        let session: Self = serde_json::from_str(&session)?;
+        if session.expiration <= chrono::Utc::now().timestamp() {
+            return Err(AuthorizationError::LoginExpired)?;
+        }
+
+        Ok(session)
+    }
+
+    pub fn save(&self) -> TicketsResult<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // the token acts as the staff member, only they may read it
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path)?;
+        Ok(std::io::Write::write_all(
+            &mut file,
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?)
+    }
+
+    /// Forgets the session, succeeds if there was none.
+    pub fn remove() -> TicketsResult<()> {
+        match std::fs::remove_file(Self::path()?) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err)?,
+            _ => Ok(()),
+        }
+    }
+
+    /// The app a command acts on, `app_id` if given, the session's app otherwise.
+    pub fn app_id(&self, app_id: Option<Uuid>) -> TicketsResult<Uuid> {
+        app_id.or(self.app_id).ok_or_else(|| {
+            ParsingError::InvalidRequest(
+                "No app given, pass `--app` or log in with one.".to_string(),
+            )
+            .into()
+        })
+    }
+
+    pub fn accessor(&self) -> JwtAccessor {
+        JwtAccessor::DiscordStaffMember {
+            user_id: self.user_id,
+            authorized_apps: HashSet::new(),
+            // the collector checks the staff member's role in the app on every request
+            role: UserRole::Staff,
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn client(&self) -> TicketsResult<SignedTicketClient> {
+        SignedTicketClient::with_issued_token(
+            &self.collector_url,
+            GATEWAY_NAME,
+            JwtData {
+                accessor: self.accessor(),
+            },
+            self.token.clone(),
+            self.expiration,
+        )
+    }
+}
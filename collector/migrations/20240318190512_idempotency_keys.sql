@@ -0,0 +1,20 @@
+-- Responses replayed for retried requests carrying an idempotency key. Keys are scoped to the
+-- caller, reusing one with another request body is refused.
+CREATE TABLE IF NOT EXISTS idempotency_key
+(
+    key          TEXT        NOT NULL,
+    method       TEXT        NOT NULL,
+    path         TEXT        NOT NULL,
+    -- `user:<id>` or `channel:<gateway>`, keys are only stored for authenticated callers
+    caller       TEXT        NOT NULL,
+    -- hex encoded SHA-256 of the request body
+    body_hash    TEXT        NOT NULL,
+    -- NULL while the request is in progress
+    status       INT4,
+    content_type TEXT,
+    body         BYTEA,
+    -- false once a response was sent which could not be stored
+    replayable   BOOL        NOT NULL DEFAULT TRUE,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key, method, path, caller)
+);
@@ -0,0 +1,24 @@
+-- Profile metadata and linked gateway identities of staff users
+ALTER TABLE tt_user ADD COLUMN IF NOT EXISTS display_name TEXT;
+ALTER TABLE tt_user ADD COLUMN IF NOT EXISTS avatar_url TEXT;
+ALTER TABLE tt_user ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE TABLE IF NOT EXISTS user_identity
+(
+    gateway      TEXT        NOT NULL,
+    external_id  TEXT        NOT NULL,
+    user_id      INT8        NOT NULL,
+    display_name TEXT,
+    avatar_url   TEXT,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES tt_user (id),
+    PRIMARY KEY (gateway, external_id)
+);
+
+CREATE INDEX IF NOT EXISTS user_identity_user_id_index ON user_identity (user_id);
+
+-- existing users were all provisioned through discord, keyed by their discord id
+INSERT INTO user_identity (gateway, external_id, user_id)
+SELECT 'discord', id::TEXT, id
+FROM tt_user
+ON CONFLICT (gateway, external_id) DO NOTHING;
@@ -0,0 +1,31 @@
+-- Customers submitting tickets to an app, identified per gateway. Apps never share customers,
+-- the same person writing to two apps is two customers.
+CREATE TABLE IF NOT EXISTS customer
+(
+    id           UUID PRIMARY KEY,
+    app_id       UUID        NOT NULL,
+    display_name TEXT,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (app_id) REFERENCES app (id)
+);
+
+CREATE INDEX IF NOT EXISTS customer_app_id_index ON customer (app_id);
+
+CREATE TABLE IF NOT EXISTS customer_identity
+(
+    app_id       UUID        NOT NULL,
+    gateway      TEXT        NOT NULL,
+    external_id  TEXT        NOT NULL,
+    customer_id  UUID        NOT NULL,
+    display_name TEXT,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (app_id) REFERENCES app (id),
+    FOREIGN KEY (customer_id) REFERENCES customer (id),
+    PRIMARY KEY (app_id, gateway, external_id)
+);
+
+CREATE INDEX IF NOT EXISTS customer_identity_customer_id_index ON customer_identity (customer_id);
+
+ALTER TABLE ticket ADD COLUMN IF NOT EXISTS customer_id UUID REFERENCES customer (id);
+
+CREATE INDEX IF NOT EXISTS ticket_customer_id_index ON ticket (customer_id, created_at);
@@ -0,0 +1,8 @@
+-- Per app limits on ticket submissions by a single submitter, apps without a row use the defaults
+CREATE TABLE IF NOT EXISTS app_rate_limit
+(
+    app_id         UUID PRIMARY KEY REFERENCES app (id),
+    max_tickets    INT4 NOT NULL CHECK (max_tickets > 0),
+    window_seconds INT4 NOT NULL CHECK (window_seconds > 0),
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,20 @@
+-- Per app rules evaluated before a ticket is stored, parameters depend on the rule's kind
+CREATE TABLE IF NOT EXISTS ticket_filter_rule
+(
+    id          UUID PRIMARY KEY,
+    app_id      UUID        NOT NULL REFERENCES app (id),
+    kind        TEXT        NOT NULL,
+    action      TEXT        NOT NULL,
+    pattern     TEXT,
+    threshold   INT4,
+    gateway     TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ticket_filter_rule_app_id_index ON ticket_filter_rule (app_id);
+
+-- NULL for tickets no filter matched, otherwise flagged, quarantined, approved or rejected
+ALTER TABLE ticket ADD COLUMN IF NOT EXISTS filter_status TEXT;
+ALTER TABLE ticket ADD COLUMN IF NOT EXISTS filter_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS ticket_filter_status_index ON ticket (app_id, filter_status, created_at);
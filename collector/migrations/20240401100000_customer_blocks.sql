@@ -0,0 +1,10 @@
+-- Customers banned from submitting tickets to an app, on every gateway they are linked to
+CREATE TABLE IF NOT EXISTS customer_block
+(
+    app_id      UUID        NOT NULL REFERENCES app (id),
+    customer_id UUID        NOT NULL REFERENCES customer (id),
+    reason      TEXT,
+    blocked_by  INT8        NOT NULL REFERENCES tt_user (id),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (app_id, customer_id)
+);
@@ -0,0 +1,26 @@
+-- Files attached to tickets, the content lives in the blob store under `storage_key`
+CREATE TABLE IF NOT EXISTS attachment
+(
+    id           UUID PRIMARY KEY,
+    app_id       UUID        NOT NULL REFERENCES app (id),
+    ticket_id    UUID        NOT NULL REFERENCES ticket (id),
+    file_name    TEXT        NOT NULL,
+    content_type TEXT        NOT NULL,
+    size_bytes   INT8        NOT NULL,
+    storage_key  TEXT        NOT NULL,
+    -- exactly one of the customer or the staff member uploaded the file
+    customer_id  UUID REFERENCES customer (id),
+    uploaded_by  INT8 REFERENCES tt_user (id),
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS attachment_ticket_id_index ON attachment (ticket_id, created_at);
+
+-- Per app limits on uploaded attachments, apps without a row use the defaults
+CREATE TABLE IF NOT EXISTS app_attachment_limit
+(
+    app_id         UUID PRIMARY KEY REFERENCES app (id),
+    max_size_bytes INT8   NOT NULL CHECK (max_size_bytes > 0),
+    allowed_types  TEXT[] NOT NULL,
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
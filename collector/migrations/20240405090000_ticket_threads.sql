@@ -0,0 +1,42 @@
+-- Either open or closed, closed tickets accept no messages until they are reopened
+ALTER TABLE ticket ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'open';
+
+-- The conversation of a ticket, written by its customer (customer_id) or staff (author_id)
+CREATE TABLE IF NOT EXISTS ticket_message
+(
+    id          UUID PRIMARY KEY,
+    app_id      UUID        NOT NULL REFERENCES app (id),
+    ticket_id   UUID        NOT NULL REFERENCES ticket (id),
+    body        TEXT        NOT NULL,
+    customer_id UUID REFERENCES customer (id),
+    author_id   INT8 REFERENCES tt_user (id),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ticket_message_ticket_id_index ON ticket_message (ticket_id, created_at);
+
+-- Remarks only visible to staff
+CREATE TABLE IF NOT EXISTS ticket_note
+(
+    id         UUID PRIMARY KEY,
+    app_id     UUID        NOT NULL REFERENCES app (id),
+    ticket_id  UUID        NOT NULL REFERENCES ticket (id),
+    body       TEXT        NOT NULL,
+    author_id  INT8        NOT NULL REFERENCES tt_user (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ticket_note_ticket_id_index ON ticket_note (ticket_id, created_at);
+
+CREATE TABLE IF NOT EXISTS ticket_status_change
+(
+    id          UUID PRIMARY KEY,
+    app_id      UUID        NOT NULL REFERENCES app (id),
+    ticket_id   UUID        NOT NULL REFERENCES ticket (id),
+    from_status TEXT        NOT NULL,
+    to_status   TEXT        NOT NULL,
+    changed_by  INT8        NOT NULL REFERENCES tt_user (id),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ticket_status_change_ticket_id_index ON ticket_status_change (ticket_id, created_at);
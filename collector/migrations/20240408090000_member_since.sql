@@ -0,0 +1,2 @@
+-- When members joined their app, rows predating the column count from the migration
+ALTER TABLE user_app ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,33 @@
+-- Endpoints receiving an app's events, an empty event_types delivers every event
+CREATE TABLE IF NOT EXISTS webhook_endpoint
+(
+    id          UUID PRIMARY KEY,
+    app_id      UUID        NOT NULL REFERENCES app (id),
+    url         TEXT        NOT NULL,
+    secret      TEXT        NOT NULL,
+    event_types TEXT[]      NOT NULL DEFAULT '{}',
+    created_by  INT8        NOT NULL REFERENCES tt_user (id),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS webhook_endpoint_app_id_index ON webhook_endpoint (app_id);
+
+-- A single event for a single endpoint, payload is the exact body signed on every attempt
+CREATE TABLE IF NOT EXISTS webhook_delivery
+(
+    id               UUID PRIMARY KEY,
+    app_id           UUID        NOT NULL REFERENCES app (id),
+    endpoint_id      UUID        NOT NULL REFERENCES webhook_endpoint (id) ON DELETE CASCADE,
+    event_type       TEXT        NOT NULL,
+    payload          TEXT        NOT NULL,
+    status           TEXT        NOT NULL DEFAULT 'pending',
+    attempts         INT4        NOT NULL DEFAULT 0,
+    next_attempt_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INT4,
+    last_error       TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at     TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS webhook_delivery_due_index ON webhook_delivery (status, next_attempt_at);
+CREATE INDEX IF NOT EXISTS webhook_delivery_endpoint_id_index ON webhook_delivery (endpoint_id, created_at);
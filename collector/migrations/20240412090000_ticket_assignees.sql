@@ -0,0 +1,4 @@
+-- The staff member handling the ticket, unassigned tickets have none
+ALTER TABLE ticket ADD COLUMN IF NOT EXISTS assignee_id INT8 REFERENCES tt_user (id);
+
+CREATE INDEX IF NOT EXISTS ticket_assignee_id_index ON ticket (app_id, assignee_id);
@@ -0,0 +1,3 @@
+-- When the ticket was last imported, tickets submitted through a gateway have none and are
+-- never overwritten by an import
+ALTER TABLE ticket ADD COLUMN IF NOT EXISTS imported_at TIMESTAMPTZ;
@@ -0,0 +1,10 @@
+-- Single use codes a staff member creates through a gateway they are signed in to, such as
+-- Discord, and exchanges for a collector issued token where no gateway signs for them, such as
+-- `tt login`; only the code's hash is kept
+CREATE TABLE IF NOT EXISTS login_code
+(
+    code_hash  TEXT        NOT NULL PRIMARY KEY,
+    user_id    BIGINT      NOT NULL REFERENCES tt_user (id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,638 @@
+//! files attached to tickets by customers through their gateway or by staff, stored in the
+//! blob store and limited in size and type per app
+
+use axum::extract::multipart::MultipartError;
+use axum::extract::Multipart;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use uuid::Uuid;
+
+use errors::{AuthorizationError, MiscError, ParsingError, TicketsError, TicketsResult};
+use events::AttachmentAddedEvent;
+use sdk::routes::consumer::{Attachment, DownloadAttachment, UploadAttachment};
+use sdk::routes::staff::{
+    AttachmentLimits, DownloadStaffAttachment, GetAttachmentLimits, SetAttachmentLimits,
+    TicketAttachments, UploadStaffAttachment,
+};
+use sdk::routes::FileData;
+
+use crate::axum_ext::{is_sdk_route, ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+/// Upper bound of every app's size limit, larger request bodies are refused outright.
+pub const MAX_ATTACHMENT_SIZE: u64 = 100 * 1024 * 1024;
+
+// room for the multipart framing around the file
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+/// Body limit of the upload routes, fitting the largest attachment any app may allow.
+const UPLOAD_BODY_LIMIT: usize = MAX_ATTACHMENT_SIZE as usize + MULTIPART_OVERHEAD;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router
+        .merge(
+            SdkRouter::new()
+                .sdk_route::<UploadAttachment>(upload_attachment::route_handler)
+                .sdk_route::<UploadStaffAttachment>(upload_staff_attachment::route_handler)
+                .body_limit(UPLOAD_BODY_LIMIT),
+        )
+        .merge(
+            SdkRouter::new()
+                .sdk_route::<DownloadAttachment>(download_attachment::route_handler)
+                .sdk_route::<TicketAttachments>(ticket_attachments::route_handler)
+                .sdk_route::<DownloadStaffAttachment>(download_staff_attachment::route_handler)
+                .sdk_route::<GetAttachmentLimits>(get_attachment_limits::route_handler)
+                .sdk_route::<SetAttachmentLimits>(set_attachment_limits::route_handler),
+        )
+}
+
+/// The raised body limit of the route matched as `matched_path`, if it is an upload route.
+pub(crate) fn body_limit(matched_path: &str) -> Option<usize> {
+    (is_sdk_route::<UploadAttachment>(matched_path)
+        || is_sdk_route::<UploadStaffAttachment>(matched_path))
+    .then_some(UPLOAD_BODY_LIMIT)
+}
+
+/// Applied to apps which never configured their own limits.
+pub fn default_attachment_limits() -> AttachmentLimits {
+    AttachmentLimits {
+        max_size_bytes: 8 * 1024 * 1024,
+        allowed_types: vec![
+            "image/*".to_string(),
+            "text/plain".to_string(),
+            "application/pdf".to_string(),
+        ],
+    }
+}
+
+pub enum Uploader {
+    Customer(Uuid),
+    Staff(u64),
+}
+
+struct TicketRecord {
+    customer_id: Option<Uuid>,
+    /// Whether staff were notified of the ticket, attachments of withheld tickets are not
+    /// published either.
+    published: bool,
+}
+
+async fn load_ticket(
+    state: &GlobalState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+) -> TicketsResult<TicketRecord> {
+    let ticket = sqlx::query!(
+        "SELECT customer_id, filter_status FROM ticket WHERE id = $1 AND app_id = $2",
+        &ticket_id,
+        &app_id
+    )
+    .fetch_one(&state.pg_client)
+    .await?;
+
+    Ok(TicketRecord {
+        customer_id: ticket.customer_id,
+        published: !matches!(
+            ticket.filter_status.as_deref(),
+            Some("quarantined" | "rejected")
+        ),
+    })
+}
+
+/// The app's configured limits, or [`default_attachment_limits`] if it has none.
+async fn load_attachment_limits(
+    state: &GlobalState,
+    app_id: Uuid,
+) -> TicketsResult<AttachmentLimits> {
+    let limits = sqlx::query!(
+        "SELECT max_size_bytes, allowed_types FROM app_attachment_limit WHERE app_id = $1",
+        &app_id
+    )
+    .fetch_optional(&state.pg_client)
+    .await?
+    .map(|record| AttachmentLimits {
+        max_size_bytes: record.max_size_bytes as u64,
+        allowed_types: record.allowed_types,
+    });
+
+    Ok(limits.unwrap_or_else(default_attachment_limits))
+}
+
+/// Compares the essence of `content_type` against the allowed types, `image/*` allows every
+/// image type.
+fn is_type_allowed(limits: &AttachmentLimits, content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    limits.allowed_types.iter().any(|allowed| {
+        let allowed = allowed.to_ascii_lowercase();
+        match allowed.strip_suffix("/*") {
+            Some(kind) => essence
+                .split_once('/')
+                .is_some_and(|(essence_kind, _)| essence_kind == kind),
+            None => allowed == essence,
+        }
+    })
+}
+
+/// Reads the `file` field of an upload, enforcing the app's limits while it streams in.
+async fn read_upload(
+    mut multipart: Multipart,
+    limits: &AttachmentLimits,
+) -> TicketsResult<FileData> {
+    let too_large = || MiscError::AttachmentTooLarge {
+        max_size_bytes: limits.max_size_bytes,
+    };
+    let malformed = |err: MultipartError| -> TicketsError {
+        if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            too_large().into()
+        } else {
+            ParsingError::InvalidRequest(err.body_text()).into()
+        }
+    };
+
+    while let Some(mut field) = multipart.next_field().await.map_err(malformed)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = field
+            .file_name()
+            .filter(|file_name| !file_name.is_empty())
+            .unwrap_or("attachment")
+            .to_string();
+        let content_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        if !is_type_allowed(limits, &content_type) {
+            return Err(MiscError::AttachmentTypeNotAllowed { content_type })?;
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(malformed)? {
+            if (bytes.len() + chunk.len()) as u64 > limits.max_size_bytes {
+                return Err(too_large())?;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        return Ok(FileData {
+            file_name,
+            content_type,
+            bytes,
+        });
+    }
+
+    Err(ParsingError::InvalidRequest(
+        "The upload is missing its `file` field.".to_string(),
+    ))?
+}
+
+/// Stores the file and its metadata, publishing it to staff if the ticket was published.
+async fn store_attachment(
+    state: &GlobalState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+    ticket: TicketRecord,
+    uploader: Uploader,
+    file: FileData,
+) -> TicketsResult<Attachment> {
+    let attachment_id = Uuid::new_v4();
+    let storage_key = format!("{app_id}/{attachment_id}");
+    let size_bytes = file.bytes.len() as u64;
+
+    state
+        .blob_store
+        .put(&storage_key, &file.content_type, file.bytes)
+        .await?;
+
+    let (customer_id, uploaded_by) = match uploader {
+        Uploader::Customer(customer_id) => (Some(customer_id), None),
+        Uploader::Staff(user_id) => (None, Some(user_id)),
+    };
+
+    let inserted = sqlx::query!(
+        r#"INSERT INTO attachment
+                (id, app_id, ticket_id, file_name, content_type, size_bytes, storage_key, customer_id, uploaded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!""#,
+        &attachment_id,
+        &app_id,
+        &ticket_id,
+        &file.file_name,
+        &file.content_type,
+        size_bytes as i64,
+        &storage_key,
+        customer_id,
+        uploaded_by.map(|user_id| user_id as i64)
+    )
+    .fetch_one(&state.pg_client)
+    .await;
+
+    let created_at = match inserted {
+        Ok(record) => record.created_at,
+        Err(err) => {
+            // the blob is unreachable without its metadata
+            if let Err(err) = state.blob_store.delete(&storage_key).await {
+                log::warn!("Failed to remove orphaned attachment {storage_key}: {err}");
+            }
+            return Err(err)?;
+        }
+    };
+
+    if ticket.published {
+        state.emitter.publish_tickets_event(
+            app_id,
+            AttachmentAddedEvent {
+                ticket_id,
+                attachment_id,
+                file_name: file.file_name.clone(),
+                content_type: file.content_type.clone(),
+                from_staff: uploaded_by.is_some(),
+            }
+            .into(),
+        )?;
+    }
+
+    Ok(Attachment {
+        attachment_id,
+        ticket_id,
+        file_name: file.file_name,
+        content_type: file.content_type,
+        size_bytes,
+        customer_id,
+        uploaded_by,
+        created_at,
+    })
+}
+
+/// Loads the attachment's content, failing with not found unless it belongs to `app_id`.
+async fn load_file(
+    state: &GlobalState,
+    app_id: Uuid,
+    attachment_id: Uuid,
+) -> TicketsResult<FileData> {
+    let attachment = sqlx::query!(
+        "SELECT file_name, content_type, storage_key FROM attachment WHERE id = $1 AND app_id = $2",
+        &attachment_id,
+        &app_id
+    )
+    .fetch_one(&state.pg_client)
+    .await?;
+
+    Ok(FileData {
+        bytes: state.blob_store.get(&attachment.storage_key).await?,
+        file_name: attachment.file_name,
+        content_type: attachment.content_type,
+    })
+}
+
+pub(crate) fn file_response(file: FileData) -> Response {
+    let content_type = HeaderValue::from_str(&file.content_type)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    // the file name is percent-encoded, so the value is always valid
+    let content_disposition = HeaderValue::from_str(&file.content_disposition())
+        .unwrap_or(HeaderValue::from_static("attachment"));
+
+    (
+        [
+            (CONTENT_TYPE, content_type),
+            (CONTENT_DISPOSITION, content_disposition),
+        ],
+        file.bytes,
+    )
+        .into_response()
+}
+
+pub(crate) async fn ensure_gateway_enabled(
+    state: &GlobalState,
+    app_id: Uuid,
+    gateway: String,
+) -> TicketsResult<()> {
+    let enabled = sqlx::query!(
+        "SELECT * FROM gateway WHERE app_id = $1 AND name = $2",
+        &app_id,
+        &gateway
+    )
+    .fetch_optional(&state.pg_client)
+    .await?
+    .is_some();
+
+    if !enabled {
+        return Err(AuthorizationError::GatewayNotEnabled { gateway })?;
+    }
+
+    Ok(())
+}
+
+pub mod upload_attachment {
+    use axum::extract::{Multipart, Query, State};
+    use axum::Json;
+
+    use auth::AuthedCaller;
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::consumer::{Attachment, AttachmentUploader, UploadAttachment};
+    use sdk::routes::staff::TicketPath;
+
+    use super::Uploader;
+    use crate::axum_ext::SdkPath;
+    use crate::blocks::ensure_not_blocked;
+    use crate::customers::find_customer;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        caller: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<UploadAttachment>,
+        Query(uploader): Query<AttachmentUploader>,
+        multipart: Multipart,
+    ) -> TicketsResult<Json<Attachment>> {
+        // only gateways relay files from customers, the customer is named by the gateway
+        let channel = caller.require_channel()?;
+        let gateway = channel.channel_type.gateway().to_string();
+
+        super::ensure_gateway_enabled(&state, app_id, gateway.clone()).await?;
+
+        let ticket = super::load_ticket(&state, app_id, ticket_id).await?;
+
+        let customer_id =
+            find_customer(&state.pg_client, app_id, &gateway, &uploader.external_id).await?;
+
+        // only the customer who submitted the ticket attaches files to it
+        let Some(customer_id) = customer_id.filter(|id| Some(*id) == ticket.customer_id) else {
+            return Err(AuthorizationError::UserCannotAccessResource)?;
+        };
+
+        ensure_not_blocked(&state.pg_client, app_id, customer_id).await?;
+
+        let limits = super::load_attachment_limits(&state, app_id).await?;
+        let file = super::read_upload(multipart, &limits).await?;
+
+        Ok(Json(
+            super::store_attachment(
+                &state,
+                app_id,
+                ticket_id,
+                ticket,
+                Uploader::Customer(customer_id),
+                file,
+            )
+            .await?,
+        ))
+    }
+}
+
+pub mod download_attachment {
+    use axum::extract::State;
+    use axum::response::Response;
+
+    use auth::AuthedCaller;
+    use errors::TicketsResult;
+    use sdk::routes::consumer::{AttachmentPath, DownloadAttachment};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        caller: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AttachmentPath {
+            app_id,
+            attachment_id,
+        }): SdkPath<DownloadAttachment>,
+    ) -> TicketsResult<Response> {
+        // only gateways relay files to customers, and only for apps which enabled them
+        let channel = caller.require_channel()?;
+        let gateway = channel.channel_type.gateway().to_string();
+
+        super::ensure_gateway_enabled(&state, app_id, gateway).await?;
+
+        let file = super::load_file(&state, app_id, attachment_id).await?;
+        Ok(super::file_response(file))
+    }
+}
+
+pub mod upload_staff_attachment {
+    use axum::extract::{Multipart, State};
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::consumer::Attachment;
+    use sdk::routes::staff::{TicketPath, UploadStaffAttachment};
+
+    use super::Uploader;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<UploadStaffAttachment>,
+        multipart: Multipart,
+    ) -> TicketsResult<Json<Attachment>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let ticket = super::load_ticket(&state, app_id, ticket_id).await?;
+        let limits = super::load_attachment_limits(&state, app_id).await?;
+        let file = super::read_upload(multipart, &limits).await?;
+
+        Ok(Json(
+            super::store_attachment(
+                &state,
+                app_id,
+                ticket_id,
+                ticket,
+                Uploader::Staff(user.user_id),
+                file,
+            )
+            .await?,
+        ))
+    }
+}
+
+pub mod ticket_attachments {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::consumer::Attachment;
+    use sdk::routes::staff::{TicketAttachments, TicketPath};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<TicketAttachments>,
+    ) -> TicketsResult<Json<Vec<Attachment>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        // fails with not found for tickets of other apps
+        super::load_ticket(&state, app_id, ticket_id).await?;
+
+        let attachments = sqlx::query!(
+            r#"SELECT id, file_name, content_type, size_bytes, customer_id, uploaded_by,
+                    EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM attachment
+                WHERE ticket_id = $1
+                ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(&state.pg_client)
+        .await?
+        .into_iter()
+        .map(|record| Attachment {
+            attachment_id: record.id,
+            ticket_id,
+            file_name: record.file_name,
+            content_type: record.content_type,
+            size_bytes: record.size_bytes as u64,
+            customer_id: record.customer_id,
+            uploaded_by: record.uploaded_by.map(|user_id| user_id as u64),
+            created_at: record.created_at,
+        })
+        .collect();
+
+        Ok(Json(attachments))
+    }
+}
+
+pub mod download_staff_attachment {
+    use axum::extract::State;
+    use axum::response::Response;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::consumer::AttachmentPath;
+    use sdk::routes::staff::DownloadStaffAttachment;
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AttachmentPath {
+            app_id,
+            attachment_id,
+        }): SdkPath<DownloadStaffAttachment>,
+    ) -> TicketsResult<Response> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let file = super::load_file(&state, app_id, attachment_id).await?;
+        Ok(super::file_response(file))
+    }
+}
+
+pub mod get_attachment_limits {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, AttachmentLimits, GetAttachmentLimits};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<GetAttachmentLimits>,
+    ) -> TicketsResult<Json<AttachmentLimits>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        Ok(Json(super::load_attachment_limits(&state, app_id).await?))
+    }
+}
+
+pub mod set_attachment_limits {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{AppPath, AttachmentLimits, SetAttachmentLimits};
+
+    use super::MAX_ATTACHMENT_SIZE;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<SetAttachmentLimits>,
+        Json(body): Json<AttachmentLimits>,
+    ) -> TicketsResult<Json<AttachmentLimits>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        if body.max_size_bytes == 0 || body.max_size_bytes > MAX_ATTACHMENT_SIZE {
+            return Err(ParsingError::InvalidRequest(format!(
+                "Attachment size limits must be between 1 and {MAX_ATTACHMENT_SIZE} bytes."
+            )))?;
+        }
+
+        let allowed_types = body
+            .allowed_types
+            .iter()
+            .map(|allowed| allowed.trim().to_ascii_lowercase())
+            .collect::<Vec<_>>();
+
+        let malformed = allowed_types.iter().find(|allowed| {
+            !matches!(allowed.split_once('/'), Some((kind, subtype))
+                if !kind.is_empty() && !subtype.is_empty() && !subtype.contains('/'))
+        });
+
+        if let Some(malformed) = malformed {
+            return Err(ParsingError::InvalidRequest(format!(
+                "`{malformed}` is not a valid MIME type."
+            )))?;
+        }
+
+        sqlx::query!(
+            "INSERT INTO app_attachment_limit (app_id, max_size_bytes, allowed_types) VALUES ($1, $2, $3) \
+                ON CONFLICT (app_id) DO UPDATE \
+                SET max_size_bytes = $2, allowed_types = $3, updated_at = NOW()",
+            &app_id,
+            body.max_size_bytes as i64,
+            &allowed_types
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        Ok(Json(AttachmentLimits {
+            max_size_bytes: body.max_size_bytes,
+            allowed_types,
+        }))
+    }
+}
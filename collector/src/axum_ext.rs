@@ -1,11 +1,20 @@
 //! extension traits for certain axum types
 
+use std::collections::BTreeSet;
+
+use axum::extract::{DefaultBodyLimit, FromRequestParts, Path};
+use axum::handler::Handler;
 use axum::http::header::AsHeaderName;
+use axum::http::request::Parts;
 use axum::http::HeaderMap;
-use axum::routing::on;
+use axum::routing::{on, MethodRouter};
 use axum::Router;
-use errors::ParsingError;
+use errors::{ParsingError, TicketsError};
 use sdk::routes::SdkRoute;
+use serde::de::DeserializeOwned;
+
+/// Body limit of routes which keep axum's default.
+pub const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
 
 pub trait RequireHeaderFromHeaderMap {
     fn require_header<K: AsHeaderName>(&self, header: K) -> Result<String, ParsingError>;
@@ -22,23 +31,72 @@ impl RequireHeaderFromHeaderMap for HeaderMap {
     }
 }
 
+/// A [`Router`] which keeps the method and path of every [`SdkRoute`] it serves, so the OpenAPI
+/// document can be checked against the routes actually served.
+pub struct SdkRouter<S> {
+    router: Router<S>,
+    sdk_routes: BTreeSet<(String, &'static str)>,
+}
+
+impl<S> SdkRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            router: Router::new(),
+            sdk_routes: BTreeSet::new(),
+        }
+    }
+
+    /// Serves a route which is not part of the sdk.
+    pub fn route(mut self, path: &str, method_router: MethodRouter<S>) -> Self {
+        self.router = self.router.route(path, method_router);
+        self
+    }
+
+    pub fn merge(mut self, other: SdkRouter<S>) -> Self {
+        self.router = self.router.merge(other.router);
+        self.sdk_routes.extend(other.sdk_routes);
+        self
+    }
+
+    /// Raises the body limit of every route served so far to `limit` bytes.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.router = self.router.layer(DefaultBodyLimit::max(limit));
+        self
+    }
+
+    /// The method and path of every [`SdkRoute`] served.
+    pub fn sdk_routes(&self) -> &BTreeSet<(String, &'static str)> {
+        &self.sdk_routes
+    }
+
+    pub fn into_router(self) -> Router<S> {
+        self.router
+    }
+}
+
 pub trait ApplySdkRoute<H, T, S> {
     fn sdk_route<X: SdkRoute>(self, handler: H) -> Self
     where
-        H: axum::handler::Handler<T, S>,
+        H: Handler<T, S>,
         T: 'static,
         S: Clone + Send + Sync + 'static;
 }
 
-impl<H, T, S> ApplySdkRoute<H, T, S> for Router<S>
+impl<H, T, S> ApplySdkRoute<H, T, S> for SdkRouter<S>
 where
-    H: axum::handler::Handler<T, S>,
+    H: Handler<T, S>,
     T: 'static,
     S: Clone + Send + Sync + 'static,
 {
-    fn sdk_route<X: SdkRoute>(self, handler: H) -> Self {
+    fn sdk_route<X: SdkRoute>(mut self, handler: H) -> Self {
+        self.sdk_routes
+            .insert((X::method().to_string(), X::route()));
+
         self.route(
-            X::route(),
+            &axum_route(X::route()),
             on(
                 X::method()
                     .try_into()
@@ -48,3 +106,52 @@ where
         )
     }
 }
+
+impl<S> Default for SdkRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Translates `{name}` path parameters of an sdk route into axum's `:name` captures.
+fn axum_route(route: &str) -> String {
+    route
+        .split('/')
+        .map(
+            |segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(parameter) => format!(":{parameter}"),
+                None => segment.to_string(),
+            },
+        )
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// Whether `matched_path`, the [`axum::extract::MatchedPath`] of a request, is the route `R`.
+pub fn is_sdk_route<R: SdkRoute>(matched_path: &str) -> bool {
+    axum_route(R::route()) == matched_path
+}
+
+/// Extracts the [`SdkRoute::PathParams`] of the route `R`.
+pub struct SdkPath<R: SdkRoute>(pub R::PathParams);
+
+#[axum::async_trait]
+impl<R, S> FromRequestParts<S> for SdkPath<R>
+where
+    R: SdkRoute,
+    R::PathParams: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = TicketsError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(params) = Path::<R::PathParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ParsingError::InvalidPathParameters(rejection.body_text()))?;
+
+        Ok(SdkPath(params))
+    }
+}
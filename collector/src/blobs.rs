@@ -0,0 +1,263 @@
+//! storage for attachment content, the metadata stays in postgres while the bytes live in
+//! the configured blob store
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, Response, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use errors::{MiscError, ParsingError, TicketsResult};
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlobStoreConfig {
+    /// Files below a directory on the collector's host.
+    Local { path: PathBuf },
+    /// Any S3 compatible object store, such as AWS S3 or MinIO.
+    S3(S3Config),
+}
+
+impl Default for BlobStoreConfig {
+    fn default() -> Self {
+        BlobStoreConfig::Local {
+            path: PathBuf::from("attachments"),
+        }
+    }
+}
+
+impl BlobStoreConfig {
+    pub fn build(self) -> TicketsResult<Arc<dyn BlobStore>> {
+        Ok(match self {
+            BlobStoreConfig::Local { path } => Arc::new(LocalBlobStore::new(path)),
+            BlobStoreConfig::S3(config) => Arc::new(S3BlobStore::new(config)?),
+        })
+    }
+}
+
+#[axum::async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> TicketsResult<()>;
+
+    async fn get(&self, key: &str) -> TicketsResult<Vec<u8>>;
+
+    /// Removes the blob, keys which do not exist are ignored.
+    async fn delete(&self, key: &str) -> TicketsResult<()>;
+}
+
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> TicketsResult<PathBuf> {
+        // keys are generated by the collector, anything escaping the root is refused regardless
+        if key
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+        {
+            return Err(ParsingError::InvalidRequest(format!(
+                "Invalid blob key `{key}`."
+            )))?;
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[axum::async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, _content_type: &str, bytes: Vec<u8>) -> TicketsResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // readers never observe a partially written blob
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, bytes).await?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> TicketsResult<Vec<u8>> {
+        Ok(tokio::fs::read(self.path(key)?).await?)
+    }
+
+    async fn delete(&self, key: &str) -> TicketsResult<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err)?,
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct S3Config {
+    /// Base url of the store, such as `https://s3.eu-west-1.amazonaws.com` or
+    /// `http://localhost:9000` for a local stand-in.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+pub struct S3BlobStore {
+    client: Client,
+    endpoint: Url,
+    config: S3Config,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3Config) -> TicketsResult<Self> {
+        Ok(Self {
+            client: Client::new(),
+            endpoint: Url::parse(&config.endpoint).map_err(ParsingError::from)?,
+            config,
+        })
+    }
+
+    /// Objects are addressed path-style, `{endpoint}/{bucket}/{key}`, which every S3
+    /// compatible store accepts.
+    fn url(&self, key: &str) -> TicketsResult<Url> {
+        let mut url = self.endpoint.clone();
+        url.path_segments_mut()
+            .map_err(|_| ParsingError::InvalidRequest("Invalid blob store endpoint.".to_string()))?
+            .pop_if_empty()
+            .push(&self.config.bucket)
+            .extend(key.split('/'));
+
+        Ok(url)
+    }
+
+    /// Sends the request signed with AWS signature version 4.
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        content_type: Option<&str>,
+        body: Vec<u8>,
+    ) -> TicketsResult<Response> {
+        let url = self.url(key)?;
+
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => Err(ParsingError::InvalidRequest(
+                "Blob store endpoint has no host.".to_string(),
+            ))?,
+        };
+
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &timestamp[..8];
+
+        // sorted by name as the canonical request requires
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", timestamp.clone()),
+        ];
+        if let Some(content_type) = content_type {
+            headers.push(("content-type", content_type.to_string()));
+        }
+        headers.sort();
+
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect::<String>();
+        let canonical_request = format!(
+            "{method}\n{}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+            url.path()
+        );
+
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request))
+        );
+
+        let signing_key = [date, &self.config.region, "s3", "aws4_request"]
+            .into_iter()
+            .fold(
+                format!("AWS4{}", self.config.secret_access_key).into_bytes(),
+                |key, part| hmac_sha256(&key, part.as_bytes()),
+            );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        let mut request = self.client.request(method, url).header(
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                self.config.access_key_id
+            ),
+        );
+
+        // reqwest derives the host header from the url itself
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+
+        Ok(request.body(body).send().await?)
+    }
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn unexpected_status(method: &str, key: &str, status: StatusCode) -> MiscError {
+    MiscError::BlobStore(format!("{method} of `{key}` failed with status {status}."))
+}
+
+#[axum::async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> TicketsResult<()> {
+        let response = self
+            .send(Method::PUT, key, Some(content_type), bytes)
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(unexpected_status("PUT", key, response.status()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> TicketsResult<Vec<u8>> {
+        let response = self.send(Method::GET, key, None, vec![]).await?;
+
+        if !response.status().is_success() {
+            return Err(unexpected_status("GET", key, response.status()))?;
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> TicketsResult<()> {
+        let response = self.send(Method::DELETE, key, None, vec![]).await?;
+
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(unexpected_status("DELETE", key, response.status()))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use errors::{AuthorizationError, TicketsResult};
+use sdk::routes::staff::{BlockCustomer, BlockedCustomers, UnblockCustomer};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<BlockCustomer>(block_customer::route_handler)
+            .sdk_route::<UnblockCustomer>(unblock_customer::route_handler)
+            .sdk_route::<BlockedCustomers>(blocked_customers::route_handler),
+    )
+}
+
+/// Fails with [`AuthorizationError::CustomerBlocked`] if the customer is blocked from the app.
+pub async fn ensure_not_blocked(
+    executor: impl PgExecutor<'_>,
+    app_id: Uuid,
+    customer_id: Uuid,
+) -> TicketsResult<()> {
+    let blocked = sqlx::query!(
+        "SELECT 1 AS blocked FROM customer_block WHERE app_id = $1 AND customer_id = $2",
+        &app_id,
+        &customer_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .is_some();
+
+    if blocked {
+        return Err(AuthorizationError::CustomerBlocked)?;
+    }
+
+    Ok(())
+}
+
+pub mod block_customer {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::consumer::Submitter;
+    use sdk::routes::staff::{AppPath, BlockCustomer, BlockCustomerBody, BlockedCustomer};
+
+    use crate::axum_ext::SdkPath;
+    use crate::customers::resolve_customer;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<BlockCustomer>,
+        Json(body): Json<BlockCustomerBody>,
+    ) -> TicketsResult<Json<BlockedCustomer>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let mut tx = state.pg_client.begin().await?;
+
+        // identities which never submitted a ticket are blocked ahead of their first one
+        let customer_id = resolve_customer(
+            &mut tx,
+            app_id,
+            &body.gateway,
+            &Submitter {
+                external_id: body.external_id,
+                display_name: None,
+            },
+        )
+        .await?;
+
+        let blocked = sqlx::query!(
+            r#"INSERT INTO customer_block (app_id, customer_id, reason, blocked_by) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (app_id, customer_id) DO UPDATE SET reason = excluded.reason
+                RETURNING reason, blocked_by, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!",
+                    (SELECT display_name FROM customer WHERE id = $2) AS display_name"#,
+            &app_id,
+            &customer_id,
+            body.reason,
+            user.user_id as i64
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Json(BlockedCustomer {
+            customer_id,
+            display_name: blocked.display_name,
+            reason: blocked.reason,
+            blocked_by: blocked.blocked_by as u64,
+            created_at: blocked.created_at,
+        }))
+    }
+}
+
+pub mod unblock_customer {
+    use axum::extract::State;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{CustomerPath, UnblockCustomer};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(CustomerPath {
+            app_id,
+            customer_id,
+        }): SdkPath<UnblockCustomer>,
+    ) -> TicketsResult<()> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let deleted = sqlx::query!(
+            "DELETE FROM customer_block WHERE app_id = $1 AND customer_id = $2",
+            &app_id,
+            &customer_id
+        )
+        .execute(&state.pg_client)
+        .await?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(sqlx::Error::RowNotFound)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub mod blocked_customers {
+    use axum::extract::{Query, State};
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, BlockedCustomer, BlockedCustomers};
+    use sdk::routes::{Page, PageQuery};
+
+    use crate::axum_ext::SdkPath;
+    use crate::pagination::{Cursor, PageRequest};
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<BlockedCustomers>,
+        Query(query): Query<PageQuery>,
+    ) -> TicketsResult<Json<Page<BlockedCustomer>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let page = PageRequest::new(query.cursor.as_deref(), query.limit)?;
+
+        let rows = sqlx::query!(
+            r#"SELECT customer_block.customer_id, customer.display_name, customer_block.reason,
+                    customer_block.blocked_by, customer_block.created_at AS cursor_created_at,
+                    EXTRACT(EPOCH FROM customer_block.created_at)::INT8 AS "created_at!"
+                FROM customer_block
+                JOIN customer ON customer.id = customer_block.customer_id
+                WHERE customer_block.app_id = $1
+                    AND ($2::TIMESTAMPTZ IS NULL OR (customer_block.created_at, customer_block.customer_id) < ($2, $3::UUID))
+                ORDER BY customer_block.created_at DESC, customer_block.customer_id DESC
+                LIMIT $4"#,
+            &app_id,
+            page.created_at(),
+            page.id(),
+            page.fetch_limit()
+        )
+        .fetch_all(&state.pg_client)
+        .await?;
+
+        let (rows, next_cursor) = page.page(rows, |row| Cursor {
+            created_at: row.cursor_created_at,
+            id: row.customer_id,
+        });
+
+        let items = rows
+            .into_iter()
+            .map(|row| BlockedCustomer {
+                customer_id: row.customer_id,
+                display_name: row.display_name,
+                reason: row.reason,
+                blocked_by: row.blocked_by as u64,
+                created_at: row.created_at,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(Page { items, next_cursor }))
+    }
+}
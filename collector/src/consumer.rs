@@ -1,23 +1,27 @@
-use axum::Router;
 use sdk::routes::consumer::*;
 
-use crate::axum_ext::ApplySdkRoute;
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
 use crate::GlobalState;
 
-pub fn extend_router(router: Router<GlobalState>) -> Router<GlobalState> {
-    router.merge(Router::new().sdk_route::<SubmitTicket>(submit_ticket::route_handler))
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(SdkRouter::new().sdk_route::<SubmitTicket>(submit_ticket::route_handler))
 }
 
 pub mod submit_ticket {
     use axum::extract::State;
     use axum::http::HeaderMap;
     use axum::Json;
-    use errors::{AuthorizationError, TicketsResult};
+    use errors::{AuthorizationError, MiscError, TicketsResult};
     use events::TicketSubmittedEvent;
     use sdk::routes::consumer::{SubmitTicketBody, SubmitTicketResponse};
+    use sdk::routes::staff::FilterAction;
     use uuid::Uuid;
 
     use crate::axum_ext::RequireHeaderFromHeaderMap;
+    use crate::blocks::ensure_not_blocked;
+    use crate::customers::resolve_customer;
+    use crate::filters::{filter_status, FilterChain};
+    use crate::rate_limit::hit_submission;
     use crate::GlobalState;
 
     pub(super) async fn route_handler(
@@ -46,25 +50,67 @@ pub mod submit_ticket {
 
         let ticket_id = Uuid::new_v4();
 
+        let mut tx = pg_client.begin().await?;
+
+        let customer_id = resolve_customer(&mut tx, app_id, &gateway, &body.submitter).await?;
+
+        ensure_not_blocked(&mut *tx, app_id, customer_id).await?;
+
+        let verdict = FilterChain::load(&state, &mut tx, app_id)
+            .await?
+            .evaluate(
+                &mut tx,
+                app_id,
+                &gateway,
+                &body.submitter,
+                customer_id,
+                &body.message,
+            )
+            .await?;
+
+        if let Some(verdict) = &verdict {
+            if verdict.action == FilterAction::Reject {
+                log::info!("Rejected ticket for app {app_id}: {}", verdict.reason);
+                return Err(MiscError::TicketRejected)?;
+            }
+        }
+
+        hit_submission(&state, app_id, &gateway, &body.submitter.external_id).await?;
+
         // insert the ticket
         sqlx::query!(
-            "INSERT INTO ticket (id, app_id, message, gateway) VALUES ($1, $2, $3, $4)",
+            "INSERT INTO ticket (id, app_id, message, gateway, customer_id, filter_status, filter_reason) \
+                VALUES ($1, $2, $3, $4, $5, $6, $7)",
             &ticket_id,
             &app_id,
             &body.message,
-            &gateway
+            &gateway,
+            &customer_id,
+            verdict.as_ref().map(|verdict| filter_status(verdict.action)),
+            verdict.as_ref().map(|verdict| verdict.reason.as_str())
         )
-        .execute(pg_client)
+        .execute(&mut *tx)
         .await?;
 
-        state.emitter.publish_tickets_event(
-            app_id,
-            TicketSubmittedEvent {
-                message: body.message,
-            }
-            .into(),
-        )?;
+        tx.commit().await?;
+
+        // quarantined tickets are published once approved
+        let quarantined =
+            matches!(&verdict, Some(verdict) if verdict.action == FilterAction::Quarantine);
+
+        if !quarantined {
+            state.emitter.publish_tickets_event(
+                app_id,
+                TicketSubmittedEvent {
+                    message: body.message,
+                }
+                .into(),
+            )?;
+        }
 
-        Ok(Json(SubmitTicketResponse { ticket_id }))
+        Ok(Json(SubmitTicketResponse {
+            ticket_id,
+            customer_id,
+        }))
     }
 }
@@ -0,0 +1,278 @@
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use errors::TicketsResult;
+use sdk::routes::consumer::Submitter;
+use sdk::routes::staff::{
+    Customer, CustomerIdentity, CustomerTickets, GetCustomer, LinkCustomerIdentity,
+};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<GetCustomer>(get_customer::route_handler)
+            .sdk_route::<CustomerTickets>(customer_tickets::route_handler)
+            .sdk_route::<LinkCustomerIdentity>(link_customer_identity::route_handler),
+    )
+}
+
+/// Finds the customer behind `submitter` on `gateway` of `app_id`, creating them on their first
+/// ticket.
+pub async fn resolve_customer(
+    tx: &mut Transaction<'_, Postgres>,
+    app_id: Uuid,
+    gateway: &str,
+    submitter: &Submitter,
+) -> TicketsResult<Uuid> {
+    if let Some(customer_id) =
+        find_customer(&mut **tx, app_id, gateway, &submitter.external_id).await?
+    {
+        return Ok(customer_id);
+    }
+
+    let customer_id = Uuid::new_v4();
+
+    sqlx::query!(
+        "INSERT INTO customer (id, app_id, display_name) VALUES ($1, $2, $3)",
+        &customer_id,
+        &app_id,
+        submitter.display_name
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    // a concurrent first ticket of the same customer waits on the identity and, once the other
+    // transaction committed, inserts nothing and reads the customer created by it instead
+    let inserted = sqlx::query!(
+        "INSERT INTO customer_identity (app_id, gateway, external_id, customer_id, display_name) \
+            VALUES ($1, $2, $3, $4, $5) \
+            ON CONFLICT (app_id, gateway, external_id) DO NOTHING",
+        &app_id,
+        gateway,
+        &submitter.external_id,
+        &customer_id,
+        submitter.display_name
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    if inserted == 1 {
+        return Ok(customer_id);
+    }
+
+    sqlx::query!("DELETE FROM customer WHERE id = $1", &customer_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let existing = find_customer(&mut **tx, app_id, gateway, &submitter.external_id).await?;
+    Ok(existing.ok_or(sqlx::Error::RowNotFound)?)
+}
+
+/// The customer of `app_id` known by `external_id` on `gateway`.
+pub async fn find_customer<'c, E>(
+    executor: E,
+    app_id: Uuid,
+    gateway: &str,
+    external_id: &str,
+) -> TicketsResult<Option<Uuid>>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    Ok(sqlx::query!(
+        "SELECT customer_id FROM customer_identity \
+            WHERE app_id = $1 AND gateway = $2 AND external_id = $3",
+        &app_id,
+        gateway,
+        external_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .map(|record| record.customer_id))
+}
+
+/// Loads the customer of `app_id`, failing with not found for customers of other apps.
+async fn load_customer(
+    state: &GlobalState,
+    app_id: Uuid,
+    customer_id: Uuid,
+) -> TicketsResult<Customer> {
+    let pg_client = &state.pg_client;
+
+    let customer = sqlx::query!(
+        "SELECT id, display_name FROM customer WHERE id = $1 AND app_id = $2",
+        &customer_id,
+        &app_id
+    )
+    .fetch_one(pg_client)
+    .await?;
+
+    let identities = sqlx::query_as!(
+        CustomerIdentity,
+        "SELECT gateway, external_id, display_name FROM customer_identity \
+            WHERE customer_id = $1 AND app_id = $2 ORDER BY created_at",
+        &customer_id,
+        &app_id
+    )
+    .fetch_all(pg_client)
+    .await?;
+
+    Ok(Customer {
+        id: customer.id,
+        display_name: customer.display_name,
+        identities,
+    })
+}
+
+pub mod get_customer {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{Customer, CustomerPath, GetCustomer};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(CustomerPath {
+            app_id,
+            customer_id,
+        }): SdkPath<GetCustomer>,
+    ) -> TicketsResult<Json<Customer>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        Ok(Json(
+            super::load_customer(&state, app_id, customer_id).await?,
+        ))
+    }
+}
+
+pub mod customer_tickets {
+    use axum::extract::{Query, State};
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{CustomerPath, CustomerTickets, TicketSummary};
+    use sdk::routes::{Page, PageQuery};
+
+    use crate::axum_ext::SdkPath;
+    use crate::pagination::{Cursor, PageRequest};
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(CustomerPath {
+            app_id,
+            customer_id,
+        }): SdkPath<CustomerTickets>,
+        Query(query): Query<PageQuery>,
+    ) -> TicketsResult<Json<Page<TicketSummary>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let page = PageRequest::new(query.cursor.as_deref(), query.limit)?;
+
+        let rows = sqlx::query!(
+            r#"SELECT id, gateway, message, created_at AS cursor_created_at,
+                    EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM ticket
+                WHERE app_id = $1 AND customer_id = $2
+                    AND ($3::TIMESTAMPTZ IS NULL OR (created_at, id) < ($3, $4::UUID))
+                ORDER BY created_at DESC, id DESC
+                LIMIT $5"#,
+            &app_id,
+            &customer_id,
+            page.created_at(),
+            page.id(),
+            page.fetch_limit()
+        )
+        .fetch_all(&state.pg_client)
+        .await?;
+
+        let (rows, next_cursor) = page.page(rows, |row| Cursor {
+            created_at: row.cursor_created_at,
+            id: row.id,
+        });
+
+        let items = rows
+            .into_iter()
+            .map(|row| TicketSummary {
+                ticket_id: row.id,
+                gateway: row.gateway,
+                message: row.message,
+                created_at: row.created_at,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(Page { items, next_cursor }))
+    }
+}
+
+pub mod link_customer_identity {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::staff::{Customer, CustomerIdentity, CustomerPath, LinkCustomerIdentity};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(CustomerPath {
+            app_id,
+            customer_id,
+        }): SdkPath<LinkCustomerIdentity>,
+        Json(body): Json<CustomerIdentity>,
+    ) -> TicketsResult<Json<Customer>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        // staff may only link customers they can see
+        super::load_customer(&state, app_id, customer_id).await?;
+
+        // identities are scoped to the app, another app's customers are never touched
+        sqlx::query!(
+            "INSERT INTO customer_identity (app_id, gateway, external_id, customer_id, display_name) \
+                VALUES ($1, $2, $3, $4, $5) \
+                ON CONFLICT (app_id, gateway, external_id) DO UPDATE \
+                SET display_name = COALESCE(excluded.display_name, customer_identity.display_name) \
+                WHERE customer_identity.customer_id = excluded.customer_id \
+                RETURNING customer_id",
+            &app_id,
+            &body.gateway,
+            &body.external_id,
+            &customer_id,
+            body.display_name
+        )
+        .fetch_optional(&state.pg_client)
+        .await?
+        .ok_or(AuthorizationError::IdentityLinkedToAnotherCustomer)?;
+
+        Ok(Json(
+            super::load_customer(&state, app_id, customer_id).await?,
+        ))
+    }
+}
@@ -0,0 +1,21 @@
+use axum::routing::get;
+
+use crate::axum_ext::SdkRouter;
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(SdkRouter::new().route("/openapi.json", get(openapi::route_handler)))
+}
+
+pub mod openapi {
+    use std::sync::OnceLock;
+
+    use axum::Json;
+    use serde_json::Value;
+
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+
+    pub(super) async fn route_handler() -> Json<Value> {
+        Json(DOCUMENT.get_or_init(sdk::openapi::document).clone())
+    }
+}
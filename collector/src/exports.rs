@@ -0,0 +1,625 @@
+//! bulk exports of an app's data for external tooling, streamed in pages so large apps never
+//! sit in memory, and imports of historical tickets from other systems
+
+use std::future::Future;
+
+use axum::body::Body;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use futures::{stream, StreamExt};
+
+use errors::TicketsResult;
+use sdk::routes::staff::{
+    ExportFormat, ExportMembers, ExportMessages, ExportTickets, ExportedMember, ExportedMessage,
+    ExportedTicket, ImportTickets,
+};
+
+use crate::axum_ext::{is_sdk_route, ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+// rows fetched per query while streaming an export
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Tickets accepted by a single import request.
+pub const MAX_IMPORT_BATCH: usize = 1000;
+
+/// Body limit of imports, fitting a full batch of long tickets.
+const IMPORT_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router
+        .merge(
+            SdkRouter::new()
+                .sdk_route::<ExportTickets>(export_tickets::route_handler)
+                .sdk_route::<ExportMessages>(export_messages::route_handler)
+                .sdk_route::<ExportMembers>(export_members::route_handler),
+        )
+        .merge(
+            SdkRouter::new()
+                .sdk_route::<ImportTickets>(import_tickets::route_handler)
+                .body_limit(IMPORT_BODY_LIMIT),
+        )
+}
+
+/// The raised body limit of the route matched as `matched_path`, if it is the import route.
+pub(crate) fn body_limit(matched_path: &str) -> Option<usize> {
+    is_sdk_route::<ImportTickets>(matched_path).then_some(IMPORT_BODY_LIMIT)
+}
+
+/// A row of an export, pages continue after the cursor of the previous page's last row.
+trait ExportRow: serde::Serialize + Send + 'static {
+    type Cursor: Send + 'static;
+
+    const CSV_HEADER: &'static [&'static str];
+
+    fn csv_record(&self) -> Vec<String>;
+
+    fn cursor(&self) -> Self::Cursor;
+}
+
+impl ExportRow for ExportedTicket {
+    type Cursor = uuid::Uuid;
+
+    const CSV_HEADER: &'static [&'static str] = &[
+        "ticket_id",
+        "gateway",
+        "status",
+        "customer_id",
+        "message",
+        "filter_status",
+        "created_at",
+    ];
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.ticket_id.to_string(),
+            self.gateway.clone(),
+            self.status.to_string(),
+            optional(self.customer_id),
+            self.message.clone(),
+            optional(self.filter_status.as_ref()),
+            self.created_at.to_string(),
+        ]
+    }
+
+    fn cursor(&self) -> Self::Cursor {
+        self.ticket_id
+    }
+}
+
+impl ExportRow for ExportedMessage {
+    type Cursor = uuid::Uuid;
+
+    const CSV_HEADER: &'static [&'static str] = &[
+        "message_id",
+        "ticket_id",
+        "customer_id",
+        "author_id",
+        "body",
+        "created_at",
+    ];
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.message_id.to_string(),
+            self.ticket_id.to_string(),
+            optional(self.customer_id),
+            optional(self.author_id),
+            self.body.clone(),
+            self.created_at.to_string(),
+        ]
+    }
+
+    fn cursor(&self) -> Self::Cursor {
+        self.message_id
+    }
+}
+
+impl ExportRow for ExportedMember {
+    type Cursor = u64;
+
+    const CSV_HEADER: &'static [&'static str] = &["user_id", "role", "display_name", "created_at"];
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.user_id.to_string(),
+            self.role.to_string(),
+            optional(self.display_name.as_ref()),
+            self.created_at.to_string(),
+        ]
+    }
+
+    fn cursor(&self) -> Self::Cursor {
+        self.user_id
+    }
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Quotes fields containing separators, quotes or line breaks as RFC 4180 describes. Fields
+/// spreadsheets would evaluate as formulas are prefixed with `'` so they are kept as text.
+fn csv_line<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = fields
+        .into_iter()
+        .map(|field| {
+            let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                format!("'{field}")
+            } else {
+                field.to_string()
+            };
+
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn encode_rows<R: ExportRow>(format: ExportFormat, rows: &[R]) -> TicketsResult<Vec<u8>> {
+    let mut out = Vec::new();
+
+    for row in rows {
+        match format {
+            ExportFormat::Csv => {
+                let record = row.csv_record();
+                out.extend_from_slice(csv_line(record.iter().map(String::as_str)).as_bytes());
+            }
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(&mut out, row)?;
+                out.push(b'\n');
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Streams every page `fetch_page` returns as a downloadable file, the first page is fetched
+/// without a cursor and the export ends with the first page that is not full.
+fn export_response<R, F, Fut>(format: ExportFormat, file_stem: String, fetch_page: F) -> Response
+where
+    R: ExportRow,
+    F: FnMut(Option<R::Cursor>) -> Fut + Send + 'static,
+    Fut: Future<Output = TicketsResult<Vec<R>>> + Send,
+{
+    let header = match format {
+        ExportFormat::Csv => csv_line(R::CSV_HEADER.iter().copied()).into_bytes(),
+        ExportFormat::Jsonl => vec![],
+    };
+
+    let pages = stream::unfold(
+        (fetch_page, Some(None)),
+        move |(mut fetch_page, cursor)| async move {
+            let rows = match fetch_page(cursor?).await {
+                Ok(rows) => rows,
+                Err(err) => {
+                    // the response has started, all that is left is to cut it short
+                    log::error!("Export of {format:?} failed: {err}");
+                    return Some((Err(err), (fetch_page, None)));
+                }
+            };
+
+            let next_cursor = match rows.last() {
+                Some(last) if rows.len() as i64 == EXPORT_PAGE_SIZE => Some(Some(last.cursor())),
+                _ => None,
+            };
+
+            Some((encode_rows(format, &rows), (fetch_page, next_cursor)))
+        },
+    );
+
+    let body = stream::once(async move { TicketsResult::Ok(header) }).chain(pages);
+
+    let (extension, content_type) = match format {
+        ExportFormat::Csv => ("csv", "text/csv; charset=utf-8"),
+        ExportFormat::Jsonl => ("jsonl", "application/x-ndjson"),
+    };
+
+    // the form `FileData::content_disposition` writes, export file names need no encoding
+    (
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename*=UTF-8''{file_stem}.{extension}"),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+/// The optional bounds of an export as arguments of `to_timestamp`.
+fn date_range(from: Option<i64>, to: Option<i64>) -> (Option<f64>, Option<f64>) {
+    (from.map(|from| from as f64), to.map(|to| to as f64))
+}
+
+pub mod export_tickets {
+    use axum::extract::{Query, State};
+    use axum::response::Response;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, ExportQuery, ExportTickets, ExportedTicket, TicketStatus};
+
+    use super::EXPORT_PAGE_SIZE;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ExportTickets>,
+        Query(query): Query<ExportQuery>,
+    ) -> TicketsResult<Response> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let (from, to) = super::date_range(query.from, query.to);
+        let pg_client = state.pg_client.clone();
+
+        Ok(super::export_response(
+            query.format,
+            format!("tickets-{app_id}"),
+            move |cursor: Option<Uuid>| {
+                let pg_client = pg_client.clone();
+                async move {
+                    sqlx::query!(
+                        r#"SELECT id, gateway, status, customer_id, message, filter_status,
+                                EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                            FROM ticket
+                            WHERE app_id = $1
+                                AND ($2::FLOAT8 IS NULL OR created_at >= to_timestamp($2))
+                                AND ($3::FLOAT8 IS NULL OR created_at < to_timestamp($3))
+                                AND ($4::UUID IS NULL OR (created_at, id) > (SELECT created_at, id FROM ticket WHERE id = $4 AND app_id = $1))
+                            ORDER BY created_at, id
+                            LIMIT $5"#,
+                        &app_id,
+                        from,
+                        to,
+                        cursor,
+                        EXPORT_PAGE_SIZE
+                    )
+                    .fetch_all(&pg_client)
+                    .await?
+                    .into_iter()
+                    .map(|record| {
+                        Ok(ExportedTicket {
+                            ticket_id: record.id,
+                            gateway: record.gateway,
+                            status: TicketStatus::try_from(record.status)?,
+                            customer_id: record.customer_id,
+                            message: record.message,
+                            filter_status: record.filter_status,
+                            created_at: record.created_at,
+                        })
+                    })
+                    .collect()
+                }
+            },
+        ))
+    }
+}
+
+pub mod export_messages {
+    use axum::extract::{Query, State};
+    use axum::response::Response;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, ExportMessages, ExportQuery, ExportedMessage};
+
+    use super::EXPORT_PAGE_SIZE;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ExportMessages>,
+        Query(query): Query<ExportQuery>,
+    ) -> TicketsResult<Response> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let (from, to) = super::date_range(query.from, query.to);
+        let pg_client = state.pg_client.clone();
+
+        Ok(super::export_response(
+            query.format,
+            format!("messages-{app_id}"),
+            move |cursor: Option<Uuid>| {
+                let pg_client = pg_client.clone();
+                async move {
+                    let messages = sqlx::query!(
+                        r#"SELECT id, ticket_id, customer_id, author_id, body,
+                                EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                            FROM ticket_message
+                            WHERE app_id = $1
+                                AND ($2::FLOAT8 IS NULL OR created_at >= to_timestamp($2))
+                                AND ($3::FLOAT8 IS NULL OR created_at < to_timestamp($3))
+                                AND ($4::UUID IS NULL OR (created_at, id) > (SELECT created_at, id FROM ticket_message WHERE id = $4 AND app_id = $1))
+                            ORDER BY created_at, id
+                            LIMIT $5"#,
+                        &app_id,
+                        from,
+                        to,
+                        cursor,
+                        EXPORT_PAGE_SIZE
+                    )
+                    .fetch_all(&pg_client)
+                    .await?
+                    .into_iter()
+                    .map(|record| ExportedMessage {
+                        message_id: record.id,
+                        ticket_id: record.ticket_id,
+                        customer_id: record.customer_id,
+                        author_id: record.author_id.map(|user_id| user_id as u64),
+                        body: record.body,
+                        created_at: record.created_at,
+                    })
+                    .collect();
+
+                    Ok(messages)
+                }
+            },
+        ))
+    }
+}
+
+pub mod export_members {
+    use axum::extract::{Query, State};
+    use axum::response::Response;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, ExportMembers, ExportQuery, ExportedMember};
+
+    use super::EXPORT_PAGE_SIZE;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ExportMembers>,
+        Query(query): Query<ExportQuery>,
+    ) -> TicketsResult<Response> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let (from, to) = super::date_range(query.from, query.to);
+        let pg_client = state.pg_client.clone();
+
+        Ok(super::export_response(
+            query.format,
+            format!("members-{app_id}"),
+            move |cursor: Option<u64>| {
+                let pg_client = pg_client.clone();
+                async move {
+                    sqlx::query!(
+                        r#"SELECT user_app.user_id, user_app.role, tt_user.display_name,
+                                EXTRACT(EPOCH FROM user_app.created_at)::INT8 AS "created_at!"
+                            FROM user_app JOIN tt_user ON tt_user.id = user_app.user_id
+                            WHERE user_app.app_id = $1
+                                AND ($2::FLOAT8 IS NULL OR user_app.created_at >= to_timestamp($2))
+                                AND ($3::FLOAT8 IS NULL OR user_app.created_at < to_timestamp($3))
+                                AND ($4::INT8 IS NULL OR user_app.user_id > $4)
+                            ORDER BY user_app.user_id
+                            LIMIT $5"#,
+                        &app_id,
+                        from,
+                        to,
+                        cursor.map(|user_id| user_id as i64),
+                        EXPORT_PAGE_SIZE
+                    )
+                    .fetch_all(&pg_client)
+                    .await?
+                    .into_iter()
+                    .map(|record| {
+                        Ok(ExportedMember {
+                            user_id: record.user_id as u64,
+                            role: UserRole::try_from(record.role)?,
+                            display_name: record.display_name,
+                            created_at: record.created_at,
+                        })
+                    })
+                    .collect()
+                }
+            },
+        ))
+    }
+}
+
+pub mod import_tickets {
+    use std::collections::HashSet;
+
+    use axum::extract::State;
+    use axum::Json;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{AppPath, ImportTickets, ImportTicketsBody, ImportTicketsResponse};
+
+    use super::MAX_IMPORT_BATCH;
+    use crate::axum_ext::SdkPath;
+    use crate::customers::resolve_customer;
+    use crate::GlobalState;
+
+    /// Refuses the batch with the position of the first invalid ticket.
+    fn validate(body: &ImportTicketsBody) -> TicketsResult<()> {
+        if body.tickets.len() > MAX_IMPORT_BATCH {
+            return Err(ParsingError::InvalidRequest(format!(
+                "Imports are limited to {MAX_IMPORT_BATCH} tickets per request."
+            )))?;
+        }
+
+        let now = Utc::now().timestamp();
+        let mut ticket_ids = HashSet::with_capacity(body.tickets.len());
+
+        for (index, ticket) in body.tickets.iter().enumerate() {
+            let problem = if !ticket_ids.insert(ticket.ticket_id) {
+                Some("repeats a ticket id of the batch")
+            } else if ticket.message.trim().is_empty() {
+                Some("has no message")
+            } else if ticket.gateway.is_empty() || ticket.gateway.contains(char::is_whitespace) {
+                Some("has an invalid gateway name")
+            } else if ticket.created_at <= 0 || ticket.created_at > now {
+                Some("was not created in the past")
+            } else if ticket
+                .submitter
+                .as_ref()
+                .is_some_and(|submitter| submitter.external_id.is_empty())
+            {
+                Some("has a submitter without an external id")
+            } else {
+                None
+            };
+
+            if let Some(problem) = problem {
+                return Err(ParsingError::InvalidRequest(format!(
+                    "Ticket {index} ({}) {problem}.",
+                    ticket.ticket_id
+                )))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ImportTickets>,
+        Json(body): Json<ImportTicketsBody>,
+    ) -> TicketsResult<Json<ImportTicketsResponse>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        validate(&body)?;
+
+        let ticket_ids = body
+            .tickets
+            .iter()
+            .map(|ticket| ticket.ticket_id)
+            .collect::<Vec<Uuid>>();
+
+        let mut tx = state.pg_client.begin().await?;
+
+        let existing = sqlx::query!(
+            "SELECT id, app_id, imported_at IS NOT NULL AS \"imported!\" \
+                FROM ticket WHERE id = Any($1) FOR UPDATE",
+            &ticket_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for record in &existing {
+            let problem = if record.app_id != app_id {
+                "belongs to another app"
+            } else if !record.imported {
+                "was not imported and cannot be replaced"
+            } else {
+                continue;
+            };
+
+            return Err(ParsingError::InvalidRequest(format!(
+                "Ticket {} {problem}.",
+                record.id
+            )))?;
+        }
+
+        for ticket in &body.tickets {
+            let customer_id = match &ticket.submitter {
+                Some(submitter) => {
+                    Some(resolve_customer(&mut tx, app_id, &ticket.gateway, submitter).await?)
+                }
+                None => None,
+            };
+
+            // the original submission is kept as it was, filters never saw it. only tickets of
+            // earlier imports are replaced, live tickets were refused above
+            sqlx::query!(
+                "INSERT INTO ticket \
+                    (id, app_id, message, gateway, customer_id, status, created_at, imported_at) \
+                    VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7), NOW()) \
+                    ON CONFLICT (id) DO UPDATE \
+                    SET message = excluded.message, gateway = excluded.gateway, \
+                        customer_id = excluded.customer_id, status = excluded.status, \
+                        created_at = excluded.created_at, imported_at = excluded.imported_at \
+                    WHERE ticket.app_id = excluded.app_id AND ticket.imported_at IS NOT NULL",
+                &ticket.ticket_id,
+                &app_id,
+                &ticket.message,
+                &ticket.gateway,
+                customer_id,
+                ticket.status.to_string(),
+                ticket.created_at as f64
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        let updated = existing.len() as u32;
+
+        Ok(Json(ImportTicketsResponse {
+            created: body.tickets.len() as u32 - updated,
+            updated,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_kept() {
+        assert_eq!(csv_line(["a", "", "b c"]), "a,,b c\r\n");
+    }
+
+    #[test]
+    fn special_fields_are_quoted() {
+        assert_eq!(
+            csv_line(["a,b", "say \"hi\"", "two\nlines", "cr\rlf"]),
+            "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\"cr\rlf\"\r\n"
+        );
+    }
+
+    #[test]
+    fn formulas_are_kept_as_text() {
+        assert_eq!(
+            csv_line(["=1+1", "+1", "-1", "@SUM(A1)", "\tx", "a=b"]),
+            "'=1+1,'+1,'-1,'@SUM(A1),'\tx,a=b\r\n"
+        );
+        assert_eq!(
+            csv_line(["=HYPERLINK(\"x\",\"y\")"]),
+            "\"'=HYPERLINK(\"\"x\"\",\"\"y\"\")\"\r\n"
+        );
+    }
+}
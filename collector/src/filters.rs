@@ -0,0 +1,712 @@
+//! pre-submission filter chain, every ticket is checked against its app's rules and the
+//! filters registered on the state before it is stored
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use regex::{Regex, RegexBuilder};
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use errors::{ParsingError, TicketsResult};
+use sdk::routes::consumer::Submitter;
+use sdk::routes::staff::{
+    CreateFilterRule, DeleteFilterRule, FilterAction, FilterRule, FilterRuleKind, ListFilterRules,
+    StoredFilterRule,
+};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+// compiled size of a single regex rule, patterns beyond it are refused
+const MAX_REGEX_SIZE: usize = 256 * 1024;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<ListFilterRules>(list_filter_rules::route_handler)
+            .sdk_route::<CreateFilterRule>(create_filter_rule::route_handler)
+            .sdk_route::<DeleteFilterRule>(delete_filter_rule::route_handler),
+    )
+}
+
+/// A ticket about to be stored.
+pub struct FilterInput<'a> {
+    pub app_id: Uuid,
+    pub gateway: &'a str,
+    pub submitter: &'a Submitter,
+    pub customer_id: Uuid,
+    pub message: &'a str,
+    /// Messages the customer submitted to the app within the chain's lookback, newest first.
+    pub recent: &'a [RecentTicket],
+}
+
+pub struct RecentTicket {
+    pub message: String,
+    /// Seconds since the ticket was submitted.
+    pub age_seconds: i64,
+}
+
+#[derive(Debug)]
+pub struct FilterVerdict {
+    pub action: FilterAction,
+    pub reason: String,
+}
+
+pub trait TicketFilter: Send + Sync {
+    fn evaluate(&self, input: &FilterInput<'_>) -> Option<FilterVerdict>;
+
+    /// How far back the customer's earlier tickets are needed in [`FilterInput::recent`].
+    fn lookback(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Matches a message the customer already submitted within `window`, ignoring case and
+/// whitespace.
+pub struct DuplicateFilter {
+    pub window: Duration,
+    pub action: FilterAction,
+}
+
+impl TicketFilter for DuplicateFilter {
+    fn evaluate(&self, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        let message = normalize(input.message);
+        let window = self.window.as_secs() as i64;
+
+        input
+            .recent
+            .iter()
+            .any(|ticket| ticket.age_seconds <= window && normalize(&ticket.message) == message)
+            .then(|| FilterVerdict {
+                action: self.action,
+                reason: "Duplicate of a recent ticket.".to_string(),
+            })
+    }
+
+    fn lookback(&self) -> Option<Duration> {
+        Some(self.window)
+    }
+}
+
+/// Matches messages containing `word`, whole words only unless it spans several words.
+pub struct BannedWordFilter {
+    word: String,
+    action: FilterAction,
+}
+
+impl BannedWordFilter {
+    pub fn new(word: &str, action: FilterAction) -> Self {
+        Self {
+            word: word.trim().to_lowercase(),
+            action,
+        }
+    }
+}
+
+impl TicketFilter for BannedWordFilter {
+    fn evaluate(&self, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        let message = input.message.to_lowercase();
+
+        let matched = if self.word.chars().all(char::is_alphanumeric) {
+            message
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| word == self.word)
+        } else {
+            message.contains(&self.word)
+        };
+
+        matched.then(|| FilterVerdict {
+            action: self.action,
+            reason: format!("Contains the banned word \"{}\".", self.word),
+        })
+    }
+}
+
+pub struct RegexFilter {
+    regex: Regex,
+    action: FilterAction,
+}
+
+impl RegexFilter {
+    pub fn new(pattern: &str, action: FilterAction) -> TicketsResult<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .size_limit(MAX_REGEX_SIZE)
+            .build()
+            .map_err(|err| ParsingError::InvalidRequest(err.to_string()))?;
+
+        Ok(Self { regex, action })
+    }
+}
+
+impl TicketFilter for RegexFilter {
+    fn evaluate(&self, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        self.regex.is_match(input.message).then(|| FilterVerdict {
+            action: self.action,
+            reason: format!("Matches the pattern \"{}\".", self.regex.as_str()),
+        })
+    }
+}
+
+pub struct LinkLimitFilter {
+    pub max_links: u32,
+    pub action: FilterAction,
+}
+
+impl TicketFilter for LinkLimitFilter {
+    fn evaluate(&self, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        let links = input
+            .message
+            .split_whitespace()
+            .filter(|word| {
+                let word = word.to_lowercase();
+                word.contains("://") || word.starts_with("www.")
+            })
+            .count();
+
+        (links > self.max_links as usize).then(|| FilterVerdict {
+            action: self.action,
+            reason: format!(
+                "Contains {links} links, at most {} are allowed.",
+                self.max_links
+            ),
+        })
+    }
+}
+
+pub struct BlockedSubmitterFilter {
+    pub gateway: String,
+    pub external_id: String,
+    pub action: FilterAction,
+}
+
+impl TicketFilter for BlockedSubmitterFilter {
+    fn evaluate(&self, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        (input.gateway == self.gateway && input.submitter.external_id == self.external_id).then(
+            || FilterVerdict {
+                action: self.action,
+                reason: "Submitted by a blocked submitter.".to_string(),
+            },
+        )
+    }
+}
+
+fn normalize(message: &str) -> String {
+    message
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Builds the built-in filter for `rule`, failing if its parameters are invalid.
+pub fn build_filter(rule: &FilterRule) -> TicketsResult<Arc<dyn TicketFilter>> {
+    let action = rule.action;
+
+    Ok(match &rule.kind {
+        FilterRuleKind::Duplicate { window_seconds } => Arc::new(DuplicateFilter {
+            window: Duration::from_secs(*window_seconds as u64),
+            action,
+        }),
+        FilterRuleKind::BannedWord { word } => {
+            if word.trim().is_empty() {
+                return Err(ParsingError::InvalidRequest(
+                    "Banned words cannot be empty.".to_string(),
+                ))?;
+            }
+            Arc::new(BannedWordFilter::new(word, action))
+        }
+        FilterRuleKind::Regex { pattern } => Arc::new(RegexFilter::new(pattern, action)?),
+        FilterRuleKind::LinkLimit { max_links } => Arc::new(LinkLimitFilter {
+            max_links: *max_links,
+            action,
+        }),
+        FilterRuleKind::BlockedSubmitter {
+            gateway,
+            external_id,
+        } => Arc::new(BlockedSubmitterFilter {
+            gateway: gateway.clone(),
+            external_id: external_id.clone(),
+            action,
+        }),
+    })
+}
+
+/// Name stored in `ticket.filter_status` for tickets matched with `action`.
+pub fn filter_status(action: FilterAction) -> &'static str {
+    match action {
+        FilterAction::Flag => "flagged",
+        FilterAction::Quarantine => "quarantined",
+        FilterAction::Reject => "rejected",
+    }
+}
+
+fn action_name(action: FilterAction) -> &'static str {
+    match action {
+        FilterAction::Flag => "flag",
+        FilterAction::Quarantine => "quarantine",
+        FilterAction::Reject => "reject",
+    }
+}
+
+fn parse_action(action: &str) -> Option<FilterAction> {
+    Some(match action {
+        "flag" => FilterAction::Flag,
+        "quarantine" => FilterAction::Quarantine,
+        "reject" => FilterAction::Reject,
+        _ => return None,
+    })
+}
+
+struct RuleRow {
+    id: Uuid,
+    kind: String,
+    action: String,
+    pattern: Option<String>,
+    threshold: Option<i32>,
+    gateway: Option<String>,
+}
+
+impl RuleRow {
+    fn into_rule(self) -> Option<StoredFilterRule> {
+        let kind = match self.kind.as_str() {
+            "duplicate" => FilterRuleKind::Duplicate {
+                window_seconds: u32::try_from(self.threshold?).ok()?,
+            },
+            "banned_word" => FilterRuleKind::BannedWord {
+                word: self.pattern?,
+            },
+            "regex" => FilterRuleKind::Regex {
+                pattern: self.pattern?,
+            },
+            "link_limit" => FilterRuleKind::LinkLimit {
+                max_links: u32::try_from(self.threshold?).ok()?,
+            },
+            "blocked_submitter" => FilterRuleKind::BlockedSubmitter {
+                gateway: self.gateway?,
+                external_id: self.pattern?,
+            },
+            _ => return None,
+        };
+
+        Some(StoredFilterRule {
+            id: self.id,
+            rule: FilterRule {
+                kind,
+                action: parse_action(&self.action)?,
+            },
+        })
+    }
+}
+
+async fn load_rules(
+    executor: impl sqlx::PgExecutor<'_>,
+    app_id: Uuid,
+) -> TicketsResult<Vec<StoredFilterRule>> {
+    let rows = sqlx::query_as!(
+        RuleRow,
+        "SELECT id, kind, action, pattern, threshold, gateway FROM ticket_filter_rule \
+            WHERE app_id = $1 ORDER BY created_at, id",
+        &app_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let id = row.id;
+            let rule = row.into_rule();
+            if rule.is_none() {
+                log::warn!("Skipping malformed ticket filter rule {id}");
+            }
+            rule
+        })
+        .collect())
+}
+
+/// Filters applying to one app's submissions.
+pub struct FilterChain {
+    filters: Vec<Arc<dyn TicketFilter>>,
+}
+
+impl FilterChain {
+    /// The app's rules followed by the filters registered on the state.
+    pub async fn load(
+        state: &GlobalState,
+        tx: &mut Transaction<'_, Postgres>,
+        app_id: Uuid,
+    ) -> TicketsResult<Self> {
+        let mut filters = Vec::new();
+
+        for rule in load_rules(&mut **tx, app_id).await? {
+            match build_filter(&rule.rule) {
+                Ok(filter) => filters.push(filter),
+                Err(err) => log::warn!("Skipping ticket filter rule {}: {err}", rule.id),
+            }
+        }
+
+        filters.extend(state.ticket_filters.iter().cloned());
+
+        Ok(Self { filters })
+    }
+
+    /// Runs every filter, the most severe action wins and the reasons of all matches are kept.
+    pub async fn evaluate(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        app_id: Uuid,
+        gateway: &str,
+        submitter: &Submitter,
+        customer_id: Uuid,
+        message: &str,
+    ) -> TicketsResult<Option<FilterVerdict>> {
+        if self.filters.is_empty() {
+            return Ok(None);
+        }
+
+        let recent = match self.filters.iter().filter_map(|f| f.lookback()).max() {
+            Some(lookback) => sqlx::query_as!(
+                RecentTicket,
+                r#"SELECT message, EXTRACT(EPOCH FROM NOW() - created_at)::INT8 AS "age_seconds!"
+                        FROM ticket
+                        WHERE app_id = $1 AND customer_id = $2
+                            AND created_at > NOW() - $3 * INTERVAL '1 second'
+                        ORDER BY created_at DESC"#,
+                &app_id,
+                &customer_id,
+                lookback.as_secs() as i64
+            )
+            .fetch_all(&mut **tx)
+            .await?,
+            None => Vec::new(),
+        };
+
+        Ok(self.verdict(&FilterInput {
+            app_id,
+            gateway,
+            submitter,
+            customer_id,
+            message,
+            recent: &recent,
+        }))
+    }
+
+    /// The merged verdict of every filter matching `input`.
+    fn verdict(&self, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        let verdicts = self
+            .filters
+            .iter()
+            .filter_map(|filter| filter.evaluate(input))
+            .collect::<Vec<_>>();
+
+        let action = verdicts.iter().map(|verdict| verdict.action).max()?;
+
+        Some(FilterVerdict {
+            action,
+            reason: verdicts
+                .into_iter()
+                .map(|verdict| verdict.reason)
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+    }
+}
+
+pub mod list_filter_rules {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, ListFilterRules, StoredFilterRule};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ListFilterRules>,
+    ) -> TicketsResult<Json<Vec<StoredFilterRule>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        Ok(Json(super::load_rules(&state.pg_client, app_id).await?))
+    }
+}
+
+pub mod create_filter_rule {
+    use axum::extract::State;
+    use axum::Json;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{
+        AppPath, CreateFilterRule, FilterRule, FilterRuleKind, StoredFilterRule,
+    };
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    fn threshold(value: u32) -> Result<i32, ParsingError> {
+        i32::try_from(value).map_err(|_| {
+            ParsingError::InvalidRequest("Rule threshold is out of range.".to_string())
+        })
+    }
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<CreateFilterRule>,
+        Json(body): Json<FilterRule>,
+    ) -> TicketsResult<Json<StoredFilterRule>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        // refuse rules the chain could not build
+        super::build_filter(&body)?;
+
+        let (kind, pattern, threshold, gateway) = match &body.kind {
+            FilterRuleKind::Duplicate { window_seconds } => {
+                ("duplicate", None, Some(threshold(*window_seconds)?), None)
+            }
+            FilterRuleKind::BannedWord { word } => ("banned_word", Some(word.as_str()), None, None),
+            FilterRuleKind::Regex { pattern } => ("regex", Some(pattern.as_str()), None, None),
+            FilterRuleKind::LinkLimit { max_links } => {
+                ("link_limit", None, Some(threshold(*max_links)?), None)
+            }
+            FilterRuleKind::BlockedSubmitter {
+                gateway,
+                external_id,
+            } => (
+                "blocked_submitter",
+                Some(external_id.as_str()),
+                None,
+                Some(gateway.as_str()),
+            ),
+        };
+
+        let id = Uuid::new_v4();
+
+        sqlx::query!(
+            "INSERT INTO ticket_filter_rule (id, app_id, kind, action, pattern, threshold, gateway) \
+                VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            &id,
+            &app_id,
+            kind,
+            super::action_name(body.action),
+            pattern,
+            threshold,
+            gateway
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        Ok(Json(StoredFilterRule { id, rule: body }))
+    }
+}
+
+pub mod delete_filter_rule {
+    use axum::extract::State;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{DeleteFilterRule, FilterRulePath};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(FilterRulePath { app_id, rule_id }): SdkPath<DeleteFilterRule>,
+    ) -> TicketsResult<()> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let deleted = sqlx::query!(
+            "DELETE FROM ticket_filter_rule WHERE id = $1 AND app_id = $2",
+            &rule_id,
+            &app_id
+        )
+        .execute(&state.pg_client)
+        .await?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(sqlx::Error::RowNotFound)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submitter(external_id: &str) -> Submitter {
+        Submitter {
+            external_id: external_id.to_string(),
+            display_name: None,
+        }
+    }
+
+    fn input<'a>(
+        submitter: &'a Submitter,
+        message: &'a str,
+        recent: &'a [RecentTicket],
+    ) -> FilterInput<'a> {
+        FilterInput {
+            app_id: Uuid::nil(),
+            gateway: "discord",
+            submitter,
+            customer_id: Uuid::nil(),
+            message,
+            recent,
+        }
+    }
+
+    fn is_matched(filter: &dyn TicketFilter, message: &str) -> bool {
+        let submitter = submitter("customer-1");
+        filter.evaluate(&input(&submitter, message, &[])).is_some()
+    }
+
+    #[test]
+    fn duplicates_are_matched_within_the_window() {
+        let filter = DuplicateFilter {
+            window: Duration::from_secs(60),
+            action: FilterAction::Reject,
+        };
+        let submitter = submitter("customer-1");
+        let recent = [
+            RecentTicket {
+                message: "Something else".to_string(),
+                age_seconds: 5,
+            },
+            RecentTicket {
+                message: "It  BROKE\nagain".to_string(),
+                age_seconds: 30,
+            },
+        ];
+
+        let verdict = filter
+            .evaluate(&input(&submitter, "it broke again", &recent))
+            .unwrap();
+        assert_eq!(verdict.action, FilterAction::Reject);
+
+        let stale = [RecentTicket {
+            message: "it broke again".to_string(),
+            age_seconds: 61,
+        }];
+        assert!(filter
+            .evaluate(&input(&submitter, "it broke again", &stale))
+            .is_none());
+        assert_eq!(filter.lookback(), Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn banned_words_match_whole_words() {
+        let filter = BannedWordFilter::new(" Scam ", FilterAction::Flag);
+
+        assert!(is_matched(&filter, "This is a SCAM!"));
+        assert!(!is_matched(&filter, "I was scammed"));
+
+        let phrase = BannedWordFilter::new("free money", FilterAction::Flag);
+        assert!(is_matched(&phrase, "Get FREE MONEY now"));
+        assert!(!is_matched(&phrase, "free of money"));
+    }
+
+    #[test]
+    fn regex_rules_match_and_refuse_invalid_patterns() {
+        let filter = RegexFilter::new(r"(?i)order #\d+", FilterAction::Quarantine).unwrap();
+
+        assert!(is_matched(&filter, "Where is ORDER #123?"));
+        assert!(!is_matched(&filter, "Where is my order?"));
+        assert!(RegexFilter::new("(unclosed", FilterAction::Flag).is_err());
+    }
+
+    #[test]
+    fn links_beyond_the_limit_are_matched() {
+        let filter = LinkLimitFilter {
+            max_links: 1,
+            action: FilterAction::Flag,
+        };
+
+        assert!(!is_matched(&filter, "See https://example.com"));
+        assert!(is_matched(
+            &filter,
+            "See https://example.com and WWW.example.org"
+        ));
+    }
+
+    #[test]
+    fn blocked_submitters_are_matched_on_their_gateway() {
+        let filter = BlockedSubmitterFilter {
+            gateway: "discord".to_string(),
+            external_id: "customer-1".to_string(),
+            action: FilterAction::Reject,
+        };
+
+        let blocked = submitter("customer-1");
+        let other = submitter("customer-2");
+        assert!(filter.evaluate(&input(&blocked, "hello", &[])).is_some());
+        assert!(filter.evaluate(&input(&other, "hello", &[])).is_none());
+
+        let mut elsewhere = input(&blocked, "hello", &[]);
+        elsewhere.gateway = "email";
+        assert!(filter.evaluate(&elsewhere).is_none());
+    }
+
+    #[test]
+    fn the_most_severe_action_wins() {
+        let chain = FilterChain {
+            filters: vec![
+                Arc::new(BannedWordFilter::new("refund", FilterAction::Flag)),
+                Arc::new(BannedWordFilter::new("scam", FilterAction::Quarantine)),
+                Arc::new(BannedWordFilter::new("unrelated", FilterAction::Reject)),
+            ],
+        };
+        let submitter = submitter("customer-1");
+
+        let verdict = chain
+            .verdict(&input(&submitter, "refund this scam", &[]))
+            .unwrap();
+        assert_eq!(verdict.action, FilterAction::Quarantine);
+        assert_eq!(
+            verdict.reason,
+            "Contains the banned word \"refund\". Contains the banned word \"scam\"."
+        );
+
+        assert!(chain.verdict(&input(&submitter, "hello", &[])).is_none());
+    }
+
+    #[test]
+    fn rules_with_negative_thresholds_are_skipped() {
+        let row = |threshold| RuleRow {
+            id: Uuid::nil(),
+            kind: "link_limit".to_string(),
+            action: "flag".to_string(),
+            pattern: None,
+            threshold: Some(threshold),
+            gateway: None,
+        };
+
+        assert!(row(-1).into_rule().is_none());
+        assert!(matches!(
+            row(3).into_rule().unwrap().rule.kind,
+            FilterRuleKind::LinkLimit { max_links: 3 }
+        ));
+    }
+}
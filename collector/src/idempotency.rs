@@ -0,0 +1,309 @@
+//! replays responses for requests carrying an idempotency key, so retried
+//! requests are only ever applied once
+
+use std::time::Duration;
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::extract::{FromRequestParts, MatchedPath, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use auth::AuthedCaller;
+use errors::{MiscError, ParsingError, TicketsResult};
+use sdk::IDEMPOTENCY_KEY_HEADER;
+
+use crate::axum_ext::DEFAULT_BODY_LIMIT;
+use crate::{attachments, exports, GlobalState};
+
+// responses are buffered in memory before being stored
+const MAX_STORED_BODY_SIZE: usize = 1024 * 1024;
+// retries arriving while the original request runs wait for its response
+const IN_PROGRESS_WAIT: Duration = Duration::from_secs(10);
+const IN_PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Identifies a request: the same key sent by another caller is a different request, sent
+/// again with another body it is refused.
+struct IdempotencyScope {
+    key: String,
+    method: String,
+    path: String,
+    caller: String,
+    body_hash: String,
+}
+
+enum Stored {
+    Response(Response),
+    InProgress,
+    /// The key was first used with another body.
+    Reused,
+    /// The original request completed, but its response could not be kept.
+    Unavailable,
+    /// Released after a server error, the request may be run again.
+    Released,
+}
+
+fn caller_scope(caller: AuthedCaller) -> String {
+    match caller {
+        AuthedCaller::User(user) => format!("user:{}", user.user_id),
+        AuthedCaller::Channel(channel) => format!("channel:{}", channel.channel_type.gateway()),
+    }
+}
+
+/// The body limit of the matched route, requests are never buffered beyond what it accepts.
+fn body_limit(path: Option<&MatchedPath>) -> usize {
+    path.map(MatchedPath::as_str)
+        .and_then(|path| attachments::body_limit(path).or_else(|| exports::body_limit(path)))
+        .unwrap_or(DEFAULT_BODY_LIMIT)
+}
+
+/// Hashes the body as sent, except for the boundary of multipart bodies, which clients pick
+/// anew for every attempt of an upload.
+fn body_hash(headers: &HeaderMap, body: &[u8]) -> String {
+    let boundary = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|content_type| content_type.starts_with("multipart/"))
+        .and_then(|content_type| {
+            content_type
+                .split(';')
+                .find_map(|param| param.trim().strip_prefix("boundary="))
+        })
+        .map(|boundary| format!("--{}", boundary.trim_matches('"')));
+
+    let mut hasher = Sha256::new();
+    let mut rest = body;
+    if let Some(delimiter) = boundary.as_deref().map(str::as_bytes) {
+        while let Some(at) = rest
+            .windows(delimiter.len())
+            .position(|window| window == delimiter)
+        {
+            hasher.update(&rest[..at]);
+            hasher.update(b"--boundary");
+            rest = &rest[at + delimiter.len()..];
+        }
+    }
+    hasher.update(rest);
+
+    hex::encode(hasher.finalize())
+}
+
+pub async fn middleware(
+    State(state): State<GlobalState>,
+    request: Request,
+    next: Next,
+) -> TicketsResult<Response> {
+    let Some(key) = request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+    else {
+        return Ok(next.run(request).await);
+    };
+
+    // keys are only stored for authenticated callers, before anything is buffered
+    let (mut parts, body) = request.into_parts();
+    let caller = caller_scope(AuthedCaller::from_request_parts(&mut parts, &state).await?);
+
+    let limit = body_limit(parts.extensions.get::<MatchedPath>());
+    let body = axum::body::to_bytes(body, limit).await.map_err(|_| {
+        ParsingError::InvalidRequest(format!("The request body exceeds {limit} bytes."))
+    })?;
+
+    let scope = IdempotencyScope {
+        key,
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        caller,
+        body_hash: body_hash(&parts.headers, &body),
+    };
+    let request = Request::from_parts(parts, Body::from(body));
+
+    let deadline = Instant::now() + IN_PROGRESS_WAIT;
+    loop {
+        if scope.claim(&state).await? {
+            return scope.run(&state, request, next).await;
+        }
+
+        match scope.stored(&state).await? {
+            Stored::Response(response) => return Ok(response),
+            Stored::Reused => return Err(MiscError::IdempotencyKeyReused)?,
+            Stored::Unavailable => return Err(MiscError::IdempotentResponseUnavailable)?,
+            Stored::Released => continue,
+            Stored::InProgress if Instant::now() < deadline => {
+                tokio::time::sleep(IN_PROGRESS_POLL_INTERVAL).await;
+            }
+            Stored::InProgress => return Err(MiscError::IdempotentRequestInProgress)?,
+        }
+    }
+}
+
+impl IdempotencyScope {
+    /// Claims the key, keys older than a day are reclaimed rather than replayed, as are claims
+    /// left in progress by a collector which stopped while running the request.
+    async fn claim(&self, state: &GlobalState) -> TicketsResult<bool> {
+        let claimed = sqlx::query!(
+            "INSERT INTO idempotency_key (key, method, path, caller, body_hash) \
+                VALUES ($1, $2, $3, $4, $5) \
+                ON CONFLICT (key, method, path, caller) DO UPDATE \
+                SET body_hash = EXCLUDED.body_hash, status = NULL, content_type = NULL, \
+                    body = NULL, replayable = TRUE, created_at = NOW() \
+                WHERE idempotency_key.created_at < NOW() - INTERVAL '1 day' \
+                    OR (idempotency_key.status IS NULL \
+                        AND idempotency_key.created_at < NOW() - INTERVAL '10 minutes')",
+            &self.key,
+            &self.method,
+            &self.path,
+            &self.caller,
+            &self.body_hash
+        )
+        .execute(&state.pg_client)
+        .await?
+        .rows_affected()
+            > 0;
+
+        Ok(claimed)
+    }
+
+    async fn run(
+        &self,
+        state: &GlobalState,
+        request: Request,
+        next: Next,
+    ) -> TicketsResult<Response> {
+        let response = next.run(request).await;
+
+        // server errors are likely transient, release the key so a retry runs again
+        if response.status().is_server_error() {
+            self.release(state).await?;
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let status = parts.status.as_u16() as i32;
+
+        // streamed or large bodies are passed through, a retry is told the response is gone
+        // instead of applying the request again
+        let storable = body
+            .size_hint()
+            .upper()
+            .is_some_and(|size| size <= MAX_STORED_BODY_SIZE as u64);
+        if !storable {
+            self.finish(state, status, None).await;
+            return Ok(Response::from_parts(parts, body));
+        }
+
+        let body = match axum::body::to_bytes(body, MAX_STORED_BODY_SIZE).await {
+            Ok(body) => body,
+            Err(err) => {
+                self.release(state).await?;
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+            }
+        };
+
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        self.finish(state, status, Some((content_type, &body)))
+            .await;
+
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+
+    /// Stores the response, or marks the key as unreplayable without one. The caller still gets
+    /// the response if this fails, the key is released as a last resort.
+    async fn finish(
+        &self,
+        state: &GlobalState,
+        status: i32,
+        response: Option<(Option<&str>, &Bytes)>,
+    ) {
+        let (content_type, body) = response.unzip();
+
+        let stored = sqlx::query!(
+            "UPDATE idempotency_key SET status = $5, content_type = $6, body = $7, replayable = $8 \
+                WHERE key = $1 AND method = $2 AND path = $3 AND caller = $4",
+            &self.key,
+            &self.method,
+            &self.path,
+            &self.caller,
+            status,
+            content_type.flatten(),
+            body.map(|body| body.as_ref()),
+            body.is_some()
+        )
+        .execute(&state.pg_client)
+        .await;
+
+        if let Err(err) = stored {
+            log::error!(
+                "Could not store the response of idempotency key {}: {err}",
+                self.key
+            );
+            if let Err(err) = self.release(state).await {
+                log::error!("Could not release idempotency key {}: {err}", self.key);
+            }
+        }
+    }
+
+    async fn release(&self, state: &GlobalState) -> TicketsResult<()> {
+        sqlx::query!(
+            "DELETE FROM idempotency_key \
+                WHERE key = $1 AND method = $2 AND path = $3 AND caller = $4",
+            &self.key,
+            &self.method,
+            &self.path,
+            &self.caller
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn stored(&self, state: &GlobalState) -> TicketsResult<Stored> {
+        let stored = sqlx::query!(
+            "SELECT body_hash, status, content_type, body, replayable FROM idempotency_key \
+                WHERE key = $1 AND method = $2 AND path = $3 AND caller = $4",
+            &self.key,
+            &self.method,
+            &self.path,
+            &self.caller
+        )
+        .fetch_optional(&state.pg_client)
+        .await?;
+
+        let Some(record) = stored else {
+            return Ok(Stored::Released);
+        };
+
+        if record.body_hash != self.body_hash {
+            return Ok(Stored::Reused);
+        }
+
+        let Some(status) = record.status else {
+            return Ok(Stored::InProgress);
+        };
+
+        if !record.replayable {
+            return Ok(Stored::Unavailable);
+        }
+
+        let mut response = Response::new(Body::from(record.body.unwrap_or_default()));
+        *response.status_mut() = StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK);
+
+        if let Some(content_type) = record
+            .content_type
+            .and_then(|value| HeaderValue::from_str(&value).ok())
+        {
+            response.headers_mut().insert(CONTENT_TYPE, content_type);
+        }
+
+        Ok(Stored::Response(response))
+    }
+}
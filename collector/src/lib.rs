@@ -0,0 +1,68 @@
+use std::collections::BTreeSet;
+
+use axum::Router;
+
+use crate::axum_ext::SdkRouter;
+use crate::state::GlobalState;
+
+mod attachments;
+mod axum_ext;
+pub mod blobs;
+mod blocks;
+mod consumer;
+mod customers;
+mod docs;
+mod exports;
+pub mod filters;
+mod idempotency;
+mod pagination;
+mod provisioning;
+mod quarantine;
+pub mod rate_limit;
+mod staff;
+pub mod state;
+mod threads;
+mod tickets;
+pub mod transcripts;
+pub mod webhooks;
+
+/// Builds the collector's REST API, every route and middleware applied to `state`.
+pub fn app(state: GlobalState) -> Router {
+    let app = routes()
+        .into_router()
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            idempotency::middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            provisioning::middleware,
+        ));
+
+    app.with_state(state)
+}
+
+/// The method and path of every sdk route the collector serves, so the OpenAPI document can be
+/// checked against the routes actually served.
+pub fn served_sdk_routes() -> BTreeSet<(String, &'static str)> {
+    routes().sdk_routes().clone()
+}
+
+fn routes() -> SdkRouter<GlobalState> {
+    let app = SdkRouter::new();
+
+    let app = consumer::extend_router(app);
+    let app = staff::extend_router(app);
+    let app = customers::extend_router(app);
+    let app = rate_limit::extend_router(app);
+    let app = filters::extend_router(app);
+    let app = quarantine::extend_router(app);
+    let app = blocks::extend_router(app);
+    let app = attachments::extend_router(app);
+    let app = threads::extend_router(app);
+    let app = tickets::extend_router(app);
+    let app = transcripts::extend_router(app);
+    let app = exports::extend_router(app);
+    let app = webhooks::extend_router(app);
+    docs::extend_router(app)
+}
@@ -1,30 +1,26 @@
 use std::sync::Arc;
 
-use axum::extract::FromRef;
-use axum::Router;
-
 use auth::jwt::{JwtConfig, JwtKeyPathsConfig};
+use collector::blobs::BlobStoreConfig;
+use collector::rate_limit::RateLimiter;
+use collector::state::GlobalState;
+use collector::webhooks::{WebhookConfig, WebhookEmitter, WebhookWorker};
 use dry::config::load_config;
 use errors::TicketsResult;
 use events::adapter::Adapter;
 
-use crate::state::GlobalState;
-
-mod axum_ext;
-mod consumer;
-mod staff;
-mod state;
-
 #[derive(serde::Deserialize)]
 pub struct TicketCollectorConfig {
     #[serde(rename = "adapter")]
     pub adapter_config: events::adapter::AdapterConfig,
     pub jwt: JwtKeyPathsConfig,
-}
-impl FromRef<GlobalState> for Arc<JwtConfig> {
-    fn from_ref(input: &GlobalState) -> Self {
-        input.jwt_config.clone()
-    }
+    #[serde(default)]
+    pub blob_store: BlobStoreConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    /// Where the nested websocket server forwards ticket actions sent over the socket.
+    #[cfg(feature = "nest-websocket-server")]
+    pub collector_url: String,
 }
 
 #[tokio::main]
@@ -36,27 +32,37 @@ async fn main() -> TicketsResult<()> {
     let pg_client = dry::database::connect().await?;
     sqlx::migrate!().set_locking(false).run(&pg_client).await?;
 
-    let adapter = match config.adapter_config.adapter_type {
+    let (adapter, rate_limiter) = match config.adapter_config.adapter_type {
         Adapter::Redis => {
             let config = config
                 .adapter_config
                 .redis
                 .as_ref()
                 .expect("Could not find redis config");
-            redis::Client::open(config.url.to_string())?
+            let client = redis::Client::open(config.url.to_string())?;
+            (client.clone(), RateLimiter::Redis(client))
         }
     };
 
+    let jwt_config: Arc<JwtConfig> = Arc::new(config.jwt.try_into()?);
+
+    let (emitter, webhook_events) = WebhookEmitter::new(Arc::new(adapter));
+    let webhook_handle = tokio::spawn(
+        WebhookWorker::new(pg_client.clone(), config.webhooks.clone())?.run(webhook_events),
+    );
+
     let state = GlobalState {
         pg_client,
-        jwt_config: Arc::new(config.jwt.try_into()?),
-        emitter: Arc::new(adapter),
+        jwt_config: jwt_config.clone(),
+        emitter: Arc::new(emitter),
+        provisioned_users: GlobalState::provisioned_users_cache(),
+        rate_limiter,
+        ticket_filters: Default::default(),
+        blob_store: config.blob_store.build()?,
+        webhooks: config.webhooks,
     };
 
-    let app = Router::new();
-
-    let app = consumer::extend_router(app);
-    let app = staff::extend_router(app);
+    let app = collector::app(state);
 
     if cfg!(feature = "nest-websocket-server") {
         #[cfg(feature = "nest-websocket-server")]
@@ -64,11 +70,12 @@ async fn main() -> TicketsResult<()> {
             let (adapter_handle, message_handle, socket_io_layer) =
                 socketio_server::setup_websocket_layer(
                     &config.adapter_config,
-                    state.jwt_config.clone(),
+                    jwt_config,
+                    &config.collector_url,
                 )
                 .await?;
 
-            let app = app.with_state(state).layer(socket_io_layer);
+            let app = app.layer(socket_io_layer);
 
             let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await?;
 
@@ -79,6 +86,7 @@ async fn main() -> TicketsResult<()> {
             Ok(tokio::select! {
                 err = adapter_handle => err??,
                 err = message_handle => err??,
+                err = webhook_handle => err??,
                 err = axum::serve(listener, app).into_future() => err?,
             })
         }
@@ -86,12 +94,17 @@ async fn main() -> TicketsResult<()> {
         #[cfg(not(feature = "nest-websocket-server"))]
         unreachable!()
     } else {
-        let app = app.with_state(state);
-
         let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await?;
 
+        use std::future::IntoFuture;
+
         log::info!("Executing rest API server on :8000");
 
-        Ok(axum::serve(listener, app).await?)
+        tokio::select! {
+            err = webhook_handle => err??,
+            err = axum::serve(listener, app).into_future() => err?,
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,150 @@
+//! keyset pagination shared by the list routes. Pages are ordered by creation time and id, and
+//! the cursor handed out is the position of the last item of a page rather than a reference to
+//! it, so it stays valid once that item is gone and never reveals anything about other rows.
+
+use sqlx::types::time::OffsetDateTime;
+use uuid::Uuid;
+
+use errors::{ParsingError, TicketsResult};
+
+/// Upper bound of the items a list route returns per page.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// The creation time and id of the last item of a page, encoded as
+/// `<created_at in microseconds>:<id>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: OffsetDateTime,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn parse(cursor: &str) -> TicketsResult<Self> {
+        cursor
+            .split_once(':')
+            .and_then(|(micros, id)| {
+                let micros = micros.parse::<i64>().ok()?;
+                let created_at =
+                    OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * 1000).ok()?;
+
+                Some(Cursor {
+                    created_at,
+                    id: Uuid::parse_str(id).ok()?,
+                })
+            })
+            .ok_or_else(|| {
+                ParsingError::InvalidRequest("Malformed page cursor.".to_string()).into()
+            })
+    }
+
+    pub fn encode(&self) -> String {
+        let micros = self.created_at.unix_timestamp_nanos() / 1000;
+        format!("{micros}:{}", self.id)
+    }
+}
+
+/// The position and size of a requested page.
+pub struct PageRequest {
+    cursor: Option<Cursor>,
+    limit: u32,
+}
+
+impl PageRequest {
+    /// Parses the `cursor` and `limit` query parameters, clamping the limit to
+    /// [`MAX_PAGE_SIZE`].
+    pub fn new(cursor: Option<&str>, limit: u32) -> TicketsResult<Self> {
+        Ok(Self {
+            cursor: cursor.map(Cursor::parse).transpose()?,
+            limit: limit.clamp(1, MAX_PAGE_SIZE),
+        })
+    }
+
+    pub fn created_at(&self) -> Option<OffsetDateTime> {
+        self.cursor.map(|cursor| cursor.created_at)
+    }
+
+    pub fn id(&self) -> Option<Uuid> {
+        self.cursor.map(|cursor| cursor.id)
+    }
+
+    /// How many rows to fetch, one more than the page holds to tell whether another page follows.
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit as i64 + 1
+    }
+
+    /// Trims the fetched `rows` to the page, along with the cursor of the next page if another
+    /// follows. `position` is the cursor of a row.
+    pub fn page<R>(
+        &self,
+        mut rows: Vec<R>,
+        position: impl Fn(&R) -> Cursor,
+    ) -> (Vec<R>, Option<String>) {
+        if rows.len() <= self.limit as usize {
+            return (rows, None);
+        }
+
+        rows.truncate(self.limit as usize);
+        let next_cursor = rows.last().map(|row| position(row).encode());
+
+        (rows, next_cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::types::time::OffsetDateTime;
+    use uuid::Uuid;
+
+    use super::{Cursor, PageRequest, MAX_PAGE_SIZE};
+
+    fn cursor(micros: i64) -> Cursor {
+        Cursor {
+            created_at: OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * 1000).unwrap(),
+            id: Uuid::new_v4(),
+        }
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = cursor(1_712_345_678_901_234);
+        assert_eq!(Cursor::parse(&cursor.encode()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn malformed_cursors_are_refused() {
+        let id = Uuid::new_v4();
+
+        for malformed in [
+            "latest".to_string(),
+            id.to_string(),
+            format!("soon:{id}"),
+            "1712345678901234:42".to_string(),
+            format!("{}:{id}", i64::MAX),
+        ] {
+            assert!(Cursor::parse(&malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn limits_are_clamped() {
+        assert_eq!(PageRequest::new(None, 0).unwrap().fetch_limit(), 2);
+        assert_eq!(
+            PageRequest::new(None, u32::MAX).unwrap().fetch_limit(),
+            MAX_PAGE_SIZE as i64 + 1
+        );
+    }
+
+    #[test]
+    fn only_full_pages_have_a_next_cursor() {
+        let request = PageRequest::new(None, 2).unwrap();
+        let rows = (0..3).map(cursor).collect::<Vec<_>>();
+
+        let (page, next_cursor) = request.page(rows.clone(), |row| *row);
+        assert_eq!(page, rows[..2]);
+        assert_eq!(next_cursor, Some(rows[1].encode()));
+
+        let (page, next_cursor) = request.page(rows[..2].to_vec(), |row| *row);
+        assert_eq!(page.len(), 2);
+        assert_eq!(next_cursor, None);
+    }
+}
@@ -0,0 +1,28 @@
+//! provisions `tt_user` rows for authenticated staff on their first request, so
+//! routes referencing the caller no longer depend on an earlier `/staff/login`. The claim
+//! verified here is kept in the request's extensions for the extractors which follow.
+
+use axum::extract::{FromRequestParts, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use auth::AuthedCaller;
+use errors::TicketsResult;
+
+use crate::GlobalState;
+
+pub async fn middleware(
+    State(state): State<GlobalState>,
+    request: Request,
+    next: Next,
+) -> TicketsResult<Response> {
+    let (mut parts, body) = request.into_parts();
+
+    // unauthenticated callers are left for the route's own extractors to reject
+    if let Ok(AuthedCaller::User(user)) = AuthedCaller::from_request_parts(&mut parts, &state).await
+    {
+        state.provision_user(&user).await?;
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
@@ -0,0 +1,130 @@
+use sdk::routes::staff::{QuarantinedTickets, ReviewQuarantinedTicket};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<QuarantinedTickets>(quarantined_tickets::route_handler)
+            .sdk_route::<ReviewQuarantinedTicket>(review_quarantined_ticket::route_handler),
+    )
+}
+
+pub mod quarantined_tickets {
+    use axum::extract::{Query, State};
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, QuarantinedTicket, QuarantinedTickets};
+    use sdk::routes::{Page, PageQuery};
+
+    use crate::axum_ext::SdkPath;
+    use crate::pagination::{Cursor, PageRequest};
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<QuarantinedTickets>,
+        Query(query): Query<PageQuery>,
+    ) -> TicketsResult<Json<Page<QuarantinedTicket>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        // oldest first, so reviewing the queue works through it in order
+        let page = PageRequest::new(query.cursor.as_deref(), query.limit)?;
+
+        let rows = sqlx::query!(
+            r#"SELECT id, gateway, customer_id, message, filter_reason, created_at AS cursor_created_at,
+                    EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM ticket
+                WHERE app_id = $1 AND filter_status = 'quarantined'
+                    AND ($2::TIMESTAMPTZ IS NULL OR (created_at, id) > ($2, $3::UUID))
+                ORDER BY created_at, id
+                LIMIT $4"#,
+            &app_id,
+            page.created_at(),
+            page.id(),
+            page.fetch_limit()
+        )
+        .fetch_all(&state.pg_client)
+        .await?;
+
+        let (rows, next_cursor) = page.page(rows, |row| Cursor {
+            created_at: row.cursor_created_at,
+            id: row.id,
+        });
+
+        let items = rows
+            .into_iter()
+            .map(|row| QuarantinedTicket {
+                ticket_id: row.id,
+                gateway: row.gateway,
+                customer_id: row.customer_id,
+                message: row.message,
+                reason: row.filter_reason,
+                created_at: row.created_at,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(Page { items, next_cursor }))
+    }
+}
+
+pub mod review_quarantined_ticket {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use events::TicketSubmittedEvent;
+    use sdk::routes::staff::{QuarantineReview, ReviewQuarantinedTicket, TicketPath};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<ReviewQuarantinedTicket>,
+        Json(body): Json<QuarantineReview>,
+    ) -> TicketsResult<()> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let status = if body.approve { "approved" } else { "rejected" };
+
+        // only the first review of a ticket applies, later ones find nothing to update
+        let ticket = sqlx::query!(
+            "UPDATE ticket SET filter_status = $3 \
+                WHERE id = $1 AND app_id = $2 AND filter_status = 'quarantined' \
+                RETURNING message",
+            &ticket_id,
+            &app_id,
+            status
+        )
+        .fetch_one(&state.pg_client)
+        .await?;
+
+        // approved tickets reach staff as if they were just submitted
+        if body.approve {
+            state.emitter.publish_tickets_event(
+                app_id,
+                TicketSubmittedEvent {
+                    message: ticket.message,
+                }
+                .into(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! limits how many tickets a single submitter may open per app and gateway, counted in fixed
+//! windows shared through redis when the collector runs on the redis adapter. Only accepted
+//! submissions are counted. As gateways let their users pick external ids freely, all submitters
+//! of a gateway are limited together as well.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use errors::{MiscError, TicketsResult};
+use sdk::routes::staff::{GetRateLimit, RateLimit, SetRateLimit};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+/// Applied to apps which never configured their own limit.
+pub const DEFAULT_RATE_LIMIT: RateLimit = RateLimit {
+    max_tickets: 5,
+    window_seconds: 10 * 60,
+};
+
+/// How many times the per-submitter limit all submitters of one gateway may open together.
+pub const GATEWAY_LIMIT_FACTOR: u32 = 20;
+
+// expired in-memory windows are swept once this many are tracked
+const MEMORY_SWEEP_THRESHOLD: usize = 10_000;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<GetRateLimit>(get_rate_limit::route_handler)
+            .sdk_route::<SetRateLimit>(set_rate_limit::route_handler),
+    )
+}
+
+#[derive(Clone)]
+pub enum RateLimiter {
+    /// Windows shared by every collector publishing to the same redis.
+    Redis(redis::Client),
+    /// Windows local to this process, for collectors without a shared adapter such as the
+    /// in-process sdk mock.
+    Memory(Arc<Mutex<HashMap<String, MemoryWindow>>>),
+}
+
+pub struct MemoryWindow {
+    count: u32,
+    expires_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn memory() -> Self {
+        RateLimiter::Memory(Default::default())
+    }
+
+    /// Counts a submission under `key`, failing once `limit` is exceeded within the window.
+    pub async fn hit(&self, key: &str, limit: RateLimit) -> TicketsResult<()> {
+        let (count, retry_after) = match self {
+            RateLimiter::Redis(client) => {
+                let mut connection = client.get_multiplexed_async_connection().await?;
+
+                // the window starts with the first hit, later hits only increment it
+                let (count, ttl): (u32, i64) = redis::pipe()
+                    .atomic()
+                    .cmd("SET")
+                    .arg(key)
+                    .arg(0)
+                    .arg("EX")
+                    .arg(limit.window_seconds)
+                    .arg("NX")
+                    .ignore()
+                    .cmd("INCR")
+                    .arg(key)
+                    .cmd("TTL")
+                    .arg(key)
+                    .query_async(&mut connection)
+                    .await?;
+
+                (count, ttl.max(1) as u64)
+            }
+            RateLimiter::Memory(windows) => {
+                let now = Instant::now();
+                let mut windows = windows.lock().unwrap();
+
+                if windows.len() >= MEMORY_SWEEP_THRESHOLD {
+                    windows.retain(|_, window| window.expires_at > now);
+                }
+
+                let window = windows
+                    .entry(key.to_string())
+                    .and_modify(|window| {
+                        if window.expires_at <= now {
+                            *window = MemoryWindow::starting(now, limit);
+                        }
+                    })
+                    .or_insert_with(|| MemoryWindow::starting(now, limit));
+
+                window.count += 1;
+
+                let remaining = window.expires_at.duration_since(now);
+                (window.count, remaining.as_secs_f64().ceil().max(1.0) as u64)
+            }
+        };
+
+        if count > limit.max_tickets {
+            return Err(MiscError::RateLimited { retry_after })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MemoryWindow {
+    fn starting(now: Instant, limit: RateLimit) -> Self {
+        Self {
+            count: 0,
+            expires_at: now + Duration::from_secs(limit.window_seconds as u64),
+        }
+    }
+}
+
+/// The app's configured limit, or [`DEFAULT_RATE_LIMIT`] if it has none.
+async fn load_rate_limit(state: &GlobalState, app_id: Uuid) -> TicketsResult<RateLimit> {
+    let limit = sqlx::query!(
+        "SELECT max_tickets, window_seconds FROM app_rate_limit WHERE app_id = $1",
+        &app_id
+    )
+    .fetch_optional(&state.pg_client)
+    .await?
+    .map(|record| RateLimit {
+        max_tickets: record.max_tickets as u32,
+        window_seconds: record.window_seconds as u32,
+    });
+
+    Ok(limit.unwrap_or(DEFAULT_RATE_LIMIT))
+}
+
+/// Counts a submission of `external_id` on `gateway` against the app's limit, and against
+/// [`GATEWAY_LIMIT_FACTOR`] times that limit for the gateway as a whole. Called once the
+/// submission passed every other check, so rejected submissions never use up the quota.
+pub async fn hit_submission(
+    state: &GlobalState,
+    app_id: Uuid,
+    gateway: &str,
+    external_id: &str,
+) -> TicketsResult<()> {
+    let limit = load_rate_limit(state, app_id).await?;
+
+    let key = format!("rate_limit:{app_id}:{gateway}:{external_id}");
+    state.rate_limiter.hit(&key, limit).await?;
+
+    // a submitter over their own limit is refused above and never counts towards the gateway's
+    let gateway_limit = RateLimit {
+        max_tickets: limit.max_tickets.saturating_mul(GATEWAY_LIMIT_FACTOR),
+        ..limit
+    };
+    let key = format!("rate_limit_gateway:{app_id}:{gateway}");
+    state.rate_limiter.hit(&key, gateway_limit).await
+}
+
+pub mod get_rate_limit {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, GetRateLimit, RateLimit};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<GetRateLimit>,
+    ) -> TicketsResult<Json<RateLimit>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        Ok(Json(super::load_rate_limit(&state, app_id).await?))
+    }
+}
+
+pub mod set_rate_limit {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{AppPath, RateLimit, SetRateLimit};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<SetRateLimit>,
+        Json(body): Json<RateLimit>,
+    ) -> TicketsResult<Json<RateLimit>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let (Ok(max_tickets), Ok(window_seconds)) = (
+            i32::try_from(body.max_tickets),
+            i32::try_from(body.window_seconds),
+        ) else {
+            return Err(ParsingError::InvalidRequest(
+                "Rate limits are out of range.".to_string(),
+            ))?;
+        };
+
+        if max_tickets == 0 || window_seconds == 0 {
+            return Err(ParsingError::InvalidRequest(
+                "Rate limits must be greater than zero.".to_string(),
+            ))?;
+        }
+
+        sqlx::query!(
+            "INSERT INTO app_rate_limit (app_id, max_tickets, window_seconds) VALUES ($1, $2, $3) \
+                ON CONFLICT (app_id) DO UPDATE \
+                SET max_tickets = $2, window_seconds = $3, updated_at = NOW()",
+            &app_id,
+            max_tickets,
+            window_seconds
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        Ok(Json(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use errors::{MiscError, TicketsError};
+    use sdk::routes::staff::RateLimit;
+
+    use super::{MemoryWindow, RateLimiter, MEMORY_SWEEP_THRESHOLD};
+
+    const LIMIT: RateLimit = RateLimit {
+        max_tickets: 2,
+        window_seconds: 60,
+    };
+
+    fn retry_after(result: errors::TicketsResult<()>) -> Option<u64> {
+        match result {
+            Err(TicketsError::Misc(MiscError::RateLimited { retry_after })) => Some(retry_after),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn hits_beyond_the_limit_are_refused() {
+        let limiter = RateLimiter::memory();
+
+        limiter.hit("submitter", LIMIT).await.unwrap();
+        limiter.hit("submitter", LIMIT).await.unwrap();
+
+        let retry_after = retry_after(limiter.hit("submitter", LIMIT).await).unwrap();
+        assert!((1..=60).contains(&retry_after));
+    }
+
+    #[tokio::test]
+    async fn keys_are_counted_apart() {
+        let limiter = RateLimiter::memory();
+
+        limiter.hit("first", LIMIT).await.unwrap();
+        limiter.hit("first", LIMIT).await.unwrap();
+
+        limiter.hit("second", LIMIT).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_windows_start_over() {
+        let limiter = RateLimiter::memory();
+        let RateLimiter::Memory(windows) = &limiter else {
+            unreachable!()
+        };
+
+        windows.lock().unwrap().insert(
+            "submitter".to_string(),
+            MemoryWindow {
+                count: LIMIT.max_tickets,
+                expires_at: Instant::now(),
+            },
+        );
+
+        limiter.hit("submitter", LIMIT).await.unwrap();
+        assert_eq!(windows.lock().unwrap()["submitter"].count, 1);
+    }
+
+    #[tokio::test]
+    async fn expired_windows_are_swept() {
+        let limiter = RateLimiter::memory();
+        let RateLimiter::Memory(windows) = &limiter else {
+            unreachable!()
+        };
+
+        let expired_at = Instant::now() - Duration::from_secs(1);
+        windows
+            .lock()
+            .unwrap()
+            .extend((0..MEMORY_SWEEP_THRESHOLD).map(|i| {
+                (
+                    format!("expired-{i}"),
+                    MemoryWindow {
+                        count: 1,
+                        expires_at: expired_at,
+                    },
+                )
+            }));
+
+        limiter.hit("submitter", LIMIT).await.unwrap();
+        assert_eq!(windows.lock().unwrap().len(), 1);
+    }
+}
@@ -1,16 +1,24 @@
-use axum::Router;
+use sdk::routes::staff::{
+    AuthorizeApps, CreateApp, CreateLoginCode, ExchangeLoginCode, GetProfile, LinkIdentity, Login,
+    ToggleGateway, UnlinkIdentity, UpdateProfile,
+};
 
-use sdk::routes::staff::{CreateApp, Login, ToggleGateway};
-
-use crate::axum_ext::ApplySdkRoute;
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
 use crate::GlobalState;
 
-pub fn extend_router(router: Router<GlobalState>) -> Router<GlobalState> {
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
     router.merge(
-        Router::new()
+        SdkRouter::new()
             .sdk_route::<CreateApp>(create_app::route_handler)
             .sdk_route::<ToggleGateway>(toggle_gateway::route_handler)
-            .sdk_route::<Login>(login::route_handler),
+            .sdk_route::<Login>(login::route_handler)
+            .sdk_route::<AuthorizeApps>(authorize_apps::route_handler)
+            .sdk_route::<CreateLoginCode>(login_codes::create_route_handler)
+            .sdk_route::<ExchangeLoginCode>(login_codes::exchange_route_handler)
+            .sdk_route::<GetProfile>(get_profile::route_handler)
+            .sdk_route::<UpdateProfile>(update_profile::route_handler)
+            .sdk_route::<LinkIdentity>(link_identity::route_handler)
+            .sdk_route::<UnlinkIdentity>(unlink_identity::route_handler),
     )
 }
 
@@ -28,16 +36,9 @@ pub mod login {
         State(state): State<GlobalState>,
     ) -> TicketsResult<()> {
         let user = user.require_user()?;
-        let pg_client = &state.pg_client;
 
         // write user if they're not in the database already
-        sqlx::query!(
-            "INSERT INTO tt_user (id) VALUES ($1) ON CONFLICT (id) DO NOTHING",
-            user.user_id as i64
-        )
-        .execute(pg_client)
-        .await?;
-        Ok(())
+        state.provision_user(&user).await
     }
 }
 
@@ -47,25 +48,25 @@ pub mod toggle_gateway {
 
     use auth::{AuthedCaller, UserRole};
     use errors::TicketsResult;
-    use sdk::routes::staff::{ToggleGatewayBody, ToggleGatewayResponse};
+    use sdk::routes::staff::{AppPath, ToggleGateway, ToggleGatewayBody, ToggleGatewayResponse};
 
-    use crate::axum_ext::RequireHeaderFromHeaderMap;
+    use crate::axum_ext::{RequireHeaderFromHeaderMap, SdkPath};
     use crate::GlobalState;
 
     pub async fn route_handler(
         user: AuthedCaller,
         headers: axum::http::HeaderMap,
         State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ToggleGateway>,
         Json(body): Json<ToggleGatewayBody>,
     ) -> TicketsResult<Json<ToggleGatewayResponse>> {
         let user = user.require_user()?;
         let gateway = headers.require_header("x-gateway")?;
-        let app_id = body.app_id;
 
         let pg_client = &state.pg_client;
 
         state
-            .validate_user_role(user.user_id, UserRole::Management, app_id)
+            .validate_user_role(&user, UserRole::Management, app_id)
             .await?;
 
         sqlx::query!(
@@ -127,3 +128,319 @@ pub mod create_app {
         Ok(Json(CreateAppResponse { app_id }))
     }
 }
+
+pub mod authorize_apps {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::jwt::{JwtAccessor, JwtData};
+    use auth::{AuthedCaller, UserRole};
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::staff::{AuthorizeAppsBody, AuthorizeAppsResponse};
+    use uuid::Uuid;
+
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        Json(body): Json<AuthorizeAppsBody>,
+    ) -> TicketsResult<Json<AuthorizeAppsResponse>> {
+        let user = user.require_user()?;
+        let pg_client = &state.pg_client;
+
+        let app_ids = body.app_ids.iter().copied().collect::<Vec<Uuid>>();
+
+        let records = sqlx::query!(
+            "SELECT role FROM user_app WHERE user_id = $1 AND app_id = Any($2)",
+            user.user_id as i64,
+            &app_ids
+        )
+        .fetch_all(pg_client)
+        .await?;
+
+        // every requested app must be backed by a membership
+        if records.len() != app_ids.len() {
+            return Err(AuthorizationError::CannotAccessApp)?;
+        }
+
+        // the claimed role has to hold for every app in the token
+        let role = records
+            .into_iter()
+            .map(|record| UserRole::try_from(record.role))
+            .collect::<Result<Vec<UserRole>, _>>()?
+            .into_iter()
+            .min()
+            .ok_or(AuthorizationError::CannotAccessApp)?;
+
+        let (token, claims) = state.jwt_config.generate_pre_authorized(JwtData {
+            accessor: JwtAccessor::DiscordStaffMember {
+                user_id: user.user_id,
+                authorized_apps: body.app_ids,
+                role,
+            },
+        })?;
+
+        Ok(Json(AuthorizeAppsResponse {
+            token,
+            expiration: claims.exp,
+            role,
+        }))
+    }
+}
+
+/// Login codes let staff sign in where no gateway signs tokens for them, such as `tt login`: a
+/// gateway they are signed in to creates a code, which is exchanged here for a token, so the
+/// private key never leaves the collector and the gateways.
+pub mod login_codes {
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    use axum::extract::State;
+    use axum::Json;
+    use sha2::{Digest, Sha256};
+    use uuid::Uuid;
+
+    use auth::jwt::{JwtAccessor, JwtData};
+    use auth::{AuthedCaller, UserRole};
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::staff::{ExchangeLoginCodeBody, ExchangeLoginCodeResponse, LoginCodeResponse};
+
+    use crate::GlobalState;
+
+    // 10 minutes = 60 seconds * 10, enough to copy the code into a terminal
+    const LOGIN_CODE_TTL_SECONDS: f64 = 60.0 * 10.0;
+    // 12 hours = 60 seconds * 60 minutes * 12, a shift, after which the staff member logs in again
+    const LOGIN_TOKEN_TTL: Duration = Duration::from_secs(60 * 60 * 12);
+
+    fn hash(code: &str) -> String {
+        hex::encode(Sha256::digest(code.as_bytes()))
+    }
+
+    pub async fn create_route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+    ) -> TicketsResult<Json<LoginCodeResponse>> {
+        let user = user.require_user()?;
+        let pg_client = &state.pg_client;
+
+        sqlx::query!("DELETE FROM login_code WHERE expires_at <= NOW()")
+            .execute(pg_client)
+            .await?;
+
+        let code = Uuid::new_v4().simple().to_string();
+
+        let expiration = sqlx::query!(
+            r#"INSERT INTO login_code (code_hash, user_id, expires_at)
+                VALUES ($1, $2, NOW() + make_interval(secs => $3))
+                RETURNING EXTRACT(EPOCH FROM expires_at)::INT8 AS "expiration!""#,
+            hash(&code),
+            user.user_id as i64,
+            LOGIN_CODE_TTL_SECONDS
+        )
+        .fetch_one(pg_client)
+        .await?
+        .expiration;
+
+        Ok(Json(LoginCodeResponse { code, expiration }))
+    }
+
+    /// Called without a token, the code is the caller's only credential and is used up by the
+    /// first exchange.
+    pub async fn exchange_route_handler(
+        State(state): State<GlobalState>,
+        Json(body): Json<ExchangeLoginCodeBody>,
+    ) -> TicketsResult<Json<ExchangeLoginCodeResponse>> {
+        let record = sqlx::query!(
+            r#"DELETE FROM login_code WHERE code_hash = $1
+                RETURNING user_id, expires_at > NOW() AS "fresh!""#,
+            hash(&body.code)
+        )
+        .fetch_optional(&state.pg_client)
+        .await?;
+
+        let Some(record) = record.filter(|record| record.fresh) else {
+            return Err(AuthorizationError::InvalidLoginCode)?;
+        };
+        let user_id = record.user_id as u64;
+
+        // the collector checks the staff member's role in the app on every request
+        let (token, claims) = state.jwt_config.generate(
+            JwtData {
+                accessor: JwtAccessor::DiscordStaffMember {
+                    user_id,
+                    authorized_apps: HashSet::new(),
+                    role: UserRole::Staff,
+                },
+            },
+            LOGIN_TOKEN_TTL,
+        )?;
+
+        Ok(Json(ExchangeLoginCodeResponse {
+            user_id,
+            token,
+            expiration: claims.exp,
+        }))
+    }
+}
+
+pub mod get_profile {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::AuthedCaller;
+    use errors::TicketsResult;
+    use sdk::routes::staff::{UserIdentity, UserProfile};
+
+    use crate::GlobalState;
+
+    pub(super) async fn load_profile(
+        state: &GlobalState,
+        user_id: u64,
+    ) -> TicketsResult<UserProfile> {
+        let pg_client = &state.pg_client;
+
+        let user = sqlx::query!(
+            "SELECT display_name, avatar_url FROM tt_user WHERE id = $1",
+            user_id as i64
+        )
+        .fetch_one(pg_client)
+        .await?;
+
+        let identities = sqlx::query_as!(
+            UserIdentity,
+            "SELECT gateway, external_id, display_name, avatar_url FROM user_identity \
+                WHERE user_id = $1 ORDER BY created_at",
+            user_id as i64
+        )
+        .fetch_all(pg_client)
+        .await?;
+
+        Ok(UserProfile {
+            user_id,
+            display_name: user.display_name,
+            avatar_url: user.avatar_url,
+            identities,
+        })
+    }
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+    ) -> TicketsResult<Json<UserProfile>> {
+        let user = user.require_user()?;
+
+        Ok(Json(load_profile(&state, user.user_id).await?))
+    }
+}
+
+pub mod update_profile {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::AuthedCaller;
+    use errors::TicketsResult;
+    use sdk::routes::staff::{UpdateProfileBody, UserProfile};
+
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        Json(body): Json<UpdateProfileBody>,
+    ) -> TicketsResult<Json<UserProfile>> {
+        let user = user.require_user()?;
+
+        sqlx::query!(
+            "UPDATE tt_user SET display_name = COALESCE($2, display_name), \
+                avatar_url = COALESCE($3, avatar_url), updated_at = NOW() WHERE id = $1",
+            user.user_id as i64,
+            body.display_name,
+            body.avatar_url
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        Ok(Json(
+            super::get_profile::load_profile(&state, user.user_id).await?,
+        ))
+    }
+}
+
+pub mod link_identity {
+    use axum::extract::State;
+    use axum::http::HeaderMap;
+    use axum::Json;
+
+    use auth::AuthedCaller;
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::staff::{LinkIdentityBody, UserIdentity};
+
+    use crate::axum_ext::RequireHeaderFromHeaderMap;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        headers: HeaderMap,
+        State(state): State<GlobalState>,
+        Json(body): Json<LinkIdentityBody>,
+    ) -> TicketsResult<Json<UserIdentity>> {
+        let user = user.require_user()?;
+        // gateways only vouch for identities they authenticated themselves
+        let gateway = headers.require_header("x-gateway")?;
+
+        // re-linking refreshes the metadata, identities of other users are left untouched
+        let identity = sqlx::query_as!(
+            UserIdentity,
+            "INSERT INTO user_identity (gateway, external_id, user_id, display_name, avatar_url) \
+                VALUES ($1, $2, $3, $4, $5) \
+                ON CONFLICT (gateway, external_id) DO UPDATE \
+                SET display_name = excluded.display_name, avatar_url = excluded.avatar_url \
+                WHERE user_identity.user_id = excluded.user_id \
+                RETURNING gateway, external_id, display_name, avatar_url",
+            &gateway,
+            &body.external_id,
+            user.user_id as i64,
+            body.display_name,
+            body.avatar_url
+        )
+        .fetch_optional(&state.pg_client)
+        .await?
+        .ok_or(AuthorizationError::IdentityLinkedToAnotherUser)?;
+
+        Ok(Json(identity))
+    }
+}
+
+pub mod unlink_identity {
+    use axum::extract::State;
+
+    use auth::AuthedCaller;
+    use errors::TicketsResult;
+    use sdk::routes::staff::{IdentityPath, UnlinkIdentity};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(IdentityPath {
+            gateway,
+            external_id,
+        }): SdkPath<UnlinkIdentity>,
+    ) -> TicketsResult<()> {
+        let user = user.require_user()?;
+
+        sqlx::query!(
+            "DELETE FROM user_identity WHERE gateway = $1 AND external_id = $2 AND user_id = $3",
+            &gateway,
+            &external_id,
+            user.user_id as i64
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        Ok(())
+    }
+}
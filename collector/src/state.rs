@@ -1,33 +1,125 @@
 use auth::jwt::JwtConfig;
-use auth::UserRole;
+use auth::{AuthedUser, UserRole};
+use axum::extract::FromRef;
 use errors::{AuthorizationError, TicketsResult};
+use socketio_emitter::adapter::memory::MemoryEmitter;
 use socketio_emitter::adapter::TicketsEventEmitter;
 use sqlx::{Pool, Postgres};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::blobs::{BlobStore, LocalBlobStore};
+use crate::filters::TicketFilter;
+use crate::rate_limit::RateLimiter;
+use crate::webhooks::WebhookConfig;
+
 #[derive(Clone)]
 pub struct GlobalState {
     pub pg_client: Pool<Postgres>,
     pub jwt_config: Arc<JwtConfig>,
     pub emitter: Arc<dyn TicketsEventEmitter + Send + Sync>,
+    /// Users recently provisioned by this process, skipping the upsert on later requests.
+    pub provisioned_users: moka::sync::Cache<u64, ()>,
+    pub rate_limiter: RateLimiter,
+    /// Filters run on every submission after the app's own filter rules.
+    pub ticket_filters: Arc<Vec<Arc<dyn TicketFilter>>>,
+    /// Content of ticket attachments.
+    pub blob_store: Arc<dyn BlobStore>,
+    /// Retries of outgoing webhooks and the endpoints they may be sent to.
+    pub webhooks: WebhookConfig,
+}
+
+impl FromRef<GlobalState> for Arc<JwtConfig> {
+    fn from_ref(input: &GlobalState) -> Self {
+        input.jwt_config.clone()
+    }
 }
 
 impl GlobalState {
+    /// Cache of provisioned users, evicted users are only upserted once more.
+    pub fn provisioned_users_cache() -> moka::sync::Cache<u64, ()> {
+        moka::sync::Cache::builder()
+            .name("ProvisionedUsers")
+            .max_capacity(10_000)
+            .time_to_idle(Duration::from_secs(60 * 60))
+            .build()
+    }
+
+    /// State for running the collector in-process, such as behind `sdk::client::MockSdkExecutor`.
+    /// It still needs a running PostgreSQL database, everything else stays in the process:
+    /// events are kept by `emitter`, submissions are rate limited in memory and attachments are
+    /// stored under `blob_root`.
+    pub fn in_process(
+        pg_client: Pool<Postgres>,
+        jwt_config: Arc<JwtConfig>,
+        emitter: Arc<MemoryEmitter>,
+        blob_root: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            pg_client,
+            jwt_config,
+            emitter,
+            provisioned_users: Self::provisioned_users_cache(),
+            rate_limiter: RateLimiter::memory(),
+            ticket_filters: Default::default(),
+            blob_store: Arc::new(LocalBlobStore::new(blob_root)),
+            webhooks: WebhookConfig::default(),
+        }
+    }
+
+    /// Creates the `tt_user` row for the user along with their identity on their own gateway.
+    pub async fn provision_user(&self, user: &AuthedUser) -> TicketsResult<()> {
+        let user_id = user.user_id;
+        if self.provisioned_users.contains_key(&user_id) {
+            return Ok(());
+        }
+
+        let mut tx = self.pg_client.begin().await?;
+
+        sqlx::query!(
+            "INSERT INTO tt_user (id) VALUES ($1) ON CONFLICT (id) DO NOTHING",
+            user_id as i64
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO user_identity (gateway, external_id, user_id) VALUES ($1, $2, $3) \
+                ON CONFLICT (gateway, external_id) DO NOTHING",
+            user.gateway,
+            user_id.to_string(),
+            user_id as i64
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.provisioned_users.insert(user_id, ());
+        Ok(())
+    }
+
     pub async fn validate_user_role(
         &self,
-        user_id: u64,
+        user: &AuthedUser,
         required_role: UserRole,
         app_id: Uuid,
     ) -> TicketsResult<UserRole> {
-        let user_role: UserRole = sqlx::query!(
+        // short-lived tokens minted by `/staff/authorize_apps` carry verified claims
+        if let Some(role) = user.pre_authorized_role(app_id, required_role) {
+            return Ok(role);
+        }
+
+        let user_role = sqlx::query!(
             "SELECT role FROM user_app WHERE app_id = $1 AND user_id = $2",
             &app_id,
-            user_id as i64
+            user.user_id as i64
         )
         .fetch_optional(&self.pg_client)
         .await?
-        .map(|record| serde_json::from_str(&record.role))
+        .map(|record| UserRole::try_from(record.role))
         .ok_or(AuthorizationError::CannotAccessApp)??;
 
         if user_role < required_role {
@@ -0,0 +1,367 @@
+//! the conversation on a ticket, messages between its customer and staff, staff-only notes
+//! and the ticket's open or closed status
+
+use uuid::Uuid;
+
+use errors::{MiscError, TicketsResult};
+use events::MessageAddedEvent;
+use sdk::routes::consumer::{SendCustomerMessage, TicketMessage};
+use sdk::routes::staff::{AddTicketNote, ReplyToTicket, SetTicketStatus, TicketStatus};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<SendCustomerMessage>(send_customer_message::route_handler)
+            .sdk_route::<ReplyToTicket>(reply_to_ticket::route_handler)
+            .sdk_route::<AddTicketNote>(add_ticket_note::route_handler)
+            .sdk_route::<SetTicketStatus>(set_ticket_status::route_handler),
+    )
+}
+
+pub enum Author {
+    Customer(Uuid),
+    Staff(u64),
+}
+
+struct TicketRecord {
+    customer_id: Option<Uuid>,
+    status: TicketStatus,
+    /// Whether staff were notified of the ticket, messages of withheld tickets are not
+    /// published either.
+    published: bool,
+}
+
+async fn load_ticket(
+    state: &GlobalState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+) -> TicketsResult<TicketRecord> {
+    let ticket = sqlx::query!(
+        "SELECT customer_id, status, filter_status FROM ticket WHERE id = $1 AND app_id = $2",
+        &ticket_id,
+        &app_id
+    )
+    .fetch_one(&state.pg_client)
+    .await?;
+
+    Ok(TicketRecord {
+        customer_id: ticket.customer_id,
+        status: TicketStatus::try_from(ticket.status)?,
+        published: !matches!(
+            ticket.filter_status.as_deref(),
+            Some("quarantined" | "rejected")
+        ),
+    })
+}
+
+/// Appends a message to an open ticket, publishing it to staff if the ticket was published.
+async fn add_message(
+    state: &GlobalState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+    ticket: TicketRecord,
+    author: Author,
+    body: String,
+) -> TicketsResult<TicketMessage> {
+    if ticket.status == TicketStatus::Closed {
+        return Err(MiscError::TicketClosed)?;
+    }
+
+    let message_id = Uuid::new_v4();
+
+    let (customer_id, author_id) = match author {
+        Author::Customer(customer_id) => (Some(customer_id), None),
+        Author::Staff(user_id) => (None, Some(user_id)),
+    };
+
+    let created_at = sqlx::query!(
+        r#"INSERT INTO ticket_message (id, app_id, ticket_id, body, customer_id, author_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!""#,
+        &message_id,
+        &app_id,
+        &ticket_id,
+        &body,
+        customer_id,
+        author_id.map(|user_id| user_id as i64)
+    )
+    .fetch_one(&state.pg_client)
+    .await?
+    .created_at;
+
+    if ticket.published {
+        state.emitter.publish_tickets_event(
+            app_id,
+            MessageAddedEvent {
+                ticket_id,
+                message_id,
+                body: body.clone(),
+                from_staff: author_id.is_some(),
+            }
+            .into(),
+        )?;
+    }
+
+    Ok(TicketMessage {
+        message_id,
+        ticket_id,
+        body,
+        customer_id,
+        author_id,
+        created_at,
+    })
+}
+
+pub mod send_customer_message {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::AuthedCaller;
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::consumer::{CustomerMessageBody, SendCustomerMessage, TicketMessage};
+    use sdk::routes::staff::TicketPath;
+
+    use super::Author;
+    use crate::attachments::ensure_gateway_enabled;
+    use crate::axum_ext::SdkPath;
+    use crate::blocks::ensure_not_blocked;
+    use crate::customers::find_customer;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        caller: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<SendCustomerMessage>,
+        Json(body): Json<CustomerMessageBody>,
+    ) -> TicketsResult<Json<TicketMessage>> {
+        // only gateways relay customer messages, the customer is named by the gateway
+        let channel = caller.require_channel()?;
+        let gateway = channel.channel_type.gateway().to_string();
+
+        ensure_gateway_enabled(&state, app_id, gateway.clone()).await?;
+
+        let ticket = super::load_ticket(&state, app_id, ticket_id).await?;
+
+        let customer_id =
+            find_customer(&state.pg_client, app_id, &gateway, &body.external_id).await?;
+
+        // only the customer who submitted the ticket writes to it
+        let Some(customer_id) = customer_id.filter(|id| Some(*id) == ticket.customer_id) else {
+            return Err(AuthorizationError::UserCannotAccessResource)?;
+        };
+
+        ensure_not_blocked(&state.pg_client, app_id, customer_id).await?;
+
+        Ok(Json(
+            super::add_message(
+                &state,
+                app_id,
+                ticket_id,
+                ticket,
+                Author::Customer(customer_id),
+                body.body,
+            )
+            .await?,
+        ))
+    }
+}
+
+pub mod reply_to_ticket {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::consumer::TicketMessage;
+    use sdk::routes::staff::{ReplyToTicket, TicketMessageBody, TicketPath};
+
+    use super::Author;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<ReplyToTicket>,
+        Json(body): Json<TicketMessageBody>,
+    ) -> TicketsResult<Json<TicketMessage>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let ticket = super::load_ticket(&state, app_id, ticket_id).await?;
+
+        Ok(Json(
+            super::add_message(
+                &state,
+                app_id,
+                ticket_id,
+                ticket,
+                Author::Staff(user.user_id),
+                body.body,
+            )
+            .await?,
+        ))
+    }
+}
+
+pub mod add_ticket_note {
+    use axum::extract::State;
+    use axum::Json;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AddTicketNote, TicketMessageBody, TicketNote, TicketPath};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<AddTicketNote>,
+        Json(body): Json<TicketMessageBody>,
+    ) -> TicketsResult<Json<TicketNote>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        // fails with not found for tickets of other apps, closed tickets may still be noted
+        super::load_ticket(&state, app_id, ticket_id).await?;
+
+        let note_id = Uuid::new_v4();
+
+        let created_at = sqlx::query!(
+            r#"INSERT INTO ticket_note (id, app_id, ticket_id, body, author_id)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!""#,
+            &note_id,
+            &app_id,
+            &ticket_id,
+            &body.body,
+            user.user_id as i64
+        )
+        .fetch_one(&state.pg_client)
+        .await?
+        .created_at;
+
+        Ok(Json(TicketNote {
+            note_id,
+            ticket_id,
+            body: body.body,
+            author_id: user.user_id,
+            created_at,
+        }))
+    }
+}
+
+pub mod set_ticket_status {
+    use axum::extract::State;
+    use axum::Json;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use events::{TicketClosedEvent, TicketReopenedEvent};
+    use sdk::routes::staff::{
+        SetTicketStatus, SetTicketStatusBody, TicketPath, TicketStatus, TicketStatusChange,
+    };
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<SetTicketStatus>,
+        Json(body): Json<SetTicketStatusBody>,
+    ) -> TicketsResult<Json<TicketStatusChange>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let mut tx = state.pg_client.begin().await?;
+
+        // the row lock orders concurrent changes, each sees the status the previous one set
+        let ticket = sqlx::query!(
+            "SELECT status, filter_status FROM ticket WHERE id = $1 AND app_id = $2 FOR UPDATE",
+            &ticket_id,
+            &app_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let from_status = TicketStatus::try_from(ticket.status)?;
+        if from_status == body.status {
+            return Err(ParsingError::InvalidRequest(format!(
+                "The ticket is already {from_status}."
+            )))?;
+        }
+
+        sqlx::query!(
+            "UPDATE ticket SET status = $2 WHERE id = $1",
+            &ticket_id,
+            body.status.to_string()
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let created_at = sqlx::query!(
+            r#"INSERT INTO ticket_status_change (id, app_id, ticket_id, from_status, to_status, changed_by)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!""#,
+            &Uuid::new_v4(),
+            &app_id,
+            &ticket_id,
+            from_status.to_string(),
+            body.status.to_string(),
+            user.user_id as i64
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .created_at;
+
+        tx.commit().await?;
+
+        let published = !matches!(
+            ticket.filter_status.as_deref(),
+            Some("quarantined" | "rejected")
+        );
+
+        if published {
+            state.emitter.publish_tickets_event(
+                app_id,
+                match body.status {
+                    TicketStatus::Closed => TicketClosedEvent {
+                        ticket_id,
+                        closed_by: user.user_id,
+                    }
+                    .into(),
+                    TicketStatus::Open => TicketReopenedEvent {
+                        ticket_id,
+                        reopened_by: user.user_id,
+                    }
+                    .into(),
+                },
+            )?;
+        }
+
+        Ok(Json(TicketStatusChange {
+            ticket_id,
+            from_status,
+            to_status: body.status,
+            changed_by: user.user_id,
+            created_at,
+        }))
+    }
+}
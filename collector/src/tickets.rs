@@ -0,0 +1,283 @@
+//! the app's tickets as staff triage them: listed and searched, read as a whole thread and
+//! assigned to a staff member
+
+use uuid::Uuid;
+
+use errors::TicketsResult;
+use sdk::routes::staff::{
+    AssignTicket, GetTicketThread, ListTickets, TicketOverview, TicketStatus,
+};
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<ListTickets>(list_tickets::route_handler)
+            .sdk_route::<GetTicketThread>(get_ticket_thread::route_handler)
+            .sdk_route::<AssignTicket>(assign_ticket::route_handler),
+    )
+}
+
+async fn load_overview(
+    state: &GlobalState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+) -> TicketsResult<TicketOverview> {
+    let ticket = sqlx::query!(
+        r#"SELECT gateway, message, status, customer_id, assignee_id,
+                EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+            FROM ticket WHERE id = $1 AND app_id = $2"#,
+        &ticket_id,
+        &app_id
+    )
+    .fetch_one(&state.pg_client)
+    .await?;
+
+    Ok(TicketOverview {
+        ticket_id,
+        gateway: ticket.gateway,
+        message: ticket.message,
+        status: TicketStatus::try_from(ticket.status)?,
+        customer_id: ticket.customer_id,
+        assignee_id: ticket.assignee_id.map(|user_id| user_id as u64),
+        created_at: ticket.created_at,
+    })
+}
+
+pub mod list_tickets {
+    use axum::extract::{Query, State};
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, ListTickets, TicketOverview, TicketStatus, TicketsQuery};
+    use sdk::routes::Page;
+
+    use crate::axum_ext::SdkPath;
+    use crate::pagination::{Cursor, PageRequest};
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ListTickets>,
+        Query(query): Query<TicketsQuery>,
+    ) -> TicketsResult<Json<Page<TicketOverview>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        let page = PageRequest::new(query.cursor.as_deref(), query.limit)?;
+
+        // searched text is matched literally, not as a pattern
+        let search = query.search.map(|search| {
+            format!(
+                "%{}%",
+                search
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            )
+        });
+
+        let tickets = sqlx::query!(
+            r#"SELECT id, gateway, message, status, customer_id, assignee_id,
+                    created_at AS cursor_created_at, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM ticket
+                WHERE app_id = $1
+                    AND (filter_status IS NULL OR filter_status NOT IN ('quarantined', 'rejected'))
+                    AND ($2::TEXT IS NULL OR status = $2)
+                    AND ($3::INT8 IS NULL OR assignee_id = $3)
+                    AND ($4::TEXT IS NULL OR message ILIKE $4 OR EXISTS (
+                        SELECT 1 FROM ticket_message
+                        WHERE ticket_message.ticket_id = ticket.id AND ticket_message.body ILIKE $4
+                    ))
+                    AND ($5::TIMESTAMPTZ IS NULL OR (created_at, id) < ($5, $6::UUID))
+                ORDER BY created_at DESC, id DESC
+                LIMIT $7"#,
+            &app_id,
+            query.status.map(|status| status.to_string()),
+            query.assignee_id.map(|user_id| user_id as i64),
+            search,
+            page.created_at(),
+            page.id(),
+            page.fetch_limit()
+        )
+        .fetch_all(&state.pg_client)
+        .await?;
+
+        let (tickets, next_cursor) = page.page(tickets, |ticket| Cursor {
+            created_at: ticket.cursor_created_at,
+            id: ticket.id,
+        });
+
+        let items = tickets
+            .into_iter()
+            .map(|ticket| {
+                Ok(TicketOverview {
+                    ticket_id: ticket.id,
+                    gateway: ticket.gateway,
+                    message: ticket.message,
+                    status: TicketStatus::try_from(ticket.status)?,
+                    customer_id: ticket.customer_id,
+                    assignee_id: ticket.assignee_id.map(|user_id| user_id as u64),
+                    created_at: ticket.created_at,
+                })
+            })
+            .collect::<TicketsResult<Vec<TicketOverview>>>()?;
+
+        Ok(Json(Page { items, next_cursor }))
+    }
+}
+
+pub mod get_ticket_thread {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::consumer::TicketMessage;
+    use sdk::routes::staff::{
+        GetTicketThread, TicketNote, TicketPath, TicketStatus, TicketStatusChange, TicketThread,
+    };
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<GetTicketThread>,
+    ) -> TicketsResult<Json<TicketThread>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        // fails with not found for tickets of other apps
+        let ticket = super::load_overview(&state, app_id, ticket_id).await?;
+
+        let messages = sqlx::query!(
+            r#"SELECT id, body, customer_id, author_id, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM ticket_message WHERE ticket_id = $1 ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(&state.pg_client)
+        .await?
+        .into_iter()
+        .map(|message| TicketMessage {
+            message_id: message.id,
+            ticket_id,
+            body: message.body,
+            customer_id: message.customer_id,
+            author_id: message.author_id.map(|user_id| user_id as u64),
+            created_at: message.created_at,
+        })
+        .collect();
+
+        let notes = sqlx::query!(
+            r#"SELECT id, body, author_id, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM ticket_note WHERE ticket_id = $1 ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(&state.pg_client)
+        .await?
+        .into_iter()
+        .map(|note| TicketNote {
+            note_id: note.id,
+            ticket_id,
+            body: note.body,
+            author_id: note.author_id as u64,
+            created_at: note.created_at,
+        })
+        .collect();
+
+        let status_changes = sqlx::query!(
+            r#"SELECT from_status, to_status, changed_by, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM ticket_status_change WHERE ticket_id = $1 ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(&state.pg_client)
+        .await?
+        .into_iter()
+        .map(|change| {
+            Ok(TicketStatusChange {
+                ticket_id,
+                from_status: TicketStatus::try_from(change.from_status)?,
+                to_status: TicketStatus::try_from(change.to_status)?,
+                changed_by: change.changed_by as u64,
+                created_at: change.created_at,
+            })
+        })
+        .collect::<TicketsResult<Vec<TicketStatusChange>>>()?;
+
+        Ok(Json(TicketThread {
+            ticket,
+            messages,
+            notes,
+            status_changes,
+        }))
+    }
+}
+
+pub mod assign_ticket {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{AssignTicket, AssignTicketBody, TicketOverview, TicketPath};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<AssignTicket>,
+        Json(body): Json<AssignTicketBody>,
+    ) -> TicketsResult<Json<TicketOverview>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Staff, app_id)
+            .await?;
+
+        if let Some(assignee_id) = body.assignee_id {
+            let member = sqlx::query!(
+                "SELECT 1 AS member FROM user_app WHERE user_id = $1 AND app_id = $2",
+                assignee_id as i64,
+                &app_id
+            )
+            .fetch_optional(&state.pg_client)
+            .await?;
+
+            if member.is_none() {
+                return Err(ParsingError::InvalidRequest(format!(
+                    "User {assignee_id} is not a staff member of the app."
+                )))?;
+            }
+        }
+
+        let updated = sqlx::query!(
+            "UPDATE ticket SET assignee_id = $3 WHERE id = $1 AND app_id = $2",
+            &ticket_id,
+            &app_id,
+            body.assignee_id.map(|user_id| user_id as i64)
+        )
+        .execute(&state.pg_client)
+        .await?;
+
+        // fails with not found for tickets of other apps
+        if updated.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound)?;
+        }
+
+        Ok(Json(super::load_overview(&state, app_id, ticket_id).await?))
+    }
+}
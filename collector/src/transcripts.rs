@@ -0,0 +1,718 @@
+//! complete records of tickets for archival, rendered as html, markdown or json from the
+//! ticket, its conversation, attachments, status changes and optionally staff notes
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use errors::TicketsResult;
+use sdk::routes::staff::{TicketStatus, TicketTranscript, TranscriptFormat};
+use sdk::routes::FileData;
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::GlobalState;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(SdkRouter::new().sdk_route::<TicketTranscript>(ticket_transcript::route_handler))
+}
+
+#[derive(serde::Serialize)]
+pub struct Transcript {
+    pub ticket_id: Uuid,
+    pub app_id: Uuid,
+    pub gateway: String,
+    pub status: TicketStatus,
+    pub customer: Option<TranscriptCustomer>,
+    /// The verdict of the app's filters, if any matched the ticket.
+    pub filter: Option<TranscriptFilter>,
+    /// Unix timestamp in seconds.
+    pub created_at: i64,
+    /// Unix timestamp in seconds.
+    pub generated_at: i64,
+    pub includes_notes: bool,
+    /// Everything that happened on the ticket, oldest first.
+    pub entries: Vec<TranscriptEntry>,
+}
+
+#[derive(serde::Serialize)]
+pub struct TranscriptCustomer {
+    pub customer_id: Uuid,
+    pub display_name: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct TranscriptFilter {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum TranscriptAuthor {
+    /// `customer_id` is missing for tickets submitted before customers were tracked.
+    Customer {
+        customer_id: Option<Uuid>,
+        display_name: Option<String>,
+    },
+    Staff {
+        user_id: u64,
+        display_name: Option<String>,
+    },
+}
+
+#[derive(serde::Serialize)]
+pub struct TranscriptEntry {
+    /// Unix timestamp in seconds.
+    pub created_at: i64,
+    // orders entries written within the same second
+    #[serde(skip)]
+    created_at_micros: i64,
+    #[serde(flatten)]
+    pub content: TranscriptContent,
+}
+
+impl TranscriptEntry {
+    fn new(created_at_micros: i64, content: TranscriptContent) -> Self {
+        Self {
+            created_at: micros_to_seconds(created_at_micros),
+            created_at_micros,
+            content,
+        }
+    }
+}
+
+/// Rounded the same way as the timestamps of every other route.
+fn micros_to_seconds(micros: i64) -> i64 {
+    (micros + 500_000).div_euclid(1_000_000)
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranscriptContent {
+    /// `message_id` is missing for the message the ticket was submitted with.
+    Message {
+        message_id: Option<Uuid>,
+        author: TranscriptAuthor,
+        body: String,
+    },
+    Note {
+        note_id: Uuid,
+        author: TranscriptAuthor,
+        body: String,
+    },
+    Attachment {
+        attachment_id: Uuid,
+        author: TranscriptAuthor,
+        file_name: String,
+        content_type: String,
+        size_bytes: u64,
+    },
+    StatusChange {
+        author: TranscriptAuthor,
+        from_status: TicketStatus,
+        to_status: TicketStatus,
+    },
+}
+
+impl Transcript {
+    /// Collects the ticket's record, failing with not found unless it belongs to `app_id`.
+    pub async fn load(
+        state: &GlobalState,
+        app_id: Uuid,
+        ticket_id: Uuid,
+        include_notes: bool,
+    ) -> TicketsResult<Transcript> {
+        let pg_client = &state.pg_client;
+
+        let ticket = sqlx::query!(
+            r#"SELECT ticket.message, ticket.gateway, ticket.status, ticket.customer_id,
+                    ticket.filter_status, ticket.filter_reason, customer.display_name AS "customer_name?",
+                    (EXTRACT(EPOCH FROM ticket.created_at) * 1000000)::INT8 AS "created_at_micros!"
+                FROM ticket LEFT JOIN customer ON customer.id = ticket.customer_id
+                WHERE ticket.id = $1 AND ticket.app_id = $2"#,
+            &ticket_id,
+            &app_id
+        )
+        .fetch_one(pg_client)
+        .await?;
+
+        let messages = sqlx::query!(
+            r#"SELECT id, body, author_id, (EXTRACT(EPOCH FROM created_at) * 1000000)::INT8 AS "created_at_micros!"
+                FROM ticket_message WHERE ticket_id = $1 ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(pg_client)
+        .await?;
+
+        let attachments = sqlx::query!(
+            r#"SELECT id, file_name, content_type, size_bytes, uploaded_by,
+                    (EXTRACT(EPOCH FROM created_at) * 1000000)::INT8 AS "created_at_micros!"
+                FROM attachment WHERE ticket_id = $1 ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(pg_client)
+        .await?;
+
+        let status_changes = sqlx::query!(
+            r#"SELECT from_status, to_status, changed_by, (EXTRACT(EPOCH FROM created_at) * 1000000)::INT8 AS "created_at_micros!"
+                FROM ticket_status_change WHERE ticket_id = $1 ORDER BY created_at, id"#,
+            &ticket_id
+        )
+        .fetch_all(pg_client)
+        .await?;
+
+        let notes = if include_notes {
+            sqlx::query!(
+                r#"SELECT id, body, author_id, (EXTRACT(EPOCH FROM created_at) * 1000000)::INT8 AS "created_at_micros!"
+                    FROM ticket_note WHERE ticket_id = $1 ORDER BY created_at, id"#,
+                &ticket_id
+            )
+            .fetch_all(pg_client)
+            .await?
+        } else {
+            vec![]
+        };
+
+        let staff_ids = messages
+            .iter()
+            .filter_map(|message| message.author_id)
+            .chain(attachments.iter().filter_map(|file| file.uploaded_by))
+            .chain(status_changes.iter().map(|change| change.changed_by))
+            .chain(notes.iter().map(|note| note.author_id))
+            .collect::<HashSet<i64>>()
+            .into_iter()
+            .collect::<Vec<i64>>();
+
+        let staff_names = sqlx::query!(
+            "SELECT id, display_name FROM tt_user WHERE id = Any($1)",
+            &staff_ids
+        )
+        .fetch_all(pg_client)
+        .await?
+        .into_iter()
+        .map(|record| (record.id, record.display_name))
+        .collect::<HashMap<i64, Option<String>>>();
+
+        let customer = TranscriptAuthor::Customer {
+            customer_id: ticket.customer_id,
+            display_name: ticket.customer_name.clone(),
+        };
+        let staff = |user_id: i64| TranscriptAuthor::Staff {
+            user_id: user_id as u64,
+            display_name: staff_names.get(&user_id).cloned().flatten(),
+        };
+        // anything not written by staff came from the ticket's own customer
+        let author = |user_id: Option<i64>| user_id.map(staff).unwrap_or_else(|| customer.clone());
+
+        let mut entries = vec![TranscriptEntry::new(
+            ticket.created_at_micros,
+            TranscriptContent::Message {
+                message_id: None,
+                author: customer.clone(),
+                body: ticket.message,
+            },
+        )];
+
+        entries.extend(messages.into_iter().map(|message| {
+            TranscriptEntry::new(
+                message.created_at_micros,
+                TranscriptContent::Message {
+                    message_id: Some(message.id),
+                    author: author(message.author_id),
+                    body: message.body,
+                },
+            )
+        }));
+
+        entries.extend(attachments.into_iter().map(|file| {
+            TranscriptEntry::new(
+                file.created_at_micros,
+                TranscriptContent::Attachment {
+                    attachment_id: file.id,
+                    author: author(file.uploaded_by),
+                    file_name: file.file_name,
+                    content_type: file.content_type,
+                    size_bytes: file.size_bytes as u64,
+                },
+            )
+        }));
+
+        for change in status_changes {
+            entries.push(TranscriptEntry::new(
+                change.created_at_micros,
+                TranscriptContent::StatusChange {
+                    author: staff(change.changed_by),
+                    from_status: TicketStatus::try_from(change.from_status)?,
+                    to_status: TicketStatus::try_from(change.to_status)?,
+                },
+            ));
+        }
+
+        entries.extend(notes.into_iter().map(|note| {
+            TranscriptEntry::new(
+                note.created_at_micros,
+                TranscriptContent::Note {
+                    note_id: note.id,
+                    author: staff(note.author_id),
+                    body: note.body,
+                },
+            )
+        }));
+
+        entries.sort_by_key(|entry| entry.created_at_micros);
+
+        Ok(Transcript {
+            ticket_id,
+            app_id,
+            gateway: ticket.gateway,
+            status: TicketStatus::try_from(ticket.status)?,
+            customer: ticket.customer_id.map(|customer_id| TranscriptCustomer {
+                customer_id,
+                display_name: ticket.customer_name,
+            }),
+            filter: ticket.filter_status.map(|status| TranscriptFilter {
+                status,
+                reason: ticket.filter_reason,
+            }),
+            created_at: micros_to_seconds(ticket.created_at_micros),
+            generated_at: Utc::now().timestamp(),
+            includes_notes: include_notes,
+            entries,
+        })
+    }
+
+    pub fn render(&self, format: TranscriptFormat) -> TicketsResult<FileData> {
+        let (extension, content_type, bytes) = match format {
+            TranscriptFormat::Html => ("html", "text/html; charset=utf-8", self.html().into()),
+            TranscriptFormat::Markdown => {
+                ("md", "text/markdown; charset=utf-8", self.markdown().into())
+            }
+            TranscriptFormat::Json => {
+                ("json", "application/json", serde_json::to_vec_pretty(self)?)
+            }
+        };
+
+        Ok(FileData {
+            file_name: format!("ticket-{}.{extension}", self.ticket_id),
+            content_type: content_type.to_string(),
+            bytes,
+        })
+    }
+
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        let mut summary = vec![
+            ("Ticket", self.ticket_id.to_string()),
+            ("App", self.app_id.to_string()),
+            ("Gateway", self.gateway.clone()),
+            ("Status", self.status.to_string()),
+            ("Opened", format_timestamp(self.created_at)),
+        ];
+
+        if let Some(customer) = &self.customer {
+            summary.push((
+                "Customer",
+                match &customer.display_name {
+                    Some(name) => format!("{name} ({})", customer.customer_id),
+                    None => customer.customer_id.to_string(),
+                },
+            ));
+        }
+
+        if let Some(filter) = &self.filter {
+            summary.push((
+                "Filter",
+                match &filter.reason {
+                    Some(reason) => format!("{} ({reason})", filter.status),
+                    None => filter.status.clone(),
+                },
+            ));
+        }
+
+        summary.push(("Generated", format_timestamp(self.generated_at)));
+        summary
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = format!("# Ticket {}\n\n| | |\n|---|---|\n", self.ticket_id);
+
+        for (label, value) in self.summary() {
+            let _ = writeln!(out, "| {label} | {} |", escape_markdown(&value));
+        }
+
+        for entry in &self.entries {
+            let _ = write!(
+                out,
+                "\n### {} · {}\n\n",
+                escape_markdown(&entry.content.heading()),
+                format_timestamp(entry.created_at)
+            );
+
+            match &entry.content {
+                TranscriptContent::Message { body, .. } | TranscriptContent::Note { body, .. } => {
+                    for line in body.lines() {
+                        let _ = writeln!(out, "> {}", escape_markdown(line));
+                    }
+                }
+                TranscriptContent::Attachment {
+                    file_name,
+                    content_type,
+                    size_bytes,
+                    ..
+                } => {
+                    let _ = writeln!(
+                        out,
+                        "`{}` ({content_type}, {size_bytes} bytes)",
+                        file_name.replace('`', "'")
+                    );
+                }
+                TranscriptContent::StatusChange {
+                    from_status,
+                    to_status,
+                    ..
+                } => {
+                    let _ = writeln!(out, "{from_status} → {to_status}");
+                }
+            }
+        }
+
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+                <title>Ticket {}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n\
+                <h1>Ticket {}</h1>\n<table>\n",
+            self.ticket_id, self.ticket_id
+        );
+
+        for (label, value) in self.summary() {
+            let _ = writeln!(
+                out,
+                "<tr><th>{label}</th><td>{}</td></tr>",
+                escape_html(&value)
+            );
+        }
+        out.push_str("</table>\n");
+
+        for entry in &self.entries {
+            let _ = write!(
+                out,
+                "<section class=\"{}\">\n<h3>{} <time>{}</time></h3>\n",
+                entry.content.kind(),
+                escape_html(&entry.content.heading()),
+                format_timestamp(entry.created_at)
+            );
+
+            match &entry.content {
+                TranscriptContent::Message { body, .. } | TranscriptContent::Note { body, .. } => {
+                    let _ = writeln!(out, "<p>{}</p>", escape_html(body));
+                }
+                TranscriptContent::Attachment {
+                    file_name,
+                    content_type,
+                    size_bytes,
+                    ..
+                } => {
+                    let _ = writeln!(
+                        out,
+                        "<p><code>{}</code> ({}, {size_bytes} bytes)</p>",
+                        escape_html(file_name),
+                        escape_html(content_type)
+                    );
+                }
+                TranscriptContent::StatusChange {
+                    from_status,
+                    to_status,
+                    ..
+                } => {
+                    let _ = writeln!(out, "<p>{from_status} → {to_status}</p>");
+                }
+            }
+
+            out.push_str("</section>\n");
+        }
+
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;max-width:50em;margin:auto}\
+    th{text-align:left;padding-right:1em}\
+    section{border-left:3px solid #888;margin:1em 0;padding-left:1em}\
+    section.note{border-color:#d9a400}\
+    section p{white-space:pre-wrap}\
+    time{color:#666;font-weight:normal;font-size:smaller}";
+
+impl TranscriptContent {
+    fn kind(&self) -> &'static str {
+        match self {
+            TranscriptContent::Message { .. } => "message",
+            TranscriptContent::Note { .. } => "note",
+            TranscriptContent::Attachment { .. } => "attachment",
+            TranscriptContent::StatusChange { .. } => "status_change",
+        }
+    }
+
+    fn heading(&self) -> String {
+        match self {
+            TranscriptContent::Message { author, .. } => author.to_string(),
+            TranscriptContent::Note { author, .. } => format!("Note by {author}"),
+            TranscriptContent::Attachment { author, .. } => format!("Attachment from {author}"),
+            TranscriptContent::StatusChange { author, .. } => format!("Status changed by {author}"),
+        }
+    }
+}
+
+impl std::fmt::Display for TranscriptAuthor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranscriptAuthor::Customer {
+                display_name: Some(name),
+                ..
+            } => write!(f, "{name} (customer)"),
+            TranscriptAuthor::Customer { .. } => write!(f, "Customer"),
+            TranscriptAuthor::Staff {
+                display_name: Some(name),
+                ..
+            } => write!(f, "{name} (staff)"),
+            TranscriptAuthor::Staff { user_id, .. } => write!(f, "Staff member {user_id}"),
+        }
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Keeps customer text from being read as markdown or html, the words are unchanged.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub mod ticket_transcript {
+    use axum::extract::{Query, State};
+    use axum::response::Response;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{AuthorizationError, TicketsResult};
+    use sdk::routes::staff::{TicketPath, TicketTranscript, TranscriptQuery};
+
+    use super::Transcript;
+    use crate::attachments::{ensure_gateway_enabled, file_response};
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        caller: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(TicketPath { app_id, ticket_id }): SdkPath<TicketTranscript>,
+        Query(query): Query<TranscriptQuery>,
+    ) -> TicketsResult<Response> {
+        let gateway = match caller {
+            AuthedCaller::User(user) => {
+                state
+                    .validate_user_role(&user, UserRole::Staff, app_id)
+                    .await?;
+                None
+            }
+            // gateways archive the tickets they carried, such as when closing a ticket channel
+            AuthedCaller::Channel(channel) => {
+                let gateway = channel.channel_type.gateway().to_string();
+                ensure_gateway_enabled(&state, app_id, gateway.clone()).await?;
+                Some(gateway)
+            }
+        };
+
+        let transcript = Transcript::load(&state, app_id, ticket_id, query.include_notes).await?;
+
+        if gateway.is_some_and(|gateway| gateway != transcript.gateway) {
+            return Err(AuthorizationError::UserCannotAccessResource)?;
+        }
+
+        Ok(file_response(transcript.render(query.format)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i64 = 1_000_000;
+
+    fn customer() -> TranscriptAuthor {
+        TranscriptAuthor::Customer {
+            customer_id: Some(Uuid::nil()),
+            display_name: Some("Ada <admin>".to_string()),
+        }
+    }
+
+    fn transcript(body: &str) -> Transcript {
+        Transcript {
+            ticket_id: Uuid::nil(),
+            app_id: Uuid::nil(),
+            gateway: "discord".to_string(),
+            status: TicketStatus::Closed,
+            customer: Some(TranscriptCustomer {
+                customer_id: Uuid::nil(),
+                display_name: Some("Ada <admin>".to_string()),
+            }),
+            filter: None,
+            created_at: 1_700_000_000,
+            generated_at: 1_700_000_100,
+            includes_notes: false,
+            entries: vec![
+                TranscriptEntry::new(
+                    1_700_000_000 * SECOND,
+                    TranscriptContent::Message {
+                        message_id: None,
+                        author: customer(),
+                        body: body.to_string(),
+                    },
+                ),
+                TranscriptEntry::new(
+                    1_700_000_060 * SECOND,
+                    TranscriptContent::StatusChange {
+                        author: TranscriptAuthor::Staff {
+                            user_id: 7,
+                            display_name: None,
+                        },
+                        from_status: TicketStatus::Open,
+                        to_status: TicketStatus::Closed,
+                    },
+                ),
+            ],
+        }
+    }
+
+    fn rendered(transcript: &Transcript, format: TranscriptFormat) -> (FileData, String) {
+        let file = transcript.render(format).unwrap();
+        let text = String::from_utf8(file.bytes.clone()).unwrap();
+        (file, text)
+    }
+
+    #[test]
+    fn html_escapes_customer_text() {
+        let (file, html) = rendered(
+            &transcript("<script>alert(\"hi\")</script> & 'bye'"),
+            TranscriptFormat::Html,
+        );
+
+        assert_eq!(file.file_name, format!("ticket-{}.html", Uuid::nil()));
+        assert_eq!(file.content_type, "text/html; charset=utf-8");
+        assert!(!html.contains("<script>"));
+        assert!(html.contains(
+            "<p>&lt;script&gt;alert(&quot;hi&quot;)&lt;/script&gt; &amp; &#39;bye&#39;</p>"
+        ));
+        assert!(html.contains("<td>Ada &lt;admin&gt; (00000000-0000-0000-0000-000000000000)</td>"));
+        assert!(html.contains("<p>open → closed</p>"));
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn markdown_quotes_and_escapes_customer_text() {
+        let (file, markdown) = rendered(
+            &transcript("**urgent** [link](https://example.com)\n# not a heading | cell"),
+            TranscriptFormat::Markdown,
+        );
+
+        assert_eq!(file.file_name, format!("ticket-{}.md", Uuid::nil()));
+        assert!(markdown.contains("> \\*\\*urgent\\*\\* \\[link\\](https://example.com)\n"));
+        assert!(markdown.contains("> \\# not a heading \\| cell\n"));
+        assert!(markdown.contains("### Ada \\<admin\\> (customer) · 2023-11-14 22:13:20 UTC"));
+        assert!(markdown.contains("### Status changed by Staff member 7"));
+    }
+
+    #[test]
+    fn json_keeps_text_as_written() {
+        let (file, json) = rendered(&transcript("<b>hi</b>"), TranscriptFormat::Json);
+        let json = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+        assert_eq!(file.content_type, "application/json");
+        assert_eq!(json["status"], "closed");
+
+        let message = &json["entries"][0];
+        assert_eq!(message["kind"], "message");
+        assert_eq!(message["body"], "<b>hi</b>");
+        assert_eq!(message["author"]["role"], "customer");
+        assert_eq!(message["created_at"], 1_700_000_000);
+        assert!(message.get("created_at_micros").is_none());
+
+        let change = &json["entries"][1];
+        assert_eq!(change["kind"], "status_change");
+        assert_eq!(change["from_status"], "open");
+        assert_eq!(change["author"]["user_id"], 7);
+    }
+
+    #[test]
+    fn micros_round_to_the_nearest_second() {
+        assert_eq!(micros_to_seconds(10 * SECOND + 499_999), 10);
+        assert_eq!(micros_to_seconds(10 * SECOND + 500_000), 11);
+        assert_eq!(micros_to_seconds(-1), 0);
+        assert_eq!(micros_to_seconds(-SECOND + 499_999), -1);
+    }
+
+    #[test]
+    fn entries_within_a_second_keep_their_order() {
+        let mut entries = [3, 1, 2]
+            .into_iter()
+            .map(|offset| {
+                TranscriptEntry::new(
+                    10 * SECOND + offset,
+                    TranscriptContent::Message {
+                        message_id: None,
+                        author: customer(),
+                        body: offset.to_string(),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.created_at_micros);
+
+        let bodies = entries
+            .iter()
+            .map(|entry| match &entry.content {
+                TranscriptContent::Message { body, .. } => body.as_str(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["1", "2", "3"]);
+        assert!(entries.iter().all(|entry| entry.created_at == 10));
+
+        // rounding never moves a later entry before an earlier one
+        let micros = [0, 499_999, 500_000, 1_499_999, 1_500_000, 2 * SECOND];
+        assert!(micros
+            .windows(2)
+            .all(|pair| micros_to_seconds(pair[0]) <= micros_to_seconds(pair[1])));
+    }
+}
@@ -0,0 +1,922 @@
+//! outgoing webhooks, every published event is queued for the app's matching endpoints and
+//! delivered as a signed `POST` with retries, deliveries failing every attempt are kept as dead
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+use sqlx::types::time::OffsetDateTime;
+use sqlx::{Pool, Postgres};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
+
+use errors::{ParsingError, TicketsResult};
+use events::{TicketEvent, WebhookPayload};
+use sdk::routes::staff::{
+    CreateWebhook, DeleteWebhook, ListWebhooks, RedeliverWebhook, Webhook, WebhookDeliveries,
+    WebhookDelivery, WebhookDeliveryStatus,
+};
+use socketio_emitter::adapter::TicketsEventEmitter;
+
+use crate::axum_ext::{ApplySdkRoute, SdkRouter};
+use crate::blobs::hmac_sha256;
+use crate::pagination::Cursor;
+use crate::GlobalState;
+
+/// Id of the delivery, the same for every attempt so receivers can drop duplicates.
+pub const DELIVERY_HEADER: &str = "x-tickets-delivery";
+/// Unix timestamp in seconds of the attempt, part of the signed content.
+pub const TIMESTAMP_HEADER: &str = "x-tickets-timestamp";
+/// `v1=` followed by the hex encoded HMAC-SHA256 of `{timestamp}.{body}` keyed with the
+/// endpoint's secret.
+pub const SIGNATURE_HEADER: &str = "x-tickets-signature";
+
+// endpoints an app may register
+const MAX_WEBHOOKS_PER_APP: i64 = 10;
+
+// deliveries claimed by a single round of the worker
+const DELIVERY_BATCH_SIZE: i64 = 50;
+
+pub fn extend_router(router: SdkRouter<GlobalState>) -> SdkRouter<GlobalState> {
+    router.merge(
+        SdkRouter::new()
+            .sdk_route::<ListWebhooks>(list_webhooks::route_handler)
+            .sdk_route::<CreateWebhook>(create_webhook::route_handler)
+            .sdk_route::<DeleteWebhook>(delete_webhook::route_handler)
+            .sdk_route::<WebhookDeliveries>(webhook_deliveries::route_handler)
+            .sdk_route::<RedeliverWebhook>(redeliver_webhook::route_handler),
+    )
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Attempts before a delivery is dead.
+    pub max_attempts: u32,
+    /// Delay after the first failed attempt, doubled after every further failure.
+    pub base_delay_seconds: u64,
+    pub max_delay_seconds: u64,
+    pub timeout_seconds: u64,
+    /// How often the worker looks for deliveries due for a retry.
+    pub poll_interval_seconds: u64,
+    /// Accepts endpoints on the collector's own host, including over plain `http`. Meant for
+    /// local development, other private addresses are refused either way.
+    pub allow_loopback: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay_seconds: 30,
+            max_delay_seconds: 6 * 60 * 60,
+            timeout_seconds: 10,
+            poll_interval_seconds: 5,
+            allow_loopback: false,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Delay before the next attempt of a delivery which failed `attempts` times, `None` once
+    /// the delivery is out of attempts.
+    pub fn retry_delay(&self, attempts: u32) -> Option<Duration> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+
+        let delay = self
+            .base_delay_seconds
+            .saturating_mul(1 << attempts.saturating_sub(1).min(32));
+
+        Some(Duration::from_secs(delay.min(self.max_delay_seconds)))
+    }
+
+    /// Whether deliveries may be sent to `ip`.
+    fn allows_address(&self, ip: IpAddr) -> bool {
+        is_public(ip) || (self.allow_loopback && ip.to_canonical().is_loopback())
+    }
+}
+
+/// Refuses the addresses of private networks, link-local and loopback addresses and other
+/// ranges which are not reachable on the internet, webhooks must not reach into the network
+/// the collector runs in.
+fn is_public(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // "this network", the shared space of carrier-grade NATs and the reserved
+                // range including broadcast
+                || first == 0
+                || (first == 100 && (64..128).contains(&second))
+                || first >= 240)
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local and link-local
+                || first & 0xfe00 == 0xfc00
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+/// The value of [`SIGNATURE_HEADER`] for `body` sent at `timestamp`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let signature = hmac_sha256(secret.as_bytes(), format!("{timestamp}.{body}").as_bytes());
+    format!("v1={}", hex::encode(signature))
+}
+
+/// The address of hosts given as an ip address rather than a name.
+fn host_address(url: &Url) -> Option<IpAddr> {
+    url.host_str()?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// Webhook endpoints receive `https` requests, plain `http` is only allowed for loopback
+/// hosts when `config` allows those at all. Hosts given by name are checked as they resolve
+/// by [`validate_endpoint`] and [`EndpointResolver`].
+fn validate_url(url: &str, config: &WebhookConfig) -> TicketsResult<Url> {
+    let url = Url::parse(url).map_err(|_| {
+        ParsingError::InvalidRequest(format!("`{url}` is not a valid webhook url."))
+    })?;
+
+    let address = host_address(&url);
+    let loopback = url.host_str() == Some("localhost")
+        || address.is_some_and(|ip| ip.to_canonical().is_loopback());
+
+    match url.scheme() {
+        "https" if url.host_str().is_some() => {}
+        "http" if loopback && config.allow_loopback => {}
+        _ => Err(ParsingError::InvalidRequest(
+            "Webhook urls must use https.".to_string(),
+        ))?,
+    }
+
+    if address.is_some_and(|ip| !config.allows_address(ip)) {
+        return Err(ParsingError::InvalidRequest(format!(
+            "Webhooks cannot be sent to `{}`.",
+            url.host_str().unwrap_or_default()
+        )))?;
+    }
+
+    Ok(url)
+}
+
+/// Validates the url of a new endpoint along with every address its host resolves to.
+async fn validate_endpoint(url: &str, config: &WebhookConfig) -> TicketsResult<()> {
+    let url = validate_url(url, config)?;
+
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Ok(());
+    };
+
+    if host_address(&url).is_some() {
+        return Ok(());
+    }
+
+    let addresses = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| ParsingError::InvalidRequest(format!("`{host}` could not be resolved.")))?
+        .collect::<Vec<_>>();
+
+    if addresses.is_empty()
+        || addresses
+            .iter()
+            .any(|address| !config.allows_address(address.ip()))
+    {
+        return Err(ParsingError::InvalidRequest(format!(
+            "Webhooks cannot be sent to `{host}`."
+        )))?;
+    }
+
+    Ok(())
+}
+
+fn validate_event_types(event_types: &[String]) -> TicketsResult<()> {
+    match event_types
+        .iter()
+        .find(|event_type| !TicketEvent::EVENT_TYPES.contains(&event_type.as_str()))
+    {
+        Some(unknown) => Err(ParsingError::InvalidRequest(format!(
+            "`{unknown}` is not an event type, expected one of {}.",
+            TicketEvent::EVENT_TYPES.join(", ")
+        )))?,
+        None => Ok(()),
+    }
+}
+
+/// Resolves the hosts of endpoints for deliveries, refusing hosts which resolve to an address
+/// deliveries may not be sent to. Registered hosts may have been pointed elsewhere since.
+struct EndpointResolver {
+    config: WebhookConfig,
+}
+
+impl EndpointResolver {
+    async fn lookup(
+        config: WebhookConfig,
+        name: Name,
+    ) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+        let addresses = tokio::net::lookup_host((name.as_str(), 0))
+            .await?
+            .collect::<Vec<_>>();
+
+        if let Some(refused) = addresses
+            .iter()
+            .find(|address| !config.allows_address(address.ip()))
+        {
+            return Err(format!(
+                "`{}` resolves to {}, webhooks cannot be sent there",
+                name.as_str(),
+                refused.ip()
+            )
+            .into());
+        }
+
+        Ok(Box::new(addresses.into_iter()))
+    }
+}
+
+impl Resolve for EndpointResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(Self::lookup(self.config.clone(), name))
+    }
+}
+
+/// Publishes through `inner` and hands every event to the [`WebhookWorker`] reading the
+/// returned queue.
+pub struct WebhookEmitter {
+    inner: Arc<dyn TicketsEventEmitter + Send + Sync>,
+    events: UnboundedSender<(Uuid, TicketEvent)>,
+}
+
+impl WebhookEmitter {
+    pub fn new(
+        inner: Arc<dyn TicketsEventEmitter + Send + Sync>,
+    ) -> (Self, UnboundedReceiver<(Uuid, TicketEvent)>) {
+        let (events, receiver) = mpsc::unbounded_channel();
+        (Self { inner, events }, receiver)
+    }
+}
+
+impl TicketsEventEmitter for WebhookEmitter {
+    fn publish_tickets_event(&self, app_id: Uuid, event: TicketEvent) -> TicketsResult<()> {
+        self.inner.publish_tickets_event(app_id, event.clone())?;
+
+        // the worker only stops with the collector, the live event above already went out
+        if self.events.send((app_id, event)).is_err() {
+            log::warn!("Webhook worker stopped, dropped an event of app {app_id}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Result of a single attempt of a delivery.
+#[derive(Debug)]
+pub struct DeliveryAttempt {
+    /// Status code of the endpoint's response, if it responded at all.
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+impl DeliveryAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+pub struct WebhookSender {
+    client: Client,
+    config: WebhookConfig,
+}
+
+impl WebhookSender {
+    pub fn new(config: &WebhookConfig) -> TicketsResult<Self> {
+        // a redirect would resend the signed payload to a url nobody registered
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .redirect(Policy::none())
+            .dns_resolver(Arc::new(EndpointResolver {
+                config: config.clone(),
+            }))
+            .build()?;
+
+        Ok(Self {
+            client,
+            config: config.clone(),
+        })
+    }
+
+    /// Posts `payload` signed with `secret`, any `2xx` response counts as delivered. The url is
+    /// validated again, endpoints registered under a laxer config are not sent to.
+    pub async fn send(
+        &self,
+        url: &str,
+        secret: &str,
+        delivery_id: Uuid,
+        payload: &str,
+    ) -> DeliveryAttempt {
+        if let Err(err) = validate_url(url, &self.config) {
+            return DeliveryAttempt {
+                status_code: None,
+                error: Some(err.to_string()),
+            };
+        }
+
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .client
+            .post(url)
+            .header("content-type", "application/json")
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign_payload(secret, timestamp, payload))
+            .body(payload.to_string())
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => DeliveryAttempt {
+                status_code: Some(response.status().as_u16()),
+                error: None,
+            },
+            Ok(response) => DeliveryAttempt {
+                status_code: Some(response.status().as_u16()),
+                error: Some(format!("Endpoint responded with {}.", response.status())),
+            },
+            Err(err) => DeliveryAttempt {
+                status_code: None,
+                error: Some(describe_error(&err.without_url())),
+            },
+        }
+    }
+}
+
+/// The error along with its causes, such as the address an endpoint was refused for.
+fn describe_error(err: &dyn std::error::Error) -> String {
+    let mut description = err.to_string();
+    let mut source = err.source();
+
+    while let Some(cause) = source {
+        description.push_str(": ");
+        description.push_str(&cause.to_string());
+        source = cause.source();
+    }
+
+    description
+}
+
+/// Queues deliveries for published events and attempts those which are due. Several
+/// collectors may run workers on the same database, each delivery is claimed by one of them.
+pub struct WebhookWorker {
+    pg_client: Pool<Postgres>,
+    sender: WebhookSender,
+    config: WebhookConfig,
+}
+
+impl WebhookWorker {
+    pub fn new(pg_client: Pool<Postgres>, config: WebhookConfig) -> TicketsResult<Self> {
+        Ok(Self {
+            pg_client,
+            sender: WebhookSender::new(&config)?,
+            config,
+        })
+    }
+
+    /// Queues a delivery of `event` for every endpoint of the app subscribed to it.
+    pub async fn enqueue(&self, app_id: Uuid, event: TicketEvent) -> TicketsResult<()> {
+        let event_type = event.event_type();
+
+        let endpoints = sqlx::query!(
+            "SELECT id FROM webhook_endpoint \
+                WHERE app_id = $1 AND (event_types = '{}' OR $2 = ANY(event_types))",
+            &app_id,
+            event_type
+        )
+        .fetch_all(&self.pg_client)
+        .await?;
+
+        let created_at = Utc::now().timestamp();
+
+        for endpoint in endpoints {
+            let delivery_id = Uuid::new_v4();
+
+            let payload = serde_json::to_string(&WebhookPayload {
+                delivery_id,
+                app_id,
+                event_type: event_type.to_string(),
+                created_at,
+                event: event.clone(),
+            })?;
+
+            sqlx::query!(
+                "INSERT INTO webhook_delivery (id, app_id, endpoint_id, event_type, payload) \
+                    VALUES ($1, $2, $3, $4, $5)",
+                &delivery_id,
+                &app_id,
+                &endpoint.id,
+                event_type,
+                &payload
+            )
+            .execute(&self.pg_client)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Attempts a batch of due deliveries, returning how many were attempted.
+    pub async fn deliver_due(&self) -> TicketsResult<usize> {
+        // claimed deliveries are pushed past the attempt, another worker only picks them up
+        // again if this one dies before recording the outcome
+        let lease = self.config.timeout_seconds + 60;
+        let leased_until = (Utc::now().timestamp() as u64 + lease) as f64;
+
+        let claimed = sqlx::query!(
+            "UPDATE webhook_delivery SET next_attempt_at = to_timestamp($1) \
+                WHERE id IN (SELECT id FROM webhook_delivery \
+                        WHERE status = 'pending' AND next_attempt_at <= NOW() \
+                        ORDER BY next_attempt_at LIMIT $2) \
+                    AND status = 'pending' AND next_attempt_at <= NOW() \
+                RETURNING id, endpoint_id, attempts, payload",
+            leased_until,
+            DELIVERY_BATCH_SIZE
+        )
+        .fetch_all(&self.pg_client)
+        .await?;
+
+        if claimed.is_empty() {
+            return Ok(0);
+        }
+
+        let endpoint_ids = claimed
+            .iter()
+            .map(|delivery| delivery.endpoint_id)
+            .collect::<Vec<_>>();
+
+        let endpoints = sqlx::query!(
+            "SELECT id, url, secret FROM webhook_endpoint WHERE id = Any($1)",
+            &endpoint_ids
+        )
+        .fetch_all(&self.pg_client)
+        .await?
+        .into_iter()
+        .map(|endpoint| (endpoint.id, (endpoint.url, endpoint.secret)))
+        .collect::<HashMap<_, _>>();
+
+        let attempts = claimed.iter().filter_map(|delivery| {
+            // endpoints deleted since the claim take their deliveries with them
+            let (url, secret) = endpoints.get(&delivery.endpoint_id)?;
+
+            Some(async move {
+                let attempt = self
+                    .sender
+                    .send(url, secret, delivery.id, &delivery.payload)
+                    .await;
+
+                if let Err(err) = self
+                    .record_attempt(delivery.id, delivery.attempts as u32 + 1, attempt)
+                    .await
+                {
+                    log::error!("Recording webhook delivery {} failed: {err}", delivery.id);
+                }
+            })
+        });
+
+        futures::future::join_all(attempts).await;
+
+        Ok(claimed.len())
+    }
+
+    async fn record_attempt(
+        &self,
+        delivery_id: Uuid,
+        attempts: u32,
+        attempt: DeliveryAttempt,
+    ) -> TicketsResult<()> {
+        let now = Utc::now().timestamp();
+
+        let (status, next_attempt_at, delivered_at) = if attempt.succeeded() {
+            (WebhookDeliveryStatus::Delivered, None, Some(now as f64))
+        } else {
+            match self.config.retry_delay(attempts) {
+                Some(delay) => (
+                    WebhookDeliveryStatus::Pending,
+                    Some((now as u64 + delay.as_secs()) as f64),
+                    None,
+                ),
+                None => (WebhookDeliveryStatus::Dead, None, None),
+            }
+        };
+
+        sqlx::query!(
+            "UPDATE webhook_delivery SET status = $2, attempts = $3, \
+                    next_attempt_at = COALESCE(to_timestamp($4), next_attempt_at), \
+                    last_status_code = $5, last_error = $6, delivered_at = to_timestamp($7) \
+                WHERE id = $1",
+            &delivery_id,
+            status.to_string(),
+            attempts as i32,
+            next_attempt_at,
+            attempt.status_code.map(i32::from),
+            attempt.error,
+            delivered_at
+        )
+        .execute(&self.pg_client)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queues the events published by the [`WebhookEmitter`] owning `events` and delivers
+    /// them, running until the emitter is dropped.
+    pub async fn run(
+        self,
+        mut events: UnboundedReceiver<(Uuid, TicketEvent)>,
+    ) -> TicketsResult<()> {
+        let mut poll = tokio::time::interval(Duration::from_secs(
+            self.config.poll_interval_seconds.max(1),
+        ));
+
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    let Some((app_id, event)) = event else {
+                        return Ok(());
+                    };
+
+                    if let Err(err) = self.enqueue(app_id, event).await {
+                        log::error!("Queueing webhooks of app {app_id} failed: {err}");
+                    }
+                }
+                _ = poll.tick() => {}
+            }
+
+            if let Err(err) = self.deliver_due().await {
+                log::error!("Delivering webhooks failed: {err}");
+            }
+        }
+    }
+}
+
+struct WebhookRow {
+    id: Uuid,
+    url: String,
+    event_types: Vec<String>,
+    created_by: i64,
+    created_at: i64,
+}
+
+impl From<WebhookRow> for Webhook {
+    fn from(row: WebhookRow) -> Self {
+        Webhook {
+            webhook_id: row.id,
+            url: row.url,
+            event_types: row.event_types,
+            created_by: row.created_by as u64,
+            created_at: row.created_at,
+        }
+    }
+}
+
+struct DeliveryRow {
+    id: Uuid,
+    endpoint_id: Uuid,
+    event_type: String,
+    status: String,
+    attempts: i32,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+    next_attempt_at: i64,
+    cursor_created_at: OffsetDateTime,
+    created_at: i64,
+    delivered_at: Option<i64>,
+}
+
+impl DeliveryRow {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.cursor_created_at,
+            id: self.id,
+        }
+    }
+
+    fn into_delivery(self) -> TicketsResult<WebhookDelivery> {
+        let status = WebhookDeliveryStatus::try_from(self.status)?;
+
+        Ok(WebhookDelivery {
+            delivery_id: self.id,
+            webhook_id: self.endpoint_id,
+            event_type: self.event_type,
+            status,
+            attempts: self.attempts as u32,
+            last_status_code: self.last_status_code.map(|code| code as u16),
+            last_error: self.last_error,
+            next_attempt_at: (status == WebhookDeliveryStatus::Pending)
+                .then_some(self.next_attempt_at),
+            created_at: self.created_at,
+            delivered_at: self.delivered_at,
+        })
+    }
+}
+
+pub mod list_webhooks {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{AppPath, ListWebhooks, Webhook};
+
+    use super::WebhookRow;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<ListWebhooks>,
+    ) -> TicketsResult<Json<Vec<Webhook>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let webhooks = sqlx::query_as!(
+            WebhookRow,
+            r#"SELECT id, url, event_types, created_by,
+                    EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!"
+                FROM webhook_endpoint WHERE app_id = $1 ORDER BY created_at, id"#,
+            &app_id
+        )
+        .fetch_all(&state.pg_client)
+        .await?
+        .into_iter()
+        .map(Webhook::from)
+        .collect();
+
+        Ok(Json(webhooks))
+    }
+}
+
+pub mod create_webhook {
+    use axum::extract::State;
+    use axum::Json;
+    use uuid::Uuid;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{AppPath, CreateWebhook, CreateWebhookBody, CreatedWebhook};
+
+    use super::{WebhookRow, MAX_WEBHOOKS_PER_APP};
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(AppPath { app_id }): SdkPath<CreateWebhook>,
+        Json(body): Json<CreateWebhookBody>,
+    ) -> TicketsResult<Json<CreatedWebhook>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        super::validate_endpoint(&body.url, &state.webhooks).await?;
+        super::validate_event_types(&body.event_types)?;
+
+        let registered = sqlx::query!(
+            r#"SELECT COUNT(*) AS "count!" FROM webhook_endpoint WHERE app_id = $1"#,
+            &app_id
+        )
+        .fetch_one(&state.pg_client)
+        .await?
+        .count;
+
+        if registered >= MAX_WEBHOOKS_PER_APP {
+            return Err(ParsingError::InvalidRequest(format!(
+                "Apps are limited to {MAX_WEBHOOKS_PER_APP} webhooks."
+            )))?;
+        }
+
+        let secret = format!(
+            "whsec_{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+
+        let webhook = sqlx::query_as!(
+            WebhookRow,
+            r#"INSERT INTO webhook_endpoint (id, app_id, url, secret, event_types, created_by)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id, url, event_types, created_by,
+                    EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!""#,
+            &Uuid::new_v4(),
+            &app_id,
+            &body.url,
+            &secret,
+            &body.event_types,
+            user.user_id as i64
+        )
+        .fetch_one(&state.pg_client)
+        .await?;
+
+        Ok(Json(CreatedWebhook {
+            webhook: webhook.into(),
+            secret,
+        }))
+    }
+}
+
+pub mod delete_webhook {
+    use axum::extract::State;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{DeleteWebhook, WebhookPath};
+
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(WebhookPath { app_id, webhook_id }): SdkPath<DeleteWebhook>,
+    ) -> TicketsResult<()> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        let deleted = sqlx::query!(
+            "DELETE FROM webhook_endpoint WHERE id = $1 AND app_id = $2",
+            &webhook_id,
+            &app_id
+        )
+        .execute(&state.pg_client)
+        .await?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(sqlx::Error::RowNotFound)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub mod webhook_deliveries {
+    use axum::extract::{Query, State};
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::TicketsResult;
+    use sdk::routes::staff::{WebhookDeliveries, WebhookDelivery, WebhookPath};
+    use sdk::routes::{Page, PageQuery};
+
+    use super::DeliveryRow;
+    use crate::axum_ext::SdkPath;
+    use crate::pagination::PageRequest;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(WebhookPath { app_id, webhook_id }): SdkPath<WebhookDeliveries>,
+        Query(query): Query<PageQuery>,
+    ) -> TicketsResult<Json<Page<WebhookDelivery>>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        // fails with not found for webhooks of other apps
+        sqlx::query!(
+            "SELECT id FROM webhook_endpoint WHERE id = $1 AND app_id = $2",
+            &webhook_id,
+            &app_id
+        )
+        .fetch_one(&state.pg_client)
+        .await?;
+
+        let page = PageRequest::new(query.cursor.as_deref(), query.limit)?;
+
+        let rows = sqlx::query_as!(
+            DeliveryRow,
+            r#"SELECT id, endpoint_id, event_type, status, attempts, last_status_code, last_error,
+                    EXTRACT(EPOCH FROM next_attempt_at)::INT8 AS "next_attempt_at!",
+                    created_at AS cursor_created_at, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!",
+                    EXTRACT(EPOCH FROM delivered_at)::INT8 AS delivered_at
+                FROM webhook_delivery
+                WHERE endpoint_id = $1
+                    AND ($2::TIMESTAMPTZ IS NULL OR (created_at, id) < ($2, $3::UUID))
+                ORDER BY created_at DESC, id DESC
+                LIMIT $4"#,
+            &webhook_id,
+            page.created_at(),
+            page.id(),
+            page.fetch_limit()
+        )
+        .fetch_all(&state.pg_client)
+        .await?;
+
+        let (rows, next_cursor) = page.page(rows, DeliveryRow::cursor);
+
+        let items = rows
+            .into_iter()
+            .map(DeliveryRow::into_delivery)
+            .collect::<TicketsResult<Vec<_>>>()?;
+
+        Ok(Json(Page { items, next_cursor }))
+    }
+}
+
+pub mod redeliver_webhook {
+    use axum::extract::State;
+    use axum::Json;
+
+    use auth::{AuthedCaller, UserRole};
+    use errors::{ParsingError, TicketsResult};
+    use sdk::routes::staff::{RedeliverWebhook, WebhookDelivery, WebhookDeliveryPath};
+
+    use super::DeliveryRow;
+    use crate::axum_ext::SdkPath;
+    use crate::GlobalState;
+
+    pub async fn route_handler(
+        user: AuthedCaller,
+        State(state): State<GlobalState>,
+        SdkPath(WebhookDeliveryPath {
+            app_id,
+            webhook_id,
+            delivery_id,
+        }): SdkPath<RedeliverWebhook>,
+    ) -> TicketsResult<Json<WebhookDelivery>> {
+        let user = user.require_user()?;
+
+        state
+            .validate_user_role(&user, UserRole::Management, app_id)
+            .await?;
+
+        // pending deliveries are left alone, resetting them could attempt them twice at once
+        let delivery = sqlx::query_as!(
+            DeliveryRow,
+            r#"UPDATE webhook_delivery
+                SET status = 'pending', attempts = 0, next_attempt_at = NOW(), delivered_at = NULL
+                WHERE id = $1 AND endpoint_id = $2 AND app_id = $3 AND status != 'pending'
+                RETURNING id, endpoint_id, event_type, status, attempts, last_status_code, last_error,
+                    EXTRACT(EPOCH FROM next_attempt_at)::INT8 AS "next_attempt_at!",
+                    created_at AS cursor_created_at, EXTRACT(EPOCH FROM created_at)::INT8 AS "created_at!",
+                    EXTRACT(EPOCH FROM delivered_at)::INT8 AS delivered_at"#,
+            &delivery_id,
+            &webhook_id,
+            &app_id
+        )
+        .fetch_optional(&state.pg_client)
+        .await?;
+
+        let Some(delivery) = delivery else {
+            // tells a missing delivery apart from one which is still pending
+            sqlx::query!(
+                "SELECT id FROM webhook_delivery WHERE id = $1 AND endpoint_id = $2 AND app_id = $3",
+                &delivery_id,
+                &webhook_id,
+                &app_id
+            )
+            .fetch_one(&state.pg_client)
+            .await?;
+
+            return Err(ParsingError::InvalidRequest(
+                "The delivery is still pending.".to_string(),
+            ))?;
+        };
+
+        Ok(Json(delivery.into_delivery()?))
+    }
+}
@@ -0,0 +1,181 @@
+//! Files attached to tickets, uploaded by staff and relayed to customers by their gateway.
+
+mod common;
+
+use axum::http::HeaderValue;
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use auth::UserRole;
+use errors::{AuthorizationError, TicketsError, TicketsResult};
+use sdk::client::{
+    SdkCallWithPathAndBody, SdkDownloadWithPath, SdkUploadWithPath, SdkUploadWithPathAndParams,
+};
+use sdk::routes::consumer::{
+    Attachment, AttachmentPath, AttachmentUploader, DownloadAttachment, UploadAttachment,
+};
+use sdk::routes::staff::{
+    AppPath, AttachmentLimits, SetAttachmentLimits, TicketPath, UploadStaffAttachment,
+};
+use sdk::routes::FileData;
+use sdk::IDEMPOTENCY_KEY_HEADER;
+
+use common::{user_id, Executor, TestCollector};
+
+/// The largest size limit an app may set.
+const MAX_ATTACHMENT_SIZE: u64 = 100 * 1024 * 1024;
+
+fn file(size: usize) -> FileData {
+    FileData {
+        file_name: "dump.bin".to_string(),
+        content_type: "application/octet-stream".to_string(),
+        bytes: vec![7; size],
+    }
+}
+
+async fn allow_large_files(management: &Executor, app_id: Uuid) {
+    SetAttachmentLimits::call_with_path_and_body(
+        management,
+        AppPath { app_id },
+        AttachmentLimits {
+            max_size_bytes: MAX_ATTACHMENT_SIZE,
+            allowed_types: vec!["application/octet-stream".to_string()],
+        },
+    )
+    .await
+    .unwrap();
+}
+
+async fn upload_as_customer(executor: &Executor, path: TicketPath) -> TicketsResult<Attachment> {
+    UploadAttachment::upload_with_path_and_query(
+        executor,
+        path,
+        file(16),
+        AttachmentUploader {
+            external_id: "customer-1".to_string(),
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn large_uploads_fit_the_idempotency_buffer() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    allow_large_files(&management, app_id).await;
+
+    let ticket = collector.submit(app_id, "customer-1", "See the dump").await;
+
+    // the sdk sends an idempotency key with every upload, buffered before the route runs
+    let key = HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap();
+    let staff = management.with_header(IDEMPOTENCY_KEY_HEADER, key);
+    let path = || TicketPath {
+        app_id,
+        ticket_id: ticket.ticket_id,
+    };
+
+    let size = 65 * 1024 * 1024;
+    let uploaded = UploadStaffAttachment::upload_with_path(&staff, path(), file(size))
+        .await
+        .unwrap();
+    assert_eq!(uploaded.size_bytes, size as u64);
+
+    let retried = UploadStaffAttachment::upload_with_path(&staff, path(), file(size))
+        .await
+        .unwrap();
+    assert_eq!(retried.attachment_id, uploaded.attachment_id);
+}
+
+#[tokio::test]
+async fn only_enabled_gateways_download_attachments() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    allow_large_files(&management, app_id).await;
+
+    let ticket = collector.submit(app_id, "customer-1", "See the dump").await;
+    let uploaded = UploadStaffAttachment::upload_with_path(
+        &management,
+        TicketPath {
+            app_id,
+            ticket_id: ticket.ticket_id,
+        },
+        file(16),
+    )
+    .await
+    .unwrap();
+
+    let path = || AttachmentPath {
+        app_id,
+        attachment_id: uploaded.attachment_id,
+    };
+
+    let downloaded = DownloadAttachment::download_with_path(&collector.system(), path())
+        .await
+        .unwrap();
+    assert_eq!(downloaded.bytes, file(16).bytes);
+
+    // staff download through their own route, never as a gateway
+    let as_staff = DownloadAttachment::download_with_path(&management, path()).await;
+    assert!(matches!(
+        as_staff,
+        Err(TicketsError::Authorization(
+            AuthorizationError::UserCannotAccessResource
+        ))
+    ));
+
+    let matrix = collector.executor(JwtAccessor::MatrixSystem);
+    let disabled = DownloadAttachment::download_with_path(&matrix, path()).await;
+    assert!(matches!(
+        disabled,
+        Err(TicketsError::Authorization(
+            AuthorizationError::GatewayNotEnabled { .. }
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn customer_uploads_are_relayed_by_enabled_gateways() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    allow_large_files(&management, app_id).await;
+
+    let ticket = collector.submit(app_id, "customer-1", "See the dump").await;
+    let path = || TicketPath {
+        app_id,
+        ticket_id: ticket.ticket_id,
+    };
+
+    let uploaded = upload_as_customer(&collector.system(), path())
+        .await
+        .unwrap();
+    assert_eq!(uploaded.customer_id, Some(ticket.customer_id));
+
+    // staff upload through their own route, never as the customer
+    assert!(matches!(
+        upload_as_customer(&management, path()).await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::UserCannotAccessResource
+        ))
+    ));
+
+    // the gateway is the token's, whatever the `x-gateway` header claims
+    let matrix = collector.executor(JwtAccessor::MatrixSystem);
+    assert!(matches!(
+        upload_as_customer(&matrix, path()).await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::GatewayNotEnabled { .. }
+        ))
+    ));
+}
@@ -0,0 +1,185 @@
+//! Resolving a staff member's role in an app, from claims the collector pre-authorized or from
+//! their membership, and the tokens it issues for login codes.
+
+mod common;
+
+use std::collections::HashSet;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
+use auth::{AuthedCaller, UserRole};
+use errors::{AuthorizationError, TicketsError};
+use sdk::client::{InternalSdk, SdkCall, SdkCallWithBody, SdkCallWithPathAndParams};
+use sdk::routes::staff::{
+    AppPath, AuthorizeApps, AuthorizeAppsBody, CreateLoginCode, ExchangeLoginCode,
+    ExchangeLoginCodeBody, ListTickets, TicketsQuery,
+};
+
+use common::{scoped_executor, user_id, Executor, TestCollector};
+
+async fn list_tickets(executor: &Executor, app_id: uuid::Uuid) -> Result<(), TicketsError> {
+    ListTickets::call_with_path_and_query(
+        executor,
+        AppPath { app_id },
+        TicketsQuery {
+            cursor: None,
+            limit: 10,
+            status: None,
+            assignee_id: None,
+            search: None,
+        },
+    )
+    .await
+    .map(|_| ())
+}
+
+fn cannot_access_app(result: Result<(), TicketsError>) -> bool {
+    matches!(
+        result,
+        Err(err) if err.code() == TicketsError::from(AuthorizationError::CannotAccessApp).code()
+    )
+}
+
+#[test]
+fn only_minted_claims_keep_their_apps() {
+    let jwt = JwtConfig::for_tests();
+    let app_id = uuid::Uuid::new_v4();
+    let data = JwtData {
+        accessor: JwtAccessor::DiscordStaffMember {
+            user_id: user_id(),
+            authorized_apps: HashSet::from([app_id]),
+            role: UserRole::Staff,
+        },
+    };
+
+    let pre_authorized_apps = |claim| match AuthedCaller::from(claim) {
+        AuthedCaller::User(user) => user.pre_authorized_role(app_id, UserRole::Staff),
+        AuthedCaller::Channel(_) => unreachable!(),
+    };
+
+    let (_, minted) = jwt.generate_pre_authorized(data.clone()).unwrap();
+    assert!(minted.is_pre_authorized());
+    assert_eq!(pre_authorized_apps(minted), Some(UserRole::Staff));
+
+    // signed by a gateway or CLI holding the key, however short lived
+    let (_, self_signed) = jwt.generate(data, JwtConfig::PRE_AUTHORIZED_TTL).unwrap();
+    assert!(!self_signed.is_pre_authorized());
+    assert_eq!(pre_authorized_apps(self_signed), None);
+}
+
+#[tokio::test]
+async fn self_signed_claims_are_checked_against_membership() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+
+    // not a member of the app, claiming it in a short lived token must not grant access
+    let outsider = user_id();
+    let apps = HashSet::from([app_id]);
+
+    let short_lived = scoped_executor(
+        &collector,
+        outsider,
+        UserRole::Staff,
+        apps.clone(),
+        JwtConfig::PRE_AUTHORIZED_TTL,
+    );
+    assert!(cannot_access_app(list_tickets(&short_lived, app_id).await));
+
+    let long_lived = scoped_executor(
+        &collector,
+        outsider,
+        UserRole::Staff,
+        apps,
+        InternalSdk::DEFAULT_TTL,
+    );
+    assert!(cannot_access_app(list_tickets(&long_lived, app_id).await));
+
+    // members are still resolved from their membership
+    let member = scoped_executor(
+        &collector,
+        owner,
+        UserRole::Staff,
+        HashSet::from([app_id]),
+        JwtConfig::PRE_AUTHORIZED_TTL,
+    );
+    list_tickets(&member, app_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn authorize_apps_requires_membership() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let apps = HashSet::from([app_id]);
+
+    let authorized = AuthorizeApps::call_with_body(
+        &collector.staff(owner, UserRole::Management),
+        AuthorizeAppsBody {
+            app_ids: apps.clone(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(authorized.role, UserRole::Management);
+
+    let claim = collector
+        .state
+        .jwt_config
+        .verify_claim(&authorized.token)
+        .unwrap();
+    assert!(claim.is_pre_authorized());
+    assert_eq!(claim.exp, authorized.expiration);
+
+    let outsider = AuthorizeApps::call_with_body(
+        &collector.staff(user_id(), UserRole::Management),
+        AuthorizeAppsBody { app_ids: apps },
+    )
+    .await
+    .map(|_| ());
+    assert!(cannot_access_app(outsider));
+}
+
+#[tokio::test]
+async fn login_codes_are_exchanged_once() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let member = user_id();
+
+    let login_code = CreateLoginCode::call(&collector.staff(member, UserRole::Staff))
+        .await
+        .unwrap();
+
+    // the code is the caller's only credential, the token it is sent with is ignored
+    let outsider = collector.staff(user_id(), UserRole::Staff);
+    let exchange =
+        |code: String| ExchangeLoginCode::call_with_body(&outsider, ExchangeLoginCodeBody { code });
+
+    let issued = exchange(login_code.code.clone()).await.unwrap();
+    assert_eq!(issued.user_id, member);
+
+    let claim = collector
+        .state
+        .jwt_config
+        .verify_claim(&issued.token)
+        .unwrap();
+    assert!(!claim.is_pre_authorized());
+    assert_eq!(claim.exp, issued.expiration);
+    assert!(matches!(
+        claim.data().accessor,
+        JwtAccessor::DiscordStaffMember { user_id, role: UserRole::Staff, .. } if user_id == member
+    ));
+
+    let invalid_login_code = |result: Result<_, TicketsError>| {
+        matches!(
+            result,
+            Err(err) if err.code() == TicketsError::from(AuthorizationError::InvalidLoginCode).code()
+        )
+    };
+    assert!(invalid_login_code(exchange(login_code.code).await));
+    assert!(invalid_login_code(exchange("unknown".to_string()).await));
+}
@@ -0,0 +1,57 @@
+//! Round trips through the blob stores. The S3 backend runs against the store configured by
+//! `S3_TEST_ENDPOINT`, such as a local MinIO or `moto_server`, with an existing bucket named
+//! by `S3_TEST_BUCKET` and is skipped when the endpoint is not set.
+
+use collector::blobs::{BlobStore, LocalBlobStore, S3BlobStore, S3Config};
+use uuid::Uuid;
+
+async fn round_trip(store: &dyn BlobStore) {
+    let key = format!("{}/{}", Uuid::new_v4(), Uuid::new_v4());
+    let content = b"attachment content".to_vec();
+
+    store
+        .put(&key, "text/plain", content.clone())
+        .await
+        .unwrap();
+    assert_eq!(store.get(&key).await.unwrap(), content);
+
+    store.delete(&key).await.unwrap();
+    assert!(store.get(&key).await.is_err());
+
+    // missing blobs are already deleted
+    store.delete(&key).await.unwrap();
+}
+
+#[tokio::test]
+async fn local_blob_store() {
+    let root = std::env::temp_dir().join(format!("blob-store-{}", Uuid::new_v4()));
+    let store = LocalBlobStore::new(&root);
+
+    round_trip(&store).await;
+
+    assert!(store.get("../outside").await.is_err());
+    assert!(store.put("a//b", "text/plain", vec![]).await.is_err());
+
+    tokio::fs::remove_dir_all(&root).await.unwrap();
+}
+
+#[tokio::test]
+async fn s3_blob_store() {
+    let Ok(endpoint) = std::env::var("S3_TEST_ENDPOINT") else {
+        eprintln!("S3_TEST_ENDPOINT is not set, skipping the S3 blob store test.");
+        return;
+    };
+
+    let var = |name: &str, default: &str| std::env::var(name).unwrap_or(default.to_string());
+
+    let store = S3BlobStore::new(S3Config {
+        endpoint,
+        bucket: var("S3_TEST_BUCKET", "tickets-test"),
+        region: var("S3_TEST_REGION", "us-east-1"),
+        access_key_id: var("S3_TEST_ACCESS_KEY_ID", "minioadmin"),
+        secret_access_key: var("S3_TEST_SECRET_ACCESS_KEY", "minioadmin"),
+    })
+    .unwrap();
+
+    round_trip(&store).await;
+}
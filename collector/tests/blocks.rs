@@ -0,0 +1,127 @@
+//! Customers blocked from an app on every gateway.
+
+mod common;
+
+use uuid::Uuid;
+
+use auth::UserRole;
+use errors::{AuthorizationError, TicketsError};
+use sdk::client::{
+    SdkCallWithBody, SdkCallWithPathAndBody, SdkCallWithPathAndParams, SdkInvokeWithPath,
+};
+use sdk::routes::consumer::{SubmitTicket, SubmitTicketBody, Submitter};
+use sdk::routes::staff::{
+    AppPath, BlockCustomer, BlockCustomerBody, BlockedCustomer, BlockedCustomers, CustomerPath,
+    UnblockCustomer,
+};
+use sdk::routes::{Page, PageQuery};
+
+use common::{user_id, Executor, TestCollector, GATEWAY};
+
+async fn block(management: &Executor, app_id: Uuid, external_id: &str) -> BlockedCustomer {
+    BlockCustomer::call_with_path_and_body(
+        management,
+        AppPath { app_id },
+        BlockCustomerBody {
+            gateway: GATEWAY.to_string(),
+            external_id: external_id.to_string(),
+            reason: Some("Spam".to_string()),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn blocked_page(
+    staff: &Executor,
+    app_id: Uuid,
+    cursor: Option<String>,
+) -> Page<BlockedCustomer> {
+    BlockedCustomers::call_with_path_and_query(
+        staff,
+        AppPath { app_id },
+        PageQuery { cursor, limit: 1 },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn blocked_customers_cannot_submit() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+
+    // identities are blocked ahead of their first ticket
+    let blocked = block(&management, app_id, "customer-1").await;
+    assert_eq!(blocked.reason.as_deref(), Some("Spam"));
+
+    let submission = SubmitTicket::call_with_body(
+        &collector.system(),
+        SubmitTicketBody {
+            app_id,
+            message: "Let me in".to_string(),
+            submitter: Submitter {
+                external_id: "customer-1".to_string(),
+                display_name: None,
+            },
+        },
+    )
+    .await;
+    assert!(matches!(
+        submission,
+        Err(TicketsError::Authorization(
+            AuthorizationError::CustomerBlocked
+        ))
+    ));
+
+    let path = || CustomerPath {
+        app_id,
+        customer_id: blocked.customer_id,
+    };
+    UnblockCustomer::invoke_with_path(&management, path())
+        .await
+        .unwrap();
+
+    let submitted = collector.submit(app_id, "customer-1", "Let me in").await;
+    assert_eq!(submitted.customer_id, blocked.customer_id);
+
+    // unblocking twice finds no block
+    let missing = UnblockCustomer::invoke_with_path(&management, path()).await;
+    assert!(matches!(missing, Err(TicketsError::Network(err)) if err.code == "database.not_found"));
+}
+
+#[tokio::test]
+async fn cursors_survive_unblocking() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+
+    let first = block(&management, app_id, "customer-1").await;
+    let second = block(&management, app_id, "customer-2").await;
+
+    // newest first
+    let page = blocked_page(&management, app_id, None).await;
+    assert_eq!(page.items[0].customer_id, second.customer_id);
+
+    UnblockCustomer::invoke_with_path(
+        &management,
+        CustomerPath {
+            app_id,
+            customer_id: second.customer_id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let page = blocked_page(&management, app_id, page.next_cursor).await;
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].customer_id, first.customer_id);
+    assert_eq!(page.next_cursor, None);
+}
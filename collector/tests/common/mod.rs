@@ -0,0 +1,173 @@
+//! The collector running in-process against the PostgreSQL database named by `DATABASE_URL`,
+//! migrated on first use. Tests built on it fail when the variable is not set, unless
+//! `SKIP_DATABASE_TESTS` is set to skip them explicitly.
+
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::postgres::PgPoolOptions;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use auth::jwt::{JwtAccessor, JwtConfig};
+use auth::UserRole;
+use collector::state::GlobalState;
+use sdk::client::{MockSdkExecutor, SdkCallWithBody, SdkCallWithPathAndBody};
+use sdk::routes::consumer::{SubmitTicket, SubmitTicketBody, SubmitTicketResponse, Submitter};
+use sdk::routes::staff::{AppPath, CreateApp, CreateAppBody, ToggleGateway, ToggleGatewayBody};
+use socketio_emitter::adapter::memory::MemoryEmitter;
+
+pub type Executor = MockSdkExecutor<GlobalState>;
+
+pub const GATEWAY: &str = "discord";
+
+static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+pub struct TestCollector {
+    pub state: GlobalState,
+    pub events: Arc<MemoryEmitter>,
+}
+
+impl TestCollector {
+    /// Connects to the test database, `None` when database tests are skipped.
+    pub async fn start() -> Option<Self> {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            if std::env::var_os("SKIP_DATABASE_TESTS").is_some() {
+                eprintln!("SKIP_DATABASE_TESTS is set, skipping");
+                return None;
+            }
+
+            panic!(
+                "DATABASE_URL is not set, point it at a PostgreSQL database or set \
+                    SKIP_DATABASE_TESTS=1 to skip the tests which need one"
+            );
+        };
+
+        let pg_client = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&url)
+            .await
+            .unwrap();
+
+        MIGRATED
+            .get_or_init(|| async {
+                sqlx::migrate!()
+                    .set_locking(false)
+                    .run(&pg_client)
+                    .await
+                    .unwrap();
+            })
+            .await;
+
+        let events = Arc::new(MemoryEmitter::default());
+        let blob_root = std::env::temp_dir().join(format!("collector-test-{}", Uuid::new_v4()));
+
+        Some(Self {
+            state: GlobalState::in_process(
+                pg_client,
+                Arc::new(JwtConfig::for_tests()),
+                events.clone(),
+                blob_root,
+            ),
+            events,
+        })
+    }
+
+    /// Calls the collector as `accessor` through the `discord` gateway.
+    pub fn executor(&self, accessor: JwtAccessor) -> Executor {
+        MockSdkExecutor::new(
+            collector::app,
+            self.state.clone(),
+            self.state.jwt_config.clone(),
+            accessor,
+            GATEWAY,
+        )
+    }
+
+    pub fn staff(&self, user_id: u64, role: UserRole) -> Executor {
+        self.executor(staff_member(user_id, role))
+    }
+
+    pub fn system(&self) -> Executor {
+        self.executor(JwtAccessor::DiscordSystem)
+    }
+
+    /// Creates an app owned by `owner` with the `discord` gateway enabled.
+    pub async fn create_app(&self, owner: u64) -> Uuid {
+        let owner = self.staff(owner, UserRole::Management);
+
+        let app = CreateApp::call_with_body(
+            &owner,
+            CreateAppBody {
+                app_name: format!("test-{}", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap();
+
+        ToggleGateway::call_with_path_and_body(
+            &owner,
+            AppPath { app_id: app.app_id },
+            ToggleGatewayBody { enabled: true },
+        )
+        .await
+        .unwrap();
+
+        app.app_id
+    }
+
+    pub async fn submit(
+        &self,
+        app_id: Uuid,
+        external_id: &str,
+        message: &str,
+    ) -> SubmitTicketResponse {
+        SubmitTicket::call_with_body(
+            &self.system(),
+            SubmitTicketBody {
+                app_id,
+                message: message.to_string(),
+                submitter: Submitter {
+                    external_id: external_id.to_string(),
+                    display_name: None,
+                },
+            },
+        )
+        .await
+        .unwrap()
+    }
+}
+
+pub fn staff_member(user_id: u64, role: UserRole) -> JwtAccessor {
+    JwtAccessor::DiscordStaffMember {
+        user_id,
+        authorized_apps: HashSet::new(),
+        role,
+    }
+}
+
+/// A user id no other test uses, within the range the database stores.
+pub fn user_id() -> u64 {
+    Uuid::new_v4().as_u64_pair().0 >> 2
+}
+
+/// Signs a token claiming `authorized_apps` for `user_id`, issued with `ttl`.
+pub fn scoped_executor(
+    collector: &TestCollector,
+    user_id: u64,
+    role: UserRole,
+    authorized_apps: HashSet<Uuid>,
+    ttl: Duration,
+) -> Executor {
+    collector.system().sign_as(
+        JwtAccessor::DiscordStaffMember {
+            user_id,
+            authorized_apps,
+            role,
+        },
+        ttl,
+    )
+}
@@ -0,0 +1,94 @@
+//! Customers resolved per app, never shared with or changed by other apps.
+
+mod common;
+
+use auth::UserRole;
+use errors::TicketsError;
+use sdk::client::{SdkCallWithPath, SdkCallWithPathAndBody};
+use sdk::routes::staff::{CustomerIdentity, CustomerPath, GetCustomer, LinkCustomerIdentity};
+
+use common::{user_id, TestCollector, GATEWAY};
+
+#[tokio::test]
+async fn apps_never_share_customers() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let (first_owner, second_owner) = (user_id(), user_id());
+    let first_app = collector.create_app(first_owner).await;
+    let second_app = collector.create_app(second_owner).await;
+
+    let first = collector.submit(first_app, "customer-1", "hello").await;
+    let second = collector.submit(second_app, "customer-1", "hello").await;
+    assert_ne!(first.customer_id, second.customer_id);
+
+    let first_staff = collector.staff(first_owner, UserRole::Staff);
+    let second_staff = collector.staff(second_owner, UserRole::Staff);
+
+    // both apps link the same identity to their own customer
+    for (staff, app_id, customer_id) in [
+        (&second_staff, second_app, second.customer_id),
+        (&first_staff, first_app, first.customer_id),
+    ] {
+        LinkCustomerIdentity::call_with_path_and_body(
+            staff,
+            CustomerPath {
+                app_id,
+                customer_id,
+            },
+            CustomerIdentity {
+                gateway: "email".to_string(),
+                external_id: "customer@example.com".to_string(),
+                display_name: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let customer = GetCustomer::call_with_path(
+        &first_staff,
+        CustomerPath {
+            app_id: first_app,
+            customer_id: first.customer_id,
+        },
+    )
+    .await
+    .unwrap();
+    let identities = customer
+        .identities
+        .iter()
+        .map(|identity| (identity.gateway.as_str(), identity.external_id.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        identities,
+        vec![(GATEWAY, "customer-1"), ("email", "customer@example.com")]
+    );
+
+    // the other app's customer can't be looked up through this app
+    let foreign = GetCustomer::call_with_path(
+        &first_staff,
+        CustomerPath {
+            app_id: first_app,
+            customer_id: second.customer_id,
+        },
+    )
+    .await;
+    assert!(matches!(foreign, Err(TicketsError::Network(err)) if err.code == "database.not_found"));
+}
+
+#[tokio::test]
+async fn concurrent_first_tickets_resolve_one_customer() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let app_id = collector.create_app(user_id()).await;
+
+    let (first, second) = tokio::join!(
+        collector.submit(app_id, "customer-2", "first"),
+        collector.submit(app_id, "customer-2", "second"),
+    );
+
+    assert_ne!(first.ticket_id, second.ticket_id);
+    assert_eq!(first.customer_id, second.customer_id);
+}
@@ -0,0 +1,211 @@
+//! Historical tickets imported from other systems.
+
+mod common;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use auth::UserRole;
+use errors::{ParsingError, TicketsError};
+use sdk::client::{SdkCallWithPathAndBody, SdkDownloadWithPathAndParams};
+use sdk::routes::consumer::Submitter;
+use sdk::routes::staff::{
+    AppPath, ExportFormat, ExportQuery, ExportTickets, ExportedTicket, ImportTickets,
+    ImportTicketsBody, ImportTicketsResponse, ImportedTicket, TicketStatus,
+};
+
+use common::{user_id, Executor, TestCollector, GATEWAY};
+
+fn imported(ticket_id: Uuid, message: &str) -> ImportedTicket {
+    ImportedTicket {
+        ticket_id,
+        message: message.to_string(),
+        gateway: GATEWAY.to_string(),
+        submitter: Some(Submitter {
+            external_id: "imported-customer".to_string(),
+            display_name: None,
+        }),
+        status: TicketStatus::Closed,
+        created_at: Utc::now().timestamp() - 3600,
+    }
+}
+
+async fn import(
+    management: &Executor,
+    app_id: Uuid,
+    tickets: Vec<ImportedTicket>,
+) -> Result<ImportTicketsResponse, TicketsError> {
+    ImportTickets::call_with_path_and_body(
+        management,
+        AppPath { app_id },
+        ImportTicketsBody { tickets },
+    )
+    .await
+}
+
+async fn exported(management: &Executor, app_id: Uuid) -> Vec<ExportedTicket> {
+    let file = ExportTickets::download_with_path_and_query(
+        management,
+        AppPath { app_id },
+        ExportQuery {
+            format: ExportFormat::Jsonl,
+            from: None,
+            to: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    String::from_utf8(file.bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn assert_refused(result: Result<ImportTicketsResponse, TicketsError>, reason: &str) {
+    match result {
+        Err(TicketsError::Parsing(ParsingError::InvalidRequest(message))) => {
+            assert!(message.contains(reason), "{message}")
+        }
+        other => panic!("expected the import to be refused, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn imports_are_retried_in_place() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let ticket_id = Uuid::new_v4();
+
+    let first = import(&management, app_id, vec![imported(ticket_id, "First try")])
+        .await
+        .unwrap();
+    assert_eq!((first.created, first.updated), (1, 0));
+
+    let retried = import(&management, app_id, vec![imported(ticket_id, "Second try")])
+        .await
+        .unwrap();
+    assert_eq!((retried.created, retried.updated), (0, 1));
+
+    let tickets = exported(&management, app_id).await;
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].ticket_id, ticket_id);
+    assert_eq!(tickets[0].message, "Second try");
+    assert_eq!(tickets[0].status, TicketStatus::Closed);
+}
+
+#[tokio::test]
+async fn live_tickets_are_never_replaced() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let live = collector.submit(app_id, "customer-1", "Still open").await;
+
+    let result = import(
+        &management,
+        app_id,
+        vec![
+            imported(Uuid::new_v4(), "Unrelated"),
+            imported(live.ticket_id, "Overwritten"),
+        ],
+    )
+    .await;
+    assert_refused(result, "was not imported");
+
+    // the whole batch was refused
+    let tickets = exported(&management, app_id).await;
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].message, "Still open");
+    assert_eq!(tickets[0].status, TicketStatus::Open);
+}
+
+#[tokio::test]
+async fn tickets_of_other_apps_are_refused() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let other_app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let ticket_id = Uuid::new_v4();
+
+    import(
+        &management,
+        other_app_id,
+        vec![imported(ticket_id, "Elsewhere")],
+    )
+    .await
+    .unwrap();
+
+    let result = import(&management, app_id, vec![imported(ticket_id, "Taken")]).await;
+    assert_refused(result, "belongs to another app");
+}
+
+/// Breaks an imported ticket, along with the reason the import is refused for.
+type Invalidation = (fn(&mut ImportedTicket), &'static str);
+
+#[tokio::test]
+async fn invalid_tickets_refuse_the_batch() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let ticket_id = Uuid::new_v4();
+
+    let cases: [Invalidation; 5] = [
+        (
+            |ticket| ticket.message = " \n".to_string(),
+            "has no message",
+        ),
+        (
+            |ticket| ticket.gateway = "dis cord".to_string(),
+            "has an invalid gateway name",
+        ),
+        (
+            |ticket| ticket.created_at = 0,
+            "was not created in the past",
+        ),
+        (
+            |ticket| ticket.created_at = Utc::now().timestamp() + 3600,
+            "was not created in the past",
+        ),
+        (
+            |ticket| ticket.submitter.as_mut().unwrap().external_id.clear(),
+            "has a submitter without an external id",
+        ),
+    ];
+
+    for (invalidate, reason) in cases {
+        let mut ticket = imported(ticket_id, "Broken");
+        invalidate(&mut ticket);
+
+        let result = import(
+            &management,
+            app_id,
+            vec![imported(Uuid::new_v4(), "Fine"), ticket],
+        )
+        .await;
+        assert_refused(result, &format!("Ticket 1 ({ticket_id}) {reason}"));
+    }
+
+    let repeated = import(
+        &management,
+        app_id,
+        vec![imported(ticket_id, "Once"), imported(ticket_id, "Twice")],
+    )
+    .await;
+    assert_refused(repeated, "repeats a ticket id");
+
+    assert!(exported(&management, app_id).await.is_empty());
+}
@@ -0,0 +1,65 @@
+//! Requests sent again with the same idempotency key, replayed instead of being applied twice.
+
+mod common;
+
+use axum::http::HeaderValue;
+use uuid::Uuid;
+
+use errors::{MiscError, TicketsError, TicketsResult};
+use sdk::client::SdkCallWithBody;
+use sdk::routes::consumer::{SubmitTicket, SubmitTicketBody, SubmitTicketResponse, Submitter};
+use sdk::IDEMPOTENCY_KEY_HEADER;
+
+use common::{user_id, Executor, TestCollector};
+
+async fn submit(
+    executor: &Executor,
+    app_id: Uuid,
+    message: &str,
+) -> TicketsResult<SubmitTicketResponse> {
+    SubmitTicket::call_with_body(
+        executor,
+        SubmitTicketBody {
+            app_id,
+            message: message.to_string(),
+            submitter: Submitter {
+                external_id: "customer-1".to_string(),
+                display_name: None,
+            },
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn retried_requests_are_replayed() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let app_id = collector.create_app(user_id()).await;
+
+    let key = HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap();
+    let system = collector.system().with_header(IDEMPOTENCY_KEY_HEADER, key);
+
+    let first = submit(&system, app_id, "It broke").await.unwrap();
+    let retried = submit(&system, app_id, "It broke").await.unwrap();
+    assert_eq!(first.ticket_id, retried.ticket_id);
+}
+
+#[tokio::test]
+async fn reused_keys_are_refused() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let app_id = collector.create_app(user_id()).await;
+
+    let key = HeaderValue::from_str(&Uuid::new_v4().to_string()).unwrap();
+    let system = collector.system().with_header(IDEMPOTENCY_KEY_HEADER, key);
+
+    submit(&system, app_id, "It broke").await.unwrap();
+    let reused = submit(&system, app_id, "Something else").await;
+    assert!(matches!(
+        reused,
+        Err(TicketsError::Misc(MiscError::IdempotencyKeyReused))
+    ));
+}
@@ -0,0 +1,123 @@
+//! The collector driven end to end through `MockSdkExecutor`, with published events kept by a
+//! `MemoryEmitter` instead of a message broker.
+
+mod common;
+
+use std::sync::Arc;
+
+use auth::UserRole;
+use collector::state::GlobalState;
+use events::{TicketEvent, TicketUpdatedEvent};
+use sdk::client::{SdkCallWithBody, SdkCallWithPath, SdkCallWithPathAndBody};
+use sdk::routes::consumer::{SubmitTicket, SubmitTicketBody, Submitter};
+use sdk::routes::staff::{
+    GetTicketThread, ReplyToTicket, SetTicketStatus, SetTicketStatusBody, TicketMessageBody,
+    TicketPath, TicketStatus,
+};
+use socketio_emitter::adapter::memory::MemoryEmitter;
+
+use common::{user_id, TestCollector};
+
+#[tokio::test]
+async fn ticket_lifecycle() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let staff = collector.staff(owner, UserRole::Management);
+
+    let submitted = collector.submit(app_id, "customer-1", "It broke").await;
+    let path = || TicketPath {
+        app_id,
+        ticket_id: submitted.ticket_id,
+    };
+
+    ReplyToTicket::call_with_path_and_body(
+        &staff,
+        path(),
+        TicketMessageBody {
+            body: "Try turning it off and on again".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    SetTicketStatus::call_with_path_and_body(
+        &staff,
+        path(),
+        SetTicketStatusBody {
+            status: TicketStatus::Closed,
+        },
+    )
+    .await
+    .unwrap();
+
+    let thread = GetTicketThread::call_with_path(&staff, path())
+        .await
+        .unwrap();
+    assert_eq!(thread.ticket.message, "It broke");
+    assert_eq!(thread.ticket.status, TicketStatus::Closed);
+    assert_eq!(thread.messages.len(), 1);
+    assert_eq!(thread.status_changes.len(), 1);
+
+    let events = collector.events.take_events();
+    assert!(events
+        .iter()
+        .all(|(event_app_id, _)| *event_app_id == app_id));
+
+    let events = events
+        .into_iter()
+        .map(|(_, event)| match event {
+            TicketEvent::TicketUpdated(event) => event,
+            TicketEvent::AppChanged(event) => panic!("unexpected app change {event:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        events.as_slice(),
+        [
+            TicketUpdatedEvent::TicketSubmitted(submitted),
+            TicketUpdatedEvent::MessageAdded(reply),
+            TicketUpdatedEvent::TicketClosed(closed),
+        ] if submitted.message == "It broke"
+            && reply.from_staff
+            && closed.closed_by == owner
+    ));
+}
+
+#[tokio::test]
+async fn replaced_state_receives_later_calls() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let app_id = collector.create_app(user_id()).await;
+    let system = collector.system();
+    let submit = |message: &str| {
+        SubmitTicket::call_with_body(
+            &system,
+            SubmitTicketBody {
+                app_id,
+                message: message.to_string(),
+                submitter: Submitter {
+                    external_id: "customer-1".to_string(),
+                    display_name: None,
+                },
+            },
+        )
+    };
+
+    submit("It broke").await.unwrap();
+    assert_eq!(collector.events.take_events().len(), 1);
+
+    let replacement = Arc::new(MemoryEmitter::default());
+    system
+        .replace_state(GlobalState {
+            emitter: replacement.clone(),
+            ..collector.state.clone()
+        })
+        .await;
+
+    submit("Still broken").await.unwrap();
+    assert!(collector.events.take_events().is_empty());
+    assert_eq!(replacement.take_events().len(), 1);
+}
@@ -0,0 +1,37 @@
+//! Guards the OpenAPI document against missing routes the collector serves. The document is
+//! generated from its own list of routes, which is easily forgotten when adding one.
+
+use std::collections::BTreeSet;
+
+#[test]
+fn openapi_document_describes_every_served_route() {
+    let documented = sdk::openapi::document()["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, operations)| {
+            operations
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(move |method| (method.to_uppercase(), path.clone()))
+        })
+        .collect::<BTreeSet<(String, String)>>();
+
+    let served = collector::served_sdk_routes()
+        .into_iter()
+        .map(|(method, path)| (method, path.to_string()))
+        .collect::<BTreeSet<(String, String)>>();
+
+    let undocumented = served.difference(&documented).collect::<Vec<_>>();
+    assert!(
+        undocumented.is_empty(),
+        "routes missing from `registered_routes!` in lib/sdk/src/openapi.rs: {undocumented:?}"
+    );
+
+    let unserved = documented.difference(&served).collect::<Vec<_>>();
+    assert!(
+        unserved.is_empty(),
+        "documented routes the collector does not serve: {unserved:?}"
+    );
+}
@@ -0,0 +1,47 @@
+//! Staff members provisioned on their first request.
+
+mod common;
+
+use uuid::Uuid;
+
+use auth::UserRole;
+use sdk::client::{MockSdkExecutor, SdkCallWithBody};
+use sdk::routes::staff::{CreateApp, CreateAppBody};
+
+use common::{staff_member, user_id, TestCollector};
+
+#[tokio::test]
+async fn identities_are_only_provisioned_on_the_callers_gateway() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let user_id = user_id();
+
+    // the header names another gateway than the one the token was issued for
+    let executor = MockSdkExecutor::new(
+        collector::app,
+        collector.state.clone(),
+        collector.state.jwt_config.clone(),
+        staff_member(user_id, UserRole::Management),
+        "matrix",
+    );
+    CreateApp::call_with_body(
+        &executor,
+        CreateAppBody {
+            app_name: format!("test-{}", Uuid::new_v4()),
+        },
+    )
+    .await
+    .unwrap();
+
+    let gateways = sqlx::query_scalar::<_, String>(
+        "SELECT gateway FROM user_identity WHERE user_id = $1 ORDER BY gateway",
+    )
+    .bind(user_id as i64)
+    .fetch_all(&collector.state.pg_client)
+    .await
+    .unwrap();
+
+    assert_eq!(gateways, vec!["discord".to_string()]);
+    assert!(collector.state.provisioned_users.contains_key(&user_id));
+}
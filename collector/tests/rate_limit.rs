@@ -0,0 +1,129 @@
+//! Submissions counted against the app's rate limit once they would be accepted.
+
+mod common;
+
+use uuid::Uuid;
+
+use auth::UserRole;
+use errors::{AuthorizationError, MiscError, TicketsError, TicketsResult};
+use sdk::client::{SdkCallWithBody, SdkCallWithPathAndBody, SdkInvokeWithPath};
+use sdk::routes::consumer::{SubmitTicket, SubmitTicketBody, SubmitTicketResponse, Submitter};
+use sdk::routes::staff::{
+    AppPath, BlockCustomer, BlockCustomerBody, CustomerPath, RateLimit, SetRateLimit,
+    UnblockCustomer,
+};
+
+use collector::rate_limit::GATEWAY_LIMIT_FACTOR;
+use common::{user_id, TestCollector, GATEWAY};
+
+async fn submit(collector: &TestCollector, app_id: Uuid) -> TicketsResult<SubmitTicketResponse> {
+    submit_as(collector, app_id, "customer-1").await
+}
+
+async fn submit_as(
+    collector: &TestCollector,
+    app_id: Uuid,
+    external_id: &str,
+) -> TicketsResult<SubmitTicketResponse> {
+    SubmitTicket::call_with_body(
+        &collector.system(),
+        SubmitTicketBody {
+            app_id,
+            message: format!("It broke, {}", Uuid::new_v4()),
+            submitter: Submitter {
+                external_id: external_id.to_string(),
+                display_name: None,
+            },
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn rejected_submissions_keep_the_quota() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+
+    SetRateLimit::call_with_path_and_body(
+        &management,
+        AppPath { app_id },
+        RateLimit {
+            max_tickets: 1,
+            window_seconds: 60,
+        },
+    )
+    .await
+    .unwrap();
+
+    let blocked = BlockCustomer::call_with_path_and_body(
+        &management,
+        AppPath { app_id },
+        BlockCustomerBody {
+            gateway: GATEWAY.to_string(),
+            external_id: "customer-1".to_string(),
+            reason: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    for _ in 0..2 {
+        assert!(matches!(
+            submit(&collector, app_id).await,
+            Err(TicketsError::Authorization(
+                AuthorizationError::CustomerBlocked
+            ))
+        ));
+    }
+
+    UnblockCustomer::invoke_with_path(
+        &management,
+        CustomerPath {
+            app_id,
+            customer_id: blocked.customer_id,
+        },
+    )
+    .await
+    .unwrap();
+
+    submit(&collector, app_id).await.unwrap();
+    assert!(matches!(
+        submit(&collector, app_id).await,
+        Err(TicketsError::Misc(MiscError::RateLimited { .. }))
+    ));
+}
+
+#[tokio::test]
+async fn rotating_external_ids_hits_the_gateway_limit() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+
+    SetRateLimit::call_with_path_and_body(
+        &collector.staff(owner, UserRole::Management),
+        AppPath { app_id },
+        RateLimit {
+            max_tickets: 1,
+            window_seconds: 60,
+        },
+    )
+    .await
+    .unwrap();
+
+    for i in 0..GATEWAY_LIMIT_FACTOR {
+        submit_as(&collector, app_id, &format!("customer-{i}"))
+            .await
+            .unwrap();
+    }
+
+    assert!(matches!(
+        submit_as(&collector, app_id, "customer-rotated").await,
+        Err(TicketsError::Misc(MiscError::RateLimited { .. }))
+    ));
+}
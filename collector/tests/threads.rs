@@ -0,0 +1,60 @@
+//! Messages customers add to their tickets, relayed by the gateway which carries the ticket.
+
+mod common;
+
+use auth::jwt::JwtAccessor;
+use auth::UserRole;
+use errors::{AuthorizationError, TicketsError, TicketsResult};
+use sdk::client::SdkCallWithPathAndBody;
+use sdk::routes::consumer::{CustomerMessageBody, SendCustomerMessage, TicketMessage};
+use sdk::routes::staff::TicketPath;
+
+use common::{user_id, Executor, TestCollector};
+
+async fn send_as_customer(executor: &Executor, path: TicketPath) -> TicketsResult<TicketMessage> {
+    SendCustomerMessage::call_with_path_and_body(
+        executor,
+        path,
+        CustomerMessageBody {
+            external_id: "customer-1".to_string(),
+            body: "Still broken".to_string(),
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn customer_messages_are_relayed_by_enabled_gateways() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+
+    let ticket = collector.submit(app_id, "customer-1", "It broke").await;
+    let path = || TicketPath {
+        app_id,
+        ticket_id: ticket.ticket_id,
+    };
+
+    let sent = send_as_customer(&collector.system(), path()).await.unwrap();
+    assert_eq!(sent.customer_id, Some(ticket.customer_id));
+
+    // staff reply through their own route, never as the customer
+    let management = collector.staff(owner, UserRole::Management);
+    assert!(matches!(
+        send_as_customer(&management, path()).await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::UserCannotAccessResource
+        ))
+    ));
+
+    // the gateway is the token's, whatever the `x-gateway` header claims
+    let matrix = collector.executor(JwtAccessor::MatrixSystem);
+    assert!(matches!(
+        send_as_customer(&matrix, path()).await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::GatewayNotEnabled { .. }
+        ))
+    ));
+}
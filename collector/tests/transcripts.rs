@@ -0,0 +1,67 @@
+//! Transcripts downloaded by staff, and by gateways archiving the tickets they carried.
+
+mod common;
+
+use auth::jwt::JwtAccessor;
+use auth::UserRole;
+use errors::{AuthorizationError, TicketsError};
+use sdk::client::SdkDownloadWithPathAndParams;
+use sdk::routes::staff::{TicketPath, TicketTranscript, TranscriptFormat, TranscriptQuery};
+use sdk::routes::FileData;
+
+use common::{user_id, Executor, TestCollector};
+
+async fn transcript(executor: &Executor, path: TicketPath) -> Result<FileData, TicketsError> {
+    TicketTranscript::download_with_path_and_query(
+        executor,
+        path,
+        TranscriptQuery {
+            format: TranscriptFormat::Json,
+            include_notes: true,
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn gateways_download_transcripts_of_their_own_tickets() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+
+    let ticket = collector.submit(app_id, "customer-1", "It broke").await;
+    let path = || TicketPath {
+        app_id,
+        ticket_id: ticket.ticket_id,
+    };
+
+    transcript(&collector.staff(owner, UserRole::Staff), path())
+        .await
+        .unwrap();
+    transcript(&collector.system(), path()).await.unwrap();
+
+    // the gateway is the token's, whatever the `x-gateway` header claims
+    let matrix = collector.executor(JwtAccessor::MatrixSystem);
+    assert!(matches!(
+        transcript(&matrix, path()).await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::GatewayNotEnabled { .. }
+        ))
+    ));
+
+    // enabled gateways still only archive the tickets submitted through them
+    sqlx::query("INSERT INTO gateway (name, app_id) VALUES ('matrix', $1)")
+        .bind(app_id)
+        .execute(&collector.state.pg_client)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        transcript(&matrix, path()).await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::UserCannotAccessResource
+        ))
+    ));
+}
@@ -0,0 +1,536 @@
+//! Webhook deliveries against a local HTTP stand-in which records what it receives and
+//! answers with the status codes it is told to.
+
+mod common;
+
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use auth::UserRole;
+use collector::webhooks::{
+    WebhookConfig, WebhookSender, WebhookWorker, DELIVERY_HEADER, SIGNATURE_HEADER,
+    TIMESTAMP_HEADER,
+};
+use errors::{ParsingError, TicketsError};
+use events::{TicketClosedEvent, TicketEvent};
+use sdk::client::{
+    SdkCallWithPath, SdkCallWithPathAndBody, SdkCallWithPathAndParams, SdkInvokeWithPath,
+};
+use sdk::routes::staff::{
+    AppPath, CreateWebhook, CreateWebhookBody, DeleteWebhook, RedeliverWebhook, WebhookDeliveries,
+    WebhookDelivery, WebhookDeliveryPath, WebhookDeliveryStatus, WebhookPath,
+};
+use sdk::routes::{Page, PageQuery};
+use test_support::StandIn;
+
+use common::{user_id, Executor, TestCollector};
+
+// workers claim every due delivery of the database, tests running them take turns so no
+// worker attempts the deliveries of another test
+static WORKERS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+type Endpoint = StandIn<(HeaderMap, String), StatusCode>;
+
+async fn receive(State(endpoint): State<Endpoint>, headers: HeaderMap, body: String) -> StatusCode {
+    endpoint.record((headers, body));
+    endpoint.next_reply().unwrap_or(StatusCode::NO_CONTENT)
+}
+
+async fn serve(endpoint: Endpoint) -> String {
+    let address = test_support::serve(
+        Router::new()
+            .route("/hook", post(receive))
+            .with_state(endpoint),
+    )
+    .await;
+
+    format!("http://{address}/hook")
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+    headers.get(name).unwrap().to_str().unwrap()
+}
+
+fn loopback_config() -> WebhookConfig {
+    WebhookConfig {
+        timeout_seconds: 5,
+        allow_loopback: true,
+        ..Default::default()
+    }
+}
+
+fn closed() -> TicketEvent {
+    TicketClosedEvent {
+        ticket_id: Uuid::new_v4(),
+        closed_by: 1,
+    }
+    .into()
+}
+
+/// The collector with loopback endpoints allowed.
+async fn start() -> Option<TestCollector> {
+    let mut collector = TestCollector::start().await?;
+    collector.state.webhooks = loopback_config();
+    Some(collector)
+}
+
+async fn register(management: &Executor, app_id: Uuid, url: &str) -> Result<Uuid, TicketsError> {
+    let created = CreateWebhook::call_with_path_and_body(
+        management,
+        AppPath { app_id },
+        CreateWebhookBody {
+            url: url.to_string(),
+            event_types: Vec::new(),
+        },
+    )
+    .await?;
+
+    Ok(created.webhook.webhook_id)
+}
+
+async fn deliveries_page(
+    management: &Executor,
+    app_id: Uuid,
+    webhook_id: Uuid,
+    cursor: Option<String>,
+    limit: u32,
+) -> Result<Page<WebhookDelivery>, TicketsError> {
+    WebhookDeliveries::call_with_path_and_query(
+        management,
+        WebhookPath { app_id, webhook_id },
+        PageQuery { cursor, limit },
+    )
+    .await
+}
+
+async fn deliveries(management: &Executor, app_id: Uuid, webhook_id: Uuid) -> Vec<WebhookDelivery> {
+    deliveries_page(management, app_id, webhook_id, None, 100)
+        .await
+        .unwrap()
+        .items
+}
+
+/// The only delivery of the endpoint.
+async fn delivery(management: &Executor, app_id: Uuid, webhook_id: Uuid) -> WebhookDelivery {
+    let mut deliveries = deliveries(management, app_id, webhook_id).await;
+    assert_eq!(deliveries.len(), 1);
+    deliveries.remove(0)
+}
+
+async fn unregister(management: &Executor, app_id: Uuid, webhook_id: Uuid) {
+    DeleteWebhook::invoke_with_path(management, WebhookPath { app_id, webhook_id })
+        .await
+        .unwrap();
+}
+
+fn assert_invalid(result: Result<impl std::fmt::Debug, TicketsError>) {
+    assert!(
+        matches!(
+            result,
+            Err(TicketsError::Parsing(ParsingError::InvalidRequest(_)))
+        ),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn signed_delivery() {
+    let stand_in = Endpoint::default();
+    stand_in.reply_with(StatusCode::INTERNAL_SERVER_ERROR);
+    let url = serve(stand_in.clone()).await;
+
+    let sender = WebhookSender::new(&loopback_config()).unwrap();
+    let delivery_id = Uuid::new_v4();
+    let payload = r#"{"event_type":"TicketClosed"}"#;
+
+    let failed = sender.send(&url, "whsec_test", delivery_id, payload).await;
+    assert!(!failed.succeeded());
+    assert_eq!(failed.status_code, Some(500));
+
+    let delivered = sender.send(&url, "whsec_test", delivery_id, payload).await;
+    assert!(delivered.succeeded());
+    assert_eq!(delivered.status_code, Some(204));
+
+    let received = stand_in.received();
+    assert_eq!(received.len(), 2);
+
+    for (headers, body) in received.iter() {
+        assert_eq!(body, payload);
+        assert_eq!(header(headers, DELIVERY_HEADER), delivery_id.to_string());
+
+        // verified the way a receiver would, independently of the collector's signing
+        let timestamp = header(headers, TIMESTAMP_HEADER);
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"whsec_test").unwrap();
+        mac.update(format!("{timestamp}.{body}").as_bytes());
+        let expected = format!("v1={}", hex::encode(mac.finalize().into_bytes()));
+
+        assert_eq!(header(headers, SIGNATURE_HEADER), expected);
+    }
+}
+
+#[tokio::test]
+async fn unreachable_endpoint() {
+    // bound and dropped, nothing listens on the port afterwards
+    let (listener, address) = test_support::listen().await;
+    drop(listener);
+
+    let sender = WebhookSender::new(&loopback_config()).unwrap();
+    let attempt = sender
+        .send(
+            &format!("http://{address}/hook"),
+            "whsec_test",
+            Uuid::new_v4(),
+            "{}",
+        )
+        .await;
+
+    assert!(!attempt.succeeded());
+    assert_eq!(attempt.status_code, None);
+}
+
+#[tokio::test]
+async fn private_endpoints_are_not_sent_to() {
+    let stand_in = Endpoint::default();
+    let url = serve(stand_in.clone()).await;
+    let port = url.split(':').nth(2).unwrap();
+
+    let sender = WebhookSender::new(&WebhookConfig {
+        timeout_seconds: 5,
+        ..Default::default()
+    })
+    .unwrap();
+
+    // registered while loopback endpoints were allowed
+    let attempt = sender.send(&url, "whsec_test", Uuid::new_v4(), "{}").await;
+    assert!(!attempt.succeeded());
+    assert_eq!(attempt.status_code, None);
+
+    // registered by name, before the name was pointed at the collector's own host
+    let attempt = sender
+        .send(
+            &format!("https://localhost:{port}/hook"),
+            "whsec_test",
+            Uuid::new_v4(),
+            "{}",
+        )
+        .await;
+    assert!(attempt
+        .error
+        .unwrap()
+        .contains("webhooks cannot be sent there"));
+
+    assert!(stand_in.received().is_empty());
+}
+
+#[tokio::test]
+async fn endpoint_urls_are_validated() {
+    let Some(collector) = TestCollector::start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+
+    for url in [
+        "not a url",
+        "ftp://example.com/hook",
+        "http://93.184.216.34/hook",
+        "http://127.0.0.1:8080/hook",
+        "https://localhost/hook",
+        "https://127.0.0.1/hook",
+        "https://10.0.0.1/hook",
+        "https://172.16.0.1/hook",
+        "https://192.168.1.1/hook",
+        "https://100.64.0.1/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://0.0.0.0/hook",
+        "https://[::1]/hook",
+        "https://[fe80::1]/hook",
+        "https://[fd00::1]/hook",
+        "https://[::ffff:10.0.0.1]/hook",
+    ] {
+        assert_invalid(register(&management, app_id, url).await);
+    }
+
+    let public = register(&management, app_id, "https://93.184.216.34/hook")
+        .await
+        .unwrap();
+    unregister(&management, app_id, public).await;
+
+    // loopback hosts are only accepted when the collector allows them
+    let Some(collector) = start().await else {
+        return;
+    };
+    let management = collector.staff(owner, UserRole::Management);
+
+    let loopback = register(&management, app_id, "http://127.0.0.1:8080/hook")
+        .await
+        .unwrap();
+    unregister(&management, app_id, loopback).await;
+
+    assert_invalid(register(&management, app_id, "http://10.0.0.1/hook").await);
+    assert_invalid(register(&management, app_id, "https://192.168.1.1/hook").await);
+}
+
+#[tokio::test]
+async fn deliveries_are_claimed_once() {
+    let Some(collector) = start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let _workers = WORKERS.lock().await;
+
+    let stand_in = Endpoint::default();
+    let webhook_id = register(&management, app_id, &serve(stand_in.clone()).await)
+        .await
+        .unwrap();
+
+    let worker = || WebhookWorker::new(collector.state.pg_client.clone(), loopback_config());
+    let (first, second) = (worker().unwrap(), worker().unwrap());
+
+    for _ in 0..3 {
+        first.enqueue(app_id, closed()).await.unwrap();
+    }
+
+    let (claimed, also_claimed) = tokio::join!(first.deliver_due(), second.deliver_due());
+    claimed.unwrap();
+    also_claimed.unwrap();
+
+    assert_eq!(stand_in.received().len(), 3);
+
+    let deliveries = deliveries(&management, app_id, webhook_id).await;
+    assert_eq!(deliveries.len(), 3);
+    assert!(deliveries.iter().all(|delivery| {
+        delivery.status == WebhookDeliveryStatus::Delivered
+            && delivery.attempts == 1
+            && delivery.last_status_code == Some(204)
+            && delivery.next_attempt_at.is_none()
+            && delivery.delivered_at.is_some()
+    }));
+
+    unregister(&management, app_id, webhook_id).await;
+}
+
+#[tokio::test]
+async fn claimed_deliveries_are_leased() {
+    let Some(collector) = start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let _workers = WORKERS.lock().await;
+
+    // accepts connections without ever responding
+    let (listener, address) = test_support::listen().await;
+    let url = format!("http://{address}/hook");
+    let webhook_id = register(&management, app_id, &url).await.unwrap();
+
+    let config = loopback_config();
+    let worker = || WebhookWorker::new(collector.state.pg_client.clone(), config.clone());
+    let (first, second) = (worker().unwrap(), worker().unwrap());
+
+    first.enqueue(app_id, closed()).await.unwrap();
+
+    let attempting = tokio::spawn(async move { first.deliver_due().await });
+    let (connection, _) = listener.accept().await.unwrap();
+
+    // the attempt is in flight, the delivery is pushed past it
+    let leased = delivery(&management, app_id, webhook_id).await;
+    assert_eq!(leased.status, WebhookDeliveryStatus::Pending);
+    assert_eq!(leased.attempts, 0);
+    assert!(
+        leased.next_attempt_at.unwrap() >= Utc::now().timestamp() + config.timeout_seconds as i64
+    );
+
+    second.deliver_due().await.unwrap();
+    let another = tokio::time::timeout(Duration::from_millis(500), listener.accept()).await;
+    assert!(another.is_err(), "the leased delivery was attempted twice");
+
+    drop(connection);
+    attempting.await.unwrap().unwrap();
+
+    let failed = delivery(&management, app_id, webhook_id).await;
+    assert_eq!(failed.status, WebhookDeliveryStatus::Pending);
+    assert_eq!(failed.attempts, 1);
+    assert_eq!(failed.last_status_code, None);
+    assert!(failed.last_error.is_some());
+
+    unregister(&management, app_id, webhook_id).await;
+}
+
+#[tokio::test]
+async fn failing_deliveries_die_until_redelivered() {
+    let Some(collector) = start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+    let _workers = WORKERS.lock().await;
+
+    let stand_in = Endpoint::default();
+    stand_in.reply_with(StatusCode::INTERNAL_SERVER_ERROR);
+    stand_in.reply_with(StatusCode::BAD_GATEWAY);
+    let webhook_id = register(&management, app_id, &serve(stand_in.clone()).await)
+        .await
+        .unwrap();
+
+    // retried right away, dead after the second failure
+    let worker = WebhookWorker::new(
+        collector.state.pg_client.clone(),
+        WebhookConfig {
+            max_attempts: 2,
+            base_delay_seconds: 0,
+            ..loopback_config()
+        },
+    )
+    .unwrap();
+
+    worker.enqueue(app_id, closed()).await.unwrap();
+    for _ in 0..3 {
+        worker.deliver_due().await.unwrap();
+    }
+
+    assert_eq!(stand_in.received().len(), 2);
+
+    let dead = delivery(&management, app_id, webhook_id).await;
+    assert_eq!(dead.status, WebhookDeliveryStatus::Dead);
+    assert_eq!(dead.attempts, 2);
+    assert_eq!(dead.last_status_code, Some(502));
+    assert_eq!(dead.next_attempt_at, None);
+
+    let path = || WebhookDeliveryPath {
+        app_id,
+        webhook_id,
+        delivery_id: dead.delivery_id,
+    };
+
+    let redelivered = RedeliverWebhook::call_with_path(&management, path())
+        .await
+        .unwrap();
+    assert_eq!(redelivered.status, WebhookDeliveryStatus::Pending);
+    assert_eq!(redelivered.attempts, 0);
+
+    // pending deliveries are not reset
+    assert_invalid(RedeliverWebhook::call_with_path(&management, path()).await);
+
+    worker.deliver_due().await.unwrap();
+
+    let delivered = delivery(&management, app_id, webhook_id).await;
+    assert_eq!(delivered.status, WebhookDeliveryStatus::Delivered);
+    assert_eq!(delivered.attempts, 1);
+    assert_eq!(delivered.last_status_code, Some(204));
+    assert_eq!(stand_in.received().len(), 3);
+
+    unregister(&management, app_id, webhook_id).await;
+}
+
+#[tokio::test]
+async fn delivery_log_pages() {
+    let Some(collector) = start().await else {
+        return;
+    };
+    let owner = user_id();
+    let app_id = collector.create_app(owner).await;
+    let management = collector.staff(owner, UserRole::Management);
+
+    let url = serve(Endpoint::default()).await;
+    let webhook_id = register(&management, app_id, &url).await.unwrap();
+    let other_webhook_id = register(&management, app_id, &url).await.unwrap();
+
+    // queued without being attempted
+    let worker = WebhookWorker::new(collector.state.pg_client.clone(), loopback_config()).unwrap();
+    for _ in 0..3 {
+        worker.enqueue(app_id, closed()).await.unwrap();
+    }
+
+    let first = deliveries_page(&management, app_id, webhook_id, None, 2)
+        .await
+        .unwrap();
+    assert_eq!(first.items.len(), 2);
+
+    let second = deliveries_page(&management, app_id, webhook_id, first.next_cursor, 2)
+        .await
+        .unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.next_cursor, None);
+
+    // newest first, every delivery once
+    let pages = first.items.iter().chain(&second.items).collect::<Vec<_>>();
+    assert!(pages
+        .windows(2)
+        .all(|pair| pair[0].created_at >= pair[1].created_at));
+    assert!(pages
+        .iter()
+        .all(|delivery| delivery.webhook_id == webhook_id));
+
+    let mut ids = pages
+        .iter()
+        .map(|delivery| delivery.delivery_id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 3);
+
+    // cursors are positions, continuing another endpoint's log from one still lists this log
+    let foreign_cursor = deliveries_page(&management, app_id, other_webhook_id, None, 1)
+        .await
+        .unwrap()
+        .next_cursor;
+    let foreign = deliveries_page(&management, app_id, webhook_id, foreign_cursor, 2)
+        .await
+        .unwrap();
+    assert!(foreign
+        .items
+        .iter()
+        .all(|delivery| delivery.webhook_id == webhook_id));
+
+    assert_invalid(
+        deliveries_page(
+            &management,
+            app_id,
+            webhook_id,
+            Some("latest".to_string()),
+            2,
+        )
+        .await,
+    );
+
+    // webhooks are only found through their own app
+    let other_app_id = collector.create_app(owner).await;
+    let elsewhere = deliveries_page(&management, other_app_id, webhook_id, None, 2).await;
+    assert!(
+        matches!(elsewhere, Err(TicketsError::Network(err)) if err.code == "database.not_found")
+    );
+
+    unregister(&management, app_id, webhook_id).await;
+    unregister(&management, app_id, other_webhook_id).await;
+}
+
+#[test]
+fn retry_backoff() {
+    let config = WebhookConfig {
+        max_attempts: 5,
+        base_delay_seconds: 10,
+        max_delay_seconds: 60,
+        ..Default::default()
+    };
+
+    let delays = (1..=5)
+        .map(|attempts| config.retry_delay(attempts).map(|delay| delay.as_secs()))
+        .collect::<Vec<_>>();
+
+    // doubled after every failure, capped, and dead once out of attempts
+    assert_eq!(delays, [Some(10), Some(20), Some(40), Some(60), None]);
+}
@@ -0,0 +1,9 @@
+-- Channels carrying a ticket's conversation with the customer who submitted it
+CREATE TABLE IF NOT EXISTS discord_ticket_channels
+(
+    channel_id  INT8        NOT NULL PRIMARY KEY,
+    ticket_id   UUID        NOT NULL UNIQUE,
+    app_id      UUID        NOT NULL,
+    customer_id INT8        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -1,27 +1,26 @@
-use crate::channels::ChannelPurpose;
+use crate::attachments;
 use crate::commands::{
-    setup_application_commands, setup_default_commands, setup_management_commands, ParsedCommand,
+    setup_application_commands, setup_consumer_commands, setup_default_commands,
+    setup_management_commands, ParsedCommand,
 };
 use crate::guilds::GuildPurpose;
-use crate::{respond, SharedAppState};
+use crate::shared_state::SharedAppState;
+use crate::{respond, tickets};
 use errors::{ParsingError, TicketsError, TicketsResult};
+use gateway::Listener;
 use serenity::all::{
-    ChannelId, CommandInteraction, ComponentInteraction, GuildId, Http, Interaction,
+    CommandInteraction, ComponentInteraction, GuildId, Http, Interaction, Message,
     ModalInteraction, PingInteraction, Ready, ResumedEvent,
 };
 use serenity::prelude::{Context, EventHandler};
-use socketio_client::TicketsWebsocketClientExt;
 use sqlx::{Pool, Postgres};
 use std::collections::HashSet;
 use uuid::Uuid;
 
 pub struct AppState {
-    #[allow(dead_code)]
-    pub(super) app_changes_client: socketio_client::Client,
-    #[allow(dead_code)]
-    pub(super) ticket_event_client: socketio_client::Client,
-    pub(super) shared_state: SharedAppState,
-    pub(super) pg_pool: Pool<Postgres>,
+    pub listener: Listener,
+    pub shared_state: SharedAppState,
+    pub pg_pool: Pool<Postgres>,
 }
 
 impl AppState {
@@ -44,6 +43,7 @@ impl AppState {
                 // todo relay missing messages
             }
             GuildPurpose::Consumer => {
+                setup_consumer_commands(http, guild_id).await?;
                 // todo retrieve existing channels
                 // todo retrieve app categories
                 // todo ensure consumer channels
@@ -139,29 +139,39 @@ impl EventHandler for AppState {
             }
         }
 
-        let listeners = guild_data.iter().fold(
-            HashSet::with_capacity(guild_data.len()),
-            |mut listeners, (_, _, app_id)| {
-                listeners.insert(app_id);
-                listeners
-            },
-        );
-
-        for listener in listeners {
-            if let Err(err) = self.app_changes_client.listen_to(*listener, None).await {
-                log::error!("Error listening to app changes for {listener}: {err}")
-            }
-
-            if let Err(err) = self.ticket_event_client.listen_to(*listener, None).await {
-                log::error!("Error listening to ticket events for {listener}: {err}")
-            }
-        }
+        let app_ids = guild_data.iter().map(|(_, _, app_id)| *app_id);
+        self.listener
+            .listen_to_all(app_ids.collect::<HashSet<_>>())
+            .await;
     }
 
     async fn resume(&self, ctx: Context, _: ResumedEvent) {
         self.shared_state.set_http(ctx.http.clone()).await;
     }
 
+    async fn message(&self, _: Context, message: Message) {
+        if message.author.bot {
+            return;
+        }
+
+        if let Err(err) =
+            tickets::relay_customer_message(&self.shared_state, &self.pg_pool, &message).await
+        {
+            log::error!("Error relaying message {}: {}", message.id, err);
+        }
+
+        if let Err(err) =
+            attachments::relay_customer_attachments(&self.shared_state, &self.pg_pool, &message)
+                .await
+        {
+            log::error!(
+                "Error relaying attachments of message {}: {}",
+                message.id,
+                err
+            );
+        }
+    }
+
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         let http = ctx.http.clone();
         if let Err((err, interaction_id, token)) = match interaction {
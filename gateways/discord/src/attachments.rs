@@ -0,0 +1,128 @@
+//! relays files between ticket channels and the collector, customers' attachments are
+//! uploaded to their ticket and staff attachments are posted back to the channel
+
+use std::time::Duration;
+
+use serenity::all::{CreateAttachment, CreateMessage, Message};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use errors::TicketsResult;
+use events::AttachmentAddedEvent;
+use sdk::client::{SdkDownloadWithPath, SdkUploadWithPathAndParams};
+use sdk::routes::consumer::{
+    AttachmentPath, AttachmentUploader, DownloadAttachment, UploadAttachment,
+};
+use sdk::routes::staff::TicketPath;
+use sdk::routes::FileData;
+
+use crate::shared_state::SharedAppState;
+use crate::ticket_channels;
+
+// covers downloading and uploading the largest attachments
+const SYSTEM_TOKEN_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Uploads the attachments of a customer's message in their ticket channel, other messages
+/// are ignored.
+pub async fn relay_customer_attachments(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    message: &Message,
+) -> TicketsResult<()> {
+    if message.attachments.is_empty() {
+        return Ok(());
+    }
+
+    let ticket =
+        ticket_channels::find_customer_ticket(pg_pool, message.channel_id, message.author.id)
+            .await?;
+
+    let Some(ticket) = ticket else {
+        return Ok(());
+    };
+
+    let client = state
+        .sdk
+        .sign_client(JwtAccessor::DiscordSystem, SYSTEM_TOKEN_TTL)?;
+
+    for attachment in &message.attachments {
+        let file = FileData {
+            file_name: attachment.filename.clone(),
+            content_type: attachment
+                .content_type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+            bytes: attachment.download().await?,
+        };
+
+        let uploaded = UploadAttachment::upload_with_path_and_query(
+            &client,
+            TicketPath {
+                app_id: ticket.app_id,
+                ticket_id: ticket.ticket_id,
+            },
+            file,
+            AttachmentUploader {
+                external_id: message.author.id.get().to_string(),
+            },
+        )
+        .await;
+
+        // files refused by the app's limits are reported back rather than dropped silently
+        if let Err(err) = uploaded {
+            let http = state.require_http().await?;
+            message
+                .channel_id
+                .say(
+                    &http,
+                    format!(
+                        "`{}` was not attached to the ticket: {err}",
+                        attachment.filename
+                    ),
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Posts an attachment added by staff to the ticket's channel, if the ticket has one.
+pub async fn post_staff_attachment(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    app_id: Uuid,
+    event: &AttachmentAddedEvent,
+) -> TicketsResult<()> {
+    let channel = ticket_channels::find_channel(pg_pool, app_id, event.ticket_id).await?;
+
+    let Some(channel) = channel else {
+        return Ok(());
+    };
+
+    let client = state
+        .sdk
+        .sign_client(JwtAccessor::DiscordSystem, SYSTEM_TOKEN_TTL)?;
+
+    let file = DownloadAttachment::download_with_path(
+        &client,
+        AttachmentPath {
+            app_id,
+            attachment_id: event.attachment_id,
+        },
+    )
+    .await?;
+
+    let http = state.require_http().await?;
+    channel
+        .send_message(
+            &http,
+            CreateMessage::new()
+                .content("Staff attached a file to your ticket.")
+                .add_file(CreateAttachment::bytes(file.bytes, file.file_name)),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -68,4 +68,4 @@ impl TryFrom<String> for ChannelPurpose {
     }
 }
 
-pub type ChannelCache = crate::cache::GenericIdCache<ChannelId, ChannelPurpose>;
+pub type ChannelCache = gateway::cache::GenericIdCache<ChannelId, ChannelPurpose>;
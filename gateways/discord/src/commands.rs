@@ -14,8 +14,11 @@ use crate::impl_interactable;
 use crate::interactions::InteractionContext;
 use crate::shared_state::SharedAppState;
 
+mod block;
 mod bootstrap;
+mod cli_login;
 mod dispose;
+mod open_ticket;
 mod promote_staff;
 
 pub struct CommandArgs {
@@ -59,6 +62,9 @@ impl ParsedCommand {
             CommandType::Bootstrap => bootstrap::run_command(self).await,
             CommandType::Dispose => dispose::run_command(self).await,
             CommandType::PromoteStaff => promote_staff::run_command(self).await,
+            CommandType::Block => block::run_command(self).await,
+            CommandType::CliLogin => cli_login::run_command(self).await,
+            CommandType::OpenTicket => open_ticket::run_command(self).await,
         }
     }
 }
@@ -70,6 +76,11 @@ pub enum CommandType {
     Bootstrap,
     Dispose,
     PromoteStaff,
+    // staff
+    Block,
+    CliLogin,
+    // consumer
+    OpenTicket,
 }
 
 impl Display for CommandType {
@@ -78,6 +89,9 @@ impl Display for CommandType {
             CommandType::Bootstrap => write!(f, "bootstrap"),
             CommandType::PromoteStaff => write!(f, "promote-staff"),
             CommandType::Dispose => write!(f, "dispose"),
+            CommandType::Block => write!(f, "block"),
+            CommandType::CliLogin => write!(f, "cli-login"),
+            CommandType::OpenTicket => write!(f, "ticket"),
         }
     }
 }
@@ -96,6 +110,9 @@ impl TryFrom<String> for CommandType {
             "bootstrap" => CommandType::Bootstrap,
             "promote-staff" => CommandType::PromoteStaff,
             "dispose" => CommandType::Dispose,
+            "block" => CommandType::Block,
+            "cli-login" => CommandType::CliLogin,
+            "ticket" => CommandType::OpenTicket,
             _ => Err(ParsingError::InvalidCommandType(value))?,
         })
     }
@@ -203,13 +220,47 @@ pub async fn setup_default_commands(http: &Http, guild_id: GuildId) -> TicketsRe
     Ok(())
 }
 
-#[allow(unused_variables)]
 pub async fn setup_application_commands(http: &Http, guild_id: GuildId) -> TicketsResult<()> {
     log::info!("Setting up application commands for guild {}", guild_id);
     commands! {
         guild_id,
         http,
+        CommandType::Block => {
+            description("Block a customer from submitting tickets on any gateway.")
+            add_option(
+                command_option! {
+                    CommandOptionType::User,
+                    "user", "The customer to block, the ticket's customer in a ticket channel."
+                }
+            )
+            add_option(
+                command_option! {
+                    "reason", "Why the customer is blocked, visible to staff."
+                }
+            )
+            default_member_permissions(Permissions::BAN_MEMBERS)
+        }
+    }
+    Ok(())
+}
+
+pub async fn setup_consumer_commands(http: &Http, guild_id: GuildId) -> TicketsResult<()> {
+    log::info!("Setting up consumer commands for guild {}", guild_id);
+    commands! {
+        guild_id,
+        http,
+        CommandType::OpenTicket => {
+            description("Open a support ticket in a channel of its own.")
+            add_option(
+                command_option! {
+                    "message", "What do you need help with?" => {
+                        required(true)
+                    }
+                }
+            )
+        }
     }
+
     Ok(())
 }
 
@@ -246,6 +297,9 @@ pub async fn setup_management_commands(http: &Http, guild_id: GuildId) -> Ticket
             )
             default_member_permissions(Permissions::ADMINISTRATOR)
         }
+        CommandType::CliLogin => {
+            description("Create a code to log in to the `tt` command line client.")
+        }
     }
 
     Ok(())
@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+use serenity::all::CommandDataOptionValue;
+
+use auth::UserRole;
+use errors::{MiscError, ParsingError, TicketsResult};
+use gateway::interactions::Interaction;
+use gateway::Gateway;
+use sdk::client::SdkCallWithPathAndBody;
+use sdk::routes::staff::{AppPath, BlockCustomer, BlockCustomerBody};
+
+use crate::commands::ParsedCommand;
+use crate::guilds::GuildPurpose;
+use crate::interactions::Interactable;
+use crate::realtime::DiscordGateway;
+use crate::respond;
+use crate::ticket_channels;
+
+pub async fn run_command(mut command: ParsedCommand) -> TicketsResult<()> {
+    let guild_id = command.require_guild_id()?;
+    let state = command.state();
+
+    // ticket channels live in the consumer guild, management may block from its own guild too
+    let app_id = match state
+        .guild_cache
+        .get_app_id(&guild_id, GuildPurpose::Consumer)
+        .await
+    {
+        Some(app_id) => app_id,
+        None => state
+            .guild_cache
+            .get_app_id(&guild_id, GuildPurpose::Management)
+            .await
+            .ok_or(MiscError::GuildDataNotFound)?,
+    };
+
+    // in a ticket channel, its customer is blocked unless another user is given
+    let target = match command.pop_command_arg("user") {
+        Some(CommandDataOptionValue::User(target)) => target,
+        _ => ticket_channels::find_ticket(&state.pg_pool, command.channel_id())
+            .await?
+            .filter(|ticket| ticket.app_id == app_id)
+            .map(|ticket| ticket.customer_id)
+            .ok_or_else(|| {
+                ParsingError::InvalidRequest(
+                    "A user to block is required outside of ticket channels.".to_string(),
+                )
+            })?,
+    };
+
+    let reason = match command.pop_command_arg("reason") {
+        Some(CommandDataOptionValue::String(reason)) => Some(reason),
+        _ => None,
+    };
+
+    // the collector checks the member's role in the app when scoping their token to it
+    let client = command
+        .staff_client(UserRole::Management, HashSet::from([app_id]))
+        .await?;
+
+    BlockCustomer::call_with_path_and_body(
+        &client,
+        AppPath { app_id },
+        BlockCustomerBody {
+            gateway: DiscordGateway::NAME.to_string(),
+            external_id: target.get().to_string(),
+            reason,
+        },
+    )
+    .await?;
+
+    respond!(
+        command.http(),
+        command.interaction_id(),
+        command.token(),
+        message {
+            ephemeral(true)
+            content(format!("Blocked <@{target}> from submitting tickets."))
+        }
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use auth::UserRole;
+use errors::TicketsResult;
+use gateway::interactions::Interaction;
+use sdk::client::SdkCall;
+use sdk::routes::staff::CreateLoginCode;
+
+use crate::commands::ParsedCommand;
+use crate::interactions::Interactable;
+use crate::respond;
+
+pub async fn run_command(command: ParsedCommand) -> TicketsResult<()> {
+    let client = command
+        .users()
+        .staff(command.identity(), UserRole::Staff)
+        .await?;
+
+    let login_code = CreateLoginCode::call(&client).await?;
+
+    // the code signs in as the member, only they see it
+    respond!(
+        command.http(),
+        command.interaction_id(),
+        command.token(),
+        message {
+            ephemeral(true)
+            content(format!(
+                "Run `tt login --code {}` <t:{}:R>, the code works once.",
+                login_code.code, login_code.expiration
+            ))
+        }
+    )?;
+
+    Ok(())
+}
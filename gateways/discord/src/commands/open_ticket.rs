@@ -0,0 +1,57 @@
+use serenity::all::CommandDataOptionValue;
+
+use errors::{MiscError, ParsingError, TicketsResult};
+
+use crate::commands::ParsedCommand;
+use crate::guilds::GuildPurpose;
+use crate::interactions::Interactable;
+use crate::respond;
+use crate::tickets::{self, OpenedTicket};
+
+pub async fn run_command(mut command: ParsedCommand) -> TicketsResult<()> {
+    let guild_id = command.require_guild_id()?;
+    let state = command.state();
+
+    let app_id = state
+        .guild_cache
+        .get_app_id(&guild_id, GuildPurpose::Consumer)
+        .await
+        .ok_or(MiscError::GuildDataNotFound)?;
+
+    let Some(CommandDataOptionValue::String(message)) = command.pop_command_arg("message") else {
+        return Err(ParsingError::InvalidRequest(
+            "A message describing the issue is required.".to_string(),
+        ))?;
+    };
+
+    let opened = tickets::open_ticket(
+        &state,
+        &state.pg_pool,
+        guild_id,
+        app_id,
+        command.user(),
+        message,
+    )
+    .await?;
+
+    let content = match opened {
+        OpenedTicket::Opened(channel_id) => {
+            format!("Your ticket was opened in <#{channel_id}>.")
+        }
+        OpenedTicket::AlreadyOpen(channel_id) => {
+            format!("You already have a ticket open in <#{channel_id}>.")
+        }
+    };
+
+    respond!(
+        command.http(),
+        command.interaction_id(),
+        command.token(),
+        message {
+            ephemeral(true)
+            content(content)
+        }
+    )?;
+
+    Ok(())
+}
@@ -39,4 +39,4 @@ impl TryFrom<String> for GuildPurpose {
     }
 }
 
-pub type GuildCache = crate::cache::GenericIdCache<GuildId, GuildPurpose>;
+pub type GuildCache = gateway::cache::GenericIdCache<GuildId, GuildPurpose>;
@@ -1,7 +1,10 @@
+use crate::realtime::DiscordGateway;
 use crate::shared_state::SharedAppState;
 use errors::{MiscError, TicketsResult};
+use gateway::interactions::Interaction;
+use gateway::users::UsersCache;
 use serenity::all::{
-    ChannelId, CommandInteraction, Context, GuildId, InteractionId, ModalInteraction, User,
+    ChannelId, CommandInteraction, Context, GuildId, InteractionId, ModalInteraction, User, UserId,
 };
 use std::sync::Arc;
 
@@ -134,7 +137,8 @@ pub struct InteractionContext {
     pub token: String,
 }
 
-pub trait Interactable {
+/// The Discord details of an interaction, next to the [`Interaction`] every gateway shares.
+pub trait Interactable: Interaction<DiscordGateway> {
     fn http(&self) -> Arc<serenity::http::Http>;
 
     fn state(&self) -> SharedAppState;
@@ -200,6 +204,16 @@ macro_rules! impl_interactable {
                 self.$field.require_member()
             }
         }
+
+        impl$(<$($generics)*>)? gateway::interactions::Interaction<$crate::realtime::DiscordGateway> for $type$(<$($generics)*>)? {
+            fn identity(&self) -> &serenity::model::id::UserId {
+                gateway::interactions::Interaction::identity(&self.$field)
+            }
+
+            fn users(&self) -> &gateway::users::UsersCache<$crate::realtime::DiscordGateway> {
+                gateway::interactions::Interaction::users(&self.$field)
+            }
+        }
     };
 }
 
@@ -230,6 +244,16 @@ impl InteractionContext {
     }
 }
 
+impl Interaction<DiscordGateway> for InteractionContext {
+    fn identity(&self) -> &UserId {
+        &self.user.id
+    }
+
+    fn users(&self) -> &UsersCache<DiscordGateway> {
+        &self.state.users
+    }
+}
+
 impl Interactable for InteractionContext {
     fn http(&self) -> Arc<serenity::http::Http> {
         self.context.http.clone()
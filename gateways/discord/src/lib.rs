@@ -0,0 +1,18 @@
+//! a Discord gateway: customers open tickets with `/ticket` in an app's consumer guild, each
+//! ticket gets a channel shared with the customer who opened it, staff replies are posted there
+//! and the channel is archived to the staff logs once the ticket closes
+
+#![feature(variant_count)]
+
+pub mod app;
+pub mod attachments;
+pub mod channels;
+pub mod commands;
+pub mod guilds;
+pub mod interactions;
+pub mod modals;
+pub mod realtime;
+pub mod roles;
+pub mod shared_state;
+pub mod ticket_channels;
+pub mod tickets;
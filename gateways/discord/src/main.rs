@@ -1,32 +1,17 @@
-#![feature(variant_count)]
-
 use std::sync::Arc;
-use std::time::Duration;
 
 use serenity::all::GatewayIntents;
 
-use app::AppState;
-use auth::jwt::{JwtAccessor, JwtConfig, JwtData, JwtKeyPathsConfig};
+use discord_tickets::app::AppState;
+use discord_tickets::realtime::DiscordGateway;
+use discord_tickets::shared_state::SharedAppState;
 use errors::TicketsResult;
-use socketio_client::{AppChangesNamespace, TicketNamespace, TicketSocketConfig};
-
-use crate::shared_state::SharedAppState;
-
-mod app;
-mod cache;
-mod channels;
-mod commands;
-mod guilds;
-mod interactions;
-mod realtime;
-mod shared_state;
-mod modals;
+use gateway::{Bootstrap, GatewayConfig};
 
 #[derive(serde::Deserialize)]
 pub struct DiscordTicketsConfig {
-    collector_url: String,
-    realtime_events_url: Option<String>,
-    jwt: JwtKeyPathsConfig,
+    #[serde(flatten)]
+    gateway: GatewayConfig,
 }
 
 #[tokio::main]
@@ -38,44 +23,25 @@ async fn main() -> TicketsResult<()> {
     let pg_pool = dry::database::connect().await?;
     sqlx::migrate!().set_locking(false).run(&pg_pool).await?;
 
-    let jwt_config: JwtConfig = config.jwt.try_into()?;
-    let jwt_config: Arc<JwtConfig> = Arc::new(jwt_config);
-
-    let socket_config = TicketSocketConfig {
-        server_url: config
-            .realtime_events_url
-            .unwrap_or_else(|| config.collector_url.to_string()),
-        token: jwt_config
-            .generate(
-                JwtData {
-                    accessor: JwtAccessor::DiscordSystem,
-                },
-                // only needs to be valid during the time of authentication
-                // the system will run re-authentication requests for updated
-                // token claim security requirements
-                Duration::from_secs(60),
-            )?
-            .0,
-    };
-
-    let (app_changes_client, app_changes_receiver) =
-        socketio_client::connect::<AppChangesNamespace>(&socket_config).await?;
+    let bootstrap = Bootstrap::<DiscordGateway>::new(config.gateway)?;
+    let subscription = bootstrap.subscribe().await?;
 
-    let (ticket_event_client, ticket_event_receiver) =
-        socketio_client::connect::<TicketNamespace>(&socket_config).await?;
-
-    let shared_app_state: SharedAppState = Default::default();
+    let shared_app_state =
+        SharedAppState::new(bootstrap.sdk.clone(), bootstrap.users(), pg_pool.clone());
 
     let mut discord_client: serenity::Client = {
         let token = std::env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
 
-        let intents = GatewayIntents::GUILD_MEMBERS | GatewayIntents::DIRECT_MESSAGES;
+        // message content carries the attachments relayed from ticket channels
+        let intents = GatewayIntents::GUILD_MEMBERS
+            | GatewayIntents::DIRECT_MESSAGES
+            | GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::MESSAGE_CONTENT;
 
         let app_state = AppState {
-            app_changes_client,
-            ticket_event_client,
+            listener: subscription.listener.clone(),
             shared_state: shared_app_state.clone(),
-            pg_pool,
+            pg_pool: pg_pool.clone(),
         };
 
         serenity::Client::builder(&token, intents)
@@ -84,17 +50,13 @@ async fn main() -> TicketsResult<()> {
             .expect("Error creating client")
     };
 
-    let realtime_app_changes =
-        realtime::app_changes::read_app_changes(app_changes_receiver, shared_app_state.clone());
-
-    let realtime_ticket_events = realtime::ticket_events::read_ticket_events(
-        ticket_event_receiver,
-        shared_app_state.clone(),
-    );
+    let realtime_events = subscription.run(Arc::new(DiscordGateway {
+        shared_state: shared_app_state,
+        pg_pool,
+    }));
 
     tokio::select! {
-        res = realtime_app_changes => res??,
-        res = realtime_ticket_events => res??,
+        res = realtime_events => res??,
         res = discord_client.start() => res?,
     }
 
@@ -1,2 +1,54 @@
-pub mod app_changes;
-pub mod ticket_events;
\ No newline at end of file
+use serenity::all::UserId;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use auth::UserRole;
+use errors::TicketsResult;
+use events::TicketUpdatedEvent;
+use gateway::Gateway;
+
+use crate::shared_state::SharedAppState;
+use crate::{attachments, tickets};
+
+pub struct DiscordGateway {
+    pub shared_state: SharedAppState,
+    pub pg_pool: Pool<Postgres>,
+}
+
+impl Gateway for DiscordGateway {
+    const NAME: &'static str = "discord";
+    const SYSTEM_ACCESSOR: JwtAccessor = JwtAccessor::DiscordSystem;
+    const APP_CHANGES: bool = true;
+
+    type Identity = UserId;
+
+    fn staff_accessor(user_id: &UserId, role: UserRole) -> Option<JwtAccessor> {
+        Some(JwtAccessor::DiscordStaffMember {
+            user_id: user_id.get(),
+            authorized_apps: Default::default(),
+            role,
+        })
+    }
+
+    async fn handle_ticket_event(
+        &self,
+        app_id: Uuid,
+        event: TicketUpdatedEvent,
+    ) -> TicketsResult<()> {
+        match &event {
+            TicketUpdatedEvent::AttachmentAdded(event) if event.from_staff => {
+                attachments::post_staff_attachment(&self.shared_state, &self.pg_pool, app_id, event)
+                    .await
+            }
+            TicketUpdatedEvent::MessageAdded(event) if event.from_staff => {
+                tickets::post_staff_reply(&self.shared_state, &self.pg_pool, app_id, event).await
+            }
+            TicketUpdatedEvent::TicketClosed(event) => {
+                tickets::close_ticket_channel(&self.shared_state, &self.pg_pool, app_id, event)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+}
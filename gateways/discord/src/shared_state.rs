@@ -1,30 +1,52 @@
 use crate::channels::ChannelCache;
 use crate::guilds::GuildCache;
+use crate::realtime::DiscordGateway;
 use errors::{MiscError, TicketsResult};
+use gateway::users::UsersCache;
+use sdk::client::InternalSdk;
 use serenity::all::Http;
+use sqlx::{Pool, Postgres};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct SharedAppState {
     http: Arc<RwLock<Option<Arc<Http>>>>,
     pub guild_cache: GuildCache,
     pub channel_cache: ChannelCache,
+    /// Signs collector clients on behalf of the members invoking commands.
+    pub sdk: InternalSdk,
+    pub users: UsersCache<DiscordGateway>,
+    /// Reaches the ticket channels from commands, such as when `/ticket` creates one.
+    pub pg_pool: Pool<Postgres>,
 }
 
 impl SharedAppState {
+    pub fn new(
+        sdk: InternalSdk,
+        users: UsersCache<DiscordGateway>,
+        pg_pool: Pool<Postgres>,
+    ) -> Self {
+        Self {
+            http: Default::default(),
+            guild_cache: Default::default(),
+            channel_cache: Default::default(),
+            sdk,
+            users,
+            pg_pool,
+        }
+    }
+
     pub async fn set_http(&self, new_http: Arc<Http>) {
         let mut write = self.http.write().await;
         *write = Some(new_http);
     }
 
-    pub async fn _http(&self) -> Option<Arc<Http>> {
+    pub async fn http(&self) -> Option<Arc<Http>> {
         self.http.read().await.clone()
     }
 
-    pub async fn _require_http(&self) -> TicketsResult<Arc<Http>> {
-        self._http()
-            .await
-            .ok_or(MiscError::MissingHttpClient.into())
+    pub async fn require_http(&self) -> TicketsResult<Arc<Http>> {
+        self.http().await.ok_or(MiscError::MissingHttpClient.into())
     }
 }
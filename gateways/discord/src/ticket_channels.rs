@@ -0,0 +1,155 @@
+//! the channels carrying tickets' conversations in consumer guilds, each is created under the
+//! app's tickets category for the customer who opened the ticket and kept until it closes
+
+use serenity::all::{
+    ChannelId, ChannelType, CreateChannel, GuildId, PermissionOverwrite, PermissionOverwriteType,
+    Permissions, User, UserId,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use errors::{MiscError, TicketsResult};
+
+use crate::channels::ChannelPurpose;
+use crate::shared_state::SharedAppState;
+
+/// The ticket a channel carries.
+pub struct TicketChannel {
+    pub ticket_id: Uuid,
+    pub app_id: Uuid,
+    pub customer_id: UserId,
+}
+
+/// What the customer may do in their ticket's channel, nobody else but staff sees it.
+const CUSTOMER_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::ATTACH_FILES)
+    .union(Permissions::READ_MESSAGE_HISTORY);
+
+/// Creates the channel of a ticket `customer` just submitted and binds it to the ticket, the
+/// customer's messages and attachments there are relayed to the ticket from then on.
+pub async fn create(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    guild_id: GuildId,
+    app_id: Uuid,
+    ticket_id: Uuid,
+    customer: &User,
+) -> TicketsResult<ChannelId> {
+    let category = state
+        .channel_cache
+        .get_id(app_id, ChannelPurpose::TicketsCategory)
+        .await
+        .ok_or(MiscError::GuildDataNotFound)?;
+
+    let http = state.require_http().await?;
+    let channel = guild_id
+        .create_channel(
+            &http,
+            CreateChannel::new(format!("ticket-{}", &ticket_id.simple().to_string()[..8]))
+                .kind(ChannelType::Text)
+                .category(category)
+                .topic(format!("Support ticket {ticket_id}"))
+                .permissions([
+                    PermissionOverwrite {
+                        allow: Permissions::empty(),
+                        deny: Permissions::VIEW_CHANNEL,
+                        kind: PermissionOverwriteType::Role(guild_id.everyone_role()),
+                    },
+                    PermissionOverwrite {
+                        allow: CUSTOMER_PERMISSIONS,
+                        deny: Permissions::empty(),
+                        kind: PermissionOverwriteType::Member(customer.id),
+                    },
+                ]),
+        )
+        .await?;
+
+    sqlx::query!(
+        "INSERT INTO discord_ticket_channels (channel_id, ticket_id, app_id, customer_id) VALUES ($1, $2, $3, $4)",
+        channel.id.get() as i64,
+        ticket_id,
+        app_id,
+        customer.id.get() as i64
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(channel.id)
+}
+
+/// The channel of the ticket `customer_id` has open in the app, if any.
+pub async fn find_customer_channel(
+    pg_pool: &Pool<Postgres>,
+    app_id: Uuid,
+    customer_id: u64,
+) -> TicketsResult<Option<ChannelId>> {
+    let channel = sqlx::query!(
+        "SELECT channel_id FROM discord_ticket_channels WHERE app_id = $1 AND customer_id = $2",
+        app_id,
+        customer_id as i64
+    )
+    .fetch_optional(pg_pool)
+    .await?;
+
+    Ok(channel.map(|channel| ChannelId::new(channel.channel_id as u64)))
+}
+
+/// The ticket `channel_id` carries, if it is a ticket channel.
+pub async fn find_ticket(
+    pg_pool: &Pool<Postgres>,
+    channel_id: ChannelId,
+) -> TicketsResult<Option<TicketChannel>> {
+    let ticket = sqlx::query!(
+        "SELECT ticket_id, app_id, customer_id FROM discord_ticket_channels WHERE channel_id = $1",
+        channel_id.get() as i64
+    )
+    .fetch_optional(pg_pool)
+    .await?;
+
+    Ok(ticket.map(|ticket| TicketChannel {
+        ticket_id: ticket.ticket_id,
+        app_id: ticket.app_id,
+        customer_id: UserId::new(ticket.customer_id as u64),
+    }))
+}
+
+/// The ticket of `channel_id` when `customer_id` is the customer who opened it, so that what
+/// others, such as staff, write in the channel is not taken for the customer's.
+pub async fn find_customer_ticket(
+    pg_pool: &Pool<Postgres>,
+    channel_id: ChannelId,
+    customer_id: UserId,
+) -> TicketsResult<Option<TicketChannel>> {
+    let ticket = find_ticket(pg_pool, channel_id).await?;
+    Ok(ticket.filter(|ticket| ticket.customer_id == customer_id))
+}
+
+/// The channel of a ticket of the app, if it has one.
+pub async fn find_channel(
+    pg_pool: &Pool<Postgres>,
+    app_id: Uuid,
+    ticket_id: Uuid,
+) -> TicketsResult<Option<ChannelId>> {
+    let channel = sqlx::query!(
+        "SELECT channel_id FROM discord_ticket_channels WHERE ticket_id = $1 AND app_id = $2",
+        ticket_id,
+        app_id
+    )
+    .fetch_optional(pg_pool)
+    .await?;
+
+    Ok(channel.map(|channel| ChannelId::new(channel.channel_id as u64)))
+}
+
+/// Unbinds a deleted channel from its ticket.
+pub async fn remove(pg_pool: &Pool<Postgres>, channel_id: ChannelId) -> TicketsResult<()> {
+    sqlx::query!(
+        "DELETE FROM discord_ticket_channels WHERE channel_id = $1",
+        channel_id.get() as i64
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! the conversation in ticket channels, `/ticket` opens a ticket in a channel of its own, the
+//! customer's messages there are added to the ticket, staff replies are posted back and the
+//! channel is archived to the staff logs once the ticket closes
+
+use std::time::Duration;
+
+use serenity::all::{ChannelId, CreateAttachment, CreateMessage, GuildId, Message, User};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use errors::{MiscError, TicketsResult};
+use events::{MessageAddedEvent, TicketClosedEvent};
+use sdk::client::{SdkCallWithBody, SdkCallWithPathAndBody, SdkDownloadWithPathAndParams};
+use sdk::routes::consumer::{
+    CustomerMessageBody, SendCustomerMessage, SubmitTicket, SubmitTicketBody, Submitter,
+};
+use sdk::routes::staff::{TicketPath, TicketTranscript, TranscriptFormat, TranscriptQuery};
+
+use crate::channels::ChannelPurpose;
+use crate::shared_state::SharedAppState;
+use crate::ticket_channels;
+
+// only needs to outlive the single collector call
+const SYSTEM_TOKEN_TTL: Duration = Duration::from_secs(60);
+
+const OPENED_NOTICE: &str =
+    "your ticket was opened. Staff replies are posted in this channel and your messages and files here are added to the ticket.";
+
+/// Where the ticket a customer asked to open is continued.
+pub enum OpenedTicket {
+    Opened(ChannelId),
+    /// The customer already has a ticket open in the app, they are pointed to its channel.
+    AlreadyOpen(ChannelId),
+}
+
+/// Submits a ticket for `customer` from the app's consumer guild and creates its channel.
+pub async fn open_ticket(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    guild_id: GuildId,
+    app_id: Uuid,
+    customer: &User,
+    message: String,
+) -> TicketsResult<OpenedTicket> {
+    if let Some(channel_id) =
+        ticket_channels::find_customer_channel(pg_pool, app_id, customer.id.get()).await?
+    {
+        return Ok(OpenedTicket::AlreadyOpen(channel_id));
+    }
+
+    let client = state
+        .sdk
+        .sign_client(JwtAccessor::DiscordSystem, SYSTEM_TOKEN_TTL)?;
+
+    let submitted = SubmitTicket::call_with_body(
+        &client,
+        SubmitTicketBody {
+            app_id,
+            message,
+            submitter: Submitter {
+                external_id: customer.id.get().to_string(),
+                display_name: Some(
+                    customer
+                        .global_name
+                        .clone()
+                        .unwrap_or_else(|| customer.name.clone()),
+                ),
+            },
+        },
+    )
+    .await?;
+
+    let ticket_id = submitted.ticket_id;
+    let channel_id =
+        ticket_channels::create(state, pg_pool, guild_id, app_id, ticket_id, customer).await?;
+
+    let http = state.require_http().await?;
+    channel_id
+        .say(&http, format!("<@{}>, {OPENED_NOTICE}", customer.id))
+        .await?;
+
+    log::info!(
+        "Submitted ticket {ticket_id} for {} to app {app_id} in channel {channel_id}",
+        customer.id
+    );
+
+    Ok(OpenedTicket::Opened(channel_id))
+}
+
+/// Adds the text of a customer's message in their ticket channel to the ticket, other
+/// messages are ignored.
+pub async fn relay_customer_message(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    message: &Message,
+) -> TicketsResult<()> {
+    if message.content.trim().is_empty() {
+        return Ok(());
+    }
+
+    let ticket =
+        ticket_channels::find_customer_ticket(pg_pool, message.channel_id, message.author.id)
+            .await?;
+
+    let Some(ticket) = ticket else {
+        return Ok(());
+    };
+
+    let client = state
+        .sdk
+        .sign_client(JwtAccessor::DiscordSystem, SYSTEM_TOKEN_TTL)?;
+
+    let sent = SendCustomerMessage::call_with_path_and_body(
+        &client,
+        TicketPath {
+            app_id: ticket.app_id,
+            ticket_id: ticket.ticket_id,
+        },
+        CustomerMessageBody {
+            external_id: message.author.id.get().to_string(),
+            body: message.content.clone(),
+        },
+    )
+    .await;
+
+    // such as messages written while the channel of a closed ticket is being archived
+    if let Err(err) = sent {
+        let http = state.require_http().await?;
+        message
+            .channel_id
+            .say(
+                &http,
+                format!("Your message was not added to the ticket: {err}"),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Posts a staff reply to the ticket's channel, if the ticket has one.
+pub async fn post_staff_reply(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    app_id: Uuid,
+    event: &MessageAddedEvent,
+) -> TicketsResult<()> {
+    let channel = ticket_channels::find_channel(pg_pool, app_id, event.ticket_id).await?;
+
+    let Some(channel) = channel else {
+        return Ok(());
+    };
+
+    let http = state.require_http().await?;
+    channel
+        .send_message(&http, CreateMessage::new().content(&event.body))
+        .await?;
+
+    Ok(())
+}
+
+/// Posts the transcript of a closed ticket to the app's staff logs and deletes the ticket's
+/// channel, the channel is kept if the transcript could not be posted.
+pub async fn close_ticket_channel(
+    state: &SharedAppState,
+    pg_pool: &Pool<Postgres>,
+    app_id: Uuid,
+    event: &TicketClosedEvent,
+) -> TicketsResult<()> {
+    let channel = ticket_channels::find_channel(pg_pool, app_id, event.ticket_id).await?;
+
+    let Some(channel) = channel else {
+        return Ok(());
+    };
+
+    let staff_logs = state
+        .channel_cache
+        .get_id(app_id, ChannelPurpose::StaffLogs)
+        .await
+        .ok_or(MiscError::GuildDataNotFound)?;
+
+    let client = state
+        .sdk
+        .sign_client(JwtAccessor::DiscordSystem, SYSTEM_TOKEN_TTL)?;
+
+    let transcript = TicketTranscript::download_with_path_and_query(
+        &client,
+        TicketPath {
+            app_id,
+            ticket_id: event.ticket_id,
+        },
+        TranscriptQuery {
+            format: TranscriptFormat::Html,
+            include_notes: true,
+        },
+    )
+    .await?;
+
+    let http = state.require_http().await?;
+    staff_logs
+        .send_message(
+            &http,
+            CreateMessage::new()
+                .content(format!(
+                    "Ticket `{}` was closed, its transcript is attached.",
+                    event.ticket_id
+                ))
+                .add_file(CreateAttachment::bytes(
+                    transcript.bytes,
+                    transcript.file_name,
+                )),
+        )
+        .await?;
+
+    channel.delete(&http).await?;
+
+    ticket_channels::remove(pg_pool, channel).await
+}
@@ -0,0 +1,641 @@
+//! Tickets opened with `/ticket` get a channel whose messages and attachments are relayed to the
+//! collector and back, and which is archived once the ticket closes, against local stand-ins for the Discord API and the collector and the PostgreSQL
+//! database named by `DATABASE_URL`. The tests fail when the variable is not set, unless
+//! `SKIP_DATABASE_TESTS` is set to skip them explicitly.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use serenity::all::{ChannelId, HttpBuilder, Message, User};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, Pool, Postgres};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
+use discord_tickets::channels::ChannelPurpose;
+use discord_tickets::shared_state::SharedAppState;
+use discord_tickets::tickets::OpenedTicket;
+use discord_tickets::{attachments, ticket_channels, tickets};
+use events::{AttachmentAddedEvent, MessageAddedEvent, TicketClosedEvent};
+use gateway::users::UsersCache;
+use sdk::client::InternalSdk;
+use sdk::routes::staff::TranscriptQuery;
+use test_support::StandIn;
+
+const GUILD_ID: u64 = 81384788765712384;
+const CATEGORY_ID: u64 = 81384788765712385;
+const STAFF_LOGS_ID: u64 = 81384788765712386;
+const TICKET_CHANNELS: &str = include_str!("../migrations/20240403090000_ticket_channels.sql");
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    path: String,
+    caller: Option<JwtData>,
+    body: String,
+}
+
+fn record<Reply>(
+    stand_in: &StandIn<Recorded, Reply>,
+    path: String,
+    caller: Option<JwtData>,
+    body: &[u8],
+) {
+    stand_in.record(Recorded {
+        path,
+        caller,
+        body: String::from_utf8_lossy(body).to_string(),
+    });
+}
+
+type Discord = StandIn<Recorded, ()>;
+/// Replies are only queued for the ids of submitted tickets.
+type Collector = StandIn<Recorded, Uuid>;
+
+#[derive(Clone)]
+struct CollectorState {
+    stand_in: Collector,
+    jwt: Arc<JwtConfig>,
+}
+
+impl CollectorState {
+    fn record(&self, path: String, headers: &HeaderMap, body: &[u8]) {
+        let caller = headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.jwt.verify(token).ok());
+
+        record(&self.stand_in, path, caller, body);
+    }
+}
+
+fn snowflake() -> u64 {
+    // discord ids are positive as `INT8`
+    (Uuid::new_v4().as_u64_pair().0 >> 1).max(1)
+}
+
+fn user(id: u64, name: &str) -> Value {
+    json!({ "id": id.to_string(), "username": name, "global_name": null, "avatar": null })
+}
+
+fn message(channel_id: u64, author: &Value, content: &str, attachments: Value) -> Value {
+    json!({
+        "id": snowflake().to_string(),
+        "channel_id": channel_id.to_string(),
+        "author": author,
+        "content": content,
+        "timestamp": "2024-04-03T09:00:00.000000+00:00",
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": attachments,
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+async fn create_channel(
+    State(stand_in): State<Discord>,
+    Path(guild_id): Path<u64>,
+    body: Bytes,
+) -> Json<Value> {
+    record(
+        &stand_in,
+        format!("/guilds/{guild_id}/channels"),
+        None,
+        &body,
+    );
+    let body: Value = serde_json::from_slice(&body).unwrap();
+
+    Json(json!({
+        "id": snowflake().to_string(),
+        "guild_id": guild_id.to_string(),
+        "type": 0,
+        "name": body["name"],
+        "parent_id": body["parent_id"],
+        "permission_overwrites": body["permission_overwrites"],
+    }))
+}
+
+async fn send_message(
+    State(stand_in): State<Discord>,
+    Path(channel_id): Path<u64>,
+    body: Bytes,
+) -> Json<Value> {
+    record(
+        &stand_in,
+        format!("/channels/{channel_id}/messages"),
+        None,
+        &body,
+    );
+
+    Json(message(channel_id, &user(1, "tickets"), "", json!([])))
+}
+
+async fn delete_channel(
+    State(stand_in): State<Discord>,
+    Path(channel_id): Path<u64>,
+) -> Json<Value> {
+    record(&stand_in, format!("/channels/{channel_id}"), None, &[]);
+
+    Json(json!({
+        "id": channel_id.to_string(),
+        "guild_id": GUILD_ID.to_string(),
+        "type": 0,
+        "name": "ticket",
+        "permission_overwrites": [],
+    }))
+}
+
+async fn attachment(Path(file_name): Path<String>) -> String {
+    format!("contents of {file_name}")
+}
+
+async fn submit_ticket(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Json<Value> {
+    state.record("/consumer/submit_ticket".to_string(), &headers, &body);
+
+    let ticket_id = state.stand_in.next_reply().unwrap_or_else(Uuid::new_v4);
+    Json(json!({ "ticket_id": ticket_id, "customer_id": Uuid::new_v4() }))
+}
+
+async fn send_customer_message(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    Path((app_id, ticket_id)): Path<(Uuid, Uuid)>,
+    body: Bytes,
+) -> Json<Value> {
+    let path = format!("/consumer/apps/{app_id}/tickets/{ticket_id}/messages");
+    state.record(path, &headers, &body);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+
+    Json(json!({
+        "message_id": Uuid::new_v4(),
+        "ticket_id": ticket_id,
+        "body": body["body"],
+        "customer_id": Uuid::new_v4(),
+        "author_id": null,
+        "created_at": 1700000000,
+    }))
+}
+
+async fn upload_attachment(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    Path((app_id, ticket_id)): Path<(Uuid, Uuid)>,
+    body: Bytes,
+) -> Json<Value> {
+    let path = format!("/consumer/apps/{app_id}/tickets/{ticket_id}/attachments");
+    state.record(path, &headers, &body);
+
+    Json(json!({
+        "attachment_id": Uuid::new_v4(),
+        "ticket_id": ticket_id,
+        "file_name": "log.txt",
+        "content_type": "text/plain",
+        "size_bytes": body.len(),
+        "customer_id": Uuid::new_v4(),
+        "uploaded_by": null,
+        "created_at": 1700000000,
+    }))
+}
+
+async fn download_attachment(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    Path((app_id, attachment_id)): Path<(Uuid, Uuid)>,
+) -> Response {
+    let path = format!("/consumer/apps/{app_id}/attachments/{attachment_id}");
+    state.record(path, &headers, &[]);
+
+    (
+        [
+            (CONTENT_TYPE, "image/png"),
+            (CONTENT_DISPOSITION, "attachment; filename*=UTF-8''fix.png"),
+        ],
+        "staff screenshot",
+    )
+        .into_response()
+}
+
+async fn transcript(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    Path((app_id, ticket_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<TranscriptQuery>,
+) -> Response {
+    let path = format!("/staff/apps/{app_id}/tickets/{ticket_id}/transcript");
+    state.record(path, &headers, &serde_json::to_vec(&query).unwrap());
+
+    (
+        [
+            (CONTENT_TYPE, "text/html"),
+            (
+                CONTENT_DISPOSITION,
+                "attachment; filename*=UTF-8''transcript.html",
+            ),
+        ],
+        "<p>I cannot log in</p>",
+    )
+        .into_response()
+}
+
+struct TestGateway {
+    state: SharedAppState,
+    discord: Discord,
+    discord_url: String,
+    collector: Collector,
+    app_id: Uuid,
+}
+
+impl TestGateway {
+    async fn start() -> Option<Self> {
+        let pg_pool = database().await?;
+
+        let discord = Discord::default();
+        let discord_address = test_support::serve(
+            Router::new()
+                .route("/api/v10/guilds/:guild_id/channels", post(create_channel))
+                .route("/api/v10/channels/:channel_id", delete(delete_channel))
+                .route("/api/v10/channels/:channel_id/messages", post(send_message))
+                .route("/attachments/:file_name", get(attachment))
+                .with_state(discord.clone()),
+        )
+        .await;
+
+        let jwt = Arc::new(JwtConfig::for_tests());
+        let collector = Collector::default();
+        let collector_address = test_support::serve(
+            Router::new()
+                .route("/consumer/submit_ticket", post(submit_ticket))
+                .route(
+                    "/consumer/apps/:app_id/tickets/:ticket_id/messages",
+                    post(send_customer_message),
+                )
+                .route(
+                    "/consumer/apps/:app_id/tickets/:ticket_id/attachments",
+                    post(upload_attachment),
+                )
+                .route(
+                    "/consumer/apps/:app_id/attachments/:attachment_id",
+                    get(download_attachment),
+                )
+                .route(
+                    "/staff/apps/:app_id/tickets/:ticket_id/transcript",
+                    get(transcript),
+                )
+                .with_state(CollectorState {
+                    stand_in: collector.clone(),
+                    jwt: jwt.clone(),
+                }),
+        )
+        .await;
+
+        let sdk: InternalSdk = (format!("http://{collector_address}"), jwt, "discord")
+            .try_into()
+            .unwrap();
+        let state = SharedAppState::new(sdk.clone(), UsersCache::new(sdk), pg_pool);
+
+        let discord_url = format!("http://{discord_address}");
+        let http = HttpBuilder::new("stand-in-token")
+            .proxy(&discord_url)
+            .ratelimiter_disabled(true)
+            .build();
+        state.set_http(Arc::new(http)).await;
+
+        let app_id = Uuid::new_v4();
+        state
+            .channel_cache
+            .insert(
+                ChannelId::new(CATEGORY_ID),
+                ChannelPurpose::TicketsCategory,
+                app_id,
+            )
+            .await;
+        state
+            .channel_cache
+            .insert(
+                ChannelId::new(STAFF_LOGS_ID),
+                ChannelPurpose::StaffLogs,
+                app_id,
+            )
+            .await;
+
+        Some(Self {
+            state,
+            discord,
+            discord_url,
+            collector,
+            app_id,
+        })
+    }
+
+    async fn open_ticket(&self, customer: &User, message: &str) -> OpenedTicket {
+        tickets::open_ticket(
+            &self.state,
+            &self.state.pg_pool,
+            GUILD_ID.into(),
+            self.app_id,
+            customer,
+            message.to_string(),
+        )
+        .await
+        .unwrap()
+    }
+}
+
+/// Only the table of the ticket channels is created, the others are not used by the relay.
+async fn database() -> Option<Pool<Postgres>> {
+    static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+    let Ok(url) = std::env::var("DATABASE_URL") else {
+        if std::env::var_os("SKIP_DATABASE_TESTS").is_some() {
+            eprintln!("SKIP_DATABASE_TESTS is set, skipping");
+            return None;
+        }
+
+        panic!(
+            "DATABASE_URL is not set, point it at a PostgreSQL database or set \
+                SKIP_DATABASE_TESTS=1 to skip the tests which need one"
+        );
+    };
+
+    let pg_pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&url)
+        .await
+        .unwrap();
+
+    MIGRATED
+        .get_or_init(|| async {
+            pg_pool.execute(TICKET_CHANNELS).await.unwrap();
+        })
+        .await;
+
+    Some(pg_pool)
+}
+
+#[tokio::test]
+async fn ticket_channels_relay_attachments_both_ways() {
+    let Some(gateway) = TestGateway::start().await else {
+        return;
+    };
+
+    let customer_id = snowflake();
+    let customer_json = user(customer_id, "jane");
+    let customer: User = serde_json::from_value(customer_json.clone()).unwrap();
+
+    let ticket_id = Uuid::new_v4();
+    gateway.collector.reply_with(ticket_id);
+    let OpenedTicket::Opened(channel_id) = gateway.open_ticket(&customer, "I cannot log in").await
+    else {
+        panic!("the customer had no ticket open");
+    };
+
+    let submitted = gateway.collector.received()[0].clone();
+    assert_eq!(submitted.path, "/consumer/submit_ticket");
+    assert!(matches!(
+        submitted.caller,
+        Some(JwtData {
+            accessor: JwtAccessor::DiscordSystem
+        })
+    ));
+    let submitted: Value = serde_json::from_str(&submitted.body).unwrap();
+    assert_eq!(
+        submitted["submitter"]["external_id"],
+        customer_id.to_string()
+    );
+    assert_eq!(submitted["message"], "I cannot log in");
+
+    {
+        let discord = gateway.discord.received();
+        assert_eq!(discord[0].path, format!("/guilds/{GUILD_ID}/channels"));
+        let created: Value = serde_json::from_str(&discord[0].body).unwrap();
+        assert_eq!(created["parent_id"], CATEGORY_ID.to_string());
+        // hidden from everyone but the customer
+        let overwrites = created["permission_overwrites"].as_array().unwrap();
+        let (everyone, customer) = (GUILD_ID.to_string(), customer_id.to_string());
+        assert!(overwrites
+            .iter()
+            .any(|overwrite| overwrite["id"] == everyone.as_str() && overwrite["deny"] != "0"));
+        assert!(overwrites
+            .iter()
+            .any(|overwrite| overwrite["id"] == customer.as_str() && overwrite["allow"] != "0"));
+
+        assert_eq!(discord[1].path, format!("/channels/{channel_id}/messages"));
+        assert!(discord[1].body.contains(&format!("<@{customer_id}>")));
+    }
+
+    // asking again points the customer to the open ticket
+    assert!(matches!(
+        gateway.open_ticket(&customer, "hello?").await,
+        OpenedTicket::AlreadyOpen(existing) if existing == channel_id
+    ));
+    assert_eq!(gateway.collector.received().len(), 1);
+
+    let attached = json!([{
+        "id": snowflake().to_string(),
+        "filename": "log.txt",
+        "content_type": "text/plain",
+        "size": 19,
+        "url": format!("{}/attachments/log.txt", gateway.discord_url),
+        "proxy_url": format!("{}/attachments/log.txt", gateway.discord_url),
+    }]);
+
+    // the files of others in the channel are not the customer's
+    let moderator = user(snowflake(), "moderator");
+    let by_moderator: Message =
+        serde_json::from_value(message(channel_id.get(), &moderator, "", attached.clone()))
+            .unwrap();
+    attachments::relay_customer_attachments(&gateway.state, &gateway.state.pg_pool, &by_moderator)
+        .await
+        .unwrap();
+    assert_eq!(gateway.collector.received().len(), 1);
+
+    let by_customer: Message =
+        serde_json::from_value(message(channel_id.get(), &customer_json, "", attached)).unwrap();
+    attachments::relay_customer_attachments(&gateway.state, &gateway.state.pg_pool, &by_customer)
+        .await
+        .unwrap();
+
+    let uploaded = gateway.collector.received()[1].clone();
+    assert_eq!(
+        uploaded.path,
+        format!(
+            "/consumer/apps/{}/tickets/{ticket_id}/attachments",
+            gateway.app_id
+        )
+    );
+    assert!(uploaded.body.contains("contents of log.txt"));
+
+    attachments::post_staff_attachment(
+        &gateway.state,
+        &gateway.state.pg_pool,
+        gateway.app_id,
+        &AttachmentAddedEvent {
+            ticket_id,
+            attachment_id: Uuid::new_v4(),
+            file_name: "fix.png".to_string(),
+            content_type: "image/png".to_string(),
+            from_staff: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let posted = gateway.discord.received().last().unwrap().clone();
+    assert_eq!(posted.path, format!("/channels/{channel_id}/messages"));
+    assert!(posted.body.contains("fix.png"));
+    assert!(posted.body.contains("staff screenshot"));
+}
+
+#[tokio::test]
+async fn ticket_channels_relay_messages_and_are_archived_on_close() {
+    let Some(gateway) = TestGateway::start().await else {
+        return;
+    };
+
+    let customer_id = snowflake();
+    let customer_json = user(customer_id, "jane");
+    let customer: User = serde_json::from_value(customer_json.clone()).unwrap();
+
+    let ticket_id = Uuid::new_v4();
+    gateway.collector.reply_with(ticket_id);
+    let OpenedTicket::Opened(channel_id) = gateway.open_ticket(&customer, "I cannot log in").await
+    else {
+        panic!("the customer had no ticket open");
+    };
+
+    // what others write in the channel is not the customer's
+    let by_moderator: Message = serde_json::from_value(message(
+        channel_id.get(),
+        &user(snowflake(), "moderator"),
+        "looking into it",
+        json!([]),
+    ))
+    .unwrap();
+    tickets::relay_customer_message(&gateway.state, &gateway.state.pg_pool, &by_moderator)
+        .await
+        .unwrap();
+    assert_eq!(gateway.collector.received().len(), 1);
+
+    let by_customer: Message = serde_json::from_value(message(
+        channel_id.get(),
+        &customer_json,
+        "still broken",
+        json!([]),
+    ))
+    .unwrap();
+    tickets::relay_customer_message(&gateway.state, &gateway.state.pg_pool, &by_customer)
+        .await
+        .unwrap();
+
+    let sent = gateway.collector.received()[1].clone();
+    assert_eq!(
+        sent.path,
+        format!(
+            "/consumer/apps/{}/tickets/{ticket_id}/messages",
+            gateway.app_id
+        )
+    );
+    let sent: Value = serde_json::from_str(&sent.body).unwrap();
+    assert_eq!(sent["external_id"], customer_id.to_string());
+    assert_eq!(sent["body"], "still broken");
+
+    tickets::post_staff_reply(
+        &gateway.state,
+        &gateway.state.pg_pool,
+        gateway.app_id,
+        &MessageAddedEvent {
+            ticket_id,
+            message_id: Uuid::new_v4(),
+            body: "try resetting your password".to_string(),
+            from_staff: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let posted = gateway.discord.received().last().unwrap().clone();
+    assert_eq!(posted.path, format!("/channels/{channel_id}/messages"));
+    assert!(posted.body.contains("try resetting your password"));
+
+    // such as when `/block` is run in the channel without naming the customer
+    let ticket = ticket_channels::find_ticket(&gateway.state.pg_pool, channel_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (ticket.ticket_id, ticket.customer_id),
+        (ticket_id, customer.id)
+    );
+
+    let closed = TicketClosedEvent {
+        ticket_id,
+        closed_by: 1,
+    };
+    tickets::close_ticket_channel(
+        &gateway.state,
+        &gateway.state.pg_pool,
+        gateway.app_id,
+        &closed,
+    )
+    .await
+    .unwrap();
+
+    let requested = gateway.collector.received()[2].clone();
+    assert_eq!(
+        requested.path,
+        format!(
+            "/staff/apps/{}/tickets/{ticket_id}/transcript",
+            gateway.app_id
+        )
+    );
+    assert!(matches!(
+        requested.caller,
+        Some(JwtData {
+            accessor: JwtAccessor::DiscordSystem
+        })
+    ));
+    // the staff logs are only seen by staff, so notes are archived along
+    let query: Value = serde_json::from_str(&requested.body).unwrap();
+    assert_eq!(query["include_notes"], true);
+
+    {
+        let discord = gateway.discord.received();
+        let [.., archived, deleted] = discord.as_slice() else {
+            panic!("the channel was not archived");
+        };
+        assert_eq!(archived.path, format!("/channels/{STAFF_LOGS_ID}/messages"));
+        assert!(archived.body.contains("transcript.html"));
+        assert!(archived.body.contains("<p>I cannot log in</p>"));
+        assert_eq!(deleted.path, format!("/channels/{channel_id}"));
+    }
+
+    assert!(
+        ticket_channels::find_channel(&gateway.state.pg_pool, gateway.app_id, ticket_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // the customer can open another ticket once theirs closed
+    gateway.collector.reply_with(Uuid::new_v4());
+    assert!(matches!(
+        gateway.open_ticket(&customer, "me again").await,
+        OpenedTicket::Opened(_)
+    ));
+}
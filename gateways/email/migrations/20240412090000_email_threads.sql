@@ -0,0 +1,24 @@
+-- Tickets submitted by email, replies find their ticket through the reply token plus-addressed
+-- onto the mailbox or through the Message-IDs of the thread
+CREATE TABLE IF NOT EXISTS email_tickets
+(
+    ticket_id   UUID        NOT NULL PRIMARY KEY,
+    app_id      UUID        NOT NULL,
+    reply_token TEXT        NOT NULL UNIQUE,
+    -- the address the customer wrote to, replies are sent from it
+    mailbox     TEXT        NOT NULL,
+    -- the customer's address
+    address     TEXT        NOT NULL,
+    subject     TEXT        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Message-IDs of the mail exchanged on a ticket, in both directions
+CREATE TABLE IF NOT EXISTS email_messages
+(
+    message_id TEXT        NOT NULL PRIMARY KEY,
+    ticket_id  UUID        NOT NULL REFERENCES email_tickets (ticket_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS email_messages_ticket_id_idx ON email_messages (ticket_id);
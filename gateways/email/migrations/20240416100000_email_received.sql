@@ -0,0 +1,7 @@
+-- Message-IDs of the mail received, claimed in the transaction handling the mail so a
+-- redelivery of the same mail waits for it and is dropped once it is handled
+CREATE TABLE IF NOT EXISTS email_received
+(
+    message_id TEXT        NOT NULL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,233 @@
+//! mail received for a mailbox either continues a ticket of the sender or submits a new one
+
+use sqlx::{Postgres, Transaction};
+
+use errors::{MiscError, ParsingError, TicketsError, TicketsResult};
+use sdk::client::{SdkCallWithBody, SdkCallWithPathAndBody};
+use sdk::routes::consumer::{
+    CustomerMessageBody, SendCustomerMessage, SubmitTicket, SubmitTicketBody, Submitter,
+};
+use sdk::routes::staff::TicketPath;
+
+use crate::mailboxes::{MailboxConfig, Recipient};
+use crate::message::InboundMail;
+use crate::smtp::{Envelope, MailReceiver, Rejection};
+use crate::threads::{self, EmailThread};
+use crate::EmailState;
+
+impl MailReceiver for EmailState {
+    fn accepts(&self, recipient: &str) -> bool {
+        self.mailboxes.resolve(recipient).is_some()
+    }
+
+    async fn receive(&self, envelope: Envelope) -> Result<(), Rejection> {
+        receive_mail(self, &envelope.recipients, &envelope.data)
+            .await
+            .map_err(|err| {
+                log::warn!(
+                    "Could not receive mail from <{}>: {err}",
+                    envelope.mail_from
+                );
+                rejection(err)
+            })
+    }
+}
+
+/// Errors a sender cannot resolve by retrying, such as a blocked customer or an unreadable mail.
+fn rejection(err: TicketsError) -> Rejection {
+    let permanent = matches!(
+        err,
+        TicketsError::Authorization(_)
+            | TicketsError::Parsing(_)
+            | TicketsError::MailParse(_)
+            | TicketsError::Misc(MiscError::TicketRejected)
+    );
+
+    Rejection {
+        permanent,
+        reason: if permanent {
+            err.to_string()
+        } else {
+            "Temporary failure, please try again later.".to_string()
+        },
+    }
+}
+
+pub async fn receive_mail(
+    state: &EmailState,
+    recipients: &[String],
+    raw: &[u8],
+) -> TicketsResult<()> {
+    let Some(recipient) = recipients
+        .iter()
+        .find_map(|recipient| state.mailboxes.resolve(recipient))
+    else {
+        return Err(ParsingError::InvalidRequest(
+            "None of the recipients is a mailbox of this gateway.".to_string(),
+        ))?;
+    };
+
+    let mail = InboundMail::parse(raw)?;
+
+    // answering out-of-office replies and bounces risks mail loops
+    if mail.automated {
+        log::info!("Dropping automated mail from {}", mail.from_address);
+        return Ok(());
+    }
+
+    // delivered again by an MTA which did not see the previous delivery succeed
+    if let Some(message_id) = &mail.message_id {
+        if threads::is_known_message(&state.pg_pool, message_id).await? {
+            return Ok(());
+        }
+    }
+
+    // claimed before the ticket is written to, a failure rolls the claim back for the MTA to
+    // retry while a concurrent delivery of the same mail waits and is dropped
+    let mut tx = state.pg_pool.begin().await?;
+
+    if let Some(message_id) = &mail.message_id {
+        if !threads::claim_received(&mut *tx, message_id).await? {
+            return Ok(());
+        }
+    }
+
+    handle_mail(state, &mut tx, &recipient, &mail).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn handle_mail(
+    state: &EmailState,
+    tx: &mut Transaction<'_, Postgres>,
+    recipient: &Recipient<'_>,
+    mail: &InboundMail,
+) -> TicketsResult<()> {
+    if let Some(thread) = find_thread(state, recipient, mail).await? {
+        if mail.text.is_empty() {
+            return record(tx, &thread, mail).await;
+        }
+
+        let sent = SendCustomerMessage::call_with_path_and_body(
+            &state.client,
+            TicketPath {
+                app_id: thread.app_id,
+                ticket_id: thread.ticket_id,
+            },
+            CustomerMessageBody {
+                external_id: mail.from_address.clone(),
+                body: mail.text.clone(),
+            },
+        )
+        .await;
+
+        match sent {
+            Ok(_) => return record(tx, &thread, mail).await,
+            // answers on closed tickets open a new one
+            Err(TicketsError::Misc(MiscError::TicketClosed)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    submit_ticket(state, tx, recipient.mailbox, mail).await
+}
+
+/// The sender's thread the mail answers, by its reply token or, when the MTA authenticated the
+/// sender, the Message-IDs it refers to.
+async fn find_thread(
+    state: &EmailState,
+    recipient: &Recipient<'_>,
+    mail: &InboundMail,
+) -> TicketsResult<Option<EmailThread>> {
+    let mut thread = match &recipient.reply_token {
+        Some(reply_token) => threads::find_by_token(&state.pg_pool, reply_token).await?,
+        None => None,
+    };
+
+    let authenticated = state
+        .authserv_id
+        .as_deref()
+        .is_some_and(|authserv_id| mail.sender_authenticated(authserv_id));
+
+    if thread.is_none() && authenticated && !mail.references.is_empty() {
+        thread = threads::find_by_message_ids(&state.pg_pool, &mail.references).await?;
+    }
+
+    // only the customer who submitted a ticket writes to it, others open their own
+    Ok(thread.filter(|thread| {
+        thread.app_id == recipient.mailbox.app_id && thread.address == mail.from_address
+    }))
+}
+
+async fn submit_ticket(
+    state: &EmailState,
+    tx: &mut Transaction<'_, Postgres>,
+    mailbox: &MailboxConfig,
+    mail: &InboundMail,
+) -> TicketsResult<()> {
+    let message = match &mail.subject {
+        Some(subject) if !mail.text.is_empty() => format!("{subject}\n\n{}", mail.text),
+        Some(subject) => subject.clone(),
+        None => mail.text.clone(),
+    };
+
+    if message.is_empty() {
+        return Err(ParsingError::InvalidRequest(
+            "The mail has neither a subject nor text.".to_string(),
+        ))?;
+    }
+
+    let submitted = SubmitTicket::call_with_body(
+        &state.client,
+        SubmitTicketBody {
+            app_id: mailbox.app_id,
+            message,
+            submitter: Submitter {
+                external_id: mail.from_address.clone(),
+                display_name: mail.from_name.clone(),
+            },
+        },
+    )
+    .await?;
+
+    let thread = EmailThread {
+        ticket_id: submitted.ticket_id,
+        app_id: mailbox.app_id,
+        reply_token: threads::new_reply_token(),
+        mailbox: mailbox.address.clone(),
+        address: mail.from_address.clone(),
+        subject: mail
+            .subject
+            .clone()
+            .unwrap_or_else(|| "Your support request".to_string()),
+    };
+
+    threads::insert(&mut **tx, &thread).await?;
+
+    if let Some(message_id) = &mail.message_id {
+        threads::record_message(&mut **tx, thread.ticket_id, message_id).await?;
+    }
+
+    log::info!(
+        "Submitted ticket {} for {} to app {}",
+        thread.ticket_id,
+        thread.address,
+        thread.app_id
+    );
+
+    Ok(())
+}
+
+async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    thread: &EmailThread,
+    mail: &InboundMail,
+) -> TicketsResult<()> {
+    if let Some(message_id) = &mail.message_id {
+        threads::record_message(&mut **tx, thread.ticket_id, message_id).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! an email gateway: mail received over SMTP, or piped in by the organisation's MTA, submits
+//! tickets and continues them, staff replies are mailed back on the same thread
+
+use std::sync::Arc;
+
+use sqlx::{Pool, Postgres};
+
+use sdk::client::SignedTicketClient;
+
+use crate::mailboxes::{MailboxConfig, Mailboxes};
+use crate::outbound::{Mailer, OutboundConfig};
+
+pub mod inbound;
+pub mod mailboxes;
+pub mod message;
+pub mod outbound;
+pub mod realtime;
+pub mod smtp;
+pub mod threads;
+
+/// Sent as `x-gateway`, apps enable the gateway under this name through `toggle_gateway`.
+pub const GATEWAY_NAME: &str = "email";
+
+#[derive(serde::Deserialize)]
+pub struct EmailGatewayConfig {
+    /// Left out when mail is piped in by the MTA, see `email-tickets pipe`.
+    #[serde(default)]
+    pub smtp_bind_address: Option<String>,
+    /// Announced by the SMTP listener and the right hand side of the replies' Message-IDs.
+    pub hostname: String,
+    #[serde(default = "default_max_message_bytes")]
+    pub max_message_bytes: usize,
+    /// The authserv-id of the organisation's MTA, whose `Authentication-Results` are trusted.
+    /// Without it, replies are only threaded by the reply token, as the `References` of an
+    /// unauthenticated mail could join anyone's ticket by forging the customer's address.
+    #[serde(default)]
+    pub authserv_id: Option<String>,
+    pub mailboxes: Vec<MailboxConfig>,
+    pub outbound: OutboundConfig,
+}
+
+fn default_max_message_bytes() -> usize {
+    // 25MB = 25 * 1024 KB * 1024 B
+    25 * 1024 * 1024
+}
+
+#[derive(Clone)]
+pub struct EmailState {
+    pub pg_pool: Pool<Postgres>,
+    pub client: SignedTicketClient,
+    pub mailboxes: Arc<Mailboxes>,
+    pub mailer: Mailer,
+    pub authserv_id: Option<String>,
+}
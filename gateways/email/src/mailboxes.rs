@@ -0,0 +1,64 @@
+//! the addresses customers write to, each one submits to a single app; replies carry the
+//! ticket's reply token plus-addressed onto the mailbox, `support+token@example.com`
+
+use uuid::Uuid;
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct MailboxConfig {
+    pub address: String,
+    pub app_id: Uuid,
+    /// Shown as the sender of staff replies, such as `Example Support`.
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+/// A recipient resolved to one of the mailboxes.
+#[derive(Debug)]
+pub struct Recipient<'a> {
+    pub mailbox: &'a MailboxConfig,
+    pub reply_token: Option<String>,
+}
+
+pub struct Mailboxes(Vec<MailboxConfig>);
+
+impl From<Vec<MailboxConfig>> for Mailboxes {
+    fn from(mailboxes: Vec<MailboxConfig>) -> Self {
+        Self(mailboxes)
+    }
+}
+
+impl Mailboxes {
+    pub fn iter(&self) -> impl Iterator<Item = &MailboxConfig> {
+        self.0.iter()
+    }
+
+    pub fn find(&self, address: &str) -> Option<&MailboxConfig> {
+        self.0
+            .iter()
+            .find(|mailbox| mailbox.address.eq_ignore_ascii_case(address))
+    }
+
+    pub fn resolve(&self, recipient: &str) -> Option<Recipient<'_>> {
+        let (local, domain) = recipient.trim().rsplit_once('@')?;
+
+        let (local, reply_token) = match local.split_once('+') {
+            Some((local, token)) if !token.is_empty() => (local, Some(token.to_ascii_lowercase())),
+            _ => (local, None),
+        };
+
+        Some(Recipient {
+            mailbox: self.find(&format!("{local}@{domain}"))?,
+            reply_token,
+        })
+    }
+}
+
+impl MailboxConfig {
+    /// The mailbox plus-addressed with `reply_token`.
+    pub fn reply_address(&self, reply_token: &str) -> String {
+        match self.address.rsplit_once('@') {
+            Some((local, domain)) => format!("{local}+{reply_token}@{domain}"),
+            None => self.address.clone(),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use tokio::io::AsyncReadExt;
+
+use email_tickets::mailboxes::Mailboxes;
+use email_tickets::outbound::Mailer;
+use email_tickets::smtp::{Envelope, MailReceiver};
+use email_tickets::{smtp, EmailGatewayConfig, EmailState};
+use errors::TicketsResult;
+use gateway::{Bootstrap, GatewayConfig};
+
+#[derive(serde::Deserialize)]
+pub struct EmailTicketsConfig {
+    #[serde(flatten)]
+    gateway: GatewayConfig,
+    email_gateway: EmailGatewayConfig,
+}
+
+// sysexits.h, which MTAs interpret when piping mail to a command
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_TEMPFAIL: i32 = 75;
+
+#[tokio::main]
+async fn main() -> TicketsResult<()> {
+    tracing_subscriber::fmt::init();
+
+    let config: EmailTicketsConfig = dry::config::load_config()?;
+
+    let pg_pool = dry::database::connect().await?;
+    sqlx::migrate!().set_locking(false).run(&pg_pool).await?;
+
+    let bootstrap = Bootstrap::<EmailState>::new(config.gateway)?;
+
+    let gateway = config.email_gateway;
+
+    let state = EmailState {
+        pg_pool,
+        client: bootstrap.system_client()?,
+        mailboxes: Arc::new(Mailboxes::from(gateway.mailboxes)),
+        mailer: Mailer::new(&gateway.outbound, gateway.hostname.clone())?,
+        authserv_id: gateway.authserv_id.clone(),
+    };
+
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match arguments.first().map(String::as_str) {
+        // a single message on stdin for the recipients given as arguments, such as from
+        // postfix's pipe(8) with `argv=email-tickets pipe ${recipient}`
+        Some("pipe") => {
+            let mut data = Vec::new();
+            tokio::io::stdin().read_to_end(&mut data).await?;
+
+            let envelope = Envelope {
+                mail_from: String::new(),
+                recipients: arguments[1..].to_vec(),
+                data,
+            };
+
+            if let Err(rejection) = state.receive(envelope).await {
+                eprintln!("{}", rejection.reason);
+                std::process::exit(if rejection.permanent {
+                    EX_DATAERR
+                } else {
+                    EX_TEMPFAIL
+                });
+            }
+
+            Ok(())
+        }
+        None | Some("serve") => {
+            let subscription = bootstrap.subscribe().await?;
+
+            subscription
+                .listener
+                .listen_to_all(state.mailboxes.iter().map(|mailbox| mailbox.app_id))
+                .await;
+
+            let realtime_ticket_events = subscription.run(Arc::new(state.clone()));
+
+            match &gateway.smtp_bind_address {
+                Some(bind_address) => {
+                    let listener = tokio::net::TcpListener::bind(bind_address).await?;
+
+                    log::info!("Accepting mail over SMTP on {bind_address}");
+
+                    tokio::select! {
+                        res = realtime_ticket_events => res??,
+                        res = smtp::serve(listener, gateway.hostname, gateway.max_message_bytes, state) => res?,
+                    }
+                }
+                None => realtime_ticket_events.await??,
+            }
+
+            Ok(())
+        }
+        Some(command) => {
+            eprintln!(
+                "Unknown command `{command}`.\nUsage: email-tickets [serve | pipe RECIPIENT...]"
+            );
+            std::process::exit(EX_USAGE);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! reading inbound mail, the text a customer wrote is taken from the plain text part with the
+//! quoted conversation below it cut off
+
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
+
+use errors::{ParsingError, TicketsResult};
+
+#[derive(Debug)]
+pub struct InboundMail {
+    pub message_id: Option<String>,
+    /// Lowercase, identifies the customer.
+    pub from_address: String,
+    pub from_name: Option<String>,
+    pub subject: Option<String>,
+    /// The `In-Reply-To` and `References` Message-IDs, most recent first.
+    pub references: Vec<String>,
+    /// Out-of-office replies, bounces and other mail sent without a person's involvement.
+    pub automated: bool,
+    /// The `Authentication-Results` headers added by the MTAs the mail passed through.
+    pub authentication_results: Vec<String>,
+    pub text: String,
+}
+
+impl InboundMail {
+    pub fn parse(raw: &[u8]) -> TicketsResult<Self> {
+        let parsed = mailparse::parse_mail(raw)?;
+        let headers = &parsed.headers;
+
+        let from = headers
+            .get_first_value("From")
+            .and_then(|from| mailparse::addrparse(&from).ok())
+            .and_then(|from| from.extract_single_info())
+            .ok_or_else(|| {
+                ParsingError::InvalidRequest("The mail has no valid sender.".to_string())
+            })?;
+
+        let mut references = headers
+            .get_first_value("In-Reply-To")
+            .map(|in_reply_to| message_ids(&in_reply_to))
+            .unwrap_or_default();
+
+        // oldest first in the header
+        for reference in headers
+            .get_first_value("References")
+            .map(|references| message_ids(&references))
+            .unwrap_or_default()
+            .into_iter()
+            .rev()
+        {
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+
+        let automated = headers
+            .get_first_value("Auto-Submitted")
+            .is_some_and(|value| !value.trim().eq_ignore_ascii_case("no"))
+            || headers.get_first_value("Precedence").is_some_and(|value| {
+                matches!(
+                    value.trim().to_ascii_lowercase().as_str(),
+                    "bulk" | "junk" | "list" | "auto_reply"
+                )
+            });
+
+        let text = match find_part(&parsed, "text/plain") {
+            Some(part) => part.get_body()?,
+            None => match find_part(&parsed, "text/html") {
+                Some(part) => html_to_text(&part.get_body()?),
+                None => String::new(),
+            },
+        };
+
+        Ok(Self {
+            message_id: headers
+                .get_first_value("Message-ID")
+                .and_then(|message_id| message_ids(&message_id).into_iter().next()),
+            from_address: from.addr.to_ascii_lowercase(),
+            from_name: from.display_name.filter(|name| !name.trim().is_empty()),
+            subject: headers
+                .get_first_value("Subject")
+                .map(|subject| subject.trim().to_string())
+                .filter(|subject| !subject.is_empty()),
+            references,
+            automated,
+            authentication_results: headers.get_all_values("Authentication-Results"),
+            text: strip_quoted_reply(&text),
+        })
+    }
+
+    /// Whether the MTA announcing itself as `authserv_id` verified the sender's domain, by a
+    /// DMARC pass or an SPF or DKIM pass for the domain of the `From` address. Only the results
+    /// of this MTA count, it removes the headers claiming its name from the mail it receives.
+    pub fn sender_authenticated(&self, authserv_id: &str) -> bool {
+        let Some((_, from_domain)) = self.from_address.rsplit_once('@') else {
+            return false;
+        };
+
+        self.authentication_results
+            .iter()
+            .filter_map(|header| {
+                let header = strip_comments(header);
+                let mut results = header.split(';');
+
+                // the authserv-id may be followed by a version
+                let id = results.next()?.split_whitespace().next()?.to_string();
+                id.eq_ignore_ascii_case(authserv_id)
+                    .then(|| results.map(str::to_string).collect::<Vec<_>>())
+            })
+            .flatten()
+            .any(|result| passes_for(&result, from_domain))
+    }
+}
+
+/// Whether a `method=result property=value...` entry is a pass for `domain`.
+fn passes_for(result: &str, domain: &str) -> bool {
+    let mut tokens = result.split_whitespace();
+
+    let Some((method, outcome)) = tokens.next().and_then(|token| token.split_once('=')) else {
+        return false;
+    };
+
+    if !outcome.eq_ignore_ascii_case("pass") {
+        return false;
+    }
+
+    let property = |name: &str| {
+        tokens.clone().find_map(|token| {
+            let (key, value) = token.split_once('=')?;
+            key.eq_ignore_ascii_case(name)
+                .then(|| value.trim_matches('"').to_ascii_lowercase())
+        })
+    };
+    // the address or the bare domain
+    let is_domain = |value: String| value.rsplit('@').next() == Some(domain);
+
+    match method.to_ascii_lowercase().as_str() {
+        "dmarc" => property("header.from").is_none_or(is_domain),
+        "dkim" => property("header.d").is_some_and(is_domain),
+        "spf" => property("smtp.mailfrom").is_some_and(is_domain),
+        _ => false,
+    }
+}
+
+/// The header without its `(comments)`.
+fn strip_comments(header: &str) -> String {
+    let mut stripped = String::with_capacity(header.len());
+    let mut depth = 0usize;
+
+    for char in header.chars() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => stripped.push(char),
+            _ => {}
+        }
+    }
+
+    stripped
+}
+
+/// The first inline part of type `mimetype`, depth first.
+fn find_part<'a>(part: &'a ParsedMail<'a>, mimetype: &str) -> Option<&'a ParsedMail<'a>> {
+    if part.get_content_disposition().disposition == DispositionType::Attachment {
+        return None;
+    }
+
+    if part.subparts.is_empty() {
+        return part
+            .ctype
+            .mimetype
+            .eq_ignore_ascii_case(mimetype)
+            .then_some(part);
+    }
+
+    part.subparts
+        .iter()
+        .find_map(|subpart| find_part(subpart, mimetype))
+}
+
+/// The `<id>` entries of a Message-ID list, without the angle brackets.
+pub fn message_ids(header: &str) -> Vec<String> {
+    header
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.split_once('>'))
+        .map(|(message_id, _)| message_id.trim().to_string())
+        .filter(|message_id| !message_id.is_empty())
+        .collect()
+}
+
+/// Cuts the text at the quoted conversation mail clients add below a reply.
+pub fn strip_quoted_reply(text: &str) -> String {
+    let mut lines = vec![];
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        let quote_header = (trimmed.starts_with("On ") && trimmed.ends_with("wrote:"))
+            || trimmed == "-----Original Message-----"
+            || trimmed.starts_with("________________________________");
+
+        // `-- ` delimits the sender's signature
+        if quote_header || line == "-- " {
+            break;
+        }
+
+        if !trimmed.starts_with('>') {
+            lines.push(line.trim_end());
+        }
+    }
+
+    lines.join("\n").trim().to_string()
+}
+
+/// Plain text of an html-only mail, good enough for the short messages written to support.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+
+        let tag = rest[start + 1..start + end].to_ascii_lowercase();
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split([' ', '/', '\n'])
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        rest = &rest[start + end + 1..];
+
+        // content which is never displayed
+        if !closing && ["head", "style", "script"].contains(&name.as_str()) {
+            let close = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(at) => rest[at..].split_once('>').map_or("", |(_, rest)| rest),
+                None => "",
+            };
+        } else if ["br", "p", "div", "li", "tr"].contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+    text.push_str(rest);
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
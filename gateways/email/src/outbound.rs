@@ -0,0 +1,135 @@
+//! mailing staff replies to customers, replies are sent from the mailbox the customer wrote to
+//! and ask for answers at the ticket's plus-addressed reply address
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use uuid::Uuid;
+
+use errors::TicketsResult;
+
+use crate::mailboxes::MailboxConfig;
+use crate::threads::EmailThread;
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Only for relays on the same host or network, such as a local stand-in.
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct OutboundConfig {
+    pub host: String,
+    /// The security's default port if left out.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    /// Right hand side of generated Message-IDs.
+    hostname: String,
+}
+
+/// A reply ready to be sent, `message_id` is recorded on the thread once it went out.
+pub struct OutboundMail {
+    pub message_id: String,
+    pub message: Message,
+}
+
+impl Mailer {
+    pub fn new(config: &OutboundConfig, hostname: String) -> TicketsResult<Self> {
+        let builder = match config.security {
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            }
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        };
+
+        let builder = match config.port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+
+        let builder = match (&config.username, &config.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            hostname,
+        })
+    }
+
+    pub fn new_message_id(&self) -> String {
+        format!("{}@{}", Uuid::new_v4().simple(), self.hostname)
+    }
+
+    /// A reply on the thread, `references` are the Message-IDs of the thread, oldest first.
+    pub fn compose_reply(
+        &self,
+        mailbox: &MailboxConfig,
+        thread: &EmailThread,
+        references: &[String],
+        body: String,
+    ) -> TicketsResult<OutboundMail> {
+        let message_id = self.new_message_id();
+
+        let subject = match thread.subject.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("re:") => thread.subject.clone(),
+            _ => format!("Re: {}", thread.subject),
+        };
+
+        let mut builder = Message::builder()
+            .message_id(Some(format!("<{message_id}>")))
+            .from(Mailbox::new(
+                mailbox.display_name.clone(),
+                mailbox.address.parse()?,
+            ))
+            .reply_to(Mailbox::new(
+                mailbox.display_name.clone(),
+                mailbox.reply_address(&thread.reply_token).parse()?,
+            ))
+            .to(Mailbox::new(None, thread.address.parse()?))
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+
+        if let Some(in_reply_to) = references.last() {
+            builder = builder.in_reply_to(format!("<{in_reply_to}>")).references(
+                references
+                    .iter()
+                    .map(|reference| format!("<{reference}>"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
+
+        Ok(OutboundMail {
+            message_id,
+            message: builder.body(body)?,
+        })
+    }
+
+    pub async fn send(&self, mail: OutboundMail) -> TicketsResult<String> {
+        self.transport.send(mail.message).await?;
+        Ok(mail.message_id)
+    }
+}
@@ -0,0 +1,64 @@
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use errors::TicketsResult;
+use events::TicketUpdatedEvent;
+use gateway::Gateway;
+
+use crate::{threads, EmailState, GATEWAY_NAME};
+
+const CLOSED_NOTICE: &str =
+    "This ticket was closed. Replying to this email opens a new ticket if you need further help.";
+
+impl Gateway for EmailState {
+    const NAME: &'static str = GATEWAY_NAME;
+    const SYSTEM_ACCESSOR: JwtAccessor = JwtAccessor::EmailSystem;
+
+    /// The customer's address, staff only reply through the collector.
+    type Identity = String;
+
+    async fn handle_ticket_event(
+        &self,
+        app_id: Uuid,
+        event: TicketUpdatedEvent,
+    ) -> TicketsResult<()> {
+        match &event {
+            TicketUpdatedEvent::MessageAdded(event) if event.from_staff => {
+                mail_customer(self, app_id, event.ticket_id, event.body.clone()).await
+            }
+            TicketUpdatedEvent::TicketClosed(event) => {
+                mail_customer(self, app_id, event.ticket_id, CLOSED_NOTICE.to_string()).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Mails `body` as a reply on the ticket's thread, if the ticket was submitted by email.
+async fn mail_customer(
+    state: &EmailState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+    body: String,
+) -> TicketsResult<()> {
+    let Some(thread) = threads::find_by_ticket(&state.pg_pool, app_id, ticket_id).await? else {
+        return Ok(());
+    };
+
+    let Some(mailbox) = state.mailboxes.find(&thread.mailbox) else {
+        log::warn!(
+            "Not mailing ticket {ticket_id}, its mailbox {} is no longer configured",
+            thread.mailbox
+        );
+        return Ok(());
+    };
+
+    let references = threads::message_ids(&state.pg_pool, ticket_id).await?;
+
+    let mail = state
+        .mailer
+        .compose_reply(mailbox, &thread, &references, body)?;
+    let message_id = state.mailer.send(mail).await?;
+
+    threads::record_message(&state.pg_pool, ticket_id, &message_id).await
+}
@@ -0,0 +1,305 @@
+//! a minimal SMTP listener accepting mail for the configured mailboxes, it offers neither TLS
+//! nor authentication and is meant to sit behind the organisation's MTA or on a private network
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use errors::TicketsResult;
+
+// RFC 5321 allows 1000 octets per line, some clients exceed it for long header lines
+const MAX_LINE_BYTES: u64 = 4096;
+const MAX_RECIPIENTS: usize = 100;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A message as handed over by the sending MTA.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// Empty for bounces, which are sent with the null reverse path `<>`.
+    pub mail_from: String,
+    pub recipients: Vec<String>,
+    pub data: Vec<u8>,
+}
+
+/// Refuses a message, temporary rejections are retried by the sending MTA.
+#[derive(Debug)]
+pub struct Rejection {
+    pub permanent: bool,
+    pub reason: String,
+}
+
+pub trait MailReceiver: Clone + Send + Sync + 'static {
+    /// Whether mail for `recipient` is accepted, asked for every `RCPT TO`.
+    fn accepts(&self, recipient: &str) -> bool;
+
+    fn receive(&self, envelope: Envelope) -> impl Future<Output = Result<(), Rejection>> + Send;
+}
+
+pub async fn serve<R: MailReceiver>(
+    listener: TcpListener,
+    hostname: String,
+    max_message_bytes: usize,
+    receiver: R,
+) -> TicketsResult<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+
+        let session = Session {
+            hostname: hostname.clone(),
+            max_message_bytes,
+            receiver: receiver.clone(),
+        };
+
+        tokio::spawn(async move {
+            let (reader, writer) = stream.into_split();
+            if let Err(err) = session.run(reader, writer).await {
+                log::warn!("SMTP session with {peer} ended: {err}");
+            }
+        });
+    }
+}
+
+struct Session<R> {
+    hostname: String,
+    max_message_bytes: usize,
+    receiver: R,
+}
+
+#[derive(Default)]
+struct Transaction {
+    mail_from: Option<String>,
+    recipients: Vec<String>,
+}
+
+enum Line {
+    Complete(Vec<u8>),
+    TooLong,
+    Closed,
+}
+
+impl<R: MailReceiver> Session<R> {
+    async fn run(
+        &self,
+        reader: impl AsyncRead + Unpin,
+        mut writer: impl AsyncWrite + Unpin,
+    ) -> std::io::Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut greeted = false;
+        let mut transaction = Transaction::default();
+
+        reply(&mut writer, &format!("220 {} ESMTP", self.hostname)).await?;
+
+        loop {
+            let line = match tokio::time::timeout(IDLE_TIMEOUT, read_line(&mut reader)).await {
+                Ok(line) => line?,
+                Err(_) => {
+                    return reply(&mut writer, "421 Idle for too long, closing connection").await;
+                }
+            };
+
+            let line = match line {
+                Line::Complete(line) => String::from_utf8_lossy(&line).trim_end().to_string(),
+                Line::TooLong => {
+                    reply(&mut writer, "500 Line too long").await?;
+                    continue;
+                }
+                Line::Closed => return Ok(()),
+            };
+
+            let (verb, argument) = line.split_once(' ').unwrap_or((&line, ""));
+
+            match verb.to_ascii_uppercase().as_str() {
+                "EHLO" => {
+                    greeted = true;
+                    transaction = Transaction::default();
+                    reply(
+                        &mut writer,
+                        &format!(
+                            "250-{}\r\n250-8BITMIME\r\n250 SIZE {}",
+                            self.hostname, self.max_message_bytes
+                        ),
+                    )
+                    .await?;
+                }
+                "HELO" => {
+                    greeted = true;
+                    transaction = Transaction::default();
+                    reply(&mut writer, &format!("250 {}", self.hostname)).await?;
+                }
+                "MAIL" if !greeted => reply(&mut writer, "503 Send EHLO first").await?,
+                "MAIL" => match parse_path(argument, "FROM:") {
+                    Some((path, parameters)) => {
+                        let size = parameters
+                            .split_whitespace()
+                            .filter_map(|parameter| parameter.split_once('='))
+                            .find(|(name, _)| name.eq_ignore_ascii_case("SIZE"))
+                            .and_then(|(_, size)| size.parse::<usize>().ok());
+
+                        if size.is_some_and(|size| size > self.max_message_bytes) {
+                            reply(&mut writer, "552 Message exceeds the size limit").await?;
+                        } else {
+                            transaction = Transaction {
+                                mail_from: Some(path),
+                                recipients: vec![],
+                            };
+                            reply(&mut writer, "250 OK").await?;
+                        }
+                    }
+                    None => reply(&mut writer, "501 Syntax: MAIL FROM:<address>").await?,
+                },
+                "RCPT" if transaction.mail_from.is_none() => {
+                    reply(&mut writer, "503 Send MAIL first").await?
+                }
+                "RCPT" => match parse_path(argument, "TO:") {
+                    Some((recipient, _)) if transaction.recipients.len() >= MAX_RECIPIENTS => {
+                        log::debug!("Deferring recipient {recipient}, too many recipients");
+                        reply(&mut writer, "452 Too many recipients").await?
+                    }
+                    Some((recipient, _)) if self.receiver.accepts(&recipient) => {
+                        transaction.recipients.push(recipient);
+                        reply(&mut writer, "250 OK").await?
+                    }
+                    Some(_) => reply(&mut writer, "550 No such mailbox").await?,
+                    None => reply(&mut writer, "501 Syntax: RCPT TO:<address>").await?,
+                },
+                "DATA" if transaction.recipients.is_empty() => {
+                    reply(&mut writer, "503 Send RCPT first").await?
+                }
+                "DATA" => {
+                    reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+
+                    let Some(data) = self.read_data(&mut reader).await? else {
+                        transaction = Transaction::default();
+                        reply(&mut writer, "552 Message exceeds the size limit").await?;
+                        continue;
+                    };
+
+                    let Transaction {
+                        mail_from,
+                        recipients,
+                    } = std::mem::take(&mut transaction);
+
+                    let envelope = Envelope {
+                        mail_from: mail_from.unwrap_or_default(),
+                        recipients,
+                        data,
+                    };
+
+                    match self.receiver.receive(envelope).await {
+                        Ok(()) => reply(&mut writer, "250 OK").await?,
+                        Err(rejection) => {
+                            let code = if rejection.permanent { 550 } else { 451 };
+                            // a reply is a single line, reasons may not break it
+                            let reason = rejection.reason.replace(['\r', '\n'], " ");
+                            reply(&mut writer, &format!("{code} {reason}")).await?
+                        }
+                    }
+                }
+                "RSET" => {
+                    transaction = Transaction::default();
+                    reply(&mut writer, "250 OK").await?
+                }
+                "NOOP" => reply(&mut writer, "250 OK").await?,
+                "VRFY" => reply(&mut writer, "252 Cannot verify users").await?,
+                "QUIT" => return reply(&mut writer, "221 Bye").await,
+                _ => reply(&mut writer, "502 Command not implemented").await?,
+            }
+        }
+    }
+
+    /// Reads the message up to the terminating `.` line, `None` if it exceeds the size limit.
+    async fn read_data(
+        &self,
+        reader: &mut (impl AsyncBufReadExt + Unpin),
+    ) -> std::io::Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        let mut exceeded = false;
+
+        loop {
+            let line = match read_line(reader).await? {
+                Line::Complete(line) => line,
+                // kept reading to stay in sync with the client, the message is refused
+                Line::TooLong => {
+                    exceeded = true;
+                    continue;
+                }
+                Line::Closed => {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+            };
+
+            if line == b".\r\n" || line == b".\n" {
+                return Ok((!exceeded).then_some(data));
+            }
+
+            // transparency, lines starting with a dot were sent with another one prepended
+            let line = line.strip_prefix(b".").unwrap_or(&line);
+
+            if data.len() + line.len() > self.max_message_bytes {
+                exceeded = true;
+            }
+
+            if !exceeded {
+                data.extend_from_slice(line);
+            }
+        }
+    }
+}
+
+async fn read_line(reader: &mut (impl AsyncBufReadExt + Unpin)) -> std::io::Result<Line> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_LINE_BYTES)
+        .read_until(b'\n', &mut line)
+        .await?;
+
+    Ok(match line.last() {
+        None => Line::Closed,
+        Some(b'\n') => Line::Complete(line),
+        // the rest of the line is consumed so the next read starts on a fresh line
+        Some(_) if line.len() as u64 == MAX_LINE_BYTES => loop {
+            let mut rest = Vec::new();
+            reader
+                .take(MAX_LINE_BYTES)
+                .read_until(b'\n', &mut rest)
+                .await?;
+
+            if matches!(rest.last(), None | Some(b'\n')) {
+                break Line::TooLong;
+            }
+        },
+        Some(_) => Line::Closed,
+    })
+}
+
+async fn reply(writer: &mut (impl AsyncWrite + Unpin), reply: &str) -> std::io::Result<()> {
+    writer.write_all(reply.as_bytes()).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await
+}
+
+/// Splits `FROM:<address> PARAMETERS` into the address and its parameters.
+fn parse_path(argument: &str, prefix: &str) -> Option<(String, String)> {
+    let argument = argument.trim();
+    let head = argument.get(..prefix.len())?;
+
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    let rest = argument[prefix.len()..].trim_start();
+
+    match rest.strip_prefix('<') {
+        Some(rest) => {
+            let (path, parameters) = rest.split_once('>')?;
+            Some((path.to_string(), parameters.trim().to_string()))
+        }
+        // some clients leave out the angle brackets
+        None => {
+            let (path, parameters) = rest.split_once(' ').unwrap_or((rest, ""));
+            (!path.is_empty()).then(|| (path.to_string(), parameters.trim().to_string()))
+        }
+    }
+}
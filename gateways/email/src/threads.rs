@@ -0,0 +1,154 @@
+//! which ticket a mail belongs to, by the reply token of the ticket or by the Message-IDs the
+//! mail refers to
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use errors::TicketsResult;
+
+// enough to thread a reply for clients trimming long References headers
+const MAX_REFERENCES: i64 = 20;
+
+#[derive(Debug, Clone)]
+pub struct EmailThread {
+    pub ticket_id: Uuid,
+    pub app_id: Uuid,
+    pub reply_token: String,
+    pub mailbox: String,
+    pub address: String,
+    pub subject: String,
+}
+
+pub fn new_reply_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+pub async fn find_by_token(
+    executor: impl PgExecutor<'_>,
+    reply_token: &str,
+) -> TicketsResult<Option<EmailThread>> {
+    Ok(sqlx::query_as!(
+        EmailThread,
+        "SELECT ticket_id, app_id, reply_token, mailbox, address, subject FROM email_tickets WHERE reply_token = $1",
+        reply_token
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// The thread of the most recent of `message_ids` known to the gateway.
+pub async fn find_by_message_ids(
+    executor: impl PgExecutor<'_>,
+    message_ids: &[String],
+) -> TicketsResult<Option<EmailThread>> {
+    Ok(sqlx::query_as!(
+        EmailThread,
+        r#"
+SELECT t.ticket_id, t.app_id, t.reply_token, t.mailbox, t.address, t.subject
+FROM email_messages m
+         JOIN email_tickets t ON t.ticket_id = m.ticket_id
+WHERE m.message_id = ANY ($1)
+ORDER BY m.created_at DESC
+LIMIT 1"#,
+        message_ids
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn find_by_ticket(
+    executor: impl PgExecutor<'_>,
+    app_id: Uuid,
+    ticket_id: Uuid,
+) -> TicketsResult<Option<EmailThread>> {
+    Ok(sqlx::query_as!(
+        EmailThread,
+        "SELECT ticket_id, app_id, reply_token, mailbox, address, subject FROM email_tickets WHERE ticket_id = $1 AND app_id = $2",
+        ticket_id,
+        app_id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn insert(executor: impl PgExecutor<'_>, thread: &EmailThread) -> TicketsResult<()> {
+    sqlx::query!(
+        "INSERT INTO email_tickets (ticket_id, app_id, reply_token, mailbox, address, subject) VALUES ($1, $2, $3, $4, $5, $6)",
+        thread.ticket_id,
+        thread.app_id,
+        &thread.reply_token,
+        &thread.mailbox,
+        &thread.address,
+        &thread.subject
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn is_known_message(
+    executor: impl PgExecutor<'_>,
+    message_id: &str,
+) -> TicketsResult<bool> {
+    Ok(sqlx::query!(
+        "SELECT ticket_id FROM email_messages WHERE message_id = $1",
+        message_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .is_some())
+}
+
+/// Claims a received mail for handling, false when it was handled already. The claim of a mail
+/// being handled blocks until its transaction ends.
+pub async fn claim_received(
+    executor: impl PgExecutor<'_>,
+    message_id: &str,
+) -> TicketsResult<bool> {
+    let claimed = sqlx::query!(
+        "INSERT INTO email_received (message_id) VALUES ($1) ON CONFLICT (message_id) DO NOTHING",
+        message_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(claimed.rows_affected() == 1)
+}
+
+pub async fn record_message(
+    executor: impl PgExecutor<'_>,
+    ticket_id: Uuid,
+    message_id: &str,
+) -> TicketsResult<()> {
+    sqlx::query!(
+        "INSERT INTO email_messages (message_id, ticket_id) VALUES ($1, $2) ON CONFLICT (message_id) DO NOTHING",
+        message_id,
+        ticket_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// The most recent Message-IDs of the thread, oldest first, for the `References` of a reply.
+pub async fn message_ids(
+    executor: impl PgExecutor<'_>,
+    ticket_id: Uuid,
+) -> TicketsResult<Vec<String>> {
+    let mut message_ids = sqlx::query!(
+        "SELECT message_id FROM email_messages WHERE ticket_id = $1 ORDER BY created_at DESC LIMIT $2",
+        ticket_id,
+        MAX_REFERENCES
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|record| record.message_id)
+    .collect::<Vec<_>>();
+
+    message_ids.reverse();
+
+    Ok(message_ids)
+}
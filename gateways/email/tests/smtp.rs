@@ -0,0 +1,319 @@
+//! The SMTP listener against a raw client, and replies sent through the listener used as a
+//! local stand-in for the outbound relay.
+
+use std::sync::Arc;
+
+use mailparse::MailHeaderMap;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use email_tickets::mailboxes::{MailboxConfig, Mailboxes};
+use email_tickets::message::InboundMail;
+use email_tickets::outbound::{Mailer, OutboundConfig, SmtpSecurity};
+use email_tickets::smtp::{self, Envelope, MailReceiver, Rejection};
+use email_tickets::threads::EmailThread;
+use test_support::StandIn;
+
+type Relay = StandIn<Envelope, Rejection>;
+
+/// Accepts mail for its mailboxes, unless a rejection is queued.
+#[derive(Clone)]
+struct Receiver {
+    mailboxes: Arc<Mailboxes>,
+    relay: Relay,
+}
+
+impl MailReceiver for Receiver {
+    fn accepts(&self, recipient: &str) -> bool {
+        self.mailboxes.resolve(recipient).is_some()
+    }
+
+    async fn receive(&self, envelope: Envelope) -> Result<(), Rejection> {
+        if let Some(rejection) = self.relay.next_reply() {
+            return Err(rejection);
+        }
+
+        self.relay.record(envelope);
+        Ok(())
+    }
+}
+
+fn mailbox() -> MailboxConfig {
+    MailboxConfig {
+        address: "support@example.com".to_string(),
+        app_id: Uuid::new_v4(),
+        display_name: Some("Example Support".to_string()),
+    }
+}
+
+async fn serve() -> (Relay, u16) {
+    let relay = Relay::default();
+    let (listener, address) = test_support::listen().await;
+
+    tokio::spawn(smtp::serve(
+        listener,
+        "mx.example.com".to_string(),
+        1024,
+        Receiver {
+            mailboxes: Arc::new(Mailboxes::from(vec![mailbox()])),
+            relay: relay.clone(),
+        },
+    ));
+
+    (relay, address.port())
+}
+
+struct Client {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl Client {
+    async fn connect(port: u16) -> Self {
+        let (reader, writer) = TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap()
+            .into_split();
+
+        let mut client = Self {
+            reader: BufReader::new(reader),
+            writer,
+        };
+        assert!(client.reply().await.starts_with("220 mx.example.com"));
+        client
+    }
+
+    /// The last line of a possibly multiline reply.
+    async fn reply(&mut self) -> String {
+        loop {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).await.unwrap();
+
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return line.trim_end().to_string();
+            }
+        }
+    }
+
+    async fn send(&mut self, command: &str) -> String {
+        self.writer
+            .write_all(format!("{command}\r\n").as_bytes())
+            .await
+            .unwrap();
+        self.reply().await
+    }
+}
+
+#[tokio::test]
+async fn smtp_session() {
+    let (stand_in, port) = serve().await;
+    let mut client = Client::connect(port).await;
+
+    assert!(client
+        .send("MAIL FROM:<jane@example.org>")
+        .await
+        .starts_with("503"));
+    assert!(client
+        .send("EHLO client.example.org")
+        .await
+        .starts_with("250 SIZE"));
+    assert!(client.send("DATA").await.starts_with("503"));
+    assert!(client
+        .send("MAIL FROM:<jane@example.org> SIZE=4096")
+        .await
+        .starts_with("552"));
+    assert!(client
+        .send("MAIL FROM:<jane@example.org>")
+        .await
+        .starts_with("250"));
+    assert!(client
+        .send("RCPT TO:<sales@example.com>")
+        .await
+        .starts_with("550"));
+    assert!(client
+        .send("RCPT TO:<Support+abc@Example.com>")
+        .await
+        .starts_with("250"));
+    assert!(client.send("DATA").await.starts_with("354"));
+
+    let reply = client
+        .send("Subject: Hello\r\n\r\nFirst line\r\n..leading dot\r\n.")
+        .await;
+    assert!(reply.starts_with("250"), "{reply}");
+
+    {
+        let received = stand_in.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].mail_from, "jane@example.org");
+        assert_eq!(received[0].recipients, ["Support+abc@Example.com"]);
+        assert_eq!(
+            received[0].data,
+            b"Subject: Hello\r\n\r\nFirst line\r\n.leading dot\r\n"
+        );
+    }
+
+    // a failing receiver, temporary failures are retried by the sender
+    stand_in.reply_with(Rejection {
+        permanent: false,
+        reason: "Refused\r\nby the stand-in".to_string(),
+    });
+    client.send("MAIL FROM:<>").await;
+    client.send("RCPT TO:<support@example.com>").await;
+    client.send("DATA").await;
+    assert_eq!(
+        client.send("Hello again\r\n.").await,
+        "451 Refused  by the stand-in"
+    );
+
+    // larger than the limit of the stand-in
+    client.send("MAIL FROM:<jane@example.org>").await;
+    client.send("RCPT TO:<support@example.com>").await;
+    client.send("DATA").await;
+    let long_body = "x".repeat(200);
+    let mut message = String::new();
+    for _ in 0..10 {
+        message.push_str(&long_body);
+        message.push_str("\r\n");
+    }
+    message.push('.');
+    assert!(client.send(&message).await.starts_with("552"));
+
+    assert!(client.send("QUIT").await.starts_with("221"));
+    assert_eq!(stand_in.received().len(), 1);
+}
+
+#[tokio::test]
+async fn threaded_reply() {
+    let (stand_in, port) = serve().await;
+
+    let mailer = Mailer::new(
+        &OutboundConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+        },
+        "tickets.example.com".to_string(),
+    )
+    .unwrap();
+
+    let mailbox = mailbox();
+    let thread = EmailThread {
+        ticket_id: Uuid::new_v4(),
+        app_id: mailbox.app_id,
+        reply_token: "0123abcd".to_string(),
+        mailbox: mailbox.address.clone(),
+        // the stand-in only accepts mail for the mailbox
+        address: "support@example.com".to_string(),
+        subject: "Cannot log in".to_string(),
+    };
+
+    let references = [
+        "first@client.example.org".to_string(),
+        "second@tickets.example.com".to_string(),
+    ];
+    let mail = mailer
+        .compose_reply(
+            &mailbox,
+            &thread,
+            &references,
+            "Have you tried resetting it?".to_string(),
+        )
+        .unwrap();
+    let message_id = mailer.send(mail).await.unwrap();
+
+    let received = stand_in.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].recipients, ["support@example.com"]);
+
+    let parsed = mailparse::parse_mail(&received[0].data).unwrap();
+    let header = |name: &str| parsed.headers.get_first_value(name).unwrap();
+
+    assert_eq!(header("Subject"), "Re: Cannot log in");
+    assert_eq!(header("Message-ID"), format!("<{message_id}>"));
+    assert!(message_id.ends_with("@tickets.example.com"));
+    assert_eq!(header("In-Reply-To"), "<second@tickets.example.com>");
+    assert_eq!(
+        header("References"),
+        "<first@client.example.org> <second@tickets.example.com>"
+    );
+    assert!(header("Reply-To").contains("support+0123abcd@example.com"));
+
+    // the customer's answer threads back onto the ticket through either header
+    let answer = format!(
+        "From: Jane <Jane@Example.org>\r\nTo: support+0123abcd@example.com\r\nSubject: Re: Cannot log in\r\nMessage-ID: <answer@client.example.org>\r\nIn-Reply-To: <{message_id}>\r\nReferences: <first@client.example.org> <{message_id}>\r\n\r\nThat worked, thanks!\r\n\r\nOn Mon, Apr 8, 2024 at 10:00 AM Example Support wrote:\r\n> Have you tried resetting it?\r\n"
+    );
+    let answer = InboundMail::parse(answer.as_bytes()).unwrap();
+
+    assert_eq!(answer.from_address, "jane@example.org");
+    assert_eq!(answer.from_name.as_deref(), Some("Jane"));
+    assert_eq!(
+        answer.message_id.as_deref(),
+        Some("answer@client.example.org")
+    );
+    assert_eq!(
+        answer.references,
+        [message_id, "first@client.example.org".to_string()]
+    );
+    assert_eq!(answer.text, "That worked, thanks!");
+    assert!(!answer.automated);
+
+    let recipient = Mailboxes::from(vec![mailbox])
+        .resolve("support+0123abcd@example.com")
+        .map(|recipient| recipient.reply_token);
+    assert_eq!(recipient, Some(Some("0123abcd".to_string())));
+}
+
+#[test]
+fn automated_mail() {
+    let mail = InboundMail::parse(
+        b"From: jane@example.org\r\nAuto-Submitted: auto-replied\r\nSubject: Out of office\r\n\r\nBack on Monday.\r\n",
+    )
+    .unwrap();
+
+    assert!(mail.automated);
+}
+
+#[test]
+fn authenticated_sender() {
+    let mail = |results: &str| {
+        InboundMail::parse(
+            format!("From: Jane <jane@example.org>\r\n{results}Subject: Re: Cannot log in\r\n\r\nThanks!\r\n").as_bytes(),
+        )
+        .unwrap()
+    };
+
+    // aligned with the From domain
+    assert!(
+        mail("Authentication-Results: mx.example.com; dmarc=pass header.from=example.org\r\n")
+            .sender_authenticated("mx.example.com")
+    );
+    assert!(mail(
+        "Authentication-Results: MX.example.com 1; spf=fail smtp.mailfrom=example.org;\r\n dkim=pass (good signature) header.d=example.org header.s=mail\r\n"
+    )
+    .sender_authenticated("mx.example.com"));
+    assert!(mail(
+        "Authentication-Results: mx.example.com; spf=pass smtp.mailfrom=bounce@example.org\r\n"
+    )
+    .sender_authenticated("mx.example.com"));
+
+    // results of other MTAs, failures and other domains
+    assert!(!mail(
+        "Authentication-Results: mx.attacker.net; dmarc=pass header.from=example.org\r\n"
+    )
+    .sender_authenticated("mx.example.com"));
+    assert!(!mail(
+        "Authentication-Results: mx.example.com; dmarc=fail header.from=example.org\r\n"
+    )
+    .sender_authenticated("mx.example.com"));
+    assert!(
+        !mail("Authentication-Results: mx.example.com; dkim=pass header.d=attacker.net\r\n")
+            .sender_authenticated("mx.example.com")
+    );
+    assert!(!mail("Authentication-Results: mx.example.com; spf=pass (smtp.mailfrom=example.org) smtp.mailfrom=attacker.net\r\n")
+        .sender_authenticated("mx.example.com"));
+    assert!(!mail("").sender_authenticated("mx.example.com"));
+}
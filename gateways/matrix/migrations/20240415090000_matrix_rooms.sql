@@ -0,0 +1,32 @@
+-- Rooms bound to an app by a gateway admin, customers open tickets in the support room and
+-- transcripts of closed tickets are posted to the staff logs room
+CREATE TABLE IF NOT EXISTS matrix_rooms
+(
+    room_id    TEXT        NOT NULL,
+    purpose    TEXT        NOT NULL,
+    app_id     UUID        NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (app_id, purpose),
+    UNIQUE (room_id, purpose)
+);
+
+-- Rooms carrying a ticket's conversation with the customer who submitted it
+CREATE TABLE IF NOT EXISTS matrix_ticket_rooms
+(
+    room_id     TEXT        NOT NULL PRIMARY KEY,
+    ticket_id   UUID        NOT NULL UNIQUE,
+    app_id      UUID        NOT NULL,
+    -- the customer's Matrix user id, `@jane:example.org`
+    customer_id TEXT        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS matrix_ticket_rooms_customer_idx ON matrix_ticket_rooms (app_id, customer_id);
+
+-- Where the sync stream of the gateway's account left off, resumed after restarts
+CREATE TABLE IF NOT EXISTS matrix_sync_state
+(
+    user_id    TEXT        NOT NULL PRIMARY KEY,
+    next_batch TEXT        NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,392 @@
+//! the parts of the Matrix client-server API the gateway uses, spoken directly over http
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use errors::{MiscError, ParsingError, TicketsResult};
+
+const CLIENT_API: [&str; 3] = ["_matrix", "client", "v3"];
+const MEDIA_API: [&str; 3] = ["_matrix", "media", "v3"];
+
+// syncs wait this long on top of the time they long-poll for
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_ATTEMPTS: usize = 3;
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+// presence and typing notifications are of no use to the gateway
+const SYNC_FILTER: &str =
+    r#"{"presence":{"not_types":["*"]},"room":{"ephemeral":{"not_types":["*"]}}}"#;
+
+#[derive(Clone)]
+pub struct MatrixClient {
+    http: reqwest::Client,
+    homeserver_url: Url,
+    access_token: String,
+    /// The gateway's account, `@tickets:example.com`.
+    pub user_id: String,
+}
+
+#[derive(serde::Deserialize, Default)]
+struct MatrixErrorBody {
+    #[serde(default)]
+    errcode: String,
+    #[serde(default)]
+    error: String,
+    retry_after_ms: Option<u64>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct SyncResponse {
+    pub next_batch: String,
+    #[serde(default)]
+    pub rooms: SyncRooms,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct SyncRooms {
+    #[serde(default)]
+    pub join: HashMap<String, JoinedRoom>,
+    #[serde(default)]
+    pub invite: HashMap<String, InvitedRoom>,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct JoinedRoom {
+    #[serde(default)]
+    pub timeline: Timeline,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct Timeline {
+    #[serde(default)]
+    pub events: Vec<RoomEvent>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RoomEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub sender: String,
+    pub event_id: String,
+    #[serde(default)]
+    pub content: Value,
+}
+
+impl RoomEvent {
+    /// The body of a text message, edits are left out as they repeat a message already seen.
+    pub fn text(&self) -> Option<&str> {
+        if self.event_type != "m.room.message" || self.content["msgtype"] != "m.text" {
+            return None;
+        }
+
+        if self.content["m.relates_to"]["rel_type"] == "m.replace" {
+            return None;
+        }
+
+        self.content["body"].as_str()
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct InvitedRoom {
+    #[serde(default)]
+    pub invite_state: InviteState,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct InviteState {
+    #[serde(default)]
+    pub events: Vec<StrippedStateEvent>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct StrippedStateEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub sender: String,
+    #[serde(default)]
+    pub state_key: Option<String>,
+    #[serde(default)]
+    pub content: Value,
+}
+
+impl InvitedRoom {
+    /// Who invited `user_id` into the room.
+    pub fn inviter(&self, user_id: &str) -> Option<&str> {
+        self.invite_state
+            .events
+            .iter()
+            .find(|event| {
+                event.event_type == "m.room.member"
+                    && event.state_key.as_deref() == Some(user_id)
+                    && event.content["membership"] == "invite"
+            })
+            .map(|event| event.sender.as_str())
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct MessageContent {
+    pub msgtype: &'static str,
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<FileInfo>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct FileInfo {
+    pub mimetype: String,
+    pub size: usize,
+}
+
+impl MessageContent {
+    pub fn text(body: impl Into<String>) -> Self {
+        Self::new("m.text", body.into())
+    }
+
+    /// Messages of the gateway itself, clients show them differently and bots do not answer them.
+    pub fn notice(body: impl Into<String>) -> Self {
+        Self::new("m.notice", body.into())
+    }
+
+    /// A file uploaded with [`MatrixClient::upload`].
+    pub fn file(file_name: String, content_uri: String, info: FileInfo) -> Self {
+        Self {
+            filename: Some(file_name.clone()),
+            url: Some(content_uri),
+            info: Some(info),
+            ..Self::new("m.file", file_name)
+        }
+    }
+
+    fn new(msgtype: &'static str, body: String) -> Self {
+        Self {
+            msgtype,
+            body,
+            filename: None,
+            url: None,
+            info: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct CreateRoom {
+    pub name: String,
+    pub topic: String,
+    pub invite: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct RoomIdResponse {
+    room_id: String,
+}
+
+impl MatrixClient {
+    pub fn new(homeserver_url: &str, user_id: String, access_token: String) -> TicketsResult<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            homeserver_url: Url::parse(homeserver_url).map_err(ParsingError::from)?,
+            access_token,
+            user_id,
+        })
+    }
+
+    /// New events since `since`, waiting up to `timeout` for one to arrive. Without `since` the
+    /// rooms' recent history is returned.
+    pub async fn sync(
+        &self,
+        since: Option<&str>,
+        timeout: Duration,
+    ) -> TicketsResult<SyncResponse> {
+        let url = self.url(&CLIENT_API, &["sync"])?;
+
+        let mut query = vec![
+            ("filter", SYNC_FILTER.to_string()),
+            ("timeout", timeout.as_millis().to_string()),
+        ];
+        if let Some(since) = since {
+            query.push(("since", since.to_string()));
+        }
+
+        self.send(|http| {
+            http.get(url.clone())
+                .query(&query)
+                .timeout(timeout + REQUEST_TIMEOUT)
+        })
+        .await
+    }
+
+    /// Sends a message to the room, returns the id of its event.
+    pub async fn send_message(
+        &self,
+        room_id: &str,
+        content: &MessageContent,
+    ) -> TicketsResult<String> {
+        // the same transaction id on retries keeps the homeserver from sending a message twice
+        let txn_id = Uuid::new_v4().simple().to_string();
+        let url = self.url(
+            &CLIENT_API,
+            &["rooms", room_id, "send", "m.room.message", &txn_id],
+        )?;
+
+        #[derive(serde::Deserialize)]
+        struct SendResponse {
+            event_id: String,
+        }
+
+        let response: SendResponse = self
+            .send(|http| http.put(url.clone()).json(content))
+            .await?;
+
+        Ok(response.event_id)
+    }
+
+    /// Creates a private room inviting `room.invite`, returns its id.
+    pub async fn create_room(&self, room: &CreateRoom) -> TicketsResult<String> {
+        let url = self.url(&CLIENT_API, &["createRoom"])?;
+        let body = json!({
+            "preset": "private_chat",
+            "is_direct": true,
+            "name": room.name,
+            "topic": room.topic,
+            "invite": room.invite,
+        });
+
+        let response: RoomIdResponse = self.send(|http| http.post(url.clone()).json(&body)).await?;
+
+        Ok(response.room_id)
+    }
+
+    pub async fn join(&self, room_id: &str) -> TicketsResult<()> {
+        let url = self.url(&CLIENT_API, &["join", room_id])?;
+
+        let _: RoomIdResponse = self
+            .send(|http| http.post(url.clone()).json(&json!({})))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Leaves a room, or rejects the invite into it.
+    pub async fn leave(&self, room_id: &str) -> TicketsResult<()> {
+        let url = self.url(&CLIENT_API, &["rooms", room_id, "leave"])?;
+
+        let _: Value = self
+            .send(|http| http.post(url.clone()).json(&json!({})))
+            .await?;
+
+        Ok(())
+    }
+
+    /// The display name of a user, `None` if they have not set one.
+    pub async fn display_name(&self, user_id: &str) -> TicketsResult<Option<String>> {
+        let url = self.url(&CLIENT_API, &["profile", user_id, "displayname"])?;
+
+        #[derive(serde::Deserialize)]
+        struct DisplayNameResponse {
+            displayname: Option<String>,
+        }
+
+        let response: DisplayNameResponse = self.send(|http| http.get(url.clone())).await?;
+
+        Ok(response.displayname)
+    }
+
+    /// Uploads a file to the homeserver's media repository, returns its `mxc://` content uri.
+    pub async fn upload(
+        &self,
+        file_name: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> TicketsResult<String> {
+        let url = self.url(&MEDIA_API, &["upload"])?;
+
+        #[derive(serde::Deserialize)]
+        struct UploadResponse {
+            content_uri: String,
+        }
+
+        let response: UploadResponse = self
+            .send(|http| {
+                http.post(url.clone())
+                    .query(&[("filename", file_name)])
+                    .header(reqwest::header::CONTENT_TYPE, content_type)
+                    .body(bytes.clone())
+            })
+            .await?;
+
+        Ok(response.content_uri)
+    }
+
+    fn url(&self, api: &[&str], segments: &[&str]) -> TicketsResult<Url> {
+        let mut url = self.homeserver_url.clone();
+
+        url.path_segments_mut()
+            .map_err(|_| {
+                ParsingError::InvalidRequest(format!(
+                    "The homeserver url `{}` cannot have a path.",
+                    self.homeserver_url
+                ))
+            })?
+            .pop_if_empty()
+            .extend(api)
+            .extend(segments);
+
+        Ok(url)
+    }
+
+    /// Sends the request built by `request`, again after the delay asked for if the homeserver
+    /// rate limits it.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> TicketsResult<T> {
+        let mut attempt = 1;
+
+        loop {
+            let response = request(&self.http)
+                .bearer_auth(&self.access_token)
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response.json().await?);
+            }
+
+            let error = response.json::<MatrixErrorBody>().await.unwrap_or_default();
+
+            if status == StatusCode::TOO_MANY_REQUESTS && attempt < MAX_ATTEMPTS {
+                let retry_after = error
+                    .retry_after_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or(Duration::from_secs(1))
+                    .min(MAX_RETRY_AFTER);
+
+                log::debug!("Rate limited by the homeserver, retrying in {retry_after:?}");
+                tokio::time::sleep(retry_after).await;
+
+                attempt += 1;
+                continue;
+            }
+
+            return Err(MiscError::Matrix(if error.errcode.is_empty() {
+                format!("Request failed with status {status}.")
+            } else {
+                format!("{}: {}", error.errcode, error.error)
+            }))?;
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! `!tickets` commands, sent by a gateway admin in the room they bind or unbind
+
+use uuid::Uuid;
+
+use errors::{ParsingError, TicketsResult};
+
+use crate::client::MessageContent;
+use crate::rooms::{self, RoomPurpose};
+use crate::{MatrixState, GATEWAY_NAME};
+
+pub const COMMAND_PREFIX: &str = "!tickets";
+
+const USAGE: &str =
+    "Usage: `!tickets bind <app id> <support | staff_logs>` or `!tickets unbind <support | staff_logs>`";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Makes the room the app's room for `purpose`.
+    Bind {
+        app_id: Uuid,
+        purpose: RoomPurpose,
+    },
+    Unbind {
+        purpose: RoomPurpose,
+    },
+}
+
+impl Command {
+    /// Parses a message starting with [`COMMAND_PREFIX`].
+    pub fn parse(body: &str) -> TicketsResult<Self> {
+        let arguments = body.split_whitespace().skip(1).collect::<Vec<_>>();
+
+        Ok(match arguments.as_slice() {
+            ["bind", app_id, purpose] => Command::Bind {
+                app_id: Uuid::parse_str(app_id).map_err(|_| {
+                    ParsingError::InvalidRequest(format!("`{app_id}` is not an app id."))
+                })?,
+                purpose: purpose.to_string().try_into()?,
+            },
+            ["unbind", purpose] => Command::Unbind {
+                purpose: purpose.to_string().try_into()?,
+            },
+            [command, ..] => Err(ParsingError::InvalidCommandType(command.to_string()))?,
+            [] => Err(ParsingError::InvalidRequest(USAGE.to_string()))?,
+        })
+    }
+}
+
+/// Executes the command in `body`, the outcome is answered in the room.
+pub async fn execute(
+    state: &MatrixState,
+    room_id: &str,
+    sender: &str,
+    body: &str,
+) -> TicketsResult<()> {
+    let reply = if !state.admins.contains(sender) {
+        "Only the gateway's admins can use `!tickets` commands.".to_string()
+    } else {
+        match Command::parse(body) {
+            Ok(command) => match run(state, room_id, command).await {
+                Ok(reply) => reply,
+                Err(err) => format!("The command failed: {err}"),
+            },
+            Err(err) => format!("{err}\n{USAGE}"),
+        }
+    };
+
+    state
+        .matrix
+        .send_message(room_id, &MessageContent::notice(reply))
+        .await?;
+
+    Ok(())
+}
+
+async fn run(state: &MatrixState, room_id: &str, command: Command) -> TicketsResult<String> {
+    match command {
+        Command::Bind { app_id, purpose } => {
+            if let Some(bound_app) = state.rooms.get_app_id(room_id, purpose).await {
+                if bound_app != app_id {
+                    return Ok(format!(
+                        "This room already is the {purpose} room of app {bound_app}, unbind it first."
+                    ));
+                }
+            }
+
+            rooms::bind(&state.pg_pool, room_id, purpose, app_id).await?;
+            state
+                .rooms
+                .insert(room_id.to_string(), purpose, app_id)
+                .await;
+
+            if let Err(err) = state.listener.listen_to(app_id).await {
+                log::error!("Could not listen to app {app_id}: {err}");
+            }
+
+            log::info!("Bound room {room_id} as the {purpose} room of app {app_id}");
+
+            Ok(format!(
+                "This room is now the {purpose} room of app {app_id}. Tickets are only submitted once the app enabled the `{GATEWAY_NAME}` gateway."
+            ))
+        }
+        Command::Unbind { purpose } => match rooms::unbind(&state.pg_pool, room_id, purpose).await?
+        {
+            Some(app_id) => {
+                state.rooms.remove(app_id, purpose).await;
+
+                log::info!("Unbound room {room_id} as the {purpose} room of app {app_id}");
+
+                Ok(format!(
+                    "This room is no longer the {purpose} room of app {app_id}."
+                ))
+            }
+            None => Ok(format!("This room is not the {purpose} room of any app.")),
+        },
+    }
+}
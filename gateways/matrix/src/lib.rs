@@ -0,0 +1,47 @@
+//! a Matrix gateway: messages in an app's support room open tickets, each ticket gets a room
+//! shared with the customer who opened it, staff replies are posted there and the room is left
+//! once the ticket closes
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use sqlx::{Pool, Postgres};
+
+use gateway::Listener;
+use sdk::client::SignedTicketClient;
+
+use crate::client::MatrixClient;
+use crate::rooms::RoomCache;
+
+pub mod client;
+pub mod commands;
+pub mod realtime;
+pub mod rooms;
+pub mod sync;
+pub mod tickets;
+
+/// Sent as `x-gateway`, apps enable the gateway under this name through `toggle_gateway`.
+pub const GATEWAY_NAME: &str = "matrix";
+
+#[derive(serde::Deserialize)]
+pub struct MatrixGatewayConfig {
+    /// Such as `https://matrix.example.com`, the access token of the gateway's account is read
+    /// from `MATRIX_ACCESS_TOKEN`.
+    pub homeserver_url: String,
+    /// The gateway's account, `@tickets:example.com`.
+    pub user_id: String,
+    /// Users who may invite the gateway into rooms and bind those rooms to apps.
+    #[serde(default)]
+    pub admins: HashSet<String>,
+}
+
+#[derive(Clone)]
+pub struct MatrixState {
+    pub pg_pool: Pool<Postgres>,
+    pub client: SignedTicketClient,
+    pub matrix: MatrixClient,
+    pub rooms: RoomCache,
+    pub admins: Arc<HashSet<String>>,
+    /// Apps bound to a room while running are listened to from then on.
+    pub listener: Listener,
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use errors::TicketsResult;
+use gateway::{Bootstrap, GatewayConfig};
+use matrix_tickets::client::MatrixClient;
+use matrix_tickets::rooms::{self, RoomCache};
+use matrix_tickets::{sync, MatrixGatewayConfig, MatrixState};
+
+#[derive(serde::Deserialize)]
+pub struct MatrixTicketsConfig {
+    #[serde(flatten)]
+    gateway: GatewayConfig,
+    matrix_gateway: MatrixGatewayConfig,
+}
+
+#[tokio::main]
+async fn main() -> TicketsResult<()> {
+    tracing_subscriber::fmt::init();
+
+    let config: MatrixTicketsConfig = dry::config::load_config()?;
+
+    let pg_pool = dry::database::connect().await?;
+    sqlx::migrate!().set_locking(false).run(&pg_pool).await?;
+
+    let bootstrap = Bootstrap::<MatrixState>::new(config.gateway)?;
+    let subscription = bootstrap.subscribe().await?;
+
+    let gateway = config.matrix_gateway;
+
+    let access_token =
+        std::env::var("MATRIX_ACCESS_TOKEN").expect("Expected an access token in the environment");
+
+    let room_cache = RoomCache::default();
+    room_cache.populate(&rooms::load(&pg_pool).await?).await;
+
+    let state = MatrixState {
+        pg_pool,
+        client: bootstrap.system_client()?,
+        matrix: MatrixClient::new(&gateway.homeserver_url, gateway.user_id, access_token)?,
+        rooms: room_cache,
+        admins: Arc::new(gateway.admins),
+        listener: subscription.listener.clone(),
+    };
+
+    state
+        .listener
+        .listen_to_all(state.rooms.app_ids().await)
+        .await;
+
+    let realtime_ticket_events = subscription.run(Arc::new(state.clone()));
+
+    tokio::select! {
+        res = realtime_ticket_events => res??,
+        res = sync::run(state) => res?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use errors::TicketsResult;
+use events::TicketUpdatedEvent;
+use gateway::Gateway;
+
+use crate::{tickets, MatrixState, GATEWAY_NAME};
+
+impl Gateway for MatrixState {
+    const NAME: &'static str = GATEWAY_NAME;
+    const SYSTEM_ACCESSOR: JwtAccessor = JwtAccessor::MatrixSystem;
+
+    /// Such as `@jane:example.com`, staff only reply through the collector.
+    type Identity = String;
+
+    async fn handle_ticket_event(
+        &self,
+        app_id: Uuid,
+        event: TicketUpdatedEvent,
+    ) -> TicketsResult<()> {
+        match &event {
+            TicketUpdatedEvent::MessageAdded(event) if event.from_staff => {
+                tickets::post_staff_reply(self, app_id, event).await
+            }
+            TicketUpdatedEvent::TicketClosed(event) => {
+                tickets::close_ticket_room(self, app_id, event).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
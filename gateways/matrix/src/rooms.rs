@@ -0,0 +1,86 @@
+//! rooms bound to an app, looked up from the room of every synced message so they are cached
+
+use std::fmt::Display;
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use errors::{ParsingError, TicketsResult};
+use gateway::cache::GenericIdCache;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum RoomPurpose {
+    Support,
+    StaffLogs,
+}
+
+const SUPPORT_ROOM_PURPOSE: &str = "support";
+const STAFF_LOGS_ROOM_PURPOSE: &str = "staff_logs";
+
+impl Display for RoomPurpose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoomPurpose::Support => write!(f, "{}", SUPPORT_ROOM_PURPOSE),
+            RoomPurpose::StaffLogs => write!(f, "{}", STAFF_LOGS_ROOM_PURPOSE),
+        }
+    }
+}
+
+impl TryFrom<String> for RoomPurpose {
+    type Error = ParsingError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(match s.as_str() {
+            SUPPORT_ROOM_PURPOSE => RoomPurpose::Support,
+            STAFF_LOGS_ROOM_PURPOSE => RoomPurpose::StaffLogs,
+            _ => Err(ParsingError::InvalidRoomPurpose(s))?,
+        })
+    }
+}
+
+pub type RoomCache = GenericIdCache<String, RoomPurpose>;
+
+pub async fn load(
+    executor: impl PgExecutor<'_>,
+) -> TicketsResult<Vec<(String, RoomPurpose, Uuid)>> {
+    sqlx::query!("SELECT room_id, purpose, app_id FROM matrix_rooms")
+        .fetch_all(executor)
+        .await?
+        .into_iter()
+        .map(|room| Ok((room.room_id, room.purpose.try_into()?, room.app_id)))
+        .collect()
+}
+
+pub async fn bind(
+    executor: impl PgExecutor<'_>,
+    room_id: &str,
+    purpose: RoomPurpose,
+    app_id: Uuid,
+) -> TicketsResult<()> {
+    sqlx::query!(
+        "INSERT INTO matrix_rooms (room_id, purpose, app_id) VALUES ($1, $2, $3) ON CONFLICT (app_id, purpose) DO UPDATE SET room_id = excluded.room_id",
+        room_id,
+        purpose.to_string(),
+        app_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Unbinds the room from the app it serves `purpose` for, returns that app.
+pub async fn unbind(
+    executor: impl PgExecutor<'_>,
+    room_id: &str,
+    purpose: RoomPurpose,
+) -> TicketsResult<Option<Uuid>> {
+    Ok(sqlx::query!(
+        "DELETE FROM matrix_rooms WHERE room_id = $1 AND purpose = $2 RETURNING app_id",
+        room_id,
+        purpose.to_string()
+    )
+    .fetch_optional(executor)
+    .await?
+    .map(|room| room.app_id))
+}
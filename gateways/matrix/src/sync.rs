@@ -0,0 +1,133 @@
+//! the sync loop, new messages of the rooms the gateway is in and invites into further rooms
+
+use std::time::Duration;
+
+use sqlx::PgExecutor;
+
+use errors::TicketsResult;
+
+use crate::client::{InvitedRoom, RoomEvent, SyncResponse};
+use crate::commands::{self, COMMAND_PREFIX};
+use crate::rooms::RoomPurpose;
+use crate::{tickets, MatrixState};
+
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub async fn run(state: MatrixState) -> TicketsResult<()> {
+    let mut since = load_next_batch(&state.pg_pool, &state.matrix.user_id).await?;
+
+    // messages sent before the gateway first started are not turned into tickets, only the
+    // invites still waiting are answered
+    if since.is_none() {
+        let response = state.matrix.sync(None, Duration::ZERO).await?;
+
+        for (room_id, invite) in &response.rooms.invite {
+            handle_invite(&state, room_id, invite).await;
+        }
+
+        save_next_batch(&state.pg_pool, &state.matrix.user_id, &response.next_batch).await?;
+        since = Some(response.next_batch);
+    }
+
+    loop {
+        let response = match state.matrix.sync(since.as_deref(), SYNC_TIMEOUT).await {
+            Ok(response) => response,
+            Err(err) => {
+                log::warn!("Sync failed, retrying in {RETRY_DELAY:?}: {err}");
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        handle_sync(&state, &response).await;
+
+        save_next_batch(&state.pg_pool, &state.matrix.user_id, &response.next_batch).await?;
+        since = Some(response.next_batch);
+    }
+}
+
+pub async fn handle_sync(state: &MatrixState, response: &SyncResponse) {
+    for (room_id, invite) in &response.rooms.invite {
+        handle_invite(state, room_id, invite).await;
+    }
+
+    for (room_id, room) in &response.rooms.join {
+        for event in &room.timeline.events {
+            if let Err(err) = handle_event(state, room_id, event).await {
+                log::error!(
+                    "Error handling event {} in room {room_id}: {err}",
+                    event.event_id
+                );
+            }
+        }
+    }
+}
+
+/// Joins rooms the gateway's admins invite it into, other invites are rejected.
+async fn handle_invite(state: &MatrixState, room_id: &str, invite: &InvitedRoom) {
+    let inviter = invite.inviter(&state.matrix.user_id);
+
+    let answered = match inviter {
+        Some(inviter) if state.admins.contains(inviter) => {
+            log::info!("Joining room {room_id} on the invite of {inviter}");
+            state.matrix.join(room_id).await
+        }
+        _ => {
+            log::info!("Rejecting the invite into room {room_id} by {inviter:?}");
+            state.matrix.leave(room_id).await
+        }
+    };
+
+    if let Err(err) = answered {
+        log::error!("Could not answer the invite into room {room_id}: {err}");
+    }
+}
+
+async fn handle_event(state: &MatrixState, room_id: &str, event: &RoomEvent) -> TicketsResult<()> {
+    if event.sender == state.matrix.user_id {
+        return Ok(());
+    }
+
+    let Some(body) = event.text() else {
+        return Ok(());
+    };
+
+    if body.split_whitespace().next() == Some(COMMAND_PREFIX) {
+        return commands::execute(state, room_id, &event.sender, body).await;
+    }
+
+    match state.rooms.get_app_id(room_id, RoomPurpose::Support).await {
+        Some(app_id) => tickets::open_ticket(state, app_id, event, body).await,
+        None => tickets::relay_customer_message(state, room_id, event, body).await,
+    }
+}
+
+async fn load_next_batch(
+    executor: impl PgExecutor<'_>,
+    user_id: &str,
+) -> TicketsResult<Option<String>> {
+    Ok(sqlx::query!(
+        "SELECT next_batch FROM matrix_sync_state WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .map(|state| state.next_batch))
+}
+
+async fn save_next_batch(
+    executor: impl PgExecutor<'_>,
+    user_id: &str,
+    next_batch: &str,
+) -> TicketsResult<()> {
+    sqlx::query!(
+        "INSERT INTO matrix_sync_state (user_id, next_batch) VALUES ($1, $2) ON CONFLICT (user_id) DO UPDATE SET next_batch = excluded.next_batch, updated_at = NOW()",
+        user_id,
+        next_batch
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,293 @@
+//! the conversation in ticket rooms, a message in the support room opens a ticket in a room of
+//! its own, the customer's messages there are added to the ticket, staff replies are posted back
+//! and the room is left once the ticket closes
+
+use uuid::Uuid;
+
+use errors::{MiscError, TicketsError, TicketsResult};
+use events::{MessageAddedEvent, TicketClosedEvent};
+use sdk::client::{SdkCallWithBody, SdkCallWithPathAndBody, SdkDownloadWithPathAndParams};
+use sdk::routes::consumer::{
+    CustomerMessageBody, SendCustomerMessage, SubmitTicket, SubmitTicketBody, Submitter,
+};
+use sdk::routes::staff::{TicketPath, TicketTranscript, TranscriptFormat, TranscriptQuery};
+
+use crate::client::{CreateRoom, FileInfo, MessageContent, RoomEvent};
+use crate::rooms::RoomPurpose;
+use crate::MatrixState;
+
+const OPENED_NOTICE: &str = "Your ticket was opened. Staff replies are posted in this room and your messages here are added to the ticket.";
+const CLOSED_NOTICE: &str =
+    "This ticket was closed. Write in the support room again if you need further help.";
+
+/// Opens a ticket from a message in the app's support room, customers who already have a ticket
+/// room continue that ticket instead.
+pub async fn open_ticket(
+    state: &MatrixState,
+    app_id: Uuid,
+    event: &RoomEvent,
+    body: &str,
+) -> TicketsResult<()> {
+    let open_ticket = sqlx::query!(
+        "SELECT room_id, ticket_id FROM matrix_ticket_rooms WHERE app_id = $1 AND customer_id = $2 ORDER BY created_at DESC LIMIT 1",
+        app_id,
+        &event.sender
+    )
+    .fetch_optional(&state.pg_pool)
+    .await?;
+
+    if let Some(open_ticket) = open_ticket {
+        match send_customer_message(state, app_id, open_ticket.ticket_id, &event.sender, body).await
+        {
+            Ok(()) => {
+                state
+                    .matrix
+                    .send_message(
+                        &open_ticket.room_id,
+                        &MessageContent::notice(
+                            "Your message in the support room was added to this ticket.",
+                        ),
+                    )
+                    .await?;
+                return Ok(());
+            }
+            // its room is about to be left, the customer gets a new one
+            Err(TicketsError::Misc(MiscError::TicketClosed)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let display_name = match state.matrix.display_name(&event.sender).await {
+        Ok(display_name) => display_name,
+        Err(err) => {
+            log::debug!(
+                "Could not look up the display name of {}: {err}",
+                event.sender
+            );
+            None
+        }
+    };
+
+    let submitted = SubmitTicket::call_with_body(
+        &state.client,
+        SubmitTicketBody {
+            app_id,
+            message: body.to_string(),
+            submitter: Submitter {
+                external_id: event.sender.clone(),
+                display_name,
+            },
+        },
+    )
+    .await?;
+
+    let ticket_id = submitted.ticket_id;
+    let room_id = state
+        .matrix
+        .create_room(&CreateRoom {
+            name: format!("Ticket {}", &ticket_id.simple().to_string()[..8]),
+            topic: format!("Support ticket {ticket_id}"),
+            invite: vec![event.sender.clone()],
+        })
+        .await?;
+
+    sqlx::query!(
+        "INSERT INTO matrix_ticket_rooms (room_id, ticket_id, app_id, customer_id) VALUES ($1, $2, $3, $4)",
+        &room_id,
+        ticket_id,
+        app_id,
+        &event.sender
+    )
+    .execute(&state.pg_pool)
+    .await?;
+
+    state
+        .matrix
+        .send_message(&room_id, &MessageContent::notice(OPENED_NOTICE))
+        .await?;
+
+    log::info!(
+        "Submitted ticket {ticket_id} for {} to app {app_id} in room {room_id}",
+        event.sender
+    );
+
+    Ok(())
+}
+
+/// Adds the text of a customer's message in their ticket room to the ticket, other messages
+/// are ignored.
+pub async fn relay_customer_message(
+    state: &MatrixState,
+    room_id: &str,
+    event: &RoomEvent,
+    body: &str,
+) -> TicketsResult<()> {
+    if body.trim().is_empty() {
+        return Ok(());
+    }
+
+    let ticket = sqlx::query!(
+        "SELECT ticket_id, app_id FROM matrix_ticket_rooms WHERE room_id = $1 AND customer_id = $2",
+        room_id,
+        &event.sender
+    )
+    .fetch_optional(&state.pg_pool)
+    .await?;
+
+    let Some(ticket) = ticket else {
+        return Ok(());
+    };
+
+    let sent =
+        send_customer_message(state, ticket.app_id, ticket.ticket_id, &event.sender, body).await;
+
+    // such as messages written while the room of a closed ticket is being left
+    if let Err(err) = sent {
+        state
+            .matrix
+            .send_message(
+                room_id,
+                &MessageContent::notice(format!("Your message was not added to the ticket: {err}")),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn send_customer_message(
+    state: &MatrixState,
+    app_id: Uuid,
+    ticket_id: Uuid,
+    customer_id: &str,
+    body: &str,
+) -> TicketsResult<()> {
+    SendCustomerMessage::call_with_path_and_body(
+        &state.client,
+        TicketPath { app_id, ticket_id },
+        CustomerMessageBody {
+            external_id: customer_id.to_string(),
+            body: body.to_string(),
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Posts a staff reply to the ticket's room, if the ticket has one.
+pub async fn post_staff_reply(
+    state: &MatrixState,
+    app_id: Uuid,
+    event: &MessageAddedEvent,
+) -> TicketsResult<()> {
+    let room = sqlx::query!(
+        "SELECT room_id FROM matrix_ticket_rooms WHERE ticket_id = $1 AND app_id = $2",
+        &event.ticket_id,
+        &app_id
+    )
+    .fetch_optional(&state.pg_pool)
+    .await?;
+
+    let Some(room) = room else {
+        return Ok(());
+    };
+
+    state
+        .matrix
+        .send_message(&room.room_id, &MessageContent::text(&event.body))
+        .await?;
+
+    Ok(())
+}
+
+/// Posts the transcript of a closed ticket to the app's staff logs room, if it has one, and
+/// leaves the ticket's room. The room is kept if the transcript could not be posted.
+pub async fn close_ticket_room(
+    state: &MatrixState,
+    app_id: Uuid,
+    event: &TicketClosedEvent,
+) -> TicketsResult<()> {
+    let room = sqlx::query!(
+        "SELECT room_id FROM matrix_ticket_rooms WHERE ticket_id = $1 AND app_id = $2",
+        &event.ticket_id,
+        &app_id
+    )
+    .fetch_optional(&state.pg_pool)
+    .await?;
+
+    let Some(room) = room else {
+        return Ok(());
+    };
+
+    match state.rooms.get_id(app_id, RoomPurpose::StaffLogs).await {
+        Some(staff_logs) => {
+            let transcript = TicketTranscript::download_with_path_and_query(
+                &state.client,
+                TicketPath {
+                    app_id,
+                    ticket_id: event.ticket_id,
+                },
+                TranscriptQuery {
+                    format: TranscriptFormat::Html,
+                    include_notes: true,
+                },
+            )
+            .await?;
+
+            let size = transcript.bytes.len();
+            let content_uri = state
+                .matrix
+                .upload(
+                    &transcript.file_name,
+                    &transcript.content_type,
+                    transcript.bytes,
+                )
+                .await?;
+
+            state
+                .matrix
+                .send_message(
+                    &staff_logs,
+                    &MessageContent::notice(format!(
+                        "Ticket `{}` was closed, its transcript is attached.",
+                        event.ticket_id
+                    )),
+                )
+                .await?;
+            state
+                .matrix
+                .send_message(
+                    &staff_logs,
+                    &MessageContent::file(
+                        transcript.file_name,
+                        content_uri,
+                        FileInfo {
+                            mimetype: transcript.content_type,
+                            size,
+                        },
+                    ),
+                )
+                .await?;
+        }
+        None => log::info!(
+            "Not posting the transcript of ticket {}, app {app_id} has no staff logs room",
+            event.ticket_id
+        ),
+    }
+
+    state
+        .matrix
+        .send_message(&room.room_id, &MessageContent::notice(CLOSED_NOTICE))
+        .await?;
+    state.matrix.leave(&room.room_id).await?;
+
+    sqlx::query!(
+        "DELETE FROM matrix_ticket_rooms WHERE room_id = $1",
+        &room.room_id
+    )
+    .execute(&state.pg_pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,333 @@
+//! The Matrix client against a local stand-in for the homeserver, and parsing of the `!tickets`
+//! commands.
+
+use std::time::Duration;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use errors::{MiscError, TicketsError};
+use matrix_tickets::client::{CreateRoom, FileInfo, MatrixClient, MessageContent};
+use matrix_tickets::commands::Command;
+use matrix_tickets::rooms::RoomPurpose;
+use test_support::StandIn;
+
+const GATEWAY_USER: &str = "@tickets:localhost";
+const ACCESS_TOKEN: &str = "stand-in-token";
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    path: String,
+    authorization: Option<String>,
+    body: Value,
+}
+
+/// Replies are only queued for sent messages.
+type Homeserver = StandIn<Recorded, (StatusCode, Json<Value>)>;
+
+fn record(stand_in: &Homeserver, path: String, headers: &HeaderMap, body: Value) {
+    stand_in.record(Recorded {
+        path,
+        authorization: headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body,
+    });
+}
+
+async fn sync(
+    State(stand_in): State<Homeserver>,
+    headers: HeaderMap,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Json<Value> {
+    record(&stand_in, "/sync".to_string(), &headers, json!(query));
+
+    Json(json!({
+        "next_batch": "s2",
+        "rooms": {
+            "join": {
+                "!support:localhost": {
+                    "timeline": {
+                        "events": [
+                            {
+                                "type": "m.room.message",
+                                "sender": "@jane:localhost",
+                                "event_id": "$1",
+                                "content": { "msgtype": "m.text", "body": "I cannot log in" }
+                            },
+                            {
+                                "type": "m.room.message",
+                                "sender": "@jane:localhost",
+                                "event_id": "$2",
+                                "content": {
+                                    "msgtype": "m.text",
+                                    "body": "* I cannot log in!",
+                                    "m.relates_to": { "rel_type": "m.replace", "event_id": "$1" }
+                                }
+                            },
+                            {
+                                "type": "m.room.member",
+                                "sender": "@jane:localhost",
+                                "event_id": "$3",
+                                "state_key": "@jane:localhost",
+                                "content": { "membership": "join" }
+                            }
+                        ]
+                    }
+                }
+            },
+            "invite": {
+                "!logs:localhost": {
+                    "invite_state": {
+                        "events": [
+                            {
+                                "type": "m.room.member",
+                                "sender": "@admin:localhost",
+                                "state_key": GATEWAY_USER,
+                                "content": { "membership": "invite" }
+                            }
+                        ]
+                    }
+                }
+            }
+        }
+    }))
+}
+
+async fn send_message(
+    State(stand_in): State<Homeserver>,
+    headers: HeaderMap,
+    Path((room_id, txn_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if let Some(refusal) = stand_in.next_reply() {
+        return Err(refusal);
+    }
+
+    record(
+        &stand_in,
+        format!("/send/{room_id}/{txn_id}"),
+        &headers,
+        body,
+    );
+
+    Ok(Json(json!({ "event_id": "$sent" })))
+}
+
+async fn create_room(
+    State(stand_in): State<Homeserver>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    record(&stand_in, "/createRoom".to_string(), &headers, body);
+
+    Json(json!({ "room_id": "!ticket:localhost" }))
+}
+
+async fn join(Path(room_id): Path<String>) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({ "errcode": "M_FORBIDDEN", "error": format!("Not invited to {room_id}") })),
+    )
+}
+
+async fn upload(
+    State(stand_in): State<Homeserver>,
+    headers: HeaderMap,
+    Query(query): Query<Vec<(String, String)>>,
+    body: axum::body::Bytes,
+) -> Json<Value> {
+    record(
+        &stand_in,
+        "/upload".to_string(),
+        &headers,
+        json!({
+            "query": query,
+            "content_type": headers.get("content-type").and_then(|value| value.to_str().ok()),
+            "size": body.len(),
+        }),
+    );
+
+    Json(json!({ "content_uri": "mxc://localhost/transcript" }))
+}
+
+async fn serve() -> (Homeserver, MatrixClient) {
+    let stand_in = Homeserver::default();
+
+    // the first message is rate limited once
+    stand_in.reply_with((
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(
+            json!({ "errcode": "M_LIMIT_EXCEEDED", "error": "Too many requests", "retry_after_ms": 10 }),
+        ),
+    ));
+
+    let address = test_support::serve(
+        Router::new()
+            .route("/_matrix/client/v3/sync", get(sync))
+            .route(
+                "/_matrix/client/v3/rooms/:room_id/send/m.room.message/:txn_id",
+                put(send_message),
+            )
+            .route("/_matrix/client/v3/createRoom", post(create_room))
+            .route("/_matrix/client/v3/join/:room_id", post(join))
+            .route("/_matrix/media/v3/upload", post(upload))
+            .with_state(stand_in.clone()),
+    )
+    .await;
+
+    let client = MatrixClient::new(
+        &format!("http://{address}/"),
+        GATEWAY_USER.to_string(),
+        ACCESS_TOKEN.to_string(),
+    )
+    .unwrap();
+
+    (stand_in, client)
+}
+
+#[tokio::test]
+async fn sync_events() {
+    let (stand_in, client) = serve().await;
+
+    let response = client
+        .sync(Some("s1"), Duration::from_secs(30))
+        .await
+        .unwrap();
+
+    assert_eq!(response.next_batch, "s2");
+
+    let events = &response.rooms.join["!support:localhost"].timeline.events;
+    let texts = events.iter().map(|event| event.text()).collect::<Vec<_>>();
+    // edits and state events are not messages to relay
+    assert_eq!(texts, [Some("I cannot log in"), None, None]);
+
+    let invite = &response.rooms.invite["!logs:localhost"];
+    assert_eq!(invite.inviter(GATEWAY_USER), Some("@admin:localhost"));
+    assert_eq!(invite.inviter("@someone:localhost"), None);
+
+    let requests = stand_in.received();
+    assert_eq!(
+        requests[0].authorization.as_deref(),
+        Some("Bearer stand-in-token")
+    );
+    let query = requests[0].body.as_array().unwrap();
+    assert!(query.contains(&json!(["since", "s1"])));
+    assert!(query.contains(&json!(["timeout", "30000"])));
+}
+
+#[tokio::test]
+async fn ticket_room() {
+    let (stand_in, client) = serve().await;
+
+    let room_id = client
+        .create_room(&CreateRoom {
+            name: "Ticket 0123abcd".to_string(),
+            topic: "Support ticket".to_string(),
+            invite: vec!["@jane:localhost".to_string()],
+        })
+        .await
+        .unwrap();
+    assert_eq!(room_id, "!ticket:localhost");
+
+    // rate limited once, sent again after the delay asked for
+    let event_id = client
+        .send_message(
+            &room_id,
+            &MessageContent::text("Have you tried resetting it?"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(event_id, "$sent");
+
+    let content_uri = client
+        .upload("transcript.html", "text/html", b"<html></html>".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(content_uri, "mxc://localhost/transcript");
+
+    client
+        .send_message(
+            "!logs:localhost",
+            &MessageContent::file(
+                "transcript.html".to_string(),
+                content_uri,
+                FileInfo {
+                    mimetype: "text/html".to_string(),
+                    size: 13,
+                },
+            ),
+        )
+        .await
+        .unwrap();
+
+    let err = client.join("!private:localhost").await.unwrap_err();
+    assert!(
+        matches!(&err, TicketsError::Misc(MiscError::Matrix(message)) if message == "M_FORBIDDEN: Not invited to !private:localhost"),
+        "{err}"
+    );
+
+    let requests = stand_in.received();
+    let paths = requests
+        .iter()
+        .map(|request| request.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(paths[0], "/createRoom");
+    assert!(paths[1].starts_with("/send/!ticket:localhost/"));
+    assert_eq!(paths[2], "/upload");
+    assert!(paths[3].starts_with("/send/!logs:localhost/"));
+    // transaction ids differ between messages
+    assert_ne!(paths[1].rsplit('/').next(), paths[3].rsplit('/').next());
+
+    assert_eq!(requests[0].body["preset"], "private_chat");
+    assert_eq!(requests[0].body["invite"], json!(["@jane:localhost"]));
+    assert_eq!(
+        requests[1].body,
+        json!({ "msgtype": "m.text", "body": "Have you tried resetting it?" })
+    );
+    assert_eq!(
+        requests[2].body["query"],
+        json!([["filename", "transcript.html"]])
+    );
+    assert_eq!(requests[2].body["content_type"], "text/html");
+    assert_eq!(requests[2].body["size"], 13);
+    assert_eq!(
+        requests[3].body,
+        json!({
+            "msgtype": "m.file",
+            "body": "transcript.html",
+            "filename": "transcript.html",
+            "url": "mxc://localhost/transcript",
+            "info": { "mimetype": "text/html", "size": 13 }
+        })
+    );
+}
+
+#[test]
+fn commands() {
+    let app_id = Uuid::new_v4();
+
+    assert_eq!(
+        Command::parse(&format!("!tickets bind {app_id} support")).unwrap(),
+        Command::Bind {
+            app_id,
+            purpose: RoomPurpose::Support
+        }
+    );
+    assert_eq!(
+        Command::parse("!tickets   unbind staff_logs").unwrap(),
+        Command::Unbind {
+            purpose: RoomPurpose::StaffLogs
+        }
+    );
+
+    assert!(Command::parse("!tickets bind not-an-app support").is_err());
+    assert!(Command::parse(&format!("!tickets bind {app_id} lobby")).is_err());
+    assert!(Command::parse("!tickets close").is_err());
+    assert!(Command::parse("!tickets").is_err());
+}
@@ -0,0 +1,124 @@
+//! an inbound gateway turning signed POSTs from external systems, such as monitoring alerts or
+//! contact forms, into tickets; every configured source belongs to one app and maps its
+//! payloads onto tickets through templates
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use uuid::Uuid;
+
+use auth::jwt::JwtAccessor;
+use errors::{AuthorizationError, TicketsResult};
+use events::TicketUpdatedEvent;
+use gateway::Gateway;
+use sdk::client::{SdkCallWithBody, SignedTicketClient};
+use sdk::routes::consumer::{SubmitTicket, SubmitTicketResponse};
+
+use crate::mapping::FieldMapping;
+
+pub mod mapping;
+pub mod signature;
+
+/// Sent as `x-gateway`, apps enable the gateway under this name through `toggle_gateway`.
+pub const GATEWAY_NAME: &str = "webhook";
+
+#[derive(serde::Deserialize)]
+pub struct WebhookGatewayConfig {
+    pub bind_address: String,
+    /// Keyed by the name senders post to, `/hooks/{name}`.
+    pub sources: HashMap<String, SourceConfig>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct SourceConfig {
+    pub app_id: Uuid,
+    pub secret: String,
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+    /// Holds the unix timestamp in seconds the request was signed at.
+    #[serde(default = "default_timestamp_header")]
+    pub timestamp_header: String,
+    pub mapping: FieldMapping,
+}
+
+fn default_signature_header() -> String {
+    "x-signature".to_string()
+}
+
+fn default_timestamp_header() -> String {
+    "x-signature-timestamp".to_string()
+}
+
+#[derive(Clone)]
+pub struct GatewayState {
+    pub sources: Arc<HashMap<String, SourceConfig>>,
+    pub client: SignedTicketClient,
+}
+
+impl Gateway for GatewayState {
+    const NAME: &'static str = GATEWAY_NAME;
+    const SYSTEM_ACCESSOR: JwtAccessor = JwtAccessor::WebhookSystem;
+
+    /// The source's name, senders only submit tickets.
+    type Identity = String;
+
+    // nothing is sent back to the sources
+    async fn handle_ticket_event(&self, _: Uuid, _: TicketUpdatedEvent) -> TicketsResult<()> {
+        Ok(())
+    }
+}
+
+pub fn app(state: GatewayState) -> Router {
+    Router::new()
+        .route("/hooks/:source", post(receive_hook))
+        .with_state(state)
+}
+
+async fn receive_hook(
+    State(state): State<GatewayState>,
+    Path(source): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> TicketsResult<(StatusCode, Json<SubmitTicketResponse>)> {
+    // unknown sources are refused like bad signatures, not revealing which names exist
+    let source = state
+        .sources
+        .get(&source)
+        .ok_or(AuthorizationError::InvalidSignature)?;
+
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(AuthorizationError::InvalidSignature)
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+
+    signature::verify(
+        &source.secret,
+        header(&source.timestamp_header)?,
+        &body,
+        header(&source.signature_header)?,
+        now,
+    )?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+
+    let payload = mapping::parse_payload(content_type, &body)?;
+    let ticket = source.mapping.build_ticket(source.app_id, &payload)?;
+
+    let submitted = SubmitTicket::call_with_body(&state.client, ticket).await?;
+
+    Ok((StatusCode::CREATED, Json(submitted)))
+}
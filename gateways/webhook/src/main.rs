@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use errors::TicketsResult;
+use gateway::{Bootstrap, GatewayConfig};
+use webhook_tickets::{GatewayState, WebhookGatewayConfig};
+
+#[derive(serde::Deserialize)]
+pub struct WebhookTicketsConfig {
+    #[serde(flatten)]
+    gateway: GatewayConfig,
+    webhook_gateway: WebhookGatewayConfig,
+}
+
+#[tokio::main]
+async fn main() -> TicketsResult<()> {
+    tracing_subscriber::fmt::init();
+
+    let config: WebhookTicketsConfig = dry::config::load_config()?;
+
+    let bootstrap = Bootstrap::<GatewayState>::new(config.gateway)?;
+
+    let state = GatewayState {
+        sources: Arc::new(config.webhook_gateway.sources),
+        client: bootstrap.system_client()?,
+    };
+
+    let listener = tokio::net::TcpListener::bind(&config.webhook_gateway.bind_address).await?;
+
+    log::info!(
+        "Accepting webhooks for {} sources on {}",
+        state.sources.len(),
+        config.webhook_gateway.bind_address
+    );
+
+    axum::serve(listener, webhook_tickets::app(state)).await?;
+
+    Ok(())
+}
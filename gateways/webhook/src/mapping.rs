@@ -0,0 +1,164 @@
+//! building tickets from arbitrary payloads, each source maps fields of the payloads it
+//! receives into the ticket's message and submitter through templates
+
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use errors::{ParsingError, TicketsResult};
+use sdk::routes::consumer::{SubmitTicketBody, Submitter};
+
+/// Text with `{{ path }}` placeholders, replaced by the payload's value at the dot separated
+/// path such as `{{ alert.labels.severity }}` or `{{ items.0.name }}`.
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(try_from = "String")]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone)]
+enum TemplatePart {
+    Text(String),
+    Field(Vec<String>),
+}
+
+impl TryFrom<String> for Template {
+    type Error = String;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        let mut parts = vec![];
+        let mut rest = template.as_str();
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_string()));
+            }
+
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| format!("Unclosed placeholder in `{template}`."))?;
+
+            let path = rest[start + 2..start + end].trim();
+            if path.is_empty() || path.split('.').any(str::is_empty) {
+                return Err(format!("Invalid placeholder `{path}` in `{template}`."));
+            }
+
+            parts.push(TemplatePart::Field(
+                path.split('.').map(str::to_string).collect(),
+            ));
+            rest = &rest[start + end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+
+        Ok(Template { parts })
+    }
+}
+
+impl Template {
+    /// Renders the template, failing with the path of the first placeholder missing from
+    /// `payload`.
+    pub fn render(&self, payload: &Value) -> Result<String, String> {
+        let mut rendered = String::new();
+
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => rendered.push_str(text),
+                TemplatePart::Field(path) => {
+                    let value = path
+                        .iter()
+                        .try_fold(payload, |value, segment| match value {
+                            Value::Object(fields) => fields.get(segment),
+                            Value::Array(items) => segment
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|index| items.get(index)),
+                            _ => None,
+                        })
+                        .filter(|value| !value.is_null())
+                        .ok_or_else(|| path.join("."))?;
+
+                    match value {
+                        Value::String(text) => rendered.push_str(text),
+                        // numbers, booleans and nested values as compact JSON
+                        value => rendered.push_str(&value.to_string()),
+                    }
+                }
+            }
+        }
+
+        Ok(rendered)
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct FieldMapping {
+    pub message: Template,
+    /// Identifies the customer on the gateway, payloads rendering the same id are tickets of
+    /// the same customer.
+    pub submitter_id: Template,
+    /// Left out of the ticket if the payload lacks a placeholder's value.
+    #[serde(default)]
+    pub submitter_name: Option<Template>,
+}
+
+impl FieldMapping {
+    pub fn build_ticket(&self, app_id: Uuid, payload: &Value) -> TicketsResult<SubmitTicketBody> {
+        let missing = |path: String| {
+            ParsingError::InvalidRequest(format!("The payload is missing the field `{path}`."))
+        };
+
+        let message = self.message.render(payload).map_err(missing)?;
+        let external_id = self.submitter_id.render(payload).map_err(missing)?;
+
+        if message.trim().is_empty() || external_id.trim().is_empty() {
+            return Err(ParsingError::InvalidRequest(
+                "The payload maps to an empty message or submitter.".to_string(),
+            ))?;
+        }
+
+        let display_name = self
+            .submitter_name
+            .as_ref()
+            .and_then(|template| template.render(payload).ok())
+            .filter(|name| !name.trim().is_empty());
+
+        Ok(SubmitTicketBody {
+            app_id,
+            message,
+            submitter: Submitter {
+                external_id,
+                display_name,
+            },
+        })
+    }
+}
+
+/// Reads a JSON or form encoded body, form fields become the string fields of an object.
+pub fn parse_payload(content_type: Option<&str>, body: &[u8]) -> TicketsResult<Value> {
+    let media_type = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|media_type| media_type.trim().to_ascii_lowercase());
+
+    match media_type.as_deref() {
+        None | Some("application/json") => Ok(serde_json::from_slice(body)
+            .map_err(|err| ParsingError::InvalidRequest(format!("Invalid JSON payload: {err}")))?),
+        Some("application/x-www-form-urlencoded") => {
+            let fields =
+                serde_urlencoded::from_bytes::<Vec<(String, String)>>(body).map_err(|err| {
+                    ParsingError::InvalidRequest(format!("Invalid form payload: {err}"))
+                })?;
+
+            Ok(Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, Value::String(value)))
+                    .collect::<Map<_, _>>(),
+            ))
+        }
+        Some(media_type) => Err(ParsingError::InvalidRequest(format!(
+            "Payloads of type `{media_type}` are not supported."
+        )))?,
+    }
+}
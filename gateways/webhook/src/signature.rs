@@ -0,0 +1,54 @@
+//! senders sign `{timestamp}.{body}` with their source's shared secret, where the timestamp is
+//! sent in its own header as unix seconds. the signature header holds `sha256=` followed by the
+//! hex encoded HMAC-SHA256, requests signed too long ago are refused so they cannot be replayed
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use errors::{AuthorizationError, TicketsResult};
+
+const PREFIX: &str = "sha256=";
+
+/// How far the signed timestamp may be from the gateway's clock, in either direction.
+pub const MAX_CLOCK_SKEW_SECONDS: i64 = 5 * 60;
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("any key length");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body);
+    mac
+}
+
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let digest = mac(secret, timestamp, body).finalize().into_bytes();
+    format!("{PREFIX}{}", hex::encode(digest))
+}
+
+/// Checks `signature` in constant time, a bare hex digest without the prefix is accepted too.
+/// `timestamp` must be within [`MAX_CLOCK_SKEW_SECONDS`] of `now`.
+pub fn verify(
+    secret: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+    now: i64,
+) -> TicketsResult<()> {
+    let timestamp = timestamp
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| AuthorizationError::InvalidSignature)?;
+
+    if timestamp.abs_diff(now) > MAX_CLOCK_SKEW_SECONDS as u64 {
+        return Err(AuthorizationError::InvalidSignature)?;
+    }
+
+    let digest = signature.trim();
+    let digest = digest.strip_prefix(PREFIX).unwrap_or(digest);
+    let digest = hex::decode(digest).map_err(|_| AuthorizationError::InvalidSignature)?;
+
+    mac(secret, timestamp, body)
+        .verify_slice(&digest)
+        .map_err(|_| AuthorizationError::InvalidSignature)?;
+
+    Ok(())
+}
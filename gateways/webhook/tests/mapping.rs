@@ -0,0 +1,153 @@
+//! Payload mappings and signatures, independent of a running collector.
+
+use serde_json::json;
+use uuid::Uuid;
+
+use errors::{AuthorizationError, ParsingError, TicketsError};
+use webhook_tickets::mapping::{parse_payload, FieldMapping};
+use webhook_tickets::signature;
+
+fn mapping() -> FieldMapping {
+    serde_json::from_value(json!({
+        "message": "[{{ alert.severity }}] {{alert.title}} ({{ alert.hosts.0 }})",
+        "submitter_id": "{{ alert.rule_id }}",
+        "submitter_name": "{{ alert.rule.name }}"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn render_json_payload() {
+    let app_id = Uuid::new_v4();
+    let payload = json!({
+        "alert": {
+            "severity": 2,
+            "title": "Disk almost full",
+            "hosts": ["db-1", "db-2"],
+            "rule_id": "disk-usage"
+        }
+    });
+
+    let ticket = mapping().build_ticket(app_id, &payload).unwrap();
+
+    assert_eq!(ticket.app_id, app_id);
+    assert_eq!(ticket.message, "[2] Disk almost full (db-1)");
+    assert_eq!(ticket.submitter.external_id, "disk-usage");
+    // the optional name's placeholder is missing from the payload
+    assert_eq!(ticket.submitter.display_name, None);
+}
+
+#[test]
+fn missing_required_field() {
+    let payload = json!({ "alert": { "severity": 2, "title": "Disk almost full" } });
+
+    let err = mapping()
+        .build_ticket(Uuid::new_v4(), &payload)
+        .err()
+        .unwrap();
+
+    assert!(matches!(
+        err,
+        TicketsError::Parsing(ParsingError::InvalidRequest(message)) if message.contains("alert.hosts.0")
+    ));
+}
+
+#[test]
+fn render_form_payload() {
+    let mapping: FieldMapping = serde_json::from_value(json!({
+        "message": "{{ message }}",
+        "submitter_id": "{{ email }}",
+        "submitter_name": "{{ name }}"
+    }))
+    .unwrap();
+
+    let payload = parse_payload(
+        Some("application/x-www-form-urlencoded; charset=utf-8"),
+        b"email=jane%40example.com&name=Jane&message=Cannot+log+in",
+    )
+    .unwrap();
+
+    let ticket = mapping.build_ticket(Uuid::new_v4(), &payload).unwrap();
+
+    assert_eq!(ticket.message, "Cannot log in");
+    assert_eq!(ticket.submitter.external_id, "jane@example.com");
+    assert_eq!(ticket.submitter.display_name.as_deref(), Some("Jane"));
+}
+
+#[test]
+fn invalid_templates() {
+    for template in ["{{ unclosed", "{{ }}", "{{ alert..title }}"] {
+        let parsed = serde_json::from_value::<FieldMapping>(json!({
+            "message": template,
+            "submitter_id": "{{ id }}"
+        }));
+
+        assert!(parsed.is_err(), "`{template}` should be refused");
+    }
+}
+
+#[test]
+fn signatures() {
+    let body = br#"{"alert":{}}"#;
+    let now = 1_700_000_000;
+    let signed = signature::sign("shared-secret", now, body);
+    let verify = |timestamp: &str, signature: &str| {
+        signature::verify("shared-secret", timestamp, body, signature, now)
+    };
+
+    assert!(signed.starts_with("sha256="));
+    assert!(verify("1700000000", &signed).is_ok());
+    assert!(verify("1700000000", &signed["sha256=".len()..]).is_ok());
+
+    // senders' clocks may be off by a few minutes in either direction
+    for timestamp in [now - signature::MAX_CLOCK_SKEW_SECONDS, now + 60] {
+        let signed = signature::sign("shared-secret", timestamp, body);
+        assert!(verify(&timestamp.to_string(), &signed).is_ok());
+    }
+
+    let stale = now - signature::MAX_CLOCK_SKEW_SECONDS - 1;
+    let early = now + signature::MAX_CLOCK_SKEW_SECONDS + 1;
+
+    for (secret, timestamp, body, signature) in [
+        ("other-secret", now.to_string(), &body[..], signed.clone()),
+        ("shared-secret", now.to_string(), b"{}", signed.clone()),
+        (
+            "shared-secret",
+            now.to_string(),
+            &body[..],
+            "sha256=not-hex".to_string(),
+        ),
+        // the timestamp is part of the signed content
+        (
+            "shared-secret",
+            (now + 1).to_string(),
+            &body[..],
+            signed.clone(),
+        ),
+        (
+            "shared-secret",
+            "yesterday".to_string(),
+            &body[..],
+            signed.clone(),
+        ),
+        (
+            "shared-secret",
+            stale.to_string(),
+            &body[..],
+            signature::sign("shared-secret", stale, body),
+        ),
+        (
+            "shared-secret",
+            early.to_string(),
+            &body[..],
+            signature::sign("shared-secret", early, body),
+        ),
+    ] {
+        assert!(matches!(
+            signature::verify(secret, &timestamp, body, &signature, now),
+            Err(TicketsError::Authorization(
+                AuthorizationError::InvalidSignature
+            ))
+        ));
+    }
+}
@@ -6,6 +6,12 @@ use uuid::Uuid;
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub enum JwtAccessor {
     DiscordSystem,
+    /// The inbound webhook gateway, submitting tickets on behalf of external systems.
+    WebhookSystem,
+    /// The email gateway, submitting tickets from inbound mail and mailing staff replies back.
+    EmailSystem,
+    /// The Matrix gateway, relaying ticket conversations to rooms on a homeserver.
+    MatrixSystem,
     DiscordStaffMember {
         user_id: u64,
         authorized_apps: HashSet<Uuid>,
@@ -13,6 +19,19 @@ pub enum JwtAccessor {
     },
 }
 
+impl JwtAccessor {
+    /// Name of the gateway the accessor acts through, as sent in the `x-gateway` header. Staff
+    /// members are identified by the gateway they signed in on.
+    pub fn gateway(&self) -> &'static str {
+        match self {
+            JwtAccessor::DiscordSystem | JwtAccessor::DiscordStaffMember { .. } => "discord",
+            JwtAccessor::WebhookSystem => "webhook",
+            JwtAccessor::EmailSystem => "email",
+            JwtAccessor::MatrixSystem => "matrix",
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct JwtData {
     pub accessor: JwtAccessor,
@@ -22,9 +41,32 @@ pub struct JwtData {
 pub struct JwtClaim {
     data: JwtData,
     pub exp: i64,
+    #[serde(default)]
+    pub iat: i64,
+    /// Set only on tokens minted by the collector after it verified the memberships behind
+    /// `authorized_apps`, see [`JwtConfig::generate_pre_authorized`].
+    #[serde(default)]
+    pub pre_authorized: bool,
+}
+
+impl JwtClaim {
+    pub fn data(&self) -> &JwtData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> JwtData {
+        self.data
+    }
+
+    /// Whether the collector minted the token after verifying its `authorized_apps`, so they
+    /// can be trusted without re-checking the database. Self-signed tokens never are, whatever
+    /// their lifetime.
+    pub fn is_pre_authorized(&self) -> bool {
+        self.pre_authorized
+    }
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct JwtKeyPathsConfig {
     pub public_key_location: String,
     pub private_key_location: String,
@@ -36,6 +78,9 @@ pub struct JwtConfig {
 }
 
 impl JwtConfig {
+    // 5 minutes = 60 seconds * 5 minutes
+    pub const PRE_AUTHORIZED_TTL: core::time::Duration = core::time::Duration::from_secs(60 * 5);
+
     pub fn from_key_paths<S1: Into<String>, S2: Into<String>>(
         public_key: S1,
         private_key: S2,
@@ -53,17 +98,68 @@ impl JwtConfig {
         }
     }
 
+    /// Signs with a key pair generated once per process, for tests only.
+    #[cfg(feature = "testing")]
+    pub fn for_tests() -> Self {
+        use rsa::pkcs1::EncodeRsaPrivateKey;
+        use rsa::pkcs8::{EncodePublicKey, LineEnding};
+
+        static KEYS: std::sync::OnceLock<(String, String)> = std::sync::OnceLock::new();
+
+        let (public_key, private_key) = KEYS.get_or_init(|| {
+            let private_key = rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 2048)
+                .expect("generating the test key pair");
+            let public_key = rsa::RsaPublicKey::from(&private_key)
+                .to_public_key_pem(LineEnding::LF)
+                .expect("encoding the test public key");
+            let private_key = private_key
+                .to_pkcs1_pem(LineEnding::LF)
+                .expect("encoding the test private key");
+
+            (public_key, private_key.to_string())
+        });
+
+        Self::from_keys(public_key.as_str(), private_key.as_str())
+    }
+
     pub fn generate(
         &self,
         jwt_data: JwtData,
         ttl: core::time::Duration,
     ) -> TicketsResult<(String, JwtClaim)> {
-        generate_jwt_token(jwt_data, ttl, &self.private_key)
+        self.generate_at(jwt_data, chrono::Utc::now().timestamp(), ttl)
+    }
+
+    /// Signs a token issued at the unix timestamp `issued_at` instead of the current time.
+    pub fn generate_at(
+        &self,
+        jwt_data: JwtData,
+        issued_at: i64,
+        ttl: core::time::Duration,
+    ) -> TicketsResult<(String, JwtClaim)> {
+        generate_jwt_token_at(jwt_data, issued_at, ttl, &self.private_key)
+    }
+
+    /// Signs a token whose `authorized_apps` the caller verified against the database, valid
+    /// for [`Self::PRE_AUTHORIZED_TTL`]. Only the collector's `/staff/authorize_apps` mints these.
+    pub fn generate_pre_authorized(&self, jwt_data: JwtData) -> TicketsResult<(String, JwtClaim)> {
+        let mut claims = new_claim(
+            jwt_data,
+            chrono::Utc::now().timestamp(),
+            Self::PRE_AUTHORIZED_TTL,
+        )?;
+        claims.pre_authorized = true;
+
+        encode_claim(claims, &self.private_key)
     }
 
     pub fn verify(&self, token: &str) -> TicketsResult<JwtData> {
         Ok(verify_jwt_token(&self.public_key, token)?)
     }
+
+    pub fn verify_claim(&self, token: &str) -> TicketsResult<JwtClaim> {
+        Ok(verify_jwt_claim(&self.public_key, token)?)
+    }
 }
 
 impl TryFrom<JwtKeyPathsConfig> for JwtConfig {
@@ -79,16 +175,37 @@ pub fn generate_jwt_token(
     ttl: core::time::Duration,
     private_key: &str,
 ) -> TicketsResult<(String, JwtClaim)> {
-    let exp = (chrono::Utc::now()
-        + chrono::Duration::from_std(ttl).map_err(|_| {
-            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid duration")
-        })?)
-    .timestamp();
-    let claims = JwtClaim {
+    generate_jwt_token_at(jwt_data, chrono::Utc::now().timestamp(), ttl, private_key)
+}
+
+pub fn generate_jwt_token_at(
+    jwt_data: JwtData,
+    issued_at: i64,
+    ttl: core::time::Duration,
+    private_key: &str,
+) -> TicketsResult<(String, JwtClaim)> {
+    encode_claim(new_claim(jwt_data, issued_at, ttl)?, private_key)
+}
+
+fn new_claim(
+    jwt_data: JwtData,
+    issued_at: i64,
+    ttl: core::time::Duration,
+) -> TicketsResult<JwtClaim> {
+    let exp = i64::try_from(ttl.as_secs())
+        .ok()
+        .and_then(|ttl| issued_at.checked_add(ttl))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid duration"))?;
+
+    Ok(JwtClaim {
         data: jwt_data,
         exp,
-    };
+        iat: issued_at,
+        pre_authorized: false,
+    })
+}
 
+fn encode_claim(claims: JwtClaim, private_key: &str) -> TicketsResult<(String, JwtClaim)> {
     let encoding_key = jsonwebtoken::EncodingKey::from_rsa_pem(private_key.as_bytes())
         .map_err(AuthorizationError::from)?;
     let token = jsonwebtoken::encode(
@@ -102,10 +219,14 @@ pub fn generate_jwt_token(
 }
 
 pub fn verify_jwt_token(public_key: &String, token: &str) -> Result<JwtData, AuthorizationError> {
+    Ok(verify_jwt_claim(public_key, token)?.data)
+}
+
+pub fn verify_jwt_claim(public_key: &String, token: &str) -> Result<JwtClaim, AuthorizationError> {
     let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::RS256);
 
     let decoding_key = jsonwebtoken::DecodingKey::from_rsa_pem(public_key.as_bytes())?;
     let decoded = jsonwebtoken::decode::<JwtClaim>(token, &decoding_key, &validation)?;
 
-    Ok(decoded.claims.data)
+    Ok(decoded.claims)
 }
@@ -2,12 +2,13 @@ pub mod jwt;
 
 use errors::ParsingError;
 #[cfg(feature = "axum")]
-pub use server_handle::AuthedCaller;
+pub use server_handle::{AuthedCaller, AuthedChannel, AuthedUser, ChannelType};
 use std::fmt::Display;
 
 #[derive(
     serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash, Copy, Ord, PartialOrd,
 )]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub enum UserRole {
     Staff,
     Management,
@@ -41,11 +42,26 @@ mod server_handle {
     use std::sync::Arc;
     use uuid::Uuid;
 
-    use super::jwt::{JwtAccessor, JwtConfig, JwtData};
+    use super::jwt::{JwtAccessor, JwtClaim, JwtConfig, JwtData};
     use errors::{AuthorizationError, TicketsError, TicketsResult};
 
     pub enum ChannelType {
         Discord,
+        Webhook,
+        Email,
+        Matrix,
+    }
+
+    impl ChannelType {
+        /// Name of the gateway, as sent in the `x-gateway` header.
+        pub fn gateway(&self) -> &'static str {
+            match self {
+                ChannelType::Discord => "discord",
+                ChannelType::Webhook => "webhook",
+                ChannelType::Email => "email",
+                ChannelType::Matrix => "matrix",
+            }
+        }
     }
 
     pub struct AuthedChannel {
@@ -54,10 +70,28 @@ mod server_handle {
 
     pub struct AuthedUser {
         pub user_id: u64,
+        /// The gateway the staff member belongs to, their identity is only known on it.
+        pub gateway: &'static str,
+        /// Apps the collector verified membership for when minting this token.
+        /// Only populated for tokens minted by [`JwtConfig::generate_pre_authorized`].
         pub pre_authorized_apps: HashSet<Uuid>,
+        /// The lowest role held across `pre_authorized_apps`.
         pub role: UserRole,
     }
 
+    impl AuthedUser {
+        /// Resolves the caller's role for `app_id` from the token claims alone, if
+        /// the app was pre-authorized and the claimed role satisfies `required_role`.
+        pub fn pre_authorized_role(
+            &self,
+            app_id: Uuid,
+            required_role: UserRole,
+        ) -> Option<UserRole> {
+            (self.pre_authorized_apps.contains(&app_id) && self.role >= required_role)
+                .then_some(self.role)
+        }
+    }
+
     pub enum AuthedCaller {
         User(AuthedUser),
         Channel(AuthedChannel),
@@ -85,12 +119,22 @@ mod server_handle {
                 JwtAccessor::DiscordSystem => AuthedCaller::Channel(AuthedChannel {
                     channel_type: ChannelType::Discord,
                 }),
+                JwtAccessor::WebhookSystem => AuthedCaller::Channel(AuthedChannel {
+                    channel_type: ChannelType::Webhook,
+                }),
+                JwtAccessor::EmailSystem => AuthedCaller::Channel(AuthedChannel {
+                    channel_type: ChannelType::Email,
+                }),
+                JwtAccessor::MatrixSystem => AuthedCaller::Channel(AuthedChannel {
+                    channel_type: ChannelType::Matrix,
+                }),
                 JwtAccessor::DiscordStaffMember {
                     user_id,
                     authorized_apps,
                     role,
                 } => AuthedCaller::User(AuthedUser {
                     user_id,
+                    gateway: "discord",
                     pre_authorized_apps: authorized_apps,
                     role,
                 }),
@@ -98,6 +142,20 @@ mod server_handle {
         }
     }
 
+    impl From<JwtClaim> for AuthedCaller {
+        fn from(value: JwtClaim) -> Self {
+            let pre_authorized = value.is_pre_authorized();
+
+            match value.into_data().into() {
+                AuthedCaller::User(mut user) if !pre_authorized => {
+                    user.pre_authorized_apps.clear();
+                    AuthedCaller::User(user)
+                }
+                caller => caller,
+            }
+        }
+    }
+
     fn get_bearer_token(header: &str) -> Option<String> {
         let prefix_len = "Bearer ".len();
 
@@ -119,6 +177,11 @@ mod server_handle {
             parts: &mut axum::http::request::Parts,
             state: &S,
         ) -> Result<Self, Self::Rejection> {
+            // verified once per request, later extractors reuse the claim
+            if let Some(claim) = parts.extensions.get::<JwtClaim>() {
+                return Ok(claim.clone().into());
+            }
+
             let jwt_config: Arc<JwtConfig> = axum::extract::FromRef::<S>::from_ref(state);
 
             let auth_header = parts
@@ -130,7 +193,10 @@ mod server_handle {
             let bearer =
                 get_bearer_token(auth_header).ok_or(AuthorizationError::MalformedBearerToken)?;
 
-            Ok(jwt_config.verify(&bearer)?.into())
+            let claim = jwt_config.verify_claim(&bearer)?;
+            parts.extensions.insert(claim.clone());
+
+            Ok(claim.into())
         }
     }
 }
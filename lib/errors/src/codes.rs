@@ -0,0 +1,355 @@
+//! Stable machine readable codes for [`TicketsError`], carried in [`NetworkError`] so clients
+//! can rebuild typed errors instead of matching on messages.
+
+use serde_json::{json, Value};
+
+#[cfg(feature = "sqlx")]
+use crate::DatabaseErrorKind;
+use crate::{AuthorizationError, MiscError, NetworkError, ParsingError, TicketsError};
+
+/// Prefix of the codes of errors raised by dependencies rather than by the request itself.
+const INTERNAL_CODE_PREFIX: &str = "internal.";
+const INTERNAL_REASON: &str = "An internal error occurred.";
+
+fn detail_string(details: Option<&Value>) -> Option<String> {
+    details.and_then(Value::as_str).map(str::to_string)
+}
+
+fn detail_field(details: Option<&Value>, field: &str) -> Option<String> {
+    details
+        .and_then(|details| details.get(field))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+fn detail_u64(details: Option<&Value>, field: &str) -> Option<u64> {
+    details
+        .and_then(|details| details.get(field))
+        .and_then(Value::as_u64)
+}
+
+impl AuthorizationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthorizationError::JsonWebToken(_) => "authorization.json_web_token",
+            AuthorizationError::UserCannotAccessResource => {
+                "authorization.user_cannot_access_resource"
+            }
+            AuthorizationError::ChannelCannotAccessResource => {
+                "authorization.channel_cannot_access_resource"
+            }
+            AuthorizationError::MissingBearerToken => "authorization.missing_bearer_token",
+            AuthorizationError::MalformedBearerToken => "authorization.malformed_bearer_token",
+            AuthorizationError::GatewayNotEnabled { .. } => "authorization.gateway_not_enabled",
+            AuthorizationError::CannotAccessApp => "authorization.cannot_access_app",
+            AuthorizationError::InsufficientRole => "authorization.insufficient_role",
+            AuthorizationError::IdentityLinkedToAnotherUser => {
+                "authorization.identity_linked_to_another_user"
+            }
+            AuthorizationError::IdentityLinkedToAnotherCustomer => {
+                "authorization.identity_linked_to_another_customer"
+            }
+            AuthorizationError::CustomerBlocked => "authorization.customer_blocked",
+            AuthorizationError::InvalidSignature => "authorization.invalid_signature",
+            AuthorizationError::InvalidLoginCode => "authorization.invalid_login_code",
+            AuthorizationError::LoginExpired => "authorization.login_expired",
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            AuthorizationError::GatewayNotEnabled { gateway } => {
+                Some(json!({ "gateway": gateway }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Rebuilds the error from its code, `None` if the code is unknown or the error cannot be
+    /// represented outside of the process which raised it.
+    pub fn from_code(code: &str, details: Option<&Value>) -> Option<Self> {
+        Some(match code {
+            "authorization.user_cannot_access_resource" => {
+                AuthorizationError::UserCannotAccessResource
+            }
+            "authorization.channel_cannot_access_resource" => {
+                AuthorizationError::ChannelCannotAccessResource
+            }
+            "authorization.missing_bearer_token" => AuthorizationError::MissingBearerToken,
+            "authorization.malformed_bearer_token" => AuthorizationError::MalformedBearerToken,
+            "authorization.gateway_not_enabled" => AuthorizationError::GatewayNotEnabled {
+                gateway: detail_field(details, "gateway")?,
+            },
+            "authorization.cannot_access_app" => AuthorizationError::CannotAccessApp,
+            "authorization.insufficient_role" => AuthorizationError::InsufficientRole,
+            "authorization.identity_linked_to_another_user" => {
+                AuthorizationError::IdentityLinkedToAnotherUser
+            }
+            "authorization.identity_linked_to_another_customer" => {
+                AuthorizationError::IdentityLinkedToAnotherCustomer
+            }
+            "authorization.customer_blocked" => AuthorizationError::CustomerBlocked,
+            "authorization.invalid_signature" => AuthorizationError::InvalidSignature,
+            "authorization.invalid_login_code" => AuthorizationError::InvalidLoginCode,
+            "authorization.login_expired" => AuthorizationError::LoginExpired,
+            _ => return None,
+        })
+    }
+}
+
+impl ParsingError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParsingError::InvalidGuildPurpose(_) => "parsing.invalid_guild_purpose",
+            ParsingError::InvalidChannelPurpose(_) => "parsing.invalid_channel_purpose",
+            ParsingError::InvalidRoomPurpose(_) => "parsing.invalid_room_purpose",
+            #[cfg(feature = "url")]
+            ParsingError::Url(_) => "parsing.url",
+            ParsingError::MissingRequiredHeader { .. } => "parsing.missing_required_header",
+            ParsingError::MissingPathParameter { .. } => "parsing.missing_path_parameter",
+            ParsingError::InvalidPathParameters(_) => "parsing.invalid_path_parameters",
+            ParsingError::InvalidRequest(_) => "parsing.invalid_request",
+            ParsingError::InvalidRole(_) => "parsing.invalid_role",
+            ParsingError::InvalidCommandType(_) => "parsing.invalid_command_type",
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            ParsingError::InvalidGuildPurpose(value)
+            | ParsingError::InvalidChannelPurpose(value)
+            | ParsingError::InvalidRoomPurpose(value)
+            | ParsingError::InvalidPathParameters(value)
+            | ParsingError::InvalidRequest(value)
+            | ParsingError::InvalidRole(value)
+            | ParsingError::InvalidCommandType(value) => Some(json!(value)),
+            ParsingError::MissingRequiredHeader { header } => Some(json!({ "header": header })),
+            ParsingError::MissingPathParameter { parameter } => {
+                Some(json!({ "parameter": parameter }))
+            }
+            #[cfg(feature = "url")]
+            ParsingError::Url(_) => None,
+        }
+    }
+
+    /// Rebuilds the error from its code, `None` if the code is unknown or the error cannot be
+    /// represented outside of the process which raised it.
+    pub fn from_code(code: &str, details: Option<&Value>) -> Option<Self> {
+        Some(match code {
+            "parsing.invalid_guild_purpose" => {
+                ParsingError::InvalidGuildPurpose(detail_string(details)?)
+            }
+            "parsing.invalid_channel_purpose" => {
+                ParsingError::InvalidChannelPurpose(detail_string(details)?)
+            }
+            "parsing.invalid_room_purpose" => {
+                ParsingError::InvalidRoomPurpose(detail_string(details)?)
+            }
+            "parsing.missing_required_header" => ParsingError::MissingRequiredHeader {
+                header: detail_field(details, "header")?,
+            },
+            "parsing.missing_path_parameter" => ParsingError::MissingPathParameter {
+                parameter: detail_field(details, "parameter")?,
+            },
+            "parsing.invalid_path_parameters" => {
+                ParsingError::InvalidPathParameters(detail_string(details)?)
+            }
+            "parsing.invalid_request" => ParsingError::InvalidRequest(detail_string(details)?),
+            "parsing.invalid_role" => ParsingError::InvalidRole(detail_string(details)?),
+            "parsing.invalid_command_type" => {
+                ParsingError::InvalidCommandType(detail_string(details)?)
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl MiscError {
+    #[allow(deprecated)]
+    pub fn code(&self) -> &'static str {
+        match self {
+            MiscError::GuildDataNotFound => "misc.guild_data_not_found",
+            MiscError::MissingHttpClient => "misc.missing_http_client",
+            MiscError::GuildContextRequired => "misc.guild_context_required",
+            MiscError::IdempotentRequestInProgress => "misc.idempotent_request_in_progress",
+            MiscError::IdempotentResponseUnavailable => "misc.idempotent_response_unavailable",
+            MiscError::IdempotencyKeyReused => "misc.idempotency_key_reused",
+            MiscError::PaginationCapExceeded { .. } => "misc.pagination_cap_exceeded",
+            MiscError::RateLimited { .. } => "misc.rate_limited",
+            MiscError::TicketRejected => "misc.ticket_rejected",
+            MiscError::AttachmentTooLarge { .. } => "misc.attachment_too_large",
+            MiscError::AttachmentTypeNotAllowed { .. } => "misc.attachment_type_not_allowed",
+            // their messages name storage keys, hosts and homeserver errors
+            MiscError::BlobStore(_) => "internal.blob_store",
+            MiscError::Matrix(_) => "internal.matrix",
+            MiscError::TicketClosed => "misc.ticket_closed",
+            MiscError::NotLoggedIn => "misc.not_logged_in",
+            MiscError::KeyGeneration(_) => "internal.key_generation",
+            MiscError::ConnectivityChecksFailed { .. } => "misc.connectivity_checks_failed",
+            MiscError::AckTimedOut { .. } => "misc.ack_timed_out",
+            MiscError::Unimplemented => "misc.unimplemented",
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            MiscError::PaginationCapExceeded { cap } => Some(json!({ "cap": cap })),
+            MiscError::RateLimited { retry_after } => Some(json!({ "retry_after": retry_after })),
+            MiscError::AttachmentTooLarge { max_size_bytes } => {
+                Some(json!({ "max_size_bytes": max_size_bytes }))
+            }
+            MiscError::AttachmentTypeNotAllowed { content_type } => {
+                Some(json!({ "content_type": content_type }))
+            }
+            MiscError::ConnectivityChecksFailed { failed } => Some(json!({ "failed": failed })),
+            MiscError::AckTimedOut { event } => Some(json!({ "event": event })),
+            _ => None,
+        }
+    }
+
+    /// Rebuilds the error from its code, `None` if the code is unknown.
+    #[allow(deprecated)]
+    pub fn from_code(code: &str, details: Option<&Value>) -> Option<Self> {
+        Some(match code {
+            "misc.guild_data_not_found" => MiscError::GuildDataNotFound,
+            "misc.missing_http_client" => MiscError::MissingHttpClient,
+            "misc.guild_context_required" => MiscError::GuildContextRequired,
+            "misc.idempotent_request_in_progress" => MiscError::IdempotentRequestInProgress,
+            "misc.idempotent_response_unavailable" => MiscError::IdempotentResponseUnavailable,
+            "misc.idempotency_key_reused" => MiscError::IdempotencyKeyReused,
+            "misc.rate_limited" => MiscError::RateLimited {
+                retry_after: detail_u64(details, "retry_after")?,
+            },
+            "misc.ticket_rejected" => MiscError::TicketRejected,
+            "misc.ticket_closed" => MiscError::TicketClosed,
+            "misc.attachment_too_large" => MiscError::AttachmentTooLarge {
+                max_size_bytes: detail_u64(details, "max_size_bytes")?,
+            },
+            "misc.attachment_type_not_allowed" => MiscError::AttachmentTypeNotAllowed {
+                content_type: detail_field(details, "content_type")?,
+            },
+            "misc.pagination_cap_exceeded" => MiscError::PaginationCapExceeded {
+                cap: detail_u64(details, "cap")? as usize,
+            },
+            "misc.not_logged_in" => MiscError::NotLoggedIn,
+            "misc.connectivity_checks_failed" => MiscError::ConnectivityChecksFailed {
+                failed: detail_u64(details, "failed")? as usize,
+            },
+            "misc.ack_timed_out" => MiscError::AckTimedOut {
+                event: detail_field(details, "event")?,
+            },
+            "misc.unimplemented" => MiscError::Unimplemented,
+            _ => return None,
+        })
+    }
+}
+
+impl TicketsError {
+    /// Stable code identifying the error, namespaced by the kind of error.
+    pub fn code(&self) -> &str {
+        match self {
+            TicketsError::Authorization(err) => err.code(),
+            TicketsError::Parsing(err) => err.code(),
+            TicketsError::Misc(err) => err.code(),
+            TicketsError::Network(err) => &err.code,
+            #[cfg(feature = "sqlx")]
+            TicketsError::Sqlx(err) => DatabaseErrorKind::classify(err).code(),
+            #[cfg(feature = "sqlx")]
+            TicketsError::Migrate(_) => "internal.migrate",
+            TicketsError::SerdeJson(_) => "internal.serde_json",
+            #[cfg(feature = "redis")]
+            TicketsError::Redis(_) => "internal.redis",
+            TicketsError::IO(_) => "internal.io",
+            #[cfg(feature = "reqwest")]
+            TicketsError::Reqwest(_) => "internal.reqwest",
+            #[cfg(feature = "axum")]
+            TicketsError::Axum(_) => "internal.axum",
+            #[cfg(feature = "socketioxide")]
+            TicketsError::WebsocketBroadcastError(_) => "internal.websocket_broadcast",
+            #[cfg(feature = "rust_socketio")]
+            TicketsError::WebsocketClientError(_) => "internal.websocket_client",
+            #[cfg(feature = "serenity")]
+            TicketsError::Serenity(_) => "internal.serenity",
+            #[cfg(feature = "lettre")]
+            TicketsError::Smtp(_) => "internal.smtp",
+            #[cfg(feature = "lettre")]
+            TicketsError::Email(_) => "internal.email",
+            #[cfg(feature = "lettre")]
+            TicketsError::EmailAddress(_) => "internal.email_address",
+            #[cfg(feature = "mailparse")]
+            TicketsError::MailParse(_) => "internal.mail_parse",
+            #[cfg(feature = "tokio")]
+            TicketsError::Join(_) => "internal.join",
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            TicketsError::Authorization(err) => err.details(),
+            TicketsError::Parsing(err) => err.details(),
+            TicketsError::Misc(err) => err.details(),
+            TicketsError::Network(err) => err.details.clone(),
+            #[cfg(feature = "sqlx")]
+            TicketsError::Sqlx(err) => DatabaseErrorKind::details(err),
+            _ => None,
+        }
+    }
+
+    /// Message which is safe to send to clients, database errors are replaced by their
+    /// classification and other internal errors by a generic reason, as their messages may
+    /// contain hosts, paths or URLs.
+    pub fn public_reason(&self) -> String {
+        match self {
+            #[cfg(feature = "sqlx")]
+            TicketsError::Sqlx(err) => DatabaseErrorKind::classify(err).reason().to_string(),
+            #[cfg(feature = "sqlx")]
+            TicketsError::Migrate(_) => DatabaseErrorKind::Other.reason().to_string(),
+            // relayed as received, instead of being prefixed once more
+            TicketsError::Network(err) => err.reason.clone(),
+            _ if self.code().starts_with(INTERNAL_CODE_PREFIX) => INTERNAL_REASON.to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Whether the same request may succeed when retried, such as after a transaction restart.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "sqlx")]
+            TicketsError::Sqlx(err) => DatabaseErrorKind::classify(err).is_retryable(),
+            TicketsError::Network(err) => err.code == "database.serialization_failure",
+            _ => false,
+        }
+    }
+}
+
+impl From<&TicketsError> for NetworkError {
+    fn from(err: &TicketsError) -> Self {
+        NetworkError {
+            reason: err.public_reason(),
+            code: err.code().to_string(),
+            details: err.details(),
+        }
+    }
+}
+
+impl From<NetworkError> for TicketsError {
+    /// Rebuilds typed authorization, parsing and misc errors, anything else stays a network
+    /// error.
+    fn from(err: NetworkError) -> Self {
+        let details = err.details.as_ref();
+
+        if let Some(authorization) = AuthorizationError::from_code(&err.code, details) {
+            return TicketsError::Authorization(authorization);
+        }
+
+        if let Some(parsing) = ParsingError::from_code(&err.code, details) {
+            return TicketsError::Parsing(parsing);
+        }
+
+        if let Some(misc) = MiscError::from_code(&err.code, details) {
+            return TicketsError::Misc(misc);
+        }
+
+        TicketsError::Network(err)
+    }
+}
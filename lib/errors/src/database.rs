@@ -0,0 +1,103 @@
+//! Classification of [`sqlx::Error`]s, so constraint violations reach clients as readable
+//! conflicts instead of internal errors and raw SQL errors never leave the collector.
+
+use serde_json::{json, Value};
+
+/// SQLSTATE raised when a transaction must be retried, CockroachDB reports every transaction
+/// restart with it.
+const SERIALIZATION_FAILURE: &str = "40001";
+const DEADLOCK_DETECTED: &str = "40P01";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseErrorKind {
+    UniqueViolation,
+    ForeignKeyViolation,
+    ConstraintViolation,
+    SerializationFailure,
+    NotFound,
+    Other,
+}
+
+impl DatabaseErrorKind {
+    pub fn classify(err: &sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => DatabaseErrorKind::NotFound,
+            sqlx::Error::Database(err) => {
+                if let Some(SERIALIZATION_FAILURE | DEADLOCK_DETECTED) = err.code().as_deref() {
+                    return DatabaseErrorKind::SerializationFailure;
+                }
+
+                match err.kind() {
+                    sqlx::error::ErrorKind::UniqueViolation => DatabaseErrorKind::UniqueViolation,
+                    sqlx::error::ErrorKind::ForeignKeyViolation => {
+                        DatabaseErrorKind::ForeignKeyViolation
+                    }
+                    sqlx::error::ErrorKind::NotNullViolation
+                    | sqlx::error::ErrorKind::CheckViolation => {
+                        DatabaseErrorKind::ConstraintViolation
+                    }
+                    _ => DatabaseErrorKind::Other,
+                }
+            }
+            _ => DatabaseErrorKind::Other,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            DatabaseErrorKind::UniqueViolation => "database.unique_violation",
+            DatabaseErrorKind::ForeignKeyViolation => "database.foreign_key_violation",
+            DatabaseErrorKind::ConstraintViolation => "database.constraint_violation",
+            DatabaseErrorKind::SerializationFailure => "database.serialization_failure",
+            DatabaseErrorKind::NotFound => "database.not_found",
+            DatabaseErrorKind::Other => "internal.sqlx",
+        }
+    }
+
+    /// Message safe to show to clients, never containing the database's own error text.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            DatabaseErrorKind::UniqueViolation => {
+                "A resource with the same unique value already exists."
+            }
+            DatabaseErrorKind::ForeignKeyViolation => {
+                "This request references a resource which does not exist."
+            }
+            DatabaseErrorKind::ConstraintViolation => "This request violates a data constraint.",
+            DatabaseErrorKind::SerializationFailure => {
+                "The request conflicted with a concurrent transaction, please retry."
+            }
+            DatabaseErrorKind::NotFound => "The requested resource does not exist.",
+            DatabaseErrorKind::Other => "An internal database error occurred.",
+        }
+    }
+
+    /// Whether the same request may succeed when retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, DatabaseErrorKind::SerializationFailure)
+    }
+
+    #[cfg(feature = "axum")]
+    pub fn status_code(&self) -> axum::http::StatusCode {
+        match self {
+            DatabaseErrorKind::UniqueViolation => axum::http::StatusCode::CONFLICT,
+            DatabaseErrorKind::ForeignKeyViolation | DatabaseErrorKind::ConstraintViolation => {
+                axum::http::StatusCode::UNPROCESSABLE_ENTITY
+            }
+            // retried by the sdk, see `RetryPolicy::is_retryable_status`
+            DatabaseErrorKind::SerializationFailure => axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            DatabaseErrorKind::NotFound => axum::http::StatusCode::NOT_FOUND,
+            DatabaseErrorKind::Other => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The violated constraint, if the database reported one.
+    pub fn details(err: &sqlx::Error) -> Option<Value> {
+        match err {
+            sqlx::Error::Database(err) => err
+                .constraint()
+                .map(|constraint| json!({ "constraint": constraint })),
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,12 @@
 pub type TicketsResult<T> = Result<T, TicketsError>;
 
+mod codes;
+#[cfg(feature = "sqlx")]
+mod database;
+
+#[cfg(feature = "sqlx")]
+pub use database::DatabaseErrorKind;
+
 #[derive(thiserror::Error, Debug)]
 pub enum MiscError {
     #[error("Could not find guild data.")]
@@ -8,6 +15,36 @@ pub enum MiscError {
     MissingHttpClient,
     #[error("A guild context is required for this action.")]
     GuildContextRequired,
+    #[error("A request with this idempotency key is still being processed.")]
+    IdempotentRequestInProgress,
+    #[error("The request with this idempotency key was processed, but its response could not be stored for replay.")]
+    IdempotentResponseUnavailable,
+    #[error("This idempotency key was already used for a request with another body.")]
+    IdempotencyKeyReused,
+    #[error("Refusing to collect more than {cap} items.")]
+    PaginationCapExceeded { cap: usize },
+    #[error("Too many requests, retry in {retry_after} seconds.")]
+    RateLimited { retry_after: u64 },
+    #[error("The ticket was rejected by the app's filters.")]
+    TicketRejected,
+    #[error("The attachment exceeds the app's limit of {max_size_bytes} bytes.")]
+    AttachmentTooLarge { max_size_bytes: u64 },
+    #[error("Attachments of type `{content_type}` are not allowed by the app.")]
+    AttachmentTypeNotAllowed { content_type: String },
+    #[error("Blob Store Error: {0}")]
+    BlobStore(String),
+    #[error("Matrix Error: {0}")]
+    Matrix(String),
+    #[error("The ticket is closed.")]
+    TicketClosed,
+    #[error("Not logged in.")]
+    NotLoggedIn,
+    #[error("Key Generation Error: {0}")]
+    KeyGeneration(String),
+    #[error("{failed} connectivity check(s) failed.")]
+    ConnectivityChecksFailed { failed: usize },
+    #[error("The websocket server did not acknowledge `{event}` in time.")]
+    AckTimedOut { event: String },
     #[deprecated]
     #[error("This feature is currently not implemented")]
     Unimplemented,
@@ -18,6 +55,16 @@ impl MiscError {
     pub fn status_code(&self) -> axum::http::StatusCode {
         match self {
             MiscError::GuildDataNotFound => axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            MiscError::IdempotentRequestInProgress => axum::http::StatusCode::CONFLICT,
+            MiscError::IdempotentResponseUnavailable => axum::http::StatusCode::CONFLICT,
+            MiscError::IdempotencyKeyReused => axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            MiscError::RateLimited { .. } => axum::http::StatusCode::TOO_MANY_REQUESTS,
+            MiscError::TicketRejected => axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            MiscError::AttachmentTooLarge { .. } => axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+            MiscError::AttachmentTypeNotAllowed { .. } => {
+                axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            MiscError::TicketClosed => axum::http::StatusCode::CONFLICT,
             _ => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -29,11 +76,19 @@ pub enum ParsingError {
     InvalidGuildPurpose(String),
     #[error("Failed to parse Channel Purpose, `{0}` is not valid.")]
     InvalidChannelPurpose(String),
+    #[error("Failed to parse Room Purpose, `{0}` is not valid.")]
+    InvalidRoomPurpose(String),
     #[cfg(feature = "url")]
     #[error("Failed to parse URL: {0}")]
     Url(#[from] url::ParseError),
     #[error("Missing required header: {header}")]
     MissingRequiredHeader { header: String },
+    #[error("Missing path parameter: {parameter}")]
+    MissingPathParameter { parameter: String },
+    #[error("Invalid path parameters: {0}")]
+    InvalidPathParameters(String),
+    #[error("Failed to build request: {0}")]
+    InvalidRequest(String),
     #[error("Failed to parse Role, `{0}` is not valid.")]
     InvalidRole(String),
     #[error("Failed to parse Command Type, `{0}` is not valid.")]
@@ -58,6 +113,18 @@ pub enum AuthorizationError {
     CannotAccessApp,
     #[error("Your role does not have permission to access this resource.")]
     InsufficientRole,
+    #[error("This identity is already linked to another user.")]
+    IdentityLinkedToAnotherUser,
+    #[error("This identity is already linked to another customer.")]
+    IdentityLinkedToAnotherCustomer,
+    #[error("You are blocked from submitting tickets to this app.")]
+    CustomerBlocked,
+    #[error("The request signature is invalid.")]
+    InvalidSignature,
+    #[error("The login code is invalid or has expired.")]
+    InvalidLoginCode,
+    #[error("The login has expired.")]
+    LoginExpired,
 }
 
 impl AuthorizationError {
@@ -67,15 +134,24 @@ impl AuthorizationError {
             AuthorizationError::MissingBearerToken | AuthorizationError::MalformedBearerToken => {
                 axum::http::StatusCode::BAD_REQUEST
             }
-            AuthorizationError::JsonWebToken(_) => axum::http::StatusCode::UNAUTHORIZED,
+            AuthorizationError::JsonWebToken(_)
+            | AuthorizationError::InvalidSignature
+            | AuthorizationError::InvalidLoginCode
+            | AuthorizationError::LoginExpired => axum::http::StatusCode::UNAUTHORIZED,
             _ => axum::http::StatusCode::FORBIDDEN,
         }
     }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct NetworkError {
     pub reason: String,
+    /// Stable code of the error, see [`TicketsError::code`].
+    #[serde(default)]
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -100,6 +176,9 @@ pub enum TicketsError {
     #[cfg(feature = "reqwest")]
     #[error("Reqwest Error: {0}")]
     Reqwest(#[from] reqwest::Error),
+    #[cfg(feature = "axum")]
+    #[error("Axum Error: {0}")]
+    Axum(#[from] axum::Error),
     #[cfg(feature = "socketioxide")]
     #[error("WebSocket Broadcast Error: {0}")]
     WebsocketBroadcastError(#[from] socketioxide::BroadcastError),
@@ -109,6 +188,18 @@ pub enum TicketsError {
     #[cfg(feature = "serenity")]
     #[error("Serenity Error: {0}")]
     Serenity(#[from] serenity::Error),
+    #[cfg(feature = "lettre")]
+    #[error("SMTP Error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[cfg(feature = "lettre")]
+    #[error("Error building email: {0}")]
+    Email(#[from] lettre::error::Error),
+    #[cfg(feature = "lettre")]
+    #[error("Invalid email address: {0}")]
+    EmailAddress(#[from] lettre::address::AddressError),
+    #[cfg(feature = "mailparse")]
+    #[error("Error parsing email: {0}")]
+    MailParse(#[from] mailparse::MailParseError),
     #[error(transparent)]
     Misc(#[from] MiscError),
     #[error("Network Error: {}", .0.reason)]
@@ -125,15 +216,25 @@ impl axum::response::IntoResponse for TicketsError {
             TicketsError::Parsing(_) => axum::http::StatusCode::BAD_REQUEST,
             TicketsError::Authorization(err) => err.status_code(),
             TicketsError::Misc(err) => err.status_code(),
+            #[cfg(feature = "sqlx")]
+            TicketsError::Sqlx(err) => DatabaseErrorKind::classify(err).status_code(),
             _ => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
         };
 
-        (
-            status,
-            axum::Json(NetworkError {
-                reason: self.to_string(),
-            }),
-        )
-            .into_response()
+        // the response only carries a sanitised reason, keep the original for operators
+        if status.is_server_error() {
+            log::error!("{self}");
+        }
+
+        let mut response = (status, axum::Json(NetworkError::from(&self))).into_response();
+
+        if let TicketsError::Misc(MiscError::RateLimited { retry_after }) = &self {
+            response.headers_mut().insert(
+                axum::http::header::RETRY_AFTER,
+                axum::http::HeaderValue::from(*retry_after),
+            );
+        }
+
+        response
     }
 }
@@ -0,0 +1,259 @@
+//! Errors sent to clients as a `NetworkError` and rebuilt from its code on the other side.
+
+#![allow(deprecated)]
+
+use errors::{AuthorizationError, MiscError, NetworkError, ParsingError, TicketsError};
+
+// the guards fail to compile once a variant is added without being listed below
+
+fn authorization_errors() -> Vec<AuthorizationError> {
+    let errors = vec![
+        AuthorizationError::UserCannotAccessResource,
+        AuthorizationError::ChannelCannotAccessResource,
+        AuthorizationError::MissingBearerToken,
+        AuthorizationError::MalformedBearerToken,
+        AuthorizationError::GatewayNotEnabled {
+            gateway: "discord".to_string(),
+        },
+        AuthorizationError::CannotAccessApp,
+        AuthorizationError::InsufficientRole,
+        AuthorizationError::IdentityLinkedToAnotherUser,
+        AuthorizationError::IdentityLinkedToAnotherCustomer,
+        AuthorizationError::CustomerBlocked,
+        AuthorizationError::InvalidSignature,
+        AuthorizationError::InvalidLoginCode,
+        AuthorizationError::LoginExpired,
+    ];
+
+    for err in &errors {
+        match err {
+            AuthorizationError::UserCannotAccessResource
+            | AuthorizationError::ChannelCannotAccessResource
+            | AuthorizationError::MissingBearerToken
+            | AuthorizationError::MalformedBearerToken
+            | AuthorizationError::GatewayNotEnabled { .. }
+            | AuthorizationError::CannotAccessApp
+            | AuthorizationError::InsufficientRole
+            | AuthorizationError::IdentityLinkedToAnotherUser
+            | AuthorizationError::IdentityLinkedToAnotherCustomer
+            | AuthorizationError::CustomerBlocked
+            | AuthorizationError::InvalidSignature
+            | AuthorizationError::InvalidLoginCode
+            | AuthorizationError::LoginExpired => {}
+            // wraps an error of another crate, see `foreign_errors_keep_their_code`
+            AuthorizationError::JsonWebToken(_) => unreachable!(),
+        }
+    }
+
+    errors
+}
+
+fn parsing_errors() -> Vec<ParsingError> {
+    let errors = vec![
+        ParsingError::InvalidGuildPurpose("lobby".to_string()),
+        ParsingError::InvalidChannelPurpose("lobby".to_string()),
+        ParsingError::InvalidRoomPurpose("lobby".to_string()),
+        ParsingError::MissingRequiredHeader {
+            header: "x-gateway".to_string(),
+        },
+        ParsingError::MissingPathParameter {
+            parameter: "app_id".to_string(),
+        },
+        ParsingError::InvalidPathParameters("app_id is not a uuid".to_string()),
+        ParsingError::InvalidRequest("missing body".to_string()),
+        ParsingError::InvalidRole("owner".to_string()),
+        ParsingError::InvalidCommandType("ping".to_string()),
+    ];
+
+    for err in &errors {
+        match err {
+            ParsingError::InvalidGuildPurpose(_)
+            | ParsingError::InvalidChannelPurpose(_)
+            | ParsingError::InvalidRoomPurpose(_)
+            | ParsingError::MissingRequiredHeader { .. }
+            | ParsingError::MissingPathParameter { .. }
+            | ParsingError::InvalidPathParameters(_)
+            | ParsingError::InvalidRequest(_)
+            | ParsingError::InvalidRole(_)
+            | ParsingError::InvalidCommandType(_) => {}
+            // wraps an error of another crate, see `foreign_errors_keep_their_code`
+            #[cfg(feature = "url")]
+            ParsingError::Url(_) => unreachable!(),
+        }
+    }
+
+    errors
+}
+
+fn misc_errors() -> Vec<MiscError> {
+    let errors = vec![
+        MiscError::GuildDataNotFound,
+        MiscError::MissingHttpClient,
+        MiscError::GuildContextRequired,
+        MiscError::IdempotentRequestInProgress,
+        MiscError::IdempotentResponseUnavailable,
+        MiscError::IdempotencyKeyReused,
+        MiscError::PaginationCapExceeded { cap: 100 },
+        MiscError::RateLimited { retry_after: 30 },
+        MiscError::TicketRejected,
+        MiscError::AttachmentTooLarge {
+            max_size_bytes: 1024,
+        },
+        MiscError::AttachmentTypeNotAllowed {
+            content_type: "application/zip".to_string(),
+        },
+        MiscError::TicketClosed,
+        MiscError::NotLoggedIn,
+        MiscError::ConnectivityChecksFailed { failed: 2 },
+        MiscError::AckTimedOut {
+            event: "reply".to_string(),
+        },
+        MiscError::Unimplemented,
+    ];
+
+    for err in &errors {
+        match err {
+            MiscError::GuildDataNotFound
+            | MiscError::MissingHttpClient
+            | MiscError::GuildContextRequired
+            | MiscError::IdempotentRequestInProgress
+            | MiscError::IdempotentResponseUnavailable
+            | MiscError::IdempotencyKeyReused
+            | MiscError::PaginationCapExceeded { .. }
+            | MiscError::RateLimited { .. }
+            | MiscError::TicketRejected
+            | MiscError::AttachmentTooLarge { .. }
+            | MiscError::AttachmentTypeNotAllowed { .. }
+            | MiscError::TicketClosed
+            | MiscError::NotLoggedIn
+            | MiscError::ConnectivityChecksFailed { .. }
+            | MiscError::AckTimedOut { .. }
+            | MiscError::Unimplemented => {}
+            // internal, see `internal_errors_keep_their_message_private`
+            MiscError::BlobStore(_) | MiscError::Matrix(_) | MiscError::KeyGeneration(_) => {
+                unreachable!()
+            }
+        }
+    }
+
+    errors
+}
+
+/// Sends the error as JSON, as the collector does, and rebuilds it.
+fn round_trip(err: &TicketsError) -> TicketsError {
+    let json = serde_json::to_string(&NetworkError::from(err)).unwrap();
+    TicketsError::from(serde_json::from_str::<NetworkError>(&json).unwrap())
+}
+
+fn assert_survives(err: TicketsError) {
+    let rebuilt = round_trip(&err);
+
+    assert!(
+        !matches!(rebuilt, TicketsError::Network(_)),
+        "`{}` was not rebuilt",
+        err.code()
+    );
+    assert_eq!(rebuilt.code(), err.code());
+    assert_eq!(rebuilt.details(), err.details());
+    assert_eq!(rebuilt.to_string(), err.to_string());
+}
+
+#[test]
+fn authorization_errors_survive_the_network() {
+    for err in authorization_errors() {
+        let rebuilt = round_trip(&err.into());
+        assert!(matches!(rebuilt, TicketsError::Authorization(_)));
+    }
+
+    authorization_errors()
+        .into_iter()
+        .for_each(|err| assert_survives(err.into()));
+}
+
+#[test]
+fn parsing_errors_survive_the_network() {
+    for err in parsing_errors() {
+        let rebuilt = round_trip(&err.into());
+        assert!(matches!(rebuilt, TicketsError::Parsing(_)));
+    }
+
+    parsing_errors()
+        .into_iter()
+        .for_each(|err| assert_survives(err.into()));
+}
+
+#[test]
+fn misc_errors_survive_the_network() {
+    for err in misc_errors() {
+        let rebuilt = round_trip(&err.into());
+        assert!(matches!(rebuilt, TicketsError::Misc(_)));
+    }
+
+    misc_errors()
+        .into_iter()
+        .for_each(|err| assert_survives(err.into()));
+}
+
+#[test]
+fn codes_are_unique() {
+    let mut codes = authorization_errors()
+        .into_iter()
+        .map(|err| err.code())
+        .chain(parsing_errors().into_iter().map(|err| err.code()))
+        .chain(misc_errors().into_iter().map(|err| err.code()))
+        .collect::<Vec<&str>>();
+    let count = codes.len();
+
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), count);
+}
+
+#[test]
+fn foreign_errors_keep_their_code() {
+    let jwt = jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidToken);
+    let err = TicketsError::from(AuthorizationError::JsonWebToken(jwt));
+
+    let rebuilt = round_trip(&err);
+    assert!(matches!(&rebuilt, TicketsError::Network(_)));
+    assert_eq!(rebuilt.code(), "authorization.json_web_token");
+
+    // relayed once more, such as by the websocket server, the reason is passed on unchanged
+    assert_eq!(
+        NetworkError::from(&rebuilt).reason,
+        NetworkError::from(&err).reason
+    );
+}
+
+#[test]
+fn internal_errors_keep_their_message_private() {
+    let io = std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "/etc/tickets/config.json not found",
+    );
+    let json = serde_json::from_str::<u64>("\"https://blobs.internal/key\"").unwrap_err();
+
+    let blob_store =
+        MiscError::BlobStore("PUT of `tickets/key` failed with status 500.".to_string());
+    let matrix = MiscError::Matrix("M_FORBIDDEN: Not invited to !tickets:blobs".to_string());
+    let key_generation = MiscError::KeyGeneration("/etc/tickets too few bits".to_string());
+
+    for err in [
+        TicketsError::from(io),
+        TicketsError::from(json),
+        TicketsError::from(blob_store),
+        TicketsError::from(matrix),
+        TicketsError::from(key_generation),
+    ] {
+        let network = NetworkError::from(&err);
+
+        assert!(network.code.starts_with("internal."));
+        assert_eq!(network.reason, "An internal error occurred.");
+        assert!(!network.reason.contains("tickets"));
+        assert!(!network.reason.contains("blobs"));
+        assert_eq!(network.details, None);
+    }
+
+    let misc = TicketsError::from(MiscError::TicketClosed);
+    assert_eq!(misc.public_reason(), misc.to_string());
+}
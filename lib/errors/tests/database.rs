@@ -0,0 +1,129 @@
+//! Database errors classified by their SQLSTATE and kind, as reported by the driver.
+
+use std::borrow::Cow;
+
+use errors::{DatabaseErrorKind, NetworkError, TicketsError};
+use sqlx::error::{DatabaseError, ErrorKind};
+
+#[derive(Debug)]
+struct FakeDatabaseError {
+    code: &'static str,
+    constraint: Option<&'static str>,
+}
+
+impl std::fmt::Display for FakeDatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for FakeDatabaseError {}
+
+impl DatabaseError for FakeDatabaseError {
+    fn message(&self) -> &str {
+        "relation \"ticket\" violates something at db.internal:5432"
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.code))
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn constraint(&self) -> Option<&str> {
+        self.constraint
+    }
+
+    /// Mirrors how the Postgres driver derives the kind from the SQLSTATE.
+    fn kind(&self) -> ErrorKind {
+        match self.code {
+            "23505" => ErrorKind::UniqueViolation,
+            "23503" => ErrorKind::ForeignKeyViolation,
+            "23502" => ErrorKind::NotNullViolation,
+            "23514" => ErrorKind::CheckViolation,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+fn database_error(code: &'static str) -> sqlx::Error {
+    sqlx::Error::Database(Box::new(FakeDatabaseError {
+        code,
+        constraint: None,
+    }))
+}
+
+#[test]
+fn errors_are_classified_by_kind() {
+    let cases = [
+        ("23505", DatabaseErrorKind::UniqueViolation),
+        ("23503", DatabaseErrorKind::ForeignKeyViolation),
+        ("23502", DatabaseErrorKind::ConstraintViolation),
+        ("23514", DatabaseErrorKind::ConstraintViolation),
+        ("42P01", DatabaseErrorKind::Other),
+    ];
+
+    for (code, expected) in cases {
+        assert_eq!(
+            DatabaseErrorKind::classify(&database_error(code)),
+            expected,
+            "{code}"
+        );
+    }
+}
+
+#[test]
+fn transaction_restarts_are_retryable() {
+    for code in ["40001", "40P01"] {
+        let err = database_error(code);
+        let kind = DatabaseErrorKind::classify(&err);
+
+        assert_eq!(kind, DatabaseErrorKind::SerializationFailure);
+        assert!(kind.is_retryable());
+        assert!(TicketsError::from(err).is_retryable());
+    }
+
+    assert!(!DatabaseErrorKind::UniqueViolation.is_retryable());
+}
+
+#[test]
+fn driver_errors_are_classified() {
+    assert_eq!(
+        DatabaseErrorKind::classify(&sqlx::Error::RowNotFound),
+        DatabaseErrorKind::NotFound
+    );
+    assert_eq!(
+        DatabaseErrorKind::classify(&sqlx::Error::PoolTimedOut),
+        DatabaseErrorKind::Other
+    );
+}
+
+#[test]
+fn responses_never_carry_the_database_message() {
+    let err = TicketsError::from(sqlx::Error::Database(Box::new(FakeDatabaseError {
+        code: "23505",
+        constraint: Some("ticket_pkey"),
+    })));
+    let network = NetworkError::from(&err);
+
+    assert_eq!(network.code, "database.unique_violation");
+    assert_eq!(network.reason, DatabaseErrorKind::UniqueViolation.reason());
+    assert_eq!(
+        network.details,
+        Some(serde_json::json!({ "constraint": "ticket_pkey" }))
+    );
+
+    let other = NetworkError::from(&TicketsError::from(sqlx::Error::PoolTimedOut));
+    assert_eq!(other.code, "internal.sqlx");
+    assert_eq!(other.reason, DatabaseErrorKind::Other.reason());
+}
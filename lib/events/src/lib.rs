@@ -24,15 +24,32 @@ macro_rules! event_hierarchy {
             ),*
         }
     ) => {
-        #[derive(serde::Serialize, serde::Deserialize, Debug)]
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
         pub enum $glob {
             $(
                 $parent($parent_event),
             )*
         }
 
+        impl $glob {
+            /// Every event type, named after the event's variant.
+            pub const EVENT_TYPES: &'static [&'static str] = &[$($(stringify!($child)),*),*];
+
+            pub fn event_type(&self) -> &'static str {
+                match self {
+                    $(
+                        $glob::$parent(event) => match event {
+                            $(
+                                $parent_event::$child(_) => stringify!($child),
+                            )*
+                        },
+                    )*
+                }
+            }
+        }
+
         $(
-            #[derive(serde::Serialize, serde::Deserialize, Debug)]
+            #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
             pub enum $parent_event {
                 $(
                 $child($child_event),
@@ -46,9 +63,9 @@ macro_rules! event_hierarchy {
             }
 
             $(
-                #[derive(serde::Serialize, serde::Deserialize, Debug)]
+                #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
                 pub struct $child_event {
-                    pub $($field: $type),*
+                    $(pub $field: $type),*
                 }
 
                 impl From<$child_event> for $parent_event {
@@ -78,6 +95,29 @@ event_hierarchy! {
         TicketUpdated(TicketUpdatedEvent) {
             TicketSubmitted(TicketSubmittedEvent) {
                 message: String,
+            },
+            AttachmentAdded(AttachmentAddedEvent) {
+                ticket_id: Uuid,
+                attachment_id: Uuid,
+                file_name: String,
+                content_type: String,
+                // staff attachments are relayed back to the customer by the ticket's gateway
+                from_staff: bool,
+            },
+            MessageAdded(MessageAddedEvent) {
+                ticket_id: Uuid,
+                message_id: Uuid,
+                body: String,
+                // staff replies are relayed back to the customer by the ticket's gateway
+                from_staff: bool,
+            },
+            TicketClosed(TicketClosedEvent) {
+                ticket_id: Uuid,
+                closed_by: u64,
+            },
+            TicketReopened(TicketReopenedEvent) {
+                ticket_id: Uuid,
+                reopened_by: u64,
             }
         }
     }
@@ -87,6 +127,10 @@ pub mod event_channels {
     pub const APP_CHANGED_EVENT: &str = "app_changed";
 
     pub const TICKET_UPDATED_EVENT: &str = "ticket_updated";
+
+    /// Sent to the sockets listening to an app while a staff member types a reply, carrying
+    /// `(app_id, StaffTyping)`.
+    pub const STAFF_TYPING_EVENT: &str = "staff_typing";
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -95,12 +139,26 @@ pub struct PublishedMessage {
     pub event: TicketEvent,
 }
 
+/// Body of a webhook delivery, `delivery_id` stays the same across retries of the delivery.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct WebhookPayload {
+    pub delivery_id: Uuid,
+    pub app_id: Uuid,
+    /// One of [`TicketEvent::EVENT_TYPES`].
+    pub event_type: String,
+    /// When the event was published, unix timestamp in seconds.
+    pub created_at: i64,
+    pub event: TicketEvent,
+}
+
 pub mod websocket {
     #[derive(serde::Serialize, serde::Deserialize, Debug)]
     pub struct SocketAuthData {
         pub token: String,
     }
 
+    use errors::NetworkError;
+    use sdk::routes::staff::TicketStatus;
     use uuid::Uuid;
 
     pub const LISTEN_TO_EVENT_NAME: &str = "listen_to";
@@ -117,4 +175,50 @@ pub mod websocket {
         Success,
         Failure,
     }
+
+    /// Replies to a ticket as the connected staff member, acked with a [`TicketActionResult`] of
+    /// the created message.
+    pub const REPLY_EVENT_NAME: &str = "reply";
+
+    /// Opens or closes a ticket as the connected staff member, acked with a [`TicketActionResult`]
+    /// of the status change.
+    pub const SET_STATUS_EVENT_NAME: &str = "set_status";
+
+    /// Tells the other sockets listening to the app that the staff member is typing, acked with
+    /// an empty [`TicketActionResult`]. Only sockets already listening to the app may send it.
+    pub const TYPING_EVENT_NAME: &str = "typing";
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    pub struct Reply {
+        pub app_id: Uuid,
+        pub ticket_id: Uuid,
+        pub body: String,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    pub struct SetStatus {
+        pub app_id: Uuid,
+        pub ticket_id: Uuid,
+        pub status: TicketStatus,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    pub struct Typing {
+        pub app_id: Uuid,
+        pub ticket_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    pub struct StaffTyping {
+        pub ticket_id: Uuid,
+        pub user_id: u64,
+    }
+
+    /// The outcome of a client event, failures carry the error the REST route would respond
+    /// with.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    pub enum TicketActionResult<T> {
+        Success(T),
+        Failure(NetworkError),
+    }
 }
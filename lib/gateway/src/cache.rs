@@ -0,0 +1,148 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use bimap::{BiMap, Overwritten};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+struct GenericIdCacheInner<Id, Purpose> {
+    id_to_purpose_map: HashMap<Id, BiMap<Purpose, Uuid>>,
+    app_purpose_to_id: HashMap<(Uuid, Purpose), Id>,
+}
+
+impl<Id, Purpose> Default for GenericIdCacheInner<Id, Purpose> {
+    fn default() -> Self {
+        Self {
+            id_to_purpose_map: HashMap::new(),
+            app_purpose_to_id: HashMap::new(),
+        }
+    }
+}
+
+impl<Id, Purpose> GenericIdCacheInner<Id, Purpose>
+where
+    Id: Eq + Hash + Clone,
+    Purpose: Eq + Hash + Copy,
+{
+    fn insert(&mut self, id: Id, purpose: Purpose, app_id: Uuid) {
+        self.remove(app_id, purpose);
+
+        let overwritten = self
+            .id_to_purpose_map
+            .entry(id.clone())
+            .or_default()
+            .insert(purpose, app_id);
+
+        // the id served the purpose for another app, or the app for another purpose
+        let evicted = match overwritten {
+            Overwritten::Neither => vec![],
+            Overwritten::Left(purpose, app_id)
+            | Overwritten::Right(purpose, app_id)
+            | Overwritten::Pair(purpose, app_id) => vec![(purpose, app_id)],
+            Overwritten::Both(left, right) => vec![left, right],
+        };
+        for (purpose, app_id) in evicted {
+            self.app_purpose_to_id.remove(&(app_id, purpose));
+        }
+
+        self.app_purpose_to_id.insert((app_id, purpose), id);
+    }
+
+    fn remove(&mut self, app_id: Uuid, purpose: Purpose) -> Option<Id> {
+        let id = self.app_purpose_to_id.remove(&(app_id, purpose))?;
+
+        if let Some(map) = self.id_to_purpose_map.get_mut(&id) {
+            map.remove_by_left(&purpose);
+            if map.is_empty() {
+                self.id_to_purpose_map.remove(&id);
+            }
+        }
+
+        Some(id)
+    }
+}
+
+/// Ids on the gateway's platform, such as guilds, channels or rooms, bound to an app for a
+/// purpose. An app has one id per purpose.
+#[derive(Clone)]
+pub struct GenericIdCache<Id, Purpose> {
+    inner: Arc<RwLock<GenericIdCacheInner<Id, Purpose>>>,
+}
+
+impl<Id, Purpose> Default for GenericIdCache<Id, Purpose> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Default::default())),
+        }
+    }
+}
+
+impl<Id: 'static, Purpose: 'static> GenericIdCache<Id, Purpose>
+where
+    Id: Eq + Hash + Clone,
+    Purpose: Eq + Hash + Copy,
+{
+    pub async fn populate<'a, I: IntoIterator<Item = &'a (Id, Purpose, Uuid)>>(&self, iter: I) {
+        let mut inner = self.inner.write().await;
+
+        let iter = iter.into_iter();
+        let size_hint = iter.size_hint().0;
+
+        let existing_capacity = inner.id_to_purpose_map.capacity() - inner.id_to_purpose_map.len();
+        if size_hint > existing_capacity {
+            inner
+                .id_to_purpose_map
+                .reserve(size_hint - existing_capacity);
+        }
+
+        let existing_capacity = inner.app_purpose_to_id.capacity() - inner.app_purpose_to_id.len();
+        if size_hint > existing_capacity {
+            inner
+                .app_purpose_to_id
+                .reserve(size_hint - existing_capacity);
+        }
+
+        for (id, purpose, app_id) in iter {
+            inner.insert(id.clone(), *purpose, *app_id);
+        }
+    }
+
+    /// Binds `id` to the app, replacing the id the app had for `purpose`.
+    pub async fn insert(&self, id: Id, purpose: Purpose, app_id: Uuid) {
+        self.inner.write().await.insert(id, purpose, app_id);
+    }
+
+    /// Unbinds the app's id for `purpose`, returns that id.
+    pub async fn remove(&self, app_id: Uuid, purpose: Purpose) -> Option<Id> {
+        self.inner.write().await.remove(app_id, purpose)
+    }
+
+    pub async fn get_id(&self, app_id: Uuid, purpose: Purpose) -> Option<Id> {
+        let inner = self.inner.read().await;
+        inner.app_purpose_to_id.get(&(app_id, purpose)).cloned()
+    }
+
+    pub async fn get_app_id<Q>(&self, id: &Q, purpose: Purpose) -> Option<Uuid>
+    where
+        Id: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let inner = self.inner.read().await;
+        inner
+            .id_to_purpose_map
+            .get(id)
+            .and_then(|map| map.get_by_left(&purpose))
+            .cloned()
+    }
+
+    pub async fn app_ids(&self) -> HashSet<Uuid> {
+        let inner = self.inner.read().await;
+        inner
+            .app_purpose_to_id
+            .keys()
+            .map(|(app_id, _)| *app_id)
+            .collect()
+    }
+}
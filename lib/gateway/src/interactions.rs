@@ -0,0 +1,36 @@
+//! what a user does on the gateway's platform, such as running a command or submitting a form,
+//! acts on the collector through the user's own staff client
+
+use std::collections::HashSet;
+use std::future::Future;
+
+use uuid::Uuid;
+
+use auth::UserRole;
+use errors::TicketsResult;
+
+use crate::users::{User, UsersCache};
+use crate::Gateway;
+
+/// The platform neutral part of an interaction, the platform's own details such as where to
+/// respond stay with the gateway.
+pub trait Interaction<G: Gateway>: Send + Sync {
+    /// The user who interacted.
+    fn identity(&self) -> &G::Identity;
+
+    fn users(&self) -> &UsersCache<G>;
+
+    /// The client of the user acting with `role` on `app_ids`, the collector checks their role
+    /// in the apps when scoping its token to them.
+    fn staff_client(
+        &self,
+        role: UserRole,
+        app_ids: HashSet<Uuid>,
+    ) -> impl Future<Output = TicketsResult<User>> + Send {
+        async move {
+            self.users()
+                .authorize_apps(self.identity(), role, app_ids)
+                .await
+        }
+    }
+}
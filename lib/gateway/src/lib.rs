@@ -0,0 +1,167 @@
+//! what every gateway shares: signing the collector sdk, subscribing to the realtime events of
+//! the apps it serves and caching the clients of its staff members, so a gateway only adapts
+//! its platform to the [`Gateway`] trait
+
+use std::future::Future;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtData, JwtKeyPathsConfig};
+use auth::UserRole;
+use errors::TicketsResult;
+use events::{AppChangedEvent, TicketUpdatedEvent};
+use sdk::client::{InternalSdk, SignedTicketClient};
+use socketio_client::{AppChangesNamespace, TicketNamespace, TicketSocketConfig};
+
+pub use crate::realtime::Listener;
+use crate::users::UsersCache;
+
+pub mod cache;
+pub mod interactions;
+pub mod realtime;
+pub mod users;
+
+pub trait Gateway: Send + Sync + 'static {
+    /// Sent as `x-gateway`, apps enable the gateway under this name through `toggle_gateway`.
+    const NAME: &'static str;
+    /// The gateway submits tickets and connects to the realtime events as this accessor.
+    const SYSTEM_ACCESSOR: JwtAccessor;
+    /// Whether app changes are subscribed to next to ticket events.
+    const APP_CHANGES: bool = false;
+
+    /// Identifies a user on the gateway's platform.
+    type Identity: Clone + Eq + Hash + Send + Sync + 'static;
+
+    /// The accessor a staff member signs in as, `None` if staff can not act through the gateway.
+    fn staff_accessor(_identity: &Self::Identity, _role: UserRole) -> Option<JwtAccessor> {
+        None
+    }
+
+    fn handle_ticket_event(
+        &self,
+        app_id: Uuid,
+        event: TicketUpdatedEvent,
+    ) -> impl Future<Output = TicketsResult<()>> + Send;
+
+    fn handle_app_change(
+        &self,
+        app_id: Uuid,
+        event: AppChangedEvent,
+    ) -> impl Future<Output = TicketsResult<()>> + Send {
+        async move {
+            log::info!("Received app change event for app {app_id}: {event:?}");
+            Ok(())
+        }
+    }
+}
+
+/// The part of `config.json` every gateway reads, flattened into the gateway's own config.
+#[derive(serde::Deserialize)]
+pub struct GatewayConfig {
+    pub collector_url: String,
+    pub realtime_events_url: Option<String>,
+    pub jwt: JwtKeyPathsConfig,
+}
+
+pub struct Bootstrap<G: Gateway> {
+    /// Signs collector clients for the gateway and its staff members.
+    pub sdk: InternalSdk,
+    jwt_config: Arc<JwtConfig>,
+    realtime_events_url: String,
+    gateway: PhantomData<G>,
+}
+
+impl<G: Gateway> Bootstrap<G> {
+    pub fn new(config: GatewayConfig) -> TicketsResult<Self> {
+        let jwt_config: Arc<JwtConfig> = Arc::new(config.jwt.try_into()?);
+        let realtime_events_url = config
+            .realtime_events_url
+            .unwrap_or_else(|| config.collector_url.clone());
+        let sdk: InternalSdk = (config.collector_url, jwt_config.clone(), G::NAME).try_into()?;
+
+        Ok(Self {
+            sdk,
+            jwt_config,
+            realtime_events_url,
+            gateway: PhantomData,
+        })
+    }
+
+    pub fn system_client(&self) -> TicketsResult<SignedTicketClient> {
+        self.sdk
+            .sign_client(G::SYSTEM_ACCESSOR, InternalSdk::DEFAULT_TTL)
+    }
+
+    pub fn users(&self) -> UsersCache<G> {
+        UsersCache::new(self.sdk.clone())
+    }
+
+    /// Connects to the realtime events, no app is listened to until [`Listener::listen_to`].
+    pub async fn subscribe(&self) -> TicketsResult<Subscription<G>> {
+        let socket_config = TicketSocketConfig {
+            server_url: self.realtime_events_url.clone(),
+            token: self
+                .jwt_config
+                .generate(
+                    JwtData {
+                        accessor: G::SYSTEM_ACCESSOR,
+                    },
+                    // only needs to be valid during the time of authentication
+                    // the system will run re-authentication requests for updated
+                    // token claim security requirements
+                    Duration::from_secs(60),
+                )?
+                .0,
+        };
+
+        let (ticket_event_client, ticket_events) =
+            socketio_client::connect::<TicketNamespace>(&socket_config).await?;
+
+        let (app_changes_client, app_changes) = if G::APP_CHANGES {
+            let (client, receiver) =
+                socketio_client::connect::<AppChangesNamespace>(&socket_config).await?;
+            (Some(client), Some(receiver))
+        } else {
+            (None, None)
+        };
+
+        Ok(Subscription {
+            listener: Listener::new(ticket_event_client, app_changes_client),
+            ticket_events,
+            app_changes,
+            gateway: PhantomData,
+        })
+    }
+}
+
+pub struct Subscription<G: Gateway> {
+    pub listener: Listener,
+    ticket_events: UnboundedReceiver<(Uuid, TicketUpdatedEvent)>,
+    app_changes: Option<UnboundedReceiver<(Uuid, AppChangedEvent)>>,
+    gateway: PhantomData<G>,
+}
+
+impl<G: Gateway> Subscription<G> {
+    /// Hands the received events to `gateway` until the connection to the realtime events ends.
+    pub fn run(self, gateway: Arc<G>) -> JoinHandle<TicketsResult<()>> {
+        tokio::spawn(async move {
+            let ticket_events = realtime::read_ticket_events(self.ticket_events, gateway.as_ref());
+
+            match self.app_changes {
+                Some(app_changes) => {
+                    tokio::select! {
+                        res = ticket_events => res,
+                        res = realtime::read_app_changes(app_changes, gateway.as_ref()) => res,
+                    }
+                }
+                None => ticket_events.await,
+            }
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use errors::TicketsResult;
+use events::{AppChangedEvent, TicketUpdatedEvent};
+use socketio_client::TicketsWebsocketClientExt;
+
+use crate::Gateway;
+
+/// Listens to the events of apps the gateway serves, each app only once.
+#[derive(Clone)]
+pub struct Listener {
+    ticket_event_client: socketio_client::Client,
+    app_changes_client: Option<socketio_client::Client>,
+    apps: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+impl Listener {
+    pub(crate) fn new(
+        ticket_event_client: socketio_client::Client,
+        app_changes_client: Option<socketio_client::Client>,
+    ) -> Self {
+        Self {
+            ticket_event_client,
+            app_changes_client,
+            apps: Default::default(),
+        }
+    }
+
+    pub async fn listen_to(&self, app_id: Uuid) -> TicketsResult<()> {
+        if !self.apps.lock().await.insert(app_id) {
+            return Ok(());
+        }
+
+        let listened = self.subscribe(app_id).await;
+
+        // tried again the next time the app is listened to
+        if listened.is_err() {
+            self.apps.lock().await.remove(&app_id);
+        }
+
+        listened
+    }
+
+    /// Listens to every app in `app_ids`, apps which could not be listened to are logged.
+    pub async fn listen_to_all(&self, app_ids: impl IntoIterator<Item = Uuid>) {
+        for app_id in app_ids {
+            if let Err(err) = self.listen_to(app_id).await {
+                log::error!("Could not listen to app {app_id}: {err}");
+            }
+        }
+    }
+
+    async fn subscribe(&self, app_id: Uuid) -> TicketsResult<()> {
+        if let Some(app_changes_client) = &self.app_changes_client {
+            app_changes_client.listen_to(app_id, None).await?;
+        }
+
+        self.ticket_event_client.listen_to(app_id, None).await
+    }
+}
+
+pub(crate) async fn read_ticket_events<G: Gateway>(
+    mut receiver: UnboundedReceiver<(Uuid, TicketUpdatedEvent)>,
+    gateway: &G,
+) -> TicketsResult<()> {
+    while let Some((app_id, event)) = receiver.recv().await {
+        log::info!("Received ticket update event for app {app_id}: {event:?}");
+
+        if let Err(err) = gateway.handle_ticket_event(app_id, event).await {
+            log::error!("Error handling ticket update event for app {app_id}: {err}");
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn read_app_changes<G: Gateway>(
+    mut receiver: UnboundedReceiver<(Uuid, AppChangedEvent)>,
+    gateway: &G,
+) -> TicketsResult<()> {
+    while let Some((app_id, event)) = receiver.recv().await {
+        if let Err(err) = gateway.handle_app_change(app_id, event).await {
+            log::error!("Error handling app change event for app {app_id}: {err}");
+        }
+    }
+    Ok(())
+}
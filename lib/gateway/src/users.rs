@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use moka::policy::EvictionPolicy;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use auth::UserRole;
+use errors::{AuthorizationError, TicketsResult};
+use sdk::client::{InternalSdk, SdkCallWithBody, SdkExecutor, SignedTicketClient};
+use sdk::routes::staff::{AuthorizeApps, AuthorizeAppsBody, AuthorizeAppsResponse};
+use sdk::routes::FileData;
+
+use crate::Gateway;
+
+#[derive(Clone)]
+pub struct User {
+    pub client: SignedTicketClient,
+    /// Apps the client's minted token is scoped to, see [`UsersCache::authorize_apps`].
+    authorized_apps: HashSet<Uuid>,
+}
+
+impl SdkExecutor for User {
+    async fn call<T: for<'de> Deserialize<'de>, S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        self.client.call(method, path, query_params).await
+    }
+
+    async fn call_with_body<
+        T: for<'de> Deserialize<'de>,
+        B: Serialize,
+        S: Into<String>,
+        Q: Serialize,
+    >(
+        &self,
+        method: Method,
+        path: S,
+        body: B,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        self.client
+            .call_with_body(method, path, body, query_params)
+            .await
+    }
+
+    async fn invoke<S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        query_params: Q,
+    ) -> TicketsResult<StatusCode> {
+        self.client.invoke(method, path, query_params).await
+    }
+
+    async fn invoke_with_body<B: Serialize, S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        body: B,
+        query_params: Q,
+    ) -> TicketsResult<StatusCode> {
+        self.client
+            .invoke_with_body(method, path, body, query_params)
+            .await
+    }
+
+    async fn upload<T: for<'de> Deserialize<'de>, S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        file: FileData,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        self.client.upload(method, path, file, query_params).await
+    }
+
+    async fn download<S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        query_params: Q,
+    ) -> TicketsResult<FileData> {
+        self.client.download(method, path, query_params).await
+    }
+}
+
+/// Collector clients of the gateway's staff members, signed on first use and kept while they
+/// are active.
+pub struct UsersCache<G: Gateway> {
+    sdk: InternalSdk,
+    inner: moka::future::Cache<(G::Identity, UserRole), User>,
+}
+
+impl<G: Gateway> Clone for UsersCache<G> {
+    fn clone(&self) -> Self {
+        Self {
+            sdk: self.sdk.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<G: Gateway> UsersCache<G> {
+    /// An empty cache signing with `sdk`, gateways get theirs from [`crate::Bootstrap::users`].
+    pub fn new(sdk: InternalSdk) -> Self {
+        let inner = moka::future::CacheBuilder::new(500)
+            .name("UsersCache")
+            .eviction_policy(EvictionPolicy::tiny_lfu())
+            .time_to_idle(Duration::from_secs(5 * 60))
+            .build();
+        Self { sdk, inner }
+    }
+
+    /// The client of the staff member, acting with `role`. The collector still checks their
+    /// role in the app they act on.
+    pub async fn staff(&self, identity: &G::Identity, role: UserRole) -> TicketsResult<User> {
+        let key = (identity.clone(), role);
+        if let Some(user) = self.inner.get(&key).await {
+            return Ok(user);
+        }
+
+        let Some(accessor) = G::staff_accessor(identity, role) else {
+            return Err(AuthorizationError::UserCannotAccessResource)?;
+        };
+
+        let user = User {
+            client: self.sdk.sign_client(accessor, InternalSdk::DEFAULT_TTL)?,
+            authorized_apps: HashSet::new(),
+        };
+        self.inner.insert(key, user.clone()).await;
+
+        Ok(user)
+    }
+
+    /// The client of the staff member acting on `app_ids`. Its token is exchanged for one
+    /// scoped to the apps, so the collector resolves their role from the token instead of
+    /// querying app membership, and reused while it is fresh and covers the apps.
+    pub async fn authorize_apps(
+        &self,
+        identity: &G::Identity,
+        role: UserRole,
+        app_ids: HashSet<Uuid>,
+    ) -> TicketsResult<User> {
+        let user = self.staff(identity, role).await?;
+        if user.client.has_fresh_minted_token() && app_ids.is_subset(&user.authorized_apps) {
+            return Ok(user);
+        }
+
+        let AuthorizeAppsResponse {
+            token, expiration, ..
+        } = AuthorizeApps::call_with_body(
+            &user,
+            AuthorizeAppsBody {
+                app_ids: app_ids.clone(),
+            },
+        )
+        .await?;
+
+        let user = User {
+            client: user.client.with_minted_token(token, expiration),
+            authorized_apps: app_ids,
+        };
+        self.inner
+            .insert((identity.clone(), role), user.clone())
+            .await;
+
+        Ok(user)
+    }
+}
@@ -4,11 +4,25 @@ use std::time::Duration;
 use errors::{NetworkError, ParsingError, TicketsError, TicketsResult};
 use reqwest::{Client, ClientBuilder, Method, Request, RequestBuilder, Response, StatusCode, Url};
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
 
-use super::routes::Empty;
+use super::routes::staff::{ExchangeLoginCode, ExchangeLoginCodeBody, ExchangeLoginCodeResponse};
+use super::routes::{Empty, FileData, SdkRoute};
+use super::IDEMPOTENCY_KEY_HEADER;
 use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
-use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE};
+use uuid::Uuid;
+
+#[cfg(feature = "mock")]
+pub use mock::MockSdkExecutor;
+pub use pagination::{PageOptions, PageStream, SdkPaginate, SdkPaginateWithPath};
+pub use retry::RetryPolicy;
+pub use tokens::{Clock, SystemClock, TokenClaim, TokenManager};
+
+#[cfg(feature = "mock")]
+mod mock;
+mod pagination;
+mod retry;
+mod tokens;
 
 struct MethodWrapper(http::Method);
 
@@ -64,12 +78,127 @@ pub trait SdkExecutor {
         body: B,
         query_params: Q,
     ) -> TicketsResult<StatusCode>;
+
+    /// Sends `file` as a `multipart/form-data` body.
+    async fn upload<T: for<'de> Deserialize<'de>, S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        file: FileData,
+        query_params: Q,
+    ) -> TicketsResult<T>;
+
+    async fn download<S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        query_params: Q,
+    ) -> TicketsResult<FileData>;
+}
+
+/// Request body along with the content type it is sent as.
+struct EncodedBody {
+    content_type: String,
+    bytes: Vec<u8>,
+}
+
+impl EncodedBody {
+    fn json<B: Serialize>(body: &B) -> TicketsResult<Self> {
+        Ok(Self {
+            content_type: "application/json".to_string(),
+            bytes: serde_json::to_vec(body)?,
+        })
+    }
+
+    /// Encodes the file as the `file` field of a `multipart/form-data` body.
+    fn multipart(file: &FileData) -> Self {
+        let boundary = format!("tickets-{}", Uuid::new_v4().simple());
+        let file_name = file
+            .file_name
+            .replace('"', "%22")
+            .replace(['\r', '\n'], " ");
+        let content_type = file.content_type.replace(['\r', '\n'], "");
+
+        let mut bytes = Vec::with_capacity(file.bytes.len() + 256);
+        bytes.extend_from_slice(
+            format!(
+                "--{boundary}\r\n\
+                Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+                Content-Type: {content_type}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        bytes.extend_from_slice(&file.bytes);
+        bytes.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        Self {
+            content_type: format!("multipart/form-data; boundary={boundary}"),
+            bytes,
+        }
+    }
+}
+
+/// Fills the `{name}` placeholders of a route template from the serialized fields of `params`.
+pub fn fill_route<P: Serialize>(route: &str, params: &P) -> TicketsResult<String> {
+    let params = match serde_json::to_value(params)? {
+        serde_json::Value::Object(params) => params,
+        _ => Default::default(),
+    };
+
+    let mut filled = String::with_capacity(route.len());
+    let mut rest = route;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| ParsingError::MissingPathParameter {
+                parameter: rest[start..].to_string(),
+            })?;
+
+        let parameter = &rest[start + 1..end];
+        let value = match params.get(parameter) {
+            Some(serde_json::Value::String(value)) => value.to_string(),
+            Some(serde_json::Value::Number(value)) => value.to_string(),
+            Some(serde_json::Value::Bool(value)) => value.to_string(),
+            _ => Err(ParsingError::MissingPathParameter {
+                parameter: parameter.to_string(),
+            })?,
+        };
+
+        filled.push_str(&rest[..start]);
+        encode_path_segment(&value, &mut filled);
+        rest = &rest[end + 1..];
+    }
+
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+fn encode_path_segment(segment: &str, out: &mut String) {
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+}
+
+macro_rules! route_path {
+    ($route:ident) => {
+        $route::route().to_string()
+    };
+    ($route:ident, $path_params:ident) => {
+        fill_route($route::route(), &$path_params)?
+    };
 }
 
 macro_rules! sdk_permutation {
     ($($name:ident$(<$($generics:ident),*>)? {
         $backing_fn:ident($($extra_function_tokens:tt)*) -> $return_type:ty {
-            $inner_call:ident($($binder_function_extras:tt)*)
+            $inner_call:ident$([$path_params:ident])?($($binder_function_extras:tt)*)
         }
 
         Restrict { $($restrictions:tt)* } $(where $($where_clauses:tt)*)?
@@ -85,7 +214,8 @@ macro_rules! sdk_permutation {
             $($($where_clauses)*)?
         {
             async fn $backing_fn(executor: &impl crate::client::SdkExecutor, $($extra_function_tokens)*) -> TicketsResult<$return_type> {
-                executor.$inner_call(MethodWrapper(T::method()).into(), T::route(), $($binder_function_extras)*).await
+                let path = route_path!(T$(, $path_params)?);
+                executor.$inner_call(MethodWrapper(T::method()).into(), path, $($binder_function_extras)*).await
             }
         }
         )*
@@ -101,7 +231,8 @@ sdk_permutation! {
         Restrict {
             Body = Empty,
             Response = ResponseType,
-            QueryParams = Empty
+            QueryParams = Empty,
+            PathParams = Empty
         } where
             ResponseType: for<'de> Deserialize<'de>
     }
@@ -115,6 +246,7 @@ sdk_permutation! {
             Body = Empty,
             Response = ResponseType,
             QueryParams = QueryParams,
+            PathParams = Empty
         } where
             ResponseType: for<'de> Deserialize<'de>,
             QueryParams: Serialize
@@ -128,7 +260,8 @@ sdk_permutation! {
         Restrict {
             Body = Body,
             Response = ResponseType,
-            QueryParams = Empty
+            QueryParams = Empty,
+            PathParams = Empty
         } where
             ResponseType: for<'de> Deserialize<'de>,
             Body: Serialize
@@ -143,7 +276,8 @@ sdk_permutation! {
         Restrict {
             Body = Body,
             Response = ResponseType,
-            QueryParams = QueryParams
+            QueryParams = QueryParams,
+            PathParams = Empty
         } where
             ResponseType: for<'de> Deserialize<'de>,
             Body: Serialize,
@@ -159,7 +293,8 @@ sdk_permutation! {
         Restrict {
             Body = Empty,
             Response = Empty,
-            QueryParams = Empty
+            QueryParams = Empty,
+            PathParams = Empty
         }
     }
 
@@ -171,7 +306,8 @@ sdk_permutation! {
         Restrict {
             Body = Empty,
             Response = Empty,
-            QueryParams = QueryParams
+            QueryParams = QueryParams,
+            PathParams = Empty
         } where
             QueryParams: Serialize
     }
@@ -184,7 +320,8 @@ sdk_permutation! {
         Restrict {
             Body = Body,
             Response = Empty,
-            QueryParams = Empty
+            QueryParams = Empty,
+            PathParams = Empty
         } where
             Body: Serialize
     }
@@ -197,11 +334,196 @@ sdk_permutation! {
         Restrict {
             Body = Body,
             Response = Empty,
-            QueryParams = QueryParams
+            QueryParams = QueryParams,
+            PathParams = Empty
         } where
             Body: Serialize,
             QueryParams: Serialize
     }
+
+    SdkCallWithPath<ResponseType, PathParams> {
+        call_with_path(path_params: PathParams) -> ResponseType {
+            call[path_params](())
+        }
+
+        Restrict {
+            Body = Empty,
+            Response = ResponseType,
+            QueryParams = Empty,
+            PathParams = PathParams
+        } where
+            ResponseType: for<'de> Deserialize<'de>,
+            PathParams: Serialize
+    }
+
+    SdkCallWithPathAndParams<ResponseType, PathParams, QueryParams> {
+        call_with_path_and_query(path_params: PathParams, query_params: QueryParams) -> ResponseType {
+            call[path_params](query_params)
+        }
+
+        Restrict {
+            Body = Empty,
+            Response = ResponseType,
+            QueryParams = QueryParams,
+            PathParams = PathParams
+        } where
+            ResponseType: for<'de> Deserialize<'de>,
+            PathParams: Serialize,
+            QueryParams: Serialize
+    }
+
+    SdkCallWithPathAndBody<PathParams, Body, ResponseType> {
+        call_with_path_and_body(path_params: PathParams, body: Body) -> ResponseType {
+            call_with_body[path_params](body, ())
+        }
+
+        Restrict {
+            Body = Body,
+            Response = ResponseType,
+            QueryParams = Empty,
+            PathParams = PathParams
+        } where
+            ResponseType: for<'de> Deserialize<'de>,
+            PathParams: Serialize,
+            Body: Serialize
+    }
+
+    SdkCallWithPathBodyAndParams<ResponseType, PathParams, Body, QueryParams> {
+        call_with_path_body_and_query(path_params: PathParams, body: Body, query_params: QueryParams) -> ResponseType {
+            call_with_body[path_params](body, query_params)
+        }
+
+        Restrict {
+            Body = Body,
+            Response = ResponseType,
+            QueryParams = QueryParams,
+            PathParams = PathParams
+        } where
+            ResponseType: for<'de> Deserialize<'de>,
+            PathParams: Serialize,
+            Body: Serialize,
+            QueryParams: Serialize
+    }
+
+    SdkInvokeWithPath<PathParams> {
+        invoke_with_path(path_params: PathParams) -> reqwest::StatusCode {
+            invoke[path_params](())
+        }
+
+        Restrict {
+            Body = Empty,
+            Response = Empty,
+            QueryParams = Empty,
+            PathParams = PathParams
+        } where
+            PathParams: Serialize
+    }
+
+    SdkInvokeWithPathAndParams<PathParams, QueryParams> {
+        invoke_with_path_and_params(path_params: PathParams, query_params: QueryParams) -> reqwest::StatusCode {
+            invoke[path_params](query_params)
+        }
+
+        Restrict {
+            Body = Empty,
+            Response = Empty,
+            QueryParams = QueryParams,
+            PathParams = PathParams
+        } where
+            PathParams: Serialize,
+            QueryParams: Serialize
+    }
+
+    SdkInvokeWithPathAndBody<PathParams, Body> {
+        invoke_with_path_and_body(path_params: PathParams, body: Body) -> reqwest::StatusCode {
+            invoke_with_body[path_params](body, ())
+        }
+
+        Restrict {
+            Body = Body,
+            Response = Empty,
+            QueryParams = Empty,
+            PathParams = PathParams
+        } where
+            PathParams: Serialize,
+            Body: Serialize
+    }
+
+    SdkInvokeWithPathBodyAndParams<PathParams, Body, QueryParams> {
+        invoke_with_path_body_and_params(path_params: PathParams, body: Body, query_params: QueryParams) -> reqwest::StatusCode {
+            invoke_with_body[path_params](body, query_params)
+        }
+
+        Restrict {
+            Body = Body,
+            Response = Empty,
+            QueryParams = QueryParams,
+            PathParams = PathParams
+        } where
+            PathParams: Serialize,
+            Body: Serialize,
+            QueryParams: Serialize
+    }
+
+    SdkUploadWithPath<PathParams, ResponseType> {
+        upload_with_path(path_params: PathParams, file: FileData) -> ResponseType {
+            upload[path_params](file, ())
+        }
+
+        Restrict {
+            Body = FileData,
+            Response = ResponseType,
+            QueryParams = Empty,
+            PathParams = PathParams
+        } where
+            ResponseType: for<'de> Deserialize<'de>,
+            PathParams: Serialize
+    }
+
+    SdkUploadWithPathAndParams<ResponseType, PathParams, QueryParams> {
+        upload_with_path_and_query(path_params: PathParams, file: FileData, query_params: QueryParams) -> ResponseType {
+            upload[path_params](file, query_params)
+        }
+
+        Restrict {
+            Body = FileData,
+            Response = ResponseType,
+            QueryParams = QueryParams,
+            PathParams = PathParams
+        } where
+            ResponseType: for<'de> Deserialize<'de>,
+            PathParams: Serialize,
+            QueryParams: Serialize
+    }
+
+    SdkDownloadWithPath<PathParams> {
+        download_with_path(path_params: PathParams) -> FileData {
+            download[path_params](())
+        }
+
+        Restrict {
+            Body = Empty,
+            Response = FileData,
+            QueryParams = Empty,
+            PathParams = PathParams
+        } where
+            PathParams: Serialize
+    }
+
+    SdkDownloadWithPathAndParams<PathParams, QueryParams> {
+        download_with_path_and_query(path_params: PathParams, query_params: QueryParams) -> FileData {
+            download[path_params](query_params)
+        }
+
+        Restrict {
+            Body = Empty,
+            Response = FileData,
+            QueryParams = QueryParams,
+            PathParams = PathParams
+        } where
+            PathParams: Serialize,
+            QueryParams: Serialize
+    }
 }
 
 #[derive(Clone)]
@@ -209,6 +531,9 @@ pub struct InternalSdk {
     headers: HeaderMap,
     base_url: Url,
     jwt: Arc<JwtConfig>,
+    refresh_window: Duration,
+    clock: Arc<dyn Clock>,
+    retry_policy: RetryPolicy,
 }
 
 impl TryFrom<(String, JwtConfig, &'static str)> for InternalSdk {
@@ -247,6 +572,9 @@ impl InternalSdk {
     // 1 hour = 60 seconds * 60 minutes
     pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
 
+    // 5 minutes = 60 seconds * 5 minutes
+    pub const DEFAULT_REFRESH_WINDOW: Duration = Duration::from_secs(60 * 5);
+
     pub(crate) fn create(url: Url, config: Arc<JwtConfig>, gateway: &'static str) -> Self {
         let mut headers = HeaderMap::with_capacity(1);
         headers.insert("x-gateway", HeaderValue::from_static(gateway));
@@ -254,9 +582,28 @@ impl InternalSdk {
             headers,
             base_url: url,
             jwt: config,
+            refresh_window: Self::DEFAULT_REFRESH_WINDOW,
+            clock: Arc::new(SystemClock),
+            retry_policy: Default::default(),
         }
     }
 
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// How long before expiry signed clients start refreshing their tokens.
+    pub fn with_refresh_window(mut self, refresh_window: Duration) -> Self {
+        self.refresh_window = refresh_window;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn sign_client(
         &self,
         accessor: JwtAccessor,
@@ -264,118 +611,209 @@ impl InternalSdk {
     ) -> TicketsResult<SignedTicketClient> {
         SignedTicketClient::new(
             self.base_url.clone(),
-            JwtData { accessor },
-            ttl,
-            self.jwt.clone(),
+            Arc::new(TokenManager::new(
+                self.jwt.clone(),
+                JwtData { accessor },
+                ttl,
+                self.refresh_window,
+                self.clock.clone(),
+            )),
             self.headers.clone(),
+            self.retry_policy.clone(),
         )
     }
 }
 
-pub struct TokenClaim {
-    token: String,
-    expiration: i64,
-}
-
-impl TokenClaim {
-    pub fn token(&self) -> &str {
-        &self.token
-    }
-
-    pub fn expiration(&self) -> i64 {
-        self.expiration
-    }
+/// Exchanges a code created through `/staff/login_codes` for a token issued by the collector,
+/// the one request sent without a token. See [`SignedTicketClient::with_issued_token`].
+pub async fn exchange_login_code(
+    base_url: &str,
+    gateway: &'static str,
+    code: String,
+) -> TicketsResult<ExchangeLoginCodeResponse> {
+    let url = Url::parse(base_url)
+        .and_then(|base_url| base_url.join(ExchangeLoginCode::route()))
+        .map_err(ParsingError::from)?;
+
+    let response = ClientBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build()?
+        .request(MethodWrapper(ExchangeLoginCode::method()).into(), url)
+        .header("x-gateway", gateway)
+        .json(&ExchangeLoginCodeBody { code })
+        .send()
+        .await?;
+
+    SignedTicketClient::parse(response).await
 }
 
 #[derive(Clone)]
 pub struct SignedTicketClient {
     base_url: Url,
     client: Client,
-    pub token_claim: Arc<RwLock<TokenClaim>>,
-    pub data: JwtData,
-    ttl: Duration,
-    jwt_partial: Arc<JwtConfig>,
+    tokens: Arc<TokenManager>,
+    /// Token minted by the collector (see `/staff/authorize_apps`), preferred until it expires.
+    minted_claim: Option<Arc<TokenClaim>>,
     headers: HeaderMap,
+    retry_policy: RetryPolicy,
 }
 
 impl SignedTicketClient {
     pub(crate) fn new(
         base_url: Url,
-        data: JwtData,
-        ttl: Duration,
-        jwt_partial: Arc<JwtConfig>,
+        tokens: Arc<TokenManager>,
         headers: HeaderMap,
+        retry_policy: RetryPolicy,
     ) -> TicketsResult<Self> {
-        let (token, claims) = jwt_partial.generate(data.clone(), ttl)?;
-
         Ok(Self {
             base_url,
             client: ClientBuilder::new()
                 .timeout(Duration::from_secs(30))
                 .build()?,
-            token_claim: Arc::new(RwLock::new(TokenClaim {
-                token,
-                expiration: claims.exp,
-            })),
-            data,
-            ttl,
-            jwt_partial,
+            tokens,
+            minted_claim: None,
             headers,
+            retry_policy,
         })
     }
 
-    async fn ensure_token(&self) -> TicketsResult<String> {
-        let read_token = self.token_claim.read().await;
+    /// A client authenticating with `token`, issued by the collector for `data` such as through
+    /// [`exchange_login_code`], until it expires at `expiration`.
+    pub fn with_issued_token(
+        base_url: &str,
+        gateway: &'static str,
+        data: JwtData,
+        token: String,
+        expiration: i64,
+    ) -> TicketsResult<Self> {
+        let mut headers = HeaderMap::with_capacity(1);
+        headers.insert("x-gateway", HeaderValue::from_static(gateway));
+
+        // the lifetime only sizes the refresh window, which issued tokens do not have
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let claim = TokenClaim::new(token, clock.now(), expiration);
+
+        Self::new(
+            Url::parse(base_url).map_err(ParsingError::from)?,
+            Arc::new(TokenManager::issued(data, claim, clock)),
+            headers,
+            Default::default(),
+        )
+    }
 
-        if read_token.expiration > chrono::Utc::now().timestamp() {
-            drop(read_token);
-            let mut write_token = self.token_claim.write().await;
-            let (new_token, claims) = self.jwt_partial.generate(self.data.clone(), self.ttl)?;
-            write_token.token = new_token.clone();
-            write_token.expiration = claims.exp + Duration::from_secs(5 * 60).as_millis() as i64;
-            drop(write_token);
-            return Ok(new_token);
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
         }
-        let token = read_token.token.clone();
-        drop(read_token);
-        Ok(token)
     }
 
-    async fn prepare_request<S: Into<String>, Q: Serialize>(
+    pub fn data(&self) -> &JwtData {
+        self.tokens.data()
+    }
+
+    pub fn tokens(&self) -> &TokenManager {
+        &self.tokens
+    }
+
+    /// Creates a copy of this client which authenticates with a token minted by the collector,
+    /// falling back to self-signed tokens once it expires.
+    pub fn with_minted_token(&self, token: String, expiration: i64) -> Self {
+        // counted from receipt, the claim's lifetime only decides its refresh window
+        let received_at = self.tokens.now();
+        Self {
+            minted_claim: Some(Arc::new(TokenClaim::new(token, received_at, expiration))),
+            ..self.clone()
+        }
+    }
+
+    /// Whether requests still authenticate with the token minted by the collector.
+    pub fn has_fresh_minted_token(&self) -> bool {
+        self.fresh_minted_claim().is_some()
+    }
+
+    fn fresh_minted_claim(&self) -> Option<&Arc<TokenClaim>> {
+        self.minted_claim
+            .as_ref()
+            .filter(|minted| self.tokens.is_fresh(minted))
+    }
+
+    async fn ensure_token(&self) -> TicketsResult<String> {
+        match self.fresh_minted_claim() {
+            Some(minted) => Ok(minted.token().to_string()),
+            None => self.tokens.token().await,
+        }
+    }
+
+    async fn prepare_request<Q: Serialize>(
         &self,
         method: Method,
-        path: S,
-        query_params: Q,
+        url: Url,
+        query_params: &Q,
     ) -> TicketsResult<RequestBuilder> {
-        let request = RequestBuilder::from_parts(
-            self.client.clone(),
-            Request::new(
-                method,
-                self.base_url
-                    .join(&path.into())
-                    .map_err(ParsingError::from)?,
-            ),
-        );
+        let request = RequestBuilder::from_parts(self.client.clone(), Request::new(method, url));
         let bearer = request.bearer_auth(self.ensure_token().await?);
-        Ok(bearer.query(&query_params).headers(self.headers.clone()))
+        Ok(bearer.query(query_params).headers(self.headers.clone()))
     }
 
-    async fn prepare_request_with_body<S: Into<String>, B: Serialize, Q: Serialize>(
+    /// Sends the request, retrying transient failures according to the retry policy.
+    ///
+    /// Only idempotent methods, or requests which carry an idempotency key, are retried.
+    async fn send<S: Into<String>, Q: Serialize>(
         &self,
         method: Method,
         path: S,
-        body: B,
+        body: Option<EncodedBody>,
         query_params: Q,
-    ) -> TicketsResult<RequestBuilder> {
-        Ok(self
-            .prepare_request(method, path, query_params)
-            .await?
-            .json(&body))
+    ) -> TicketsResult<Response> {
+        let url = self
+            .base_url
+            .join(&path.into())
+            .map_err(ParsingError::from)?;
+
+        let idempotent = RetryPolicy::is_idempotent(&method);
+        // a single key for all attempts so the collector can replay the first response
+        let idempotency_key =
+            (!idempotent && self.retry_policy.idempotency_keys).then(|| Uuid::new_v4().to_string());
+        let retryable = idempotent || idempotency_key.is_some();
+
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .prepare_request(method.clone(), url.clone(), &query_params)
+                .await?;
+
+            if let Some(idempotency_key) = &idempotency_key {
+                request = request.header(IDEMPOTENCY_KEY_HEADER, idempotency_key);
+            }
+
+            if let Some(body) = &body {
+                request = request
+                    .header(CONTENT_TYPE, &body.content_type)
+                    .body(body.bytes.clone());
+            }
+
+            let result = request.send().await;
+
+            let retry = retryable
+                && attempt < self.retry_policy.max_retries
+                && match &result {
+                    Ok(response) => RetryPolicy::is_retryable_status(response.status()),
+                    Err(err) => RetryPolicy::is_retryable_error(err),
+                };
+
+            if !retry {
+                return Ok(result?);
+            }
+
+            tokio::time::sleep(self.retry_policy.backoff(attempt)).await;
+            attempt += 1;
+        }
     }
 
     async fn parse_err(response: Response) -> TicketsResult<TicketsError> {
         let value = response.json::<NetworkError>().await?;
-        Ok(TicketsError::Network(value))
+        Ok(value.into())
     }
 
     async fn parse<B: for<'de> Deserialize<'de>>(response: Response) -> TicketsResult<B> {
@@ -402,8 +840,7 @@ impl SdkExecutor for SignedTicketClient {
         path: S,
         query_params: Q,
     ) -> TicketsResult<T> {
-        let prep: RequestBuilder = self.prepare_request(method, path, query_params).await?;
-        let send = prep.send().await?;
+        let send = self.send(method, path, None, query_params).await?;
         Self::parse(send).await
     }
 
@@ -419,10 +856,9 @@ impl SdkExecutor for SignedTicketClient {
         body: B,
         query_params: Q,
     ) -> TicketsResult<T> {
-        let prep: RequestBuilder = self
-            .prepare_request_with_body(method, path, body, query_params)
+        let send = self
+            .send(method, path, Some(EncodedBody::json(&body)?), query_params)
             .await?;
-        let send = prep.send().await?;
         Self::parse(send).await
     }
 
@@ -432,8 +868,7 @@ impl SdkExecutor for SignedTicketClient {
         path: S,
         query_params: Q,
     ) -> TicketsResult<reqwest::StatusCode> {
-        let prep: RequestBuilder = self.prepare_request(method, path, query_params).await?;
-        let send = prep.send().await?;
+        let send = self.send(method, path, None, query_params).await?;
         Self::dispose(send).await
     }
 
@@ -444,10 +879,54 @@ impl SdkExecutor for SignedTicketClient {
         body: B,
         query_params: Q,
     ) -> TicketsResult<reqwest::StatusCode> {
-        let prep: RequestBuilder = self
-            .prepare_request_with_body(method, path, body, query_params)
+        let send = self
+            .send(method, path, Some(EncodedBody::json(&body)?), query_params)
             .await?;
-        let send = prep.send().await?;
         Self::dispose(send).await
     }
+
+    async fn upload<T: for<'de> Deserialize<'de>, S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        file: FileData,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        let send = self
+            .send(
+                method,
+                path,
+                Some(EncodedBody::multipart(&file)),
+                query_params,
+            )
+            .await?;
+        Self::parse(send).await
+    }
+
+    async fn download<S: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: S,
+        query_params: Q,
+    ) -> TicketsResult<FileData> {
+        let send = self.send(method, path, None, query_params).await?;
+        if !send.status().is_success() {
+            return Err(Self::parse_err(send).await?);
+        }
+
+        let header = |name| {
+            send.headers()
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(CONTENT_TYPE);
+        let content_disposition = header(CONTENT_DISPOSITION);
+
+        Ok(FileData::from_download(
+            content_type.as_deref(),
+            content_disposition.as_deref(),
+            send.bytes().await?.to_vec(),
+        ))
+    }
 }
@@ -0,0 +1,290 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::Router;
+use http::header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE};
+use http::{HeaderMap, HeaderValue, Request, Response};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
+use errors::{NetworkError, ParsingError, TicketsError, TicketsResult};
+
+use super::{EncodedBody, SdkExecutor, SystemClock, TokenManager};
+use crate::routes::FileData;
+
+type AppFactory<S> = dyn Fn(S) -> Router + Send + Sync;
+
+struct MockApp<S> {
+    state: S,
+    router: Router,
+}
+
+/// [`SdkExecutor`] which dispatches calls straight into an axum [`Router`] in-process, so
+/// gateways and SDK users can be exercised end to end without a network listener.
+///
+/// The router is built from `S` by the factory, e.g. `collector::app`, and rebuilt whenever
+/// the state is swapped with [`MockSdkExecutor::replace_state`]. Only the network is left out:
+/// the collector's router still needs its PostgreSQL database, events and blobs are kept in
+/// memory and on disk by `GlobalState::in_process`.
+pub struct MockSdkExecutor<S> {
+    factory: Arc<AppFactory<S>>,
+    app: Arc<RwLock<MockApp<S>>>,
+    jwt: Arc<JwtConfig>,
+    tokens: Arc<TokenManager>,
+    headers: HeaderMap,
+}
+
+impl<S> Clone for MockSdkExecutor<S> {
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            app: self.app.clone(),
+            jwt: self.jwt.clone(),
+            tokens: self.tokens.clone(),
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+impl<S: Clone> MockSdkExecutor<S> {
+    pub fn new(
+        factory: impl Fn(S) -> Router + Send + Sync + 'static,
+        state: S,
+        jwt: Arc<JwtConfig>,
+        accessor: JwtAccessor,
+        gateway: &'static str,
+    ) -> Self {
+        let router = factory(state.clone());
+
+        let mut headers = HeaderMap::with_capacity(1);
+        headers.insert("x-gateway", HeaderValue::from_static(gateway));
+
+        Self {
+            factory: Arc::new(factory),
+            app: Arc::new(RwLock::new(MockApp { state, router })),
+            jwt: jwt.clone(),
+            tokens: Arc::new(TokenManager::new(
+                jwt,
+                JwtData { accessor },
+                super::InternalSdk::DEFAULT_TTL,
+                super::InternalSdk::DEFAULT_REFRESH_WINDOW,
+                Arc::new(SystemClock),
+            )),
+            headers,
+        }
+    }
+
+    /// Creates an executor sharing this one's app but calling as a different accessor.
+    pub fn sign_as(&self, accessor: JwtAccessor, ttl: Duration) -> Self {
+        Self {
+            tokens: Arc::new(TokenManager::new(
+                self.jwt.clone(),
+                JwtData { accessor },
+                ttl,
+                self.tokens.refresh_window(),
+                Arc::new(SystemClock),
+            )),
+            ..self.clone()
+        }
+    }
+
+    /// Creates an executor sending `value` as the header `name` with every call, such as a
+    /// fixed idempotency key.
+    pub fn with_header(&self, name: &'static str, value: HeaderValue) -> Self {
+        let mut headers = self.headers.clone();
+        headers.insert(name, value);
+
+        Self {
+            headers,
+            ..self.clone()
+        }
+    }
+
+    pub fn data(&self) -> &JwtData {
+        self.tokens.data()
+    }
+
+    pub async fn state(&self) -> S {
+        self.app.read().await.state.clone()
+    }
+
+    /// Swaps the state backing the router, returning the previous state.
+    pub async fn replace_state(&self, state: S) -> S {
+        let router = (self.factory)(state.clone());
+        let mut app = self.app.write().await;
+        app.router = router;
+        std::mem::replace(&mut app.state, state)
+    }
+
+    async fn dispatch<P: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: P,
+        body: Option<EncodedBody>,
+        query_params: Q,
+    ) -> TicketsResult<Response<Body>> {
+        let mut uri = path.into();
+        let query = serde_urlencoded::to_string(&query_params)
+            .map_err(|err| ParsingError::InvalidRequest(err.to_string()))?;
+        if !query.is_empty() {
+            uri.push('?');
+            uri.push_str(&query);
+        }
+
+        let mut request = Request::builder().method(method.as_str()).uri(uri).header(
+            AUTHORIZATION,
+            format!("Bearer {}", self.tokens.token().await?),
+        );
+
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let request = match body {
+            Some(body) => request
+                .header(CONTENT_TYPE, body.content_type)
+                .body(Body::from(body.bytes)),
+            None => request.body(Body::empty()),
+        }
+        .map_err(|err| ParsingError::InvalidRequest(err.to_string()))?;
+
+        let router = self.app.read().await.router.clone();
+        match router.oneshot(request).await {
+            Ok(response) => Ok(response),
+            Err(infallible) => match infallible {},
+        }
+    }
+
+    fn status(response: &Response<Body>) -> TicketsResult<StatusCode> {
+        StatusCode::from_u16(response.status().as_u16()).map_err(|err| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()).into()
+        })
+    }
+
+    async fn parse_err(response: Response<Body>) -> TicketsResult<TicketsError> {
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let value = serde_json::from_slice::<NetworkError>(&body)?;
+        Ok(value.into())
+    }
+
+    async fn parse<B: for<'de> Deserialize<'de>>(response: Response<Body>) -> TicketsResult<B> {
+        if response.status().is_success() {
+            let body = to_bytes(response.into_body(), usize::MAX).await?;
+            Ok(serde_json::from_slice(&body)?)
+        } else {
+            Err(Self::parse_err(response).await?)
+        }
+    }
+
+    async fn dispose(response: Response<Body>) -> TicketsResult<StatusCode> {
+        if response.status().is_success() {
+            Self::status(&response)
+        } else {
+            Err(Self::parse_err(response).await?)
+        }
+    }
+}
+
+impl<S: Clone> SdkExecutor for MockSdkExecutor<S> {
+    async fn call<T: for<'de> Deserialize<'de>, P: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: P,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        let response = self.dispatch(method, path, None, query_params).await?;
+        Self::parse(response).await
+    }
+
+    async fn call_with_body<
+        T: for<'de> Deserialize<'de>,
+        B: Serialize,
+        P: Into<String>,
+        Q: Serialize,
+    >(
+        &self,
+        method: Method,
+        path: P,
+        body: B,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        let response = self
+            .dispatch(method, path, Some(EncodedBody::json(&body)?), query_params)
+            .await?;
+        Self::parse(response).await
+    }
+
+    async fn invoke<P: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: P,
+        query_params: Q,
+    ) -> TicketsResult<StatusCode> {
+        let response = self.dispatch(method, path, None, query_params).await?;
+        Self::dispose(response).await
+    }
+
+    async fn invoke_with_body<B: Serialize, P: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: P,
+        body: B,
+        query_params: Q,
+    ) -> TicketsResult<StatusCode> {
+        let response = self
+            .dispatch(method, path, Some(EncodedBody::json(&body)?), query_params)
+            .await?;
+        Self::dispose(response).await
+    }
+
+    async fn upload<T: for<'de> Deserialize<'de>, P: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: P,
+        file: FileData,
+        query_params: Q,
+    ) -> TicketsResult<T> {
+        let response = self
+            .dispatch(
+                method,
+                path,
+                Some(EncodedBody::multipart(&file)),
+                query_params,
+            )
+            .await?;
+        Self::parse(response).await
+    }
+
+    async fn download<P: Into<String>, Q: Serialize>(
+        &self,
+        method: Method,
+        path: P,
+        query_params: Q,
+    ) -> TicketsResult<FileData> {
+        let response = self.dispatch(method, path, None, query_params).await?;
+        if !response.status().is_success() {
+            return Err(Self::parse_err(response).await?);
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(CONTENT_TYPE);
+        let content_disposition = header(CONTENT_DISPOSITION);
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+        Ok(FileData::from_download(
+            content_type.as_deref(),
+            content_disposition.as_deref(),
+            bytes.to_vec(),
+        ))
+    }
+}
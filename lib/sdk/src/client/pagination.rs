@@ -0,0 +1,325 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use errors::{MiscError, TicketsResult};
+
+use super::{fill_route, MethodWrapper, SdkExecutor};
+use crate::routes::{Empty, Page, Paginated, SdkRoute};
+
+#[derive(Clone, Copy, Debug)]
+pub struct PageOptions {
+    /// Number of items requested per page.
+    pub page_size: u32,
+    /// Whether the next page is requested while the current one is still being consumed.
+    pub prefetch: bool,
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        Self {
+            page_size: Self::DEFAULT_PAGE_SIZE,
+            prefetch: true,
+        }
+    }
+}
+
+impl PageOptions {
+    pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+    pub fn with_page_size(self, page_size: u32) -> Self {
+        Self { page_size, ..self }
+    }
+
+    pub fn with_prefetch(self, prefetch: bool) -> Self {
+        Self { prefetch, ..self }
+    }
+}
+
+/// Stream of items across every page of a paginated route.
+///
+/// Pages are only requested while the stream is polled, dropping it or calling
+/// [`PageStream::cancel`] stops pagination. The stream ends after the first error.
+pub trait PageStream<T>: Stream<Item = TicketsResult<T>> + Unpin {
+    /// Drops any in-flight request and buffered items, ending the stream.
+    fn cancel(&mut self);
+}
+
+struct Paginator<F, Fut, T> {
+    fetch: F,
+    next: Option<Pin<Box<Fut>>>,
+    // a prefetched page, held until the buffer drains
+    ready: Option<TicketsResult<Page<T>>>,
+    buffer: VecDeque<T>,
+    prefetch: bool,
+}
+
+// futures are pinned on the heap and no other field is structurally pinned
+impl<F, Fut, T> Unpin for Paginator<F, Fut, T> {}
+
+impl<F, Fut, T> Paginator<F, Fut, T>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = TicketsResult<Page<T>>>,
+{
+    fn new(mut fetch: F, options: PageOptions) -> Self {
+        let first = Box::pin(fetch(None));
+        Self {
+            fetch,
+            next: Some(first),
+            ready: None,
+            buffer: VecDeque::new(),
+            prefetch: options.prefetch,
+        }
+    }
+
+    /// Drives the pending page request without waiting on it.
+    fn poll_prefetch(&mut self, cx: &mut Context<'_>) {
+        if self.ready.is_some() {
+            return;
+        }
+
+        if let Some(next) = &mut self.next {
+            if let Poll::Ready(page) = next.as_mut().poll(cx) {
+                self.next = None;
+                self.ready = Some(page);
+            }
+        }
+    }
+}
+
+impl<F, Fut, T> Stream for Paginator<F, Fut, T>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = TicketsResult<Page<T>>>,
+{
+    type Item = TicketsResult<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(item) = this.buffer.pop_front() {
+                if this.prefetch {
+                    this.poll_prefetch(cx);
+                }
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            let page = match this.ready.take() {
+                Some(page) => page,
+                None => match &mut this.next {
+                    Some(next) => match next.as_mut().poll(cx) {
+                        Poll::Ready(page) => {
+                            this.next = None;
+                            page
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    },
+                    None => return Poll::Ready(None),
+                },
+            };
+
+            match page {
+                Ok(page) => {
+                    this.buffer.extend(page.items);
+                    if let Some(cursor) = page.next_cursor {
+                        this.next = Some(Box::pin((this.fetch)(Some(cursor))));
+                    }
+                }
+                Err(err) => {
+                    this.cancel();
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+    }
+}
+
+impl<F, Fut, T> PageStream<T> for Paginator<F, Fut, T>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = TicketsResult<Page<T>>>,
+{
+    fn cancel(&mut self) {
+        self.next = None;
+        self.ready = None;
+        self.buffer.clear();
+    }
+}
+
+async fn collect_capped<T>(mut stream: impl PageStream<T>, cap: usize) -> TicketsResult<Vec<T>> {
+    let mut items = Vec::new();
+    while let Some(item) = stream.try_next().await? {
+        if items.len() == cap {
+            stream.cancel();
+            return Err(MiscError::PaginationCapExceeded { cap }.into());
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Streams the items of routes answering with a [`Page`], such as the app's tickets.
+pub trait SdkPaginate<Item, Query> {
+    /// Streams every item, each page requested with the filters of `query`.
+    fn paginate_with_query(
+        executor: &impl SdkExecutor,
+        query: Query,
+        options: PageOptions,
+    ) -> impl PageStream<Item>;
+
+    fn paginate(executor: &impl SdkExecutor, options: PageOptions) -> impl PageStream<Item>
+    where
+        Query: Default,
+    {
+        Self::paginate_with_query(executor, Query::default(), options)
+    }
+
+    /// Collects every item, failing once more than `cap` items are returned.
+    async fn collect_all(
+        executor: &impl SdkExecutor,
+        options: PageOptions,
+        cap: usize,
+    ) -> TicketsResult<Vec<Item>>
+    where
+        Query: Default,
+    {
+        collect_capped(Self::paginate(executor, options), cap).await
+    }
+
+    /// Collects every item matching `query`, failing once more than `cap` items are returned.
+    async fn collect_all_with_query(
+        executor: &impl SdkExecutor,
+        query: Query,
+        options: PageOptions,
+        cap: usize,
+    ) -> TicketsResult<Vec<Item>> {
+        collect_capped(Self::paginate_with_query(executor, query, options), cap).await
+    }
+}
+
+/// The request of every page: the query with its limit set to the page size and the cursor of
+/// the previous page.
+fn page_query<Query: Paginated + Clone>(
+    query: &Query,
+    cursor: Option<String>,
+    options: PageOptions,
+) -> Query {
+    let mut query = query.clone();
+    query.set_cursor(cursor);
+    query.set_limit(options.page_size);
+    query
+}
+
+impl<T, Item, Query> SdkPaginate<Item, Query> for T
+where
+    T: SdkRoute<Body = Empty, Response = Page<Item>, QueryParams = Query, PathParams = Empty>,
+    Item: for<'de> Deserialize<'de>,
+    Query: Paginated + Clone + Serialize,
+{
+    fn paginate_with_query(
+        executor: &impl SdkExecutor,
+        query: Query,
+        options: PageOptions,
+    ) -> impl PageStream<Item> {
+        Paginator::new(
+            move |cursor| {
+                executor.call(
+                    MethodWrapper(T::method()).into(),
+                    T::route(),
+                    page_query(&query, cursor, options),
+                )
+            },
+            options,
+        )
+    }
+}
+
+/// Streams the items of routes answering with a [`Page`] under a path, such as a customer's
+/// tickets.
+pub trait SdkPaginateWithPath<Item, PathParams, Query> {
+    /// Streams every item, each page requested with the filters of `query`.
+    fn paginate_with_path_and_query(
+        executor: &impl SdkExecutor,
+        path_params: PathParams,
+        query: Query,
+        options: PageOptions,
+    ) -> TicketsResult<impl PageStream<Item>>;
+
+    fn paginate_with_path(
+        executor: &impl SdkExecutor,
+        path_params: PathParams,
+        options: PageOptions,
+    ) -> TicketsResult<impl PageStream<Item>>
+    where
+        Query: Default,
+    {
+        Self::paginate_with_path_and_query(executor, path_params, Query::default(), options)
+    }
+
+    /// Collects every item, failing once more than `cap` items are returned.
+    async fn collect_all_with_path(
+        executor: &impl SdkExecutor,
+        path_params: PathParams,
+        options: PageOptions,
+        cap: usize,
+    ) -> TicketsResult<Vec<Item>>
+    where
+        Query: Default,
+    {
+        collect_capped(
+            Self::paginate_with_path(executor, path_params, options)?,
+            cap,
+        )
+        .await
+    }
+
+    /// Collects every item matching `query`, failing once more than `cap` items are returned.
+    async fn collect_all_with_path_and_query(
+        executor: &impl SdkExecutor,
+        path_params: PathParams,
+        query: Query,
+        options: PageOptions,
+        cap: usize,
+    ) -> TicketsResult<Vec<Item>> {
+        collect_capped(
+            Self::paginate_with_path_and_query(executor, path_params, query, options)?,
+            cap,
+        )
+        .await
+    }
+}
+
+impl<T, Item, PathParams, Query> SdkPaginateWithPath<Item, PathParams, Query> for T
+where
+    T: SdkRoute<Body = Empty, Response = Page<Item>, QueryParams = Query, PathParams = PathParams>,
+    Item: for<'de> Deserialize<'de>,
+    PathParams: Serialize,
+    Query: Paginated + Clone + Serialize,
+{
+    fn paginate_with_path_and_query(
+        executor: &impl SdkExecutor,
+        path_params: PathParams,
+        query: Query,
+        options: PageOptions,
+    ) -> TicketsResult<impl PageStream<Item>> {
+        // the path is filled once, only the cursor changes between pages
+        let path = fill_route(T::route(), &path_params)?;
+
+        Ok(Paginator::new(
+            move |cursor| {
+                executor.call(
+                    MethodWrapper(T::method()).into(),
+                    path.clone(),
+                    page_query(&query, cursor, options),
+                )
+            },
+            options,
+        ))
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{Method, StatusCode};
+
+/// Controls how [`super::SignedTicketClient`] retries requests that failed for
+/// transient reasons, such as the collector restarting.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, `0` disables retries.
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on every following attempt.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Attach an idempotency key to non-idempotent requests so they can be retried
+    /// without being applied twice.
+    pub idempotency_keys: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            idempotency_keys: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            idempotency_keys: false,
+            ..Default::default()
+        }
+    }
+
+    /// Exponential backoff with full jitter for the given zero-based retry attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        rand::thread_rng().gen_range(Duration::ZERO..=ceiling)
+    }
+
+    pub fn is_idempotent(method: &Method) -> bool {
+        matches!(
+            *method,
+            Method::GET
+                | Method::HEAD
+                | Method::PUT
+                | Method::DELETE
+                | Method::OPTIONS
+                | Method::TRACE
+        )
+    }
+
+    pub fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    pub fn is_retryable_error(err: &reqwest::Error) -> bool {
+        err.is_connect() || err.is_timeout()
+    }
+}
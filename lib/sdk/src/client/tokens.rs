@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, RwLock};
+
+use auth::jwt::{JwtConfig, JwtData};
+use errors::{AuthorizationError, TicketsResult};
+
+/// Source of the current time used to decide when tokens need refreshing.
+pub trait Clock: Send + Sync {
+    /// Current unix timestamp in seconds.
+    fn now(&self) -> i64;
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+}
+
+pub struct TokenClaim {
+    token: String,
+    issued_at: i64,
+    expiration: i64,
+}
+
+impl TokenClaim {
+    pub fn new(token: String, issued_at: i64, expiration: i64) -> Self {
+        Self {
+            token,
+            issued_at,
+            expiration,
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn issued_at(&self) -> i64 {
+        self.issued_at
+    }
+
+    pub fn expiration(&self) -> i64 {
+        self.expiration
+    }
+
+    /// Seconds between issuing and expiry.
+    pub fn lifetime(&self) -> i64 {
+        self.expiration - self.issued_at
+    }
+}
+
+/// Signs tokens for a single [`JwtData`] and reuses them until they enter the
+/// refresh window before expiry.
+pub struct TokenManager {
+    /// `None` when the manager holds a token issued by the collector, see [`Self::issued`].
+    jwt: Option<Arc<JwtConfig>>,
+    data: JwtData,
+    ttl: Duration,
+    refresh_window: Duration,
+    clock: Arc<dyn Clock>,
+    current: RwLock<Option<Arc<TokenClaim>>>,
+    // held while signing so concurrent callers wait for a single refresh
+    refresh_lock: Mutex<()>,
+}
+
+impl TokenManager {
+    pub fn new(
+        jwt: Arc<JwtConfig>,
+        data: JwtData,
+        ttl: Duration,
+        refresh_window: Duration,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            jwt: Some(jwt),
+            data,
+            ttl,
+            // a window as long as the ttl would refresh on every call
+            refresh_window: refresh_window.min(ttl / 2),
+            clock,
+            current: RwLock::new(None),
+            refresh_lock: Mutex::new(()),
+        }
+    }
+
+    /// Hands out `claim`, a token the collector issued for `data`, until it expires. Without
+    /// the private key no other token is signed, callers are asked to log in again instead.
+    pub fn issued(data: JwtData, claim: TokenClaim, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            jwt: None,
+            data,
+            ttl: Duration::from_secs(claim.lifetime().max(0) as u64),
+            refresh_window: Duration::ZERO,
+            clock,
+            current: RwLock::new(Some(Arc::new(claim))),
+            refresh_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn data(&self) -> &JwtData {
+        &self.data
+    }
+
+    pub fn refresh_window(&self) -> Duration {
+        self.refresh_window
+    }
+
+    /// Current unix timestamp according to the manager's clock.
+    pub fn now(&self) -> i64 {
+        self.clock.now()
+    }
+
+    /// Whether `claim` can still be used without entering the refresh window.
+    ///
+    /// The window shrinks to half the claim's lifetime, so claims living shorter than the
+    /// window, such as the collector's pre-authorized ones, are still used before refreshing.
+    pub fn is_fresh(&self, claim: &TokenClaim) -> bool {
+        let refresh_window = (self.refresh_window.as_secs() as i64).min(claim.lifetime() / 2);
+        claim.expiration - refresh_window > self.clock.now()
+    }
+
+    /// The claim currently in use, if one has been signed.
+    pub async fn current(&self) -> Option<Arc<TokenClaim>> {
+        self.current.read().await.clone()
+    }
+
+    /// Returns a token which is valid for at least the refresh window, signing
+    /// a new one if necessary.
+    pub async fn token(&self) -> TicketsResult<String> {
+        if let Some(claim) = self.fresh_claim().await {
+            return Ok(claim.token.clone());
+        }
+
+        let _refresh = self.refresh_lock.lock().await;
+
+        // another caller may have refreshed while we were waiting for the lock
+        if let Some(claim) = self.fresh_claim().await {
+            return Ok(claim.token.clone());
+        }
+
+        let claim = Arc::new(self.sign()?);
+        *self.current.write().await = Some(claim.clone());
+
+        Ok(claim.token.clone())
+    }
+
+    async fn fresh_claim(&self) -> Option<Arc<TokenClaim>> {
+        self.current
+            .read()
+            .await
+            .as_ref()
+            .filter(|claim| self.is_fresh(claim))
+            .cloned()
+    }
+
+    fn sign(&self) -> TicketsResult<TokenClaim> {
+        let Some(jwt) = &self.jwt else {
+            return Err(AuthorizationError::LoginExpired)?;
+        };
+
+        let (token, claims) = jwt.generate_at(self.data.clone(), self.clock.now(), self.ttl)?;
+        Ok(TokenClaim::new(token, claims.iat, claims.exp))
+    }
+}
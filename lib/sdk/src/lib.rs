@@ -3,7 +3,13 @@
 
 pub use http;
 
+/// Header carrying a client generated key which makes retried requests safe to replay.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
 #[cfg(feature = "client")]
 pub mod client;
 
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
 pub mod routes;
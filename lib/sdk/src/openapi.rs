@@ -0,0 +1,251 @@
+//! OpenAPI 3 document generated from the registered [`SdkRoute`]s.
+
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use errors::NetworkError;
+
+use crate::routes::{consumer, staff, Empty, FileData, SdkRoute};
+
+/// Resolves the schema of a route's associated type, [`Empty`] has none.
+pub trait OptionalSchema {
+    fn optional_schema(generator: &mut SchemaGenerator) -> Option<Schema>;
+
+    /// Fields of the type, used to describe path and query parameters.
+    fn optional_fields(generator: &mut SchemaGenerator) -> Vec<(String, Schema, bool)>;
+
+    /// Content of the type as a request or response body, JSON unless the type is transferred
+    /// as something else.
+    fn optional_content(generator: &mut SchemaGenerator, _request: bool) -> Option<Value> {
+        Self::optional_schema(generator).map(json_content)
+    }
+}
+
+impl OptionalSchema for Empty {
+    fn optional_schema(_: &mut SchemaGenerator) -> Option<Schema> {
+        None
+    }
+
+    fn optional_fields(_: &mut SchemaGenerator) -> Vec<(String, Schema, bool)> {
+        vec![]
+    }
+}
+
+impl OptionalSchema for FileData {
+    fn optional_schema(_: &mut SchemaGenerator) -> Option<Schema> {
+        Some(Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("binary".to_string()),
+            ..Default::default()
+        }))
+    }
+
+    fn optional_fields(_: &mut SchemaGenerator) -> Vec<(String, Schema, bool)> {
+        vec![]
+    }
+
+    fn optional_content(generator: &mut SchemaGenerator, request: bool) -> Option<Value> {
+        let schema = Self::optional_schema(generator)?;
+
+        Some(if request {
+            json!({
+                "multipart/form-data": {
+                    "schema": {
+                        "type": "object",
+                        "required": ["file"],
+                        "properties": { "file": schema },
+                    },
+                },
+            })
+        } else {
+            json!({ "application/octet-stream": { "schema": schema } })
+        })
+    }
+}
+
+impl<T: JsonSchema> OptionalSchema for T {
+    fn optional_schema(generator: &mut SchemaGenerator) -> Option<Schema> {
+        Some(generator.subschema_for::<T>())
+    }
+
+    fn optional_fields(generator: &mut SchemaGenerator) -> Vec<(String, Schema, bool)> {
+        let root = generator.root_schema_for::<T>();
+
+        root.schema
+            .object
+            .map(|object| {
+                object
+                    .properties
+                    .into_iter()
+                    .map(|(name, schema)| {
+                        let required = object.required.contains(&name);
+                        (name, schema, required)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn parameters<T: OptionalSchema>(generator: &mut SchemaGenerator, location: &str) -> Vec<Value> {
+    T::optional_fields(generator)
+        .into_iter()
+        .map(|(name, schema, required)| {
+            json!({
+                "name": name,
+                "in": location,
+                // path parameters are always required
+                "required": required || location == "path",
+                "schema": schema,
+            })
+        })
+        .collect()
+}
+
+fn json_content(schema: Schema) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn operation<R>(generator: &mut SchemaGenerator) -> Value
+where
+    R: SdkRoute,
+    R::Body: OptionalSchema,
+    R::Response: OptionalSchema,
+    R::QueryParams: OptionalSchema,
+    R::PathParams: OptionalSchema,
+{
+    let operation_id = std::any::type_name::<R>()
+        .rsplit("::")
+        .next()
+        .unwrap_or_default();
+
+    let mut parameters_list = vec![json!({ "$ref": "#/components/parameters/Gateway" })];
+    parameters_list.extend(parameters::<R::PathParams>(generator, "path"));
+    parameters_list.extend(parameters::<R::QueryParams>(generator, "query"));
+
+    let mut success = json!({ "description": "Success" });
+    if let Some(content) = R::Response::optional_content(generator, false) {
+        success["content"] = content;
+    }
+
+    let mut operation = json!({
+        "operationId": operation_id,
+        "parameters": parameters_list,
+        "responses": {
+            "200": success,
+            "default": {
+                "description": "Error",
+                "content": json_content(generator.subschema_for::<NetworkError>()),
+            },
+        },
+    });
+
+    if let Some(content) = R::Body::optional_content(generator, true) {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": content,
+        });
+    }
+
+    operation
+}
+
+macro_rules! registered_routes {
+    ($($route:ty),* $(,)?) => {
+        /// Generates the OpenAPI document describing every registered route.
+        pub fn document() -> Value {
+            let mut generator = SchemaSettings::openapi3().into_generator();
+            let mut paths = Map::new();
+
+            $(
+                let path = paths
+                    .entry(<$route as SdkRoute>::route())
+                    .or_insert_with(|| json!({}));
+                path[<$route as SdkRoute>::method().as_str().to_lowercase()] =
+                    operation::<$route>(&mut generator);
+            )*
+
+            json!({
+                "openapi": "3.0.3",
+                "info": {
+                    "title": "Tech Tickets Collector",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "paths": paths,
+                "components": {
+                    "schemas": generator.take_definitions(),
+                    "parameters": {
+                        "Gateway": {
+                            "name": "x-gateway",
+                            "in": "header",
+                            "required": true,
+                            "description": "The gateway the request originates from.",
+                            "schema": { "type": "string" },
+                        },
+                    },
+                    "securitySchemes": {
+                        "bearer": {
+                            "type": "http",
+                            "scheme": "bearer",
+                            "bearerFormat": "JWT",
+                        },
+                    },
+                },
+                "security": [{ "bearer": [] }],
+            })
+        }
+    };
+}
+
+registered_routes! {
+    consumer::SubmitTicket,
+    consumer::UploadAttachment,
+    consumer::DownloadAttachment,
+    consumer::SendCustomerMessage,
+    staff::Login,
+    staff::ToggleGateway,
+    staff::CreateApp,
+    staff::AuthorizeApps,
+    staff::CreateLoginCode,
+    staff::ExchangeLoginCode,
+    staff::GetProfile,
+    staff::UpdateProfile,
+    staff::LinkIdentity,
+    staff::UnlinkIdentity,
+    staff::GetCustomer,
+    staff::CustomerTickets,
+    staff::LinkCustomerIdentity,
+    staff::GetRateLimit,
+    staff::SetRateLimit,
+    staff::ListFilterRules,
+    staff::CreateFilterRule,
+    staff::DeleteFilterRule,
+    staff::QuarantinedTickets,
+    staff::ReviewQuarantinedTicket,
+    staff::BlockCustomer,
+    staff::UnblockCustomer,
+    staff::BlockedCustomers,
+    staff::UploadStaffAttachment,
+    staff::TicketAttachments,
+    staff::DownloadStaffAttachment,
+    staff::GetAttachmentLimits,
+    staff::SetAttachmentLimits,
+    staff::ReplyToTicket,
+    staff::AddTicketNote,
+    staff::SetTicketStatus,
+    staff::ListTickets,
+    staff::GetTicketThread,
+    staff::AssignTicket,
+    staff::TicketTranscript,
+    staff::ExportTickets,
+    staff::ExportMessages,
+    staff::ExportMembers,
+    staff::ImportTickets,
+    staff::ListWebhooks,
+    staff::CreateWebhook,
+    staff::DeleteWebhook,
+    staff::WebhookDeliveries,
+    staff::RedeliverWebhook,
+}
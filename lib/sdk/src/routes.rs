@@ -3,32 +3,157 @@ use http::Method;
 /// Marker struct which cannot be serialized or deserialized.
 pub struct Empty;
 
+/// Query parameters accepted by paginated routes.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PageQuery {
+    /// Opaque cursor returned by the previous page, omitted for the first page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub limit: u32,
+}
+
+/// Query parameters of a paginated route, every page is requested with the same filters and
+/// the cursor of the previous page.
+pub trait Paginated {
+    fn set_cursor(&mut self, cursor: Option<String>);
+
+    fn set_limit(&mut self, limit: u32);
+}
+
+impl Paginated for PageQuery {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+
+    fn set_limit(&mut self, limit: u32) {
+        self.limit = limit;
+    }
+}
+
+/// Response of paginated routes, further pages are requested with `next_cursor` until it is
+/// `None`.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// A file transferred by the attachment routes, uploaded as `multipart/form-data` with the
+/// content in its `file` field and downloaded as the raw response body.
+#[derive(Debug, Clone)]
+pub struct FileData {
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl FileData {
+    /// `Content-Disposition` of a download, the file name is percent-encoded as UTF-8.
+    pub fn content_disposition(&self) -> String {
+        let mut encoded = String::with_capacity(self.file_name.len());
+        for byte in self.file_name.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    encoded.push(byte as char)
+                }
+                _ => encoded.push_str(&format!("%{byte:02X}")),
+            }
+        }
+
+        format!("attachment; filename*=UTF-8''{encoded}")
+    }
+
+    /// Rebuilds a downloaded file from the response's `Content-Type` and
+    /// `Content-Disposition` headers.
+    pub fn from_download(
+        content_type: Option<&str>,
+        content_disposition: Option<&str>,
+        bytes: Vec<u8>,
+    ) -> Self {
+        let file_name = content_disposition
+            .and_then(|disposition| disposition.split_once("filename*=UTF-8''"))
+            .map(|(_, encoded)| percent_decode(encoded.split(';').next().unwrap_or_default()))
+            .unwrap_or_default();
+
+        Self {
+            file_name,
+            content_type: content_type
+                .unwrap_or("application/octet-stream")
+                .to_string(),
+            bytes,
+        }
+    }
+}
+
+fn percent_decode(encoded: &str) -> String {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut bytes = encoded.bytes();
+
+    while let Some(byte) = bytes.next() {
+        let escaped = (byte == b'%')
+            .then(|| {
+                let hex = [bytes.clone().next()?, bytes.clone().nth(1)?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()
+            })
+            .flatten();
+
+        match escaped {
+            Some(escaped) => {
+                decoded.push(escaped);
+                bytes.nth(1);
+            }
+            None => decoded.push(byte),
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 pub trait SdkRoute {
     type Body = Empty;
     type Response = Empty;
     type QueryParams = Empty;
+    type PathParams = Empty;
 
+    /// Path template of the route, segments written as `{name}` are filled from the
+    /// field of the same name in [`SdkRoute::PathParams`].
     fn route() -> &'static str;
 
     fn method() -> Method;
 }
 
 pub mod consumer {
-    use super::SdkRoute;
+    use super::staff::TicketPath;
+    use super::{FileData, SdkRoute};
     use http::Method;
     use uuid::Uuid;
 
     pub struct SubmitTicket;
 
     #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
     pub struct SubmitTicketBody {
         pub app_id: Uuid,
         pub message: String,
+        pub submitter: Submitter,
+    }
+
+    /// The customer submitting a ticket, identified on the calling gateway (`x-gateway`).
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct Submitter {
+        /// The customer's id on the gateway, such as a Discord user id or an email address.
+        pub external_id: String,
+        pub display_name: Option<String>,
     }
 
     #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
     pub struct SubmitTicketResponse {
         pub ticket_id: Uuid,
+        pub customer_id: Uuid,
     }
 
     impl SdkRoute for SubmitTicket {
@@ -43,14 +168,126 @@ pub mod consumer {
             Method::POST
         }
     }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct Attachment {
+        pub attachment_id: Uuid,
+        pub ticket_id: Uuid,
+        pub file_name: String,
+        pub content_type: String,
+        pub size_bytes: u64,
+        /// Set when the customer uploaded the file.
+        pub customer_id: Option<Uuid>,
+        /// Set when a staff member uploaded the file.
+        pub uploaded_by: Option<u64>,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AttachmentPath {
+        pub app_id: Uuid,
+        pub attachment_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AttachmentUploader {
+        /// The uploader's id on the gateway, see [`Submitter::external_id`].
+        pub external_id: String,
+    }
+
+    /// Attaches a file to a ticket on behalf of a customer, such as one posted to the ticket
+    /// on the gateway.
+    pub struct UploadAttachment;
+
+    impl SdkRoute for UploadAttachment {
+        type Body = FileData;
+        type Response = Attachment;
+        type QueryParams = AttachmentUploader;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/consumer/apps/{app_id}/tickets/{ticket_id}/attachments"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// Fetches an attachment of the app, such as one uploaded by staff to relay it back to
+    /// the customer. Only callable by gateways enabled for the app.
+    pub struct DownloadAttachment;
+
+    impl SdkRoute for DownloadAttachment {
+        type Response = FileData;
+        type PathParams = AttachmentPath;
+
+        fn route() -> &'static str {
+            "/consumer/apps/{app_id}/attachments/{attachment_id}"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// A message in a ticket's conversation, written by either its customer or staff.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketMessage {
+        pub message_id: Uuid,
+        pub ticket_id: Uuid,
+        pub body: String,
+        /// Set when the customer wrote the message.
+        pub customer_id: Option<Uuid>,
+        /// Set when a staff member wrote the message.
+        pub author_id: Option<u64>,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct CustomerMessageBody {
+        /// The author's id on the gateway, see [`Submitter::external_id`].
+        pub external_id: String,
+        pub body: String,
+    }
+
+    /// Adds a message to a ticket on behalf of its customer, such as a follow-up they wrote
+    /// on the gateway.
+    pub struct SendCustomerMessage;
+
+    impl SdkRoute for SendCustomerMessage {
+        type Body = CustomerMessageBody;
+        type Response = TicketMessage;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/consumer/apps/{app_id}/tickets/{ticket_id}/messages"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
 }
 
 pub mod staff {
-    use super::SdkRoute;
+    use super::consumer::{Attachment, AttachmentPath, Submitter, TicketMessage};
+    use super::{FileData, Page, PageQuery, Paginated, SdkRoute};
     use auth::UserRole;
+    use errors::ParsingError;
     use http::Method;
+    use std::collections::HashSet;
+    use std::fmt::Display;
     use uuid::Uuid;
 
+    /// Users are provisioned on their first authenticated request, calling this is optional.
     pub struct Login;
 
     impl SdkRoute for Login {
@@ -66,6 +303,7 @@ pub mod staff {
     pub struct PromoteStaff;
 
     #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
     pub struct PromoteStaffRequest {
         staff_user_id: u64,
         role: UserRole,
@@ -74,12 +312,19 @@ pub mod staff {
     pub struct ToggleGateway;
 
     #[derive(serde::Serialize, serde::Deserialize, Debug)]
-    pub struct ToggleGatewayBody {
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AppPath {
         pub app_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ToggleGatewayBody {
         pub enabled: bool,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
     pub struct ToggleGatewayResponse {
         pub gateway: String,
         pub enabled: bool,
@@ -88,9 +333,10 @@ pub mod staff {
     impl SdkRoute for ToggleGateway {
         type Body = ToggleGatewayBody;
         type Response = ToggleGatewayResponse;
+        type PathParams = AppPath;
 
         fn route() -> &'static str {
-            "/staff/toggle_gateway"
+            "/staff/apps/{app_id}/toggle_gateway"
         }
 
         fn method() -> Method {
@@ -101,11 +347,13 @@ pub mod staff {
     pub struct CreateApp;
 
     #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
     pub struct CreateAppBody {
         pub app_name: String,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
     pub struct CreateAppResponse {
         pub app_id: Uuid,
     }
@@ -122,4 +370,1237 @@ pub mod staff {
             Method::POST
         }
     }
+
+    pub struct AuthorizeApps;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AuthorizeAppsBody {
+        pub app_ids: HashSet<Uuid>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AuthorizeAppsResponse {
+        pub token: String,
+        pub expiration: i64,
+        pub role: UserRole,
+    }
+
+    impl SdkRoute for AuthorizeApps {
+        type Body = AuthorizeAppsBody;
+        type Response = AuthorizeAppsResponse;
+
+        fn route() -> &'static str {
+            "/staff/authorize_apps"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// A single use code the staff member exchanges through [`ExchangeLoginCode`] where no
+    /// gateway signs tokens for them, such as `tt login`.
+    pub struct CreateLoginCode;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct LoginCodeResponse {
+        pub code: String,
+        /// Unix timestamp in seconds.
+        pub expiration: i64,
+    }
+
+    impl SdkRoute for CreateLoginCode {
+        type Response = LoginCodeResponse;
+
+        fn route() -> &'static str {
+            "/staff/login_codes"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// Issues a token for the staff member who created the code, called without a token.
+    pub struct ExchangeLoginCode;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ExchangeLoginCodeBody {
+        pub code: String,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ExchangeLoginCodeResponse {
+        pub user_id: u64,
+        pub token: String,
+        /// Unix timestamp in seconds.
+        pub expiration: i64,
+    }
+
+    impl SdkRoute for ExchangeLoginCode {
+        type Body = ExchangeLoginCodeBody;
+        type Response = ExchangeLoginCodeResponse;
+
+        fn route() -> &'static str {
+            "/staff/login_codes/exchange"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct UserIdentity {
+        pub gateway: String,
+        pub external_id: String,
+        pub display_name: Option<String>,
+        pub avatar_url: Option<String>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct UserProfile {
+        pub user_id: u64,
+        pub display_name: Option<String>,
+        pub avatar_url: Option<String>,
+        pub identities: Vec<UserIdentity>,
+    }
+
+    pub struct GetProfile;
+
+    impl SdkRoute for GetProfile {
+        type Response = UserProfile;
+
+        fn route() -> &'static str {
+            "/staff/profile"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    pub struct UpdateProfile;
+
+    /// Fields left as `None` keep their current value.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct UpdateProfileBody {
+        pub display_name: Option<String>,
+        pub avatar_url: Option<String>,
+    }
+
+    impl SdkRoute for UpdateProfile {
+        type Body = UpdateProfileBody;
+        type Response = UserProfile;
+
+        fn route() -> &'static str {
+            "/staff/profile"
+        }
+
+        fn method() -> Method {
+            Method::PATCH
+        }
+    }
+
+    /// Links an identity on the calling gateway (`x-gateway`) to the caller's user.
+    pub struct LinkIdentity;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct LinkIdentityBody {
+        pub external_id: String,
+        pub display_name: Option<String>,
+        pub avatar_url: Option<String>,
+    }
+
+    impl SdkRoute for LinkIdentity {
+        type Body = LinkIdentityBody;
+        type Response = UserIdentity;
+
+        fn route() -> &'static str {
+            "/staff/identities"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    pub struct UnlinkIdentity;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct IdentityPath {
+        pub gateway: String,
+        pub external_id: String,
+    }
+
+    impl SdkRoute for UnlinkIdentity {
+        type PathParams = IdentityPath;
+
+        fn route() -> &'static str {
+            "/staff/identities/{gateway}/{external_id}"
+        }
+
+        fn method() -> Method {
+            Method::DELETE
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct CustomerPath {
+        pub app_id: Uuid,
+        pub customer_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct CustomerIdentity {
+        pub gateway: String,
+        pub external_id: String,
+        pub display_name: Option<String>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct Customer {
+        pub id: Uuid,
+        pub display_name: Option<String>,
+        pub identities: Vec<CustomerIdentity>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketSummary {
+        pub ticket_id: Uuid,
+        pub gateway: String,
+        pub message: String,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    /// Customers are visible to staff of any app they submitted a ticket to.
+    pub struct GetCustomer;
+
+    impl SdkRoute for GetCustomer {
+        type Response = Customer;
+        type PathParams = CustomerPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/customers/{customer_id}"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// The customer's tickets in the app across every gateway, newest first.
+    pub struct CustomerTickets;
+
+    impl SdkRoute for CustomerTickets {
+        type Response = Page<TicketSummary>;
+        type QueryParams = PageQuery;
+        type PathParams = CustomerPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/customers/{customer_id}/tickets"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// Links another gateway identity to the customer, merging their ticket history.
+    pub struct LinkCustomerIdentity;
+
+    impl SdkRoute for LinkCustomerIdentity {
+        type Body = CustomerIdentity;
+        type Response = Customer;
+        type PathParams = CustomerPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/customers/{customer_id}/identities"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// How many tickets a single submitter may open in an app per window. All submitters of one
+    /// gateway together may open twenty times as many.
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct RateLimit {
+        pub max_tickets: u32,
+        pub window_seconds: u32,
+    }
+
+    pub struct GetRateLimit;
+
+    impl SdkRoute for GetRateLimit {
+        type Response = RateLimit;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/rate_limit"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    pub struct SetRateLimit;
+
+    impl SdkRoute for SetRateLimit {
+        type Body = RateLimit;
+        type Response = RateLimit;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/rate_limit"
+        }
+
+        fn method() -> Method {
+            Method::PUT
+        }
+    }
+
+    /// What happens to a ticket matched by a filter rule, the most severe matching action wins.
+    #[derive(
+        serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+    )]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "snake_case")]
+    pub enum FilterAction {
+        /// The ticket is created and published, but marked for staff attention.
+        Flag,
+        /// The ticket is created but withheld from staff until it is reviewed.
+        Quarantine,
+        /// The submission is refused.
+        Reject,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    pub enum FilterRuleKind {
+        /// Matches a message the same customer already submitted within the window.
+        Duplicate {
+            window_seconds: u32,
+        },
+        /// Matches messages containing the word or phrase, ignoring case.
+        BannedWord {
+            word: String,
+        },
+        Regex {
+            pattern: String,
+        },
+        /// Matches messages containing more than `max_links` links.
+        LinkLimit {
+            max_links: u32,
+        },
+        BlockedSubmitter {
+            gateway: String,
+            external_id: String,
+        },
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct FilterRule {
+        #[serde(flatten)]
+        pub kind: FilterRuleKind,
+        pub action: FilterAction,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct StoredFilterRule {
+        pub id: Uuid,
+        #[serde(flatten)]
+        pub rule: FilterRule,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct FilterRulePath {
+        pub app_id: Uuid,
+        pub rule_id: Uuid,
+    }
+
+    pub struct ListFilterRules;
+
+    impl SdkRoute for ListFilterRules {
+        type Response = Vec<StoredFilterRule>;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/filters"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    pub struct CreateFilterRule;
+
+    impl SdkRoute for CreateFilterRule {
+        type Body = FilterRule;
+        type Response = StoredFilterRule;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/filters"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    pub struct DeleteFilterRule;
+
+    impl SdkRoute for DeleteFilterRule {
+        type PathParams = FilterRulePath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/filters/{rule_id}"
+        }
+
+        fn method() -> Method {
+            Method::DELETE
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct QuarantinedTicket {
+        pub ticket_id: Uuid,
+        pub gateway: String,
+        pub customer_id: Option<Uuid>,
+        pub message: String,
+        /// Why the filters withheld the ticket.
+        pub reason: Option<String>,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    /// Tickets withheld by the app's filters and awaiting review, oldest first.
+    pub struct QuarantinedTickets;
+
+    impl SdkRoute for QuarantinedTickets {
+        type Response = Page<QuarantinedTicket>;
+        type QueryParams = PageQuery;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/quarantine"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketPath {
+        pub app_id: Uuid,
+        pub ticket_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct QuarantineReview {
+        /// Approved tickets are released to staff, anything else is rejected for good.
+        pub approve: bool,
+    }
+
+    pub struct ReviewQuarantinedTicket;
+
+    impl SdkRoute for ReviewQuarantinedTicket {
+        type Body = QuarantineReview;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/quarantine/{ticket_id}"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// Blocks the customer behind a gateway identity from the app on every gateway.
+    pub struct BlockCustomer;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct BlockCustomerBody {
+        pub gateway: String,
+        pub external_id: String,
+        pub reason: Option<String>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct BlockedCustomer {
+        pub customer_id: Uuid,
+        pub display_name: Option<String>,
+        pub reason: Option<String>,
+        /// The staff member who blocked the customer.
+        pub blocked_by: u64,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    impl SdkRoute for BlockCustomer {
+        type Body = BlockCustomerBody;
+        type Response = BlockedCustomer;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/blocks"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    pub struct UnblockCustomer;
+
+    impl SdkRoute for UnblockCustomer {
+        type PathParams = CustomerPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/blocks/{customer_id}"
+        }
+
+        fn method() -> Method {
+            Method::DELETE
+        }
+    }
+
+    /// Customers blocked from the app, most recently blocked first.
+    pub struct BlockedCustomers;
+
+    impl SdkRoute for BlockedCustomers {
+        type Response = Page<BlockedCustomer>;
+        type QueryParams = PageQuery;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/blocks"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// Attaches a file to a ticket as the calling staff member.
+    pub struct UploadStaffAttachment;
+
+    impl SdkRoute for UploadStaffAttachment {
+        type Body = FileData;
+        type Response = Attachment;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/attachments"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// Attachments of a ticket, oldest first.
+    pub struct TicketAttachments;
+
+    impl SdkRoute for TicketAttachments {
+        type Response = Vec<Attachment>;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/attachments"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    pub struct DownloadStaffAttachment;
+
+    impl SdkRoute for DownloadStaffAttachment {
+        type Response = FileData;
+        type PathParams = AttachmentPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/attachments/{attachment_id}"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AttachmentLimits {
+        pub max_size_bytes: u64,
+        /// MIME types accepted for upload, `image/*` accepts every image type.
+        pub allowed_types: Vec<String>,
+    }
+
+    pub struct GetAttachmentLimits;
+
+    impl SdkRoute for GetAttachmentLimits {
+        type Response = AttachmentLimits;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/attachment_limits"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    pub struct SetAttachmentLimits;
+
+    impl SdkRoute for SetAttachmentLimits {
+        type Body = AttachmentLimits;
+        type Response = AttachmentLimits;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/attachment_limits"
+        }
+
+        fn method() -> Method {
+            Method::PUT
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "snake_case")]
+    pub enum TicketStatus {
+        Open,
+        /// Closed tickets accept no further messages until they are reopened.
+        Closed,
+    }
+
+    impl Display for TicketStatus {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                TicketStatus::Open => write!(f, "open"),
+                TicketStatus::Closed => write!(f, "closed"),
+            }
+        }
+    }
+
+    impl TryFrom<String> for TicketStatus {
+        type Error = ParsingError;
+
+        fn try_from(status: String) -> Result<Self, Self::Error> {
+            Ok(match status.as_str() {
+                "open" => TicketStatus::Open,
+                "closed" => TicketStatus::Closed,
+                _ => Err(ParsingError::InvalidRequest(format!(
+                    "`{status}` is not a ticket status."
+                )))?,
+            })
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketMessageBody {
+        pub body: String,
+    }
+
+    /// Replies to the ticket's customer as the calling staff member.
+    pub struct ReplyToTicket;
+
+    impl SdkRoute for ReplyToTicket {
+        type Body = TicketMessageBody;
+        type Response = TicketMessage;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/messages"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// A remark on a ticket only visible to staff.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketNote {
+        pub note_id: Uuid,
+        pub ticket_id: Uuid,
+        pub body: String,
+        pub author_id: u64,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    pub struct AddTicketNote;
+
+    impl SdkRoute for AddTicketNote {
+        type Body = TicketMessageBody;
+        type Response = TicketNote;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/notes"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct SetTicketStatusBody {
+        pub status: TicketStatus,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketStatusChange {
+        pub ticket_id: Uuid,
+        pub from_status: TicketStatus,
+        pub to_status: TicketStatus,
+        /// The staff member who changed the status.
+        pub changed_by: u64,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    /// Opens or closes a ticket, setting the status it already has is refused.
+    pub struct SetTicketStatus;
+
+    impl SdkRoute for SetTicketStatus {
+        type Body = SetTicketStatusBody;
+        type Response = TicketStatusChange;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/status"
+        }
+
+        fn method() -> Method {
+            Method::PUT
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketsQuery {
+        /// Opaque cursor returned by the previous page, omitted for the first page.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub cursor: Option<String>,
+        pub limit: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub status: Option<TicketStatus>,
+        /// Only tickets assigned to this staff member.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub assignee_id: Option<u64>,
+        /// Case-insensitive text the ticket's message or one of its replies contains.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub search: Option<String>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketOverview {
+        pub ticket_id: Uuid,
+        pub gateway: String,
+        pub message: String,
+        pub status: TicketStatus,
+        pub customer_id: Option<Uuid>,
+        /// The staff member handling the ticket.
+        pub assignee_id: Option<u64>,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    impl Paginated for TicketsQuery {
+        fn set_cursor(&mut self, cursor: Option<String>) {
+            self.cursor = cursor;
+        }
+
+        fn set_limit(&mut self, limit: u32) {
+            self.limit = limit;
+        }
+    }
+
+    /// The app's tickets, newest first. Tickets withheld by the filters are left out, they
+    /// are reviewed through [`QuarantinedTickets`].
+    pub struct ListTickets;
+
+    impl SdkRoute for ListTickets {
+        type Response = Page<TicketOverview>;
+        type QueryParams = TicketsQuery;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// A ticket with its whole conversation, each list oldest first.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TicketThread {
+        pub ticket: TicketOverview,
+        pub messages: Vec<TicketMessage>,
+        pub notes: Vec<TicketNote>,
+        pub status_changes: Vec<TicketStatusChange>,
+    }
+
+    pub struct GetTicketThread;
+
+    impl SdkRoute for GetTicketThread {
+        type Response = TicketThread;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct AssignTicketBody {
+        /// A staff member of the app, `None` unassigns the ticket.
+        pub assignee_id: Option<u64>,
+    }
+
+    /// Hands the ticket to a staff member of the app.
+    pub struct AssignTicket;
+
+    impl SdkRoute for AssignTicket {
+        type Body = AssignTicketBody;
+        type Response = TicketOverview;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/assignee"
+        }
+
+        fn method() -> Method {
+            Method::PUT
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "snake_case")]
+    pub enum TranscriptFormat {
+        Html,
+        Markdown,
+        Json,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct TranscriptQuery {
+        pub format: TranscriptFormat,
+        /// Staff notes are left out unless requested.
+        #[serde(default)]
+        pub include_notes: bool,
+    }
+
+    /// The complete record of a ticket, its messages, attachments and status changes,
+    /// rendered as a downloadable file. Gateways enabled for the app may fetch those of the
+    /// tickets submitted through them too, such as to archive the ticket when it closes.
+    pub struct TicketTranscript;
+
+    impl SdkRoute for TicketTranscript {
+        type Response = FileData;
+        type QueryParams = TranscriptQuery;
+        type PathParams = TicketPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/tickets/{ticket_id}/transcript"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "snake_case")]
+    pub enum ExportFormat {
+        /// Comma separated values with a header row.
+        Csv,
+        /// One JSON object per line.
+        Jsonl,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ExportQuery {
+        pub format: ExportFormat,
+        /// Unix timestamp in seconds, rows created before it are left out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub from: Option<i64>,
+        /// Unix timestamp in seconds, rows created at or after it are left out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub to: Option<i64>,
+    }
+
+    /// A row of the ticket export.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ExportedTicket {
+        pub ticket_id: Uuid,
+        pub gateway: String,
+        pub status: TicketStatus,
+        pub customer_id: Option<Uuid>,
+        pub message: String,
+        pub filter_status: Option<String>,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    /// A row of the message export, see [`TicketMessage`].
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ExportedMessage {
+        pub message_id: Uuid,
+        pub ticket_id: Uuid,
+        pub customer_id: Option<Uuid>,
+        pub author_id: Option<u64>,
+        pub body: String,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    /// A row of the member export.
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ExportedMember {
+        pub user_id: u64,
+        pub role: UserRole,
+        pub display_name: Option<String>,
+        /// When the user joined the app, unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    /// Every ticket of the app created within the query's range, oldest first.
+    pub struct ExportTickets;
+
+    impl SdkRoute for ExportTickets {
+        type Response = FileData;
+        type QueryParams = ExportQuery;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/export/tickets"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// Every message on the app's tickets written within the query's range, oldest first.
+    pub struct ExportMessages;
+
+    impl SdkRoute for ExportMessages {
+        type Response = FileData;
+        type QueryParams = ExportQuery;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/export/messages"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// Every member of the app who joined within the query's range.
+    pub struct ExportMembers;
+
+    impl SdkRoute for ExportMembers {
+        type Response = FileData;
+        type QueryParams = ExportQuery;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/export/members"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// A ticket carried over from another system, imported as it was rather than submitted.
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ImportedTicket {
+        /// Importing a ticket id again updates the imported ticket, so failed imports can be
+        /// retried. Tickets submitted through a gateway are never replaced.
+        pub ticket_id: Uuid,
+        pub message: String,
+        /// The gateway the ticket was originally submitted through.
+        pub gateway: String,
+        /// Identifies the customer on `gateway`, tickets without one have no customer.
+        pub submitter: Option<Submitter>,
+        pub status: TicketStatus,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ImportTicketsBody {
+        pub tickets: Vec<ImportedTicket>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct ImportTicketsResponse {
+        pub created: u32,
+        pub updated: u32,
+    }
+
+    /// Upserts a batch of historical tickets, the whole batch is refused if any ticket is
+    /// invalid. Imported tickets skip the app's filters and limits and are not published.
+    pub struct ImportTickets;
+
+    impl SdkRoute for ImportTickets {
+        type Body = ImportTicketsBody;
+        type Response = ImportTicketsResponse;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/import/tickets"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct Webhook {
+        pub webhook_id: Uuid,
+        pub url: String,
+        /// Event types delivered to the endpoint, every event is delivered when empty.
+        pub event_types: Vec<String>,
+        pub created_by: u64,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct CreateWebhookBody {
+        /// An `https` url of a public host. Loopback hosts, also over plain `http`, are only
+        /// accepted by collectors configured for local development.
+        pub url: String,
+        #[serde(default)]
+        pub event_types: Vec<String>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct CreatedWebhook {
+        #[serde(flatten)]
+        pub webhook: Webhook,
+        /// Signs every delivery to the endpoint, it is only returned once.
+        pub secret: String,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct WebhookPath {
+        pub app_id: Uuid,
+        pub webhook_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct WebhookDeliveryPath {
+        pub app_id: Uuid,
+        pub webhook_id: Uuid,
+        pub delivery_id: Uuid,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "snake_case")]
+    pub enum WebhookDeliveryStatus {
+        /// Waiting for its first or next attempt.
+        Pending,
+        Delivered,
+        /// Every attempt failed, dead deliveries are only retried when redelivered.
+        Dead,
+    }
+
+    impl Display for WebhookDeliveryStatus {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                WebhookDeliveryStatus::Pending => write!(f, "pending"),
+                WebhookDeliveryStatus::Delivered => write!(f, "delivered"),
+                WebhookDeliveryStatus::Dead => write!(f, "dead"),
+            }
+        }
+    }
+
+    impl TryFrom<String> for WebhookDeliveryStatus {
+        type Error = ParsingError;
+
+        fn try_from(status: String) -> Result<Self, Self::Error> {
+            Ok(match status.as_str() {
+                "pending" => WebhookDeliveryStatus::Pending,
+                "delivered" => WebhookDeliveryStatus::Delivered,
+                "dead" => WebhookDeliveryStatus::Dead,
+                _ => Err(ParsingError::InvalidRequest(format!(
+                    "`{status}` is not a webhook delivery status."
+                )))?,
+            })
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+    pub struct WebhookDelivery {
+        pub delivery_id: Uuid,
+        pub webhook_id: Uuid,
+        pub event_type: String,
+        pub status: WebhookDeliveryStatus,
+        pub attempts: u32,
+        /// Status code of the latest attempt, if the endpoint responded.
+        pub last_status_code: Option<u16>,
+        /// Why the latest attempt failed.
+        pub last_error: Option<String>,
+        /// When a pending delivery is attempted next, unix timestamp in seconds.
+        pub next_attempt_at: Option<i64>,
+        /// Unix timestamp in seconds.
+        pub created_at: i64,
+        pub delivered_at: Option<i64>,
+    }
+
+    pub struct ListWebhooks;
+
+    impl SdkRoute for ListWebhooks {
+        type Response = Vec<Webhook>;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/webhooks"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// Registers an endpoint receiving the app's events as signed `POST` requests.
+    pub struct CreateWebhook;
+
+    impl SdkRoute for CreateWebhook {
+        type Body = CreateWebhookBody;
+        type Response = CreatedWebhook;
+        type PathParams = AppPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/webhooks"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
+
+    /// Removes the endpoint along with its delivery log.
+    pub struct DeleteWebhook;
+
+    impl SdkRoute for DeleteWebhook {
+        type PathParams = WebhookPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/webhooks/{webhook_id}"
+        }
+
+        fn method() -> Method {
+            Method::DELETE
+        }
+    }
+
+    /// The endpoint's deliveries, newest first.
+    pub struct WebhookDeliveries;
+
+    impl SdkRoute for WebhookDeliveries {
+        type Response = Page<WebhookDelivery>;
+        type QueryParams = PageQuery;
+        type PathParams = WebhookPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/webhooks/{webhook_id}/deliveries"
+        }
+
+        fn method() -> Method {
+            Method::GET
+        }
+    }
+
+    /// Queues the delivery for another round of attempts, such as a dead delivery once the
+    /// endpoint is fixed.
+    pub struct RedeliverWebhook;
+
+    impl SdkRoute for RedeliverWebhook {
+        type Response = WebhookDelivery;
+        type PathParams = WebhookDeliveryPath;
+
+        fn route() -> &'static str {
+            "/staff/apps/{app_id}/webhooks/{webhook_id}/deliveries/{delivery_id}/redeliver"
+        }
+
+        fn method() -> Method {
+            Method::POST
+        }
+    }
 }
@@ -0,0 +1,23 @@
+//! Guards the committed `openapi.json` against drifting from the route definitions.
+//!
+//! Run with `UPDATE_OPENAPI=1` to regenerate the committed document.
+
+use std::path::PathBuf;
+
+#[test]
+fn openapi_document_is_up_to_date() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+    let generated = serde_json::to_string_pretty(&sdk::openapi::document()).unwrap() + "\n";
+
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+        return;
+    }
+
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+
+    assert!(
+        committed == generated,
+        "lib/sdk/openapi.json is out of date, re-run this test with UPDATE_OPENAPI=1"
+    );
+}
@@ -0,0 +1,201 @@
+//! Streaming the pages of paginated routes, served by a router counting the pages requested.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::{StreamExt, TryStreamExt};
+use http::Method;
+
+use auth::jwt::{JwtAccessor, JwtConfig};
+use errors::{MiscError, TicketsError};
+use sdk::client::{MockSdkExecutor, PageOptions, PageStream, SdkPaginate, SdkPaginateWithPath};
+use sdk::routes::{Page, PageQuery, Paginated, SdkRoute};
+
+const COUNT: u32 = 25;
+
+type Executor = MockSdkExecutor<Arc<AtomicUsize>>;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+struct NumbersQuery {
+    cursor: Option<String>,
+    limit: u32,
+    odd: bool,
+}
+
+impl Paginated for NumbersQuery {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+
+    fn set_limit(&mut self, limit: u32) {
+        self.limit = limit;
+    }
+}
+
+/// The numbers below [`COUNT`], only the odd ones if filtered.
+struct Numbers;
+
+impl SdkRoute for Numbers {
+    type Response = Page<u32>;
+    type QueryParams = NumbersQuery;
+
+    fn route() -> &'static str {
+        "/numbers"
+    }
+
+    fn method() -> Method {
+        Method::GET
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BucketPath {
+    bucket: u32,
+}
+
+/// The numbers below [`COUNT`] offset by a hundred times the bucket.
+struct BucketNumbers;
+
+impl SdkRoute for BucketNumbers {
+    type Response = Page<u32>;
+    type QueryParams = PageQuery;
+    type PathParams = BucketPath;
+
+    fn route() -> &'static str {
+        "/buckets/{bucket}/numbers"
+    }
+
+    fn method() -> Method {
+        Method::GET
+    }
+}
+
+/// The page of `items` starting at the index encoded in `cursor`.
+fn page(items: Vec<u32>, cursor: Option<String>, limit: u32) -> Page<u32> {
+    let start = cursor.map_or(0, |cursor| cursor.parse::<usize>().unwrap());
+    let end = (start + limit as usize).min(items.len());
+
+    Page {
+        items: items[start..end].to_vec(),
+        next_cursor: (end < items.len()).then(|| end.to_string()),
+    }
+}
+
+async fn numbers(
+    State(requests): State<Arc<AtomicUsize>>,
+    Query(query): Query<NumbersQuery>,
+) -> Json<Page<u32>> {
+    requests.fetch_add(1, Ordering::SeqCst);
+
+    let items = (0..COUNT).filter(|n| !query.odd || n % 2 == 1).collect();
+    Json(page(items, query.cursor, query.limit))
+}
+
+async fn bucket_numbers(
+    State(requests): State<Arc<AtomicUsize>>,
+    Path(path): Path<BucketPath>,
+    Query(query): Query<PageQuery>,
+) -> Json<Page<u32>> {
+    requests.fetch_add(1, Ordering::SeqCst);
+
+    let items = (0..COUNT).map(|n| path.bucket * 100 + n).collect();
+    Json(page(items, query.cursor, query.limit))
+}
+
+fn executor() -> (Executor, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let executor = MockSdkExecutor::new(
+        |requests| {
+            Router::new()
+                .route("/numbers", get(numbers))
+                .route("/buckets/:bucket/numbers", get(bucket_numbers))
+                .with_state(requests)
+        },
+        requests.clone(),
+        Arc::new(JwtConfig::for_tests()),
+        JwtAccessor::DiscordSystem,
+        "discord",
+    );
+
+    (executor, requests)
+}
+
+fn pages(page_size: u32) -> PageOptions {
+    PageOptions::default().with_page_size(page_size)
+}
+
+#[tokio::test]
+async fn streams_every_page() {
+    let (executor, requests) = executor();
+
+    let items = Numbers::paginate(&executor, pages(10))
+        .try_collect::<Vec<u32>>()
+        .await
+        .unwrap();
+
+    assert_eq!(items, (0..COUNT).collect::<Vec<u32>>());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn repeats_the_query_filters_on_every_page() {
+    let (executor, requests) = executor();
+
+    let query = NumbersQuery {
+        odd: true,
+        ..Default::default()
+    };
+    let items = Numbers::collect_all_with_query(&executor, query, pages(4), 100)
+        .await
+        .unwrap();
+
+    assert_eq!(items, (1..COUNT).step_by(2).collect::<Vec<u32>>());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn fills_the_path_of_every_page() {
+    let (executor, _) = executor();
+
+    let items =
+        BucketNumbers::collect_all_with_path(&executor, BucketPath { bucket: 3 }, pages(7), 100)
+            .await
+            .unwrap();
+
+    assert_eq!(items, (300..300 + COUNT).collect::<Vec<u32>>());
+}
+
+#[tokio::test]
+async fn collecting_beyond_the_cap_fails() {
+    let (executor, requests) = executor();
+
+    let result = Numbers::collect_all(&executor, pages(5).with_prefetch(false), 12).await;
+
+    assert!(matches!(
+        result,
+        Err(TicketsError::Misc(MiscError::PaginationCapExceeded {
+            cap: 12
+        }))
+    ));
+    // the page holding the item over the cap is the last one requested
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn pages_are_only_requested_while_polled() {
+    let (executor, requests) = executor();
+
+    let mut stream = Numbers::paginate(&executor, pages(5).with_prefetch(false));
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first, 0);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    stream.cancel();
+    assert!(stream.next().await.is_none());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,151 @@
+//! Token reuse and refreshing, driven by a clock the tests control.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
+use errors::{AuthorizationError, TicketsError};
+use sdk::client::{Clock, TokenClaim, TokenManager};
+
+const START: i64 = 1_700_000_000;
+const TTL: Duration = Duration::from_secs(60 * 60);
+const REFRESH_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// A clock standing still unless advanced, or moving a second forward on every reading.
+struct FakeClock {
+    now: AtomicI64,
+    ticking: bool,
+}
+
+impl FakeClock {
+    fn at(now: i64) -> Arc<Self> {
+        Arc::new(Self {
+            now: AtomicI64::new(now),
+            ticking: false,
+        })
+    }
+
+    fn ticking(now: i64) -> Arc<Self> {
+        Arc::new(Self {
+            now: AtomicI64::new(now),
+            ticking: true,
+        })
+    }
+
+    fn advance(&self, by: Duration) {
+        self.now.fetch_add(by.as_secs() as i64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> i64 {
+        match self.ticking {
+            true => self.now.fetch_add(1, Ordering::SeqCst),
+            false => self.now.load(Ordering::SeqCst),
+        }
+    }
+}
+
+fn manager(clock: Arc<FakeClock>) -> TokenManager {
+    TokenManager::new(
+        Arc::new(JwtConfig::for_tests()),
+        JwtData {
+            accessor: JwtAccessor::DiscordSystem,
+        },
+        TTL,
+        REFRESH_WINDOW,
+        clock,
+    )
+}
+
+#[tokio::test]
+async fn reuses_fresh_token() {
+    let clock = FakeClock::at(START);
+    let tokens = manager(clock.clone());
+
+    let first = tokens.token().await.unwrap();
+    let claim = tokens.current().await.unwrap();
+    assert_eq!(claim.issued_at(), START);
+    assert_eq!(claim.expiration(), START + TTL.as_secs() as i64);
+
+    clock.advance(TTL - REFRESH_WINDOW - Duration::from_secs(1));
+    assert_eq!(tokens.token().await.unwrap(), first);
+}
+
+#[tokio::test]
+async fn refreshes_expiring_token() {
+    let clock = FakeClock::at(START);
+    let tokens = manager(clock.clone());
+
+    let first = tokens.token().await.unwrap();
+
+    clock.advance(TTL - REFRESH_WINDOW);
+    let refreshed = tokens.token().await.unwrap();
+    assert_ne!(refreshed, first);
+
+    let claim = tokens.current().await.unwrap();
+    assert_eq!(
+        claim.issued_at(),
+        START + (TTL - REFRESH_WINDOW).as_secs() as i64
+    );
+    assert_eq!(claim.token(), refreshed);
+}
+
+#[tokio::test]
+async fn short_lived_claims_are_fresh_for_half_their_life() {
+    let clock = FakeClock::at(START);
+    let tokens = manager(clock.clone());
+
+    // as long as the refresh window, like the collector's pre-authorized tokens
+    let lifetime = REFRESH_WINDOW.as_secs() as i64;
+    let minted = TokenClaim::new("minted".to_string(), START, START + lifetime);
+    assert!(tokens.is_fresh(&minted));
+
+    clock.advance(Duration::from_secs(lifetime as u64 / 2 - 1));
+    assert!(tokens.is_fresh(&minted));
+
+    clock.advance(Duration::from_secs(1));
+    assert!(!tokens.is_fresh(&minted));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_callers_share_one_refresh() {
+    // every signature is issued at a different second, so separate refreshes differ
+    let tokens = Arc::new(manager(FakeClock::ticking(START)));
+
+    let callers = (0..16).map(|_| {
+        let tokens = tokens.clone();
+        tokio::spawn(async move { tokens.token().await.unwrap() })
+    });
+    let issued = futures::future::join_all(callers).await;
+
+    let current = tokens.current().await.unwrap();
+    for token in issued {
+        assert_eq!(token.unwrap(), current.token());
+    }
+}
+
+#[tokio::test]
+async fn issued_tokens_are_used_until_they_expire() {
+    let clock = FakeClock::at(START);
+    let tokens = TokenManager::issued(
+        JwtData {
+            accessor: JwtAccessor::DiscordSystem,
+        },
+        TokenClaim::new("issued".to_string(), START, START + TTL.as_secs() as i64),
+        clock.clone(),
+    );
+
+    // used up to the last second, without a key there is nothing to refresh it with
+    clock.advance(TTL - Duration::from_secs(1));
+    assert_eq!(tokens.token().await.unwrap(), "issued");
+
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(
+        tokens.token().await,
+        Err(TicketsError::Authorization(
+            AuthorizationError::LoginExpired
+        ))
+    ));
+}
@@ -0,0 +1,69 @@
+//! local stand-ins for the services the collector and the gateways talk to, shared by their
+//! integration tests. a stand-in listens on an ephemeral loopback port, records what it
+//! receives and answers with the replies it was told to give
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::Router;
+use tokio::net::TcpListener;
+
+/// Requests a stand-in received, oldest first, and the replies queued for the next ones.
+pub struct StandIn<Request, Reply> {
+    received: Arc<Mutex<Vec<Request>>>,
+    replies: Arc<Mutex<VecDeque<Reply>>>,
+}
+
+impl<Request, Reply> Clone for StandIn<Request, Reply> {
+    fn clone(&self) -> Self {
+        Self {
+            received: self.received.clone(),
+            replies: self.replies.clone(),
+        }
+    }
+}
+
+impl<Request, Reply> Default for StandIn<Request, Reply> {
+    fn default() -> Self {
+        Self {
+            received: Default::default(),
+            replies: Default::default(),
+        }
+    }
+}
+
+impl<Request, Reply> StandIn<Request, Reply> {
+    pub fn record(&self, request: Request) {
+        self.received.lock().unwrap().push(request);
+    }
+
+    /// Everything recorded so far, the stand-in records nothing while the guard is held.
+    pub fn received(&self) -> MutexGuard<'_, Vec<Request>> {
+        self.received.lock().unwrap()
+    }
+
+    /// Queues `reply` behind the replies queued before, requests arriving once the queue is
+    /// empty get the stand-in's default reply.
+    pub fn reply_with(&self, reply: Reply) {
+        self.replies.lock().unwrap().push_back(reply);
+    }
+
+    pub fn next_reply(&self) -> Option<Reply> {
+        self.replies.lock().unwrap().pop_front()
+    }
+}
+
+/// Binds an ephemeral port on the loopback interface.
+pub async fn listen() -> (TcpListener, SocketAddr) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    (listener, address)
+}
+
+/// Serves `router` on an ephemeral loopback port for the rest of the test.
+pub async fn serve(router: Router) -> SocketAddr {
+    let (listener, address) = listen().await;
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    address
+}
@@ -0,0 +1,8 @@
+#!/usr/bin/env bash
+
+source .env
+if [ -f etc/.env ]; then
+  source etc/.env
+fi
+
+export COLLECTOR_DATABASE_URL DISCORD_DATABASE_URL EMAIL_DATABASE_URL MATRIX_DATABASE_URL && cargo run -q -p admin -- "$@"
@@ -0,0 +1,8 @@
+#!/usr/bin/env bash
+
+source .env
+if [ -f etc/.env ]; then
+  source etc/.env
+fi
+
+export DATABASE_URL=$EMAIL_DATABASE_URL && cargo run -p email-tickets
\ No newline at end of file
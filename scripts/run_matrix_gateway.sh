@@ -0,0 +1,8 @@
+#!/usr/bin/env bash
+
+source .env
+if [ -f etc/.env ]; then
+  source etc/.env
+fi
+
+export DATABASE_URL=$MATRIX_DATABASE_URL && export MATRIX_ACCESS_TOKEN=$MATRIX_ACCESS_TOKEN && cargo run -p matrix-tickets
\ No newline at end of file
@@ -0,0 +1,3 @@
+#!/usr/bin/env bash
+
+cargo run -p webhook-tickets
\ No newline at end of file
@@ -1,14 +1,22 @@
 use std::time::Duration;
 
-use errors::TicketsResult;
+use errors::{MiscError, TicketsResult};
 pub use rust_socketio::asynchronous::Client;
 use rust_socketio::asynchronous::ClientBuilder;
 use rust_socketio::Payload;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::sync::mpsc::UnboundedReceiver;
 use uuid::Uuid;
 
-use events::websocket::ListenToResult;
+use events::websocket::{
+    ListenToResult, Reply, SetStatus, SocketAuthData, TicketActionResult, Typing,
+};
 use events::{AppChangedEvent, TicketUpdatedEvent};
+use sdk::routes::consumer::TicketMessage;
+use sdk::routes::staff::{TicketStatus, TicketStatusChange};
+
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub trait Namespace {
     type Message: for<'de> serde::Deserialize<'de> + Send + Sync + 'static;
@@ -54,11 +62,14 @@ pub struct TicketSocketConfig {
 pub async fn connect<N: Namespace>(
     config: &TicketSocketConfig,
 ) -> TicketsResult<(Client, UnboundedReceiver<N::Message>)> {
-    println!("Connecting to the server...");
+    log::info!("Connecting to the server...");
     let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
 
     let client = ClientBuilder::new(&config.server_url)
         .namespace(N::namespace())
+        .auth(serde_json::to_value(SocketAuthData {
+            token: config.token.clone(),
+        })?)
         .on(N::callback_event(), move |payload, client| {
             let sender = sender.clone();
             Box::pin(async move {
@@ -87,6 +98,55 @@ pub async fn connect<N: Namespace>(
     Ok((client, receiver))
 }
 
+/// Emits a ticket action and waits for the server to acknowledge it with its result.
+async fn emit_action<T: DeserializeOwned + Send + 'static>(
+    client: &Client,
+    event: &'static str,
+    data: impl Serialize,
+) -> TicketsResult<T> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let mut sender = Some(sender);
+
+    client
+        .emit_with_ack(
+            event,
+            Payload::String(serde_json::to_string(&data)?),
+            ACK_TIMEOUT,
+            move |payload, _client| {
+                let sender = sender.take();
+                Box::pin(async move {
+                    let result = match payload {
+                        Payload::String(value) => {
+                            // acks carry their arguments as an array
+                            serde_json::from_str::<(TicketActionResult<T>,)>(&value)
+                                .map(|(result,)| result)
+                        }
+                        Payload::Binary(_) => {
+                            log::error!("Received binary payload.");
+                            return;
+                        }
+                    };
+
+                    if let Some(sender) = sender {
+                        let _ = sender.send(result);
+                    }
+                })
+            },
+        )
+        .await?;
+
+    // the callback is never called for acks which do not arrive in time
+    match tokio::time::timeout(ACK_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => match result? {
+            TicketActionResult::Success(value) => Ok(value),
+            TicketActionResult::Failure(err) => Err(err.into()),
+        },
+        _ => Err(MiscError::AckTimedOut {
+            event: event.to_string(),
+        })?,
+    }
+}
+
 #[allow(async_fn_in_trait)]
 pub trait TicketsWebsocketClientExt {
     async fn listen_to(
@@ -94,6 +154,24 @@ pub trait TicketsWebsocketClientExt {
         app_id: Uuid,
         authorized_app_token: Option<String>,
     ) -> TicketsResult<()>;
+
+    /// Replies to the ticket's customer as the connected staff member.
+    async fn reply(
+        &self,
+        app_id: Uuid,
+        ticket_id: Uuid,
+        body: String,
+    ) -> TicketsResult<TicketMessage>;
+
+    async fn set_status(
+        &self,
+        app_id: Uuid,
+        ticket_id: Uuid,
+        status: TicketStatus,
+    ) -> TicketsResult<TicketStatusChange>;
+
+    /// Tells the app's other listeners that the connected staff member is typing on the ticket.
+    async fn typing(&self, app_id: Uuid, ticket_id: Uuid) -> TicketsResult<()>;
 }
 
 impl TicketsWebsocketClientExt for Client {
@@ -140,4 +218,49 @@ impl TicketsWebsocketClientExt for Client {
         .await?;
         Ok(())
     }
+
+    async fn reply(
+        &self,
+        app_id: Uuid,
+        ticket_id: Uuid,
+        body: String,
+    ) -> TicketsResult<TicketMessage> {
+        emit_action(
+            self,
+            events::websocket::REPLY_EVENT_NAME,
+            Reply {
+                app_id,
+                ticket_id,
+                body,
+            },
+        )
+        .await
+    }
+
+    async fn set_status(
+        &self,
+        app_id: Uuid,
+        ticket_id: Uuid,
+        status: TicketStatus,
+    ) -> TicketsResult<TicketStatusChange> {
+        emit_action(
+            self,
+            events::websocket::SET_STATUS_EVENT_NAME,
+            SetStatus {
+                app_id,
+                ticket_id,
+                status,
+            },
+        )
+        .await
+    }
+
+    async fn typing(&self, app_id: Uuid, ticket_id: Uuid) -> TicketsResult<()> {
+        emit_action(
+            self,
+            events::websocket::TYPING_EVENT_NAME,
+            Typing { app_id, ticket_id },
+        )
+        .await
+    }
 }
@@ -1,5 +1,6 @@
 use errors::TicketsResult;
 
+pub mod memory;
 #[cfg(feature = "redis")]
 pub mod redis;
 
@@ -0,0 +1,24 @@
+use std::sync::Mutex;
+
+use events::TicketEvent;
+use uuid::Uuid;
+
+/// Emitter which keeps published events in memory, used when running the collector in-process.
+#[derive(Default)]
+pub struct MemoryEmitter {
+    events: Mutex<Vec<(Uuid, TicketEvent)>>,
+}
+
+impl MemoryEmitter {
+    /// Drains every event published since the last call.
+    pub fn take_events(&self) -> Vec<(Uuid, TicketEvent)> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl super::TicketsEventEmitter for MemoryEmitter {
+    fn publish_tickets_event(&self, app_id: Uuid, event: TicketEvent) -> errors::TicketsResult<()> {
+        self.events.lock().unwrap().push((app_id, event));
+        Ok(())
+    }
+}
@@ -9,9 +9,12 @@ use tokio::task::JoinHandle;
 mod adapter;
 mod websocket;
 
+pub use websocket::setup_server;
+
 pub async fn setup_websocket_layer(
     adapter_config: &AdapterConfig,
     jwt: Arc<JwtConfig>,
+    collector_url: &str,
 ) -> TicketsResult<(
     JoinHandle<TicketsResult<()>>,
     JoinHandle<TicketsResult<()>>,
@@ -22,7 +25,8 @@ pub async fn setup_websocket_layer(
         Adapter::Redis => RedisWebsocketAdapter::create_adapter(adapter_config)?,
     };
 
-    let (message_receiver_handle, websocket_layer) = websocket::setup_server(jwt, message_pipe);
+    let (message_receiver_handle, websocket_layer) =
+        websocket::setup_server(jwt, collector_url, message_pipe)?;
 
     Ok((adapter_handle, message_receiver_handle, websocket_layer))
 }
@@ -15,6 +15,8 @@ mod websocket;
 struct Config {
     adapter_config: AdapterConfig,
     jwt: JwtKeyPathsConfig,
+    /// Ticket actions sent over the socket are forwarded to the collector's REST API.
+    collector_url: String,
 }
 
 #[tokio::main]
@@ -36,7 +38,8 @@ async fn main() {
     };
 
     let (message_receiver_handle, websocket_layer) =
-        websocket::setup_server(Arc::new(jwt), message_pipe);
+        websocket::setup_server(Arc::new(jwt), &config.collector_url, message_pipe)
+            .expect("Failed to set up websocket server.");
 
     let router = Router::new().layer(websocket_layer);
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
@@ -1,27 +1,41 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
-use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
-use errors::{TicketsError, TicketsResult};
-use socketioxide::extract::{AckSender, Data, SocketRef, State};
+use auth::jwt::{JwtAccessor, JwtClaim, JwtConfig, JwtData};
+use errors::{AuthorizationError, NetworkError, ParsingError, TicketsError, TicketsResult};
+use sdk::client::{Clock, SdkCallWithPathAndBody, SignedTicketClient, SystemClock};
+use sdk::routes::staff::{
+    ReplyToTicket, SetTicketStatus, SetTicketStatusBody, TicketMessageBody, TicketPath,
+};
+use socketioxide::extract::{AckSender, Data, SocketRef, TryData};
 use socketioxide::layer::SocketIoLayer;
 use socketioxide::SocketIo;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
-use events::event_channels::{APP_CHANGED_EVENT, TICKET_UPDATED_EVENT};
+use events::event_channels::{APP_CHANGED_EVENT, STAFF_TYPING_EVENT, TICKET_UPDATED_EVENT};
 use events::websocket::SocketAuthData;
-use events::websocket::{ListenTo, ListenToResult};
+use events::websocket::{
+    ListenTo, ListenToResult, Reply, SetStatus, StaffTyping, TicketActionResult, Typing,
+};
 use events::{PublishedMessage, TicketEvent, APP_CHANGES_NAMESPACE, TICKETS_NAMESPACE};
 
 #[derive(Clone)]
 struct SocketIoState {
     jwt: Arc<JwtConfig>,
+    /// Ticket actions sent over the socket are forwarded to the collector's REST API.
+    collector_url: String,
 }
 
+/// Unix timestamp at which the token the socket connected with expires.
+#[derive(Clone, Copy)]
+struct TokenExpiry(i64);
+
 macro_rules! auth {
     ($caller:ident = ($socket:ident, $state:ident, $data:ident).into()) => {
-        let $caller = match $state.jwt.verify(&$data.token) {
+        let $caller = match $state.jwt.verify_claim(&$data.token) {
             Ok($caller) => $caller,
             Err(_) => {
                 let _ = $socket.disconnect();
@@ -33,7 +47,7 @@ macro_rules! auth {
 
 async fn listen_handler(
     socket: SocketRef,
-    State(state): State<SocketIoState>,
+    state: SocketIoState,
     Data(data): Data<ListenTo>,
     ack: AckSender,
 ) {
@@ -47,17 +61,30 @@ async fn listen_handler(
     };
 
     match accessor {
-        JwtAccessor::DiscordSystem => {
+        JwtAccessor::DiscordSystem | JwtAccessor::EmailSystem | JwtAccessor::MatrixSystem => {
             let _ = socket.join(data.app_id.to_string());
             if ack.send(ListenToResult::Success).is_err() {
                 let _ = socket.disconnect();
             }
         }
+        // the webhook gateway only submits tickets, it has no use for live events
+        JwtAccessor::WebhookSystem => {
+            if ack.send(ListenToResult::Failure).is_err() {
+                let _ = socket.disconnect();
+            }
+        }
         JwtAccessor::DiscordStaffMember {
             user_id,
             authorized_apps,
             ..
         } => {
+            if ensure_unexpired(&socket).is_err() {
+                if ack.send(ListenToResult::Failure).is_err() {
+                    let _ = socket.disconnect();
+                }
+                return;
+            }
+
             let app_listen_authorized = if authorized_apps.contains(&data.app_id) {
                 true
             } else {
@@ -106,37 +133,223 @@ async fn listen_handler(
     }
 }
 
-async fn authed_continue(
+/// The caller as the collector sees it, `None` for the gateways, which act through the collector
+/// with their own clients. Apps authorized by the connecting token are left for the collector to
+/// check again, so the re-signed token grants no more than the staff member's memberships.
+fn collector_accessor(accessor: &JwtAccessor) -> Option<JwtAccessor> {
+    match accessor {
+        JwtAccessor::DiscordStaffMember { user_id, role, .. } => {
+            Some(JwtAccessor::DiscordStaffMember {
+                user_id: *user_id,
+                authorized_apps: HashSet::new(),
+                role: *role,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn send_result<T: serde::Serialize>(socket: SocketRef, ack: AckSender, result: TicketsResult<T>) {
+    let result = match result {
+        Ok(value) => TicketActionResult::Success(value),
+        Err(err) => TicketActionResult::Failure(NetworkError::from(&err)),
+    };
+
+    if ack.send(result).is_err() {
+        let _ = socket.disconnect();
+    }
+}
+
+fn parse_action<T>(data: Result<T, serde_json::Error>) -> TicketsResult<T> {
+    Ok(data.map_err(|err| ParsingError::InvalidRequest(err.to_string()))?)
+}
+
+/// A client acting as the staff member behind `claim` until their token expires. The token it
+/// signs is never refreshed, so the socket loses access to the collector with the staff member.
+fn sign_collector_client(
+    state: &SocketIoState,
+    claim: &JwtClaim,
+) -> TicketsResult<Option<SignedTicketClient>> {
+    let Some(accessor) = collector_accessor(&claim.data().accessor) else {
+        return Ok(None);
+    };
+
+    let now = SystemClock.now();
+    let ttl = Duration::from_secs((claim.exp - now).max(0) as u64);
+    let data = JwtData { accessor };
+    let (token, signed) = state.jwt.generate_at(data.clone(), now, ttl)?;
+
+    Ok(Some(SignedTicketClient::with_issued_token(
+        &state.collector_url,
+        claim.data().accessor.gateway(),
+        data,
+        token,
+        signed.exp,
+    )?))
+}
+
+/// Refuses the socket's caller once the token it connected with expired.
+fn ensure_unexpired(socket: &SocketRef) -> TicketsResult<()> {
+    let expiry = socket
+        .extensions
+        .get::<TokenExpiry>()
+        .map(|expiry| expiry.value().0)
+        .ok_or(AuthorizationError::ChannelCannotAccessResource)?;
+
+    if expiry <= SystemClock.now() {
+        return Err(AuthorizationError::LoginExpired.into());
+    }
+
+    Ok(())
+}
+
+fn collector_client(socket: &SocketRef) -> TicketsResult<SignedTicketClient> {
+    socket
+        .extensions
+        .get::<SignedTicketClient>()
+        .map(|client| client.value().clone())
+        .ok_or(AuthorizationError::ChannelCannotAccessResource.into())
+}
+
+// replies and status changes go through the collector's REST routes, which authorize and
+// persist them and publish the resulting events back to the listening sockets
+async fn reply_handler(socket: SocketRef, TryData(data): TryData<Reply>, ack: AckSender) {
+    let reply = async {
+        let Reply {
+            app_id,
+            ticket_id,
+            body,
+        } = parse_action(data)?;
+
+        ReplyToTicket::call_with_path_and_body(
+            &collector_client(&socket)?,
+            TicketPath { app_id, ticket_id },
+            TicketMessageBody { body },
+        )
+        .await
+    };
+
+    let result = reply.await;
+    send_result(socket, ack, result);
+}
+
+async fn set_status_handler(socket: SocketRef, TryData(data): TryData<SetStatus>, ack: AckSender) {
+    let set_status = async {
+        let SetStatus {
+            app_id,
+            ticket_id,
+            status,
+        } = parse_action(data)?;
+
+        SetTicketStatus::call_with_path_and_body(
+            &collector_client(&socket)?,
+            TicketPath { app_id, ticket_id },
+            SetTicketStatusBody { status },
+        )
+        .await
+    };
+
+    let result = set_status.await;
+    send_result(socket, ack, result);
+}
+
+fn typing_handler(socket: SocketRef, TryData(data): TryData<Typing>, ack: AckSender) {
+    let typing = || -> TicketsResult<()> {
+        let Typing { app_id, ticket_id } = parse_action(data)?;
+
+        let user_id = match socket
+            .extensions
+            .get::<JwtData>()
+            .map(|caller| caller.value().clone())
+        {
+            Some(JwtData {
+                accessor: JwtAccessor::DiscordStaffMember { user_id, .. },
+            }) => user_id,
+            _ => return Err(AuthorizationError::ChannelCannotAccessResource.into()),
+        };
+
+        ensure_unexpired(&socket)?;
+
+        // nothing is persisted, the socket listening to the app is what authorizes it
+        let room = app_id.to_string();
+        let listening = socket
+            .rooms()
+            .map(|rooms| rooms.iter().any(|joined| *joined == room))
+            .unwrap_or(false);
+        if !listening {
+            return Err(AuthorizationError::CannotAccessApp.into());
+        }
+
+        Ok(socket.to(room).emit(
+            STAFF_TYPING_EVENT,
+            ((app_id, StaffTyping { ticket_id, user_id }),),
+        )?)
+    };
+
+    let result = typing();
+    send_result(socket, ack, result);
+}
+
+fn authed_continue(
     socket: SocketRef,
-    caller: JwtData,
+    state: &SocketIoState,
+    caller: JwtClaim,
 ) -> Result<(), (SocketRef, TicketsError)> {
-    socket.extensions.insert(caller);
-    socket.on(events::websocket::LISTEN_TO_EVENT_NAME, listen_handler);
+    if socket.ns() == TICKETS_NAMESPACE {
+        // only staff act on tickets over the socket, the others are refused in the acks
+        match sign_collector_client(state, &caller) {
+            Ok(Some(client)) => {
+                socket.extensions.insert(client);
+            }
+            Ok(None) => {}
+            Err(err) => return Err((socket, err)),
+        }
+
+        socket.on(events::websocket::REPLY_EVENT_NAME, reply_handler);
+        socket.on(events::websocket::SET_STATUS_EVENT_NAME, set_status_handler);
+        socket.on(events::websocket::TYPING_EVENT_NAME, typing_handler);
+    }
+
+    socket.extensions.insert(TokenExpiry(caller.exp));
+    socket.extensions.insert(caller.into_data());
+    let state = state.clone();
+    socket.on(
+        events::websocket::LISTEN_TO_EVENT_NAME,
+        move |socket: SocketRef, data: Data<ListenTo>, ack: AckSender| {
+            listen_handler(socket, state, data, ack)
+        },
+    );
     Ok(())
 }
 
-async fn prepare_auth(
-    socket: SocketRef,
-    State(state): State<SocketIoState>,
-    Data(data): Data<SocketAuthData>,
-) {
+// synchronous, so the handlers are in place before the socket's first event is dispatched
+fn prepare_auth(socket: SocketRef, state: &SocketIoState, Data(data): Data<SocketAuthData>) {
     auth!(caller = (socket, state, data).into());
 
-    if let Err((socket, _)) = authed_continue(socket, caller).await {
+    if let Err((socket, _)) = authed_continue(socket, state, caller) {
         let _ = socket.disconnect();
     }
 }
 
 pub fn setup_server(
     jwt_config: Arc<JwtConfig>,
+    collector_url: &str,
     mut recv_handle: UnboundedReceiver<PublishedMessage>,
-) -> (JoinHandle<TicketsResult<()>>, SocketIoLayer) {
-    let (layer, io) = SocketIo::builder()
-        .with_state(SocketIoState { jwt: jwt_config })
-        .build_layer();
+) -> TicketsResult<(JoinHandle<TicketsResult<()>>, SocketIoLayer)> {
+    let (layer, io) = SocketIo::new_layer();
 
-    io.ns(APP_CHANGES_NAMESPACE, prepare_auth);
-    io.ns(TICKETS_NAMESPACE, prepare_auth);
+    // passed to the handlers rather than kept in socketioxide's state, which is shared by every
+    // server of the process
+    let state = SocketIoState {
+        jwt: jwt_config,
+        collector_url: collector_url.to_string(),
+    };
+    for namespace in [APP_CHANGES_NAMESPACE, TICKETS_NAMESPACE] {
+        let state = state.clone();
+        io.ns(namespace, move |socket: SocketRef, data: Data<SocketAuthData>| {
+            prepare_auth(socket, &state, data)
+        });
+    }
 
     log::info!("Websocket I/O Prepared");
 
@@ -147,15 +360,16 @@ pub fn setup_server(
 
     let message_receiver_handle: JoinHandle<TicketsResult<()>> = tokio::spawn(async move {
         while let Some(msg) = recv_handle.recv().await {
+            // a tuple is emitted as separate arguments, clients read `(app_id, event)` as one
             match msg.event {
                 TicketEvent::AppChanged(app_changed) => {
                     if let Some(broadcast) = broadcast(&io, APP_CHANGES_NAMESPACE, msg.app_id) {
-                        broadcast.emit(APP_CHANGED_EVENT, (msg.app_id, app_changed))?;
+                        broadcast.emit(APP_CHANGED_EVENT, ((msg.app_id, app_changed),))?;
                     }
                 }
                 TicketEvent::TicketUpdated(ticket_updated) => {
                     if let Some(broadcast) = broadcast(&io, TICKETS_NAMESPACE, msg.app_id) {
-                        broadcast.emit(TICKET_UPDATED_EVENT, (msg.app_id, ticket_updated))?;
+                        broadcast.emit(TICKET_UPDATED_EVENT, ((msg.app_id, ticket_updated),))?;
                     }
                 }
             }
@@ -164,5 +378,5 @@ pub fn setup_server(
         Ok(())
     });
 
-    (message_receiver_handle, layer)
+    Ok((message_receiver_handle, layer))
 }
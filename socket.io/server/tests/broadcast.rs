@@ -0,0 +1,384 @@
+//! Events handed to the websocket server reach the clients listening to their app, and ticket
+//! actions sent by staff over the socket reach a local stand-in for the collector.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{post, put};
+use axum::{Json, Router};
+use rust_socketio::asynchronous::ClientBuilder;
+use rust_socketio::Payload;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+use auth::jwt::{JwtAccessor, JwtConfig, JwtData};
+use auth::UserRole;
+use errors::{AuthorizationError, NetworkError, TicketsError};
+use events::event_channels::STAFF_TYPING_EVENT;
+use events::websocket::{SocketAuthData, StaffTyping};
+use events::{PublishedMessage, TicketSubmittedEvent, TicketUpdatedEvent, TICKETS_NAMESPACE};
+use sdk::routes::staff::TicketStatus;
+use socketio_client::{Client, TicketNamespace, TicketSocketConfig, TicketsWebsocketClientExt};
+use test_support::StandIn;
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    path: String,
+    caller: Option<JwtData>,
+    body: Value,
+}
+
+/// Replies are only queued for refusals, the actions succeed otherwise.
+type Collector = StandIn<Recorded, TicketsError>;
+
+#[derive(Clone)]
+struct CollectorState {
+    stand_in: Collector,
+    jwt: Arc<JwtConfig>,
+}
+
+fn record(state: &CollectorState, path: String, headers: &HeaderMap, body: Value) {
+    let caller = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt.verify(token).ok());
+
+    state.stand_in.record(Recorded { path, caller, body });
+}
+
+async fn reply(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    Path((app_id, ticket_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let path = format!("/staff/apps/{app_id}/tickets/{ticket_id}/messages");
+    record(&state, path, &headers, body.clone());
+
+    if let Some(refusal) = state.stand_in.next_reply() {
+        return refusal.into_response();
+    }
+
+    Json(json!({
+        "message_id": Uuid::new_v4(),
+        "ticket_id": ticket_id,
+        "body": body["body"],
+        "customer_id": null,
+        "author_id": 1,
+        "created_at": 1700000000,
+    }))
+    .into_response()
+}
+
+async fn set_status(
+    State(state): State<CollectorState>,
+    headers: HeaderMap,
+    Path((app_id, ticket_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let path = format!("/staff/apps/{app_id}/tickets/{ticket_id}/status");
+    record(&state, path, &headers, body.clone());
+
+    if let Some(refusal) = state.stand_in.next_reply() {
+        return refusal.into_response();
+    }
+
+    Json(json!({
+        "ticket_id": ticket_id,
+        "from_status": "open",
+        "to_status": body["status"],
+        "changed_by": 1,
+        "created_at": 1700000000,
+    }))
+    .into_response()
+}
+
+struct TestServer {
+    jwt: Arc<JwtConfig>,
+    sender: UnboundedSender<PublishedMessage>,
+    collector: Collector,
+    server_url: String,
+}
+
+impl TestServer {
+    async fn start() -> Self {
+        let jwt = Arc::new(JwtConfig::for_tests());
+        let collector = Collector::default();
+        let collector_address = test_support::serve(
+            Router::new()
+                .route(
+                    "/staff/apps/:app_id/tickets/:ticket_id/messages",
+                    post(reply),
+                )
+                .route(
+                    "/staff/apps/:app_id/tickets/:ticket_id/status",
+                    put(set_status),
+                )
+                .with_state(CollectorState {
+                    stand_in: collector.clone(),
+                    jwt: jwt.clone(),
+                }),
+        )
+        .await;
+
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (_handle, layer) = socketio_server::setup_server(
+            jwt.clone(),
+            &format!("http://{collector_address}"),
+            receiver,
+        )
+        .unwrap();
+        let server_address = test_support::serve(Router::new().layer(layer)).await;
+
+        Self {
+            jwt,
+            sender,
+            collector,
+            server_url: format!("http://{server_address}"),
+        }
+    }
+
+    fn token(&self, accessor: JwtAccessor) -> String {
+        self.token_valid_for(accessor, Duration::from_secs(60))
+    }
+
+    fn token_valid_for(&self, accessor: JwtAccessor, ttl: Duration) -> String {
+        let (token, _) = self.jwt.generate(JwtData { accessor }, ttl).unwrap();
+        token
+    }
+
+    fn staff_token(&self, user_id: u64, app_id: Uuid) -> String {
+        self.token(staff_member(user_id, app_id))
+    }
+
+    async fn connect(&self, token: String) -> Client {
+        let config = TicketSocketConfig {
+            server_url: self.server_url.clone(),
+            token,
+        };
+        let (client, _events) = socketio_client::connect::<TicketNamespace>(&config)
+            .await
+            .unwrap();
+        client
+    }
+}
+
+fn staff_member(user_id: u64, app_id: Uuid) -> JwtAccessor {
+    JwtAccessor::DiscordStaffMember {
+        user_id,
+        authorized_apps: HashSet::from([app_id]),
+        role: UserRole::Staff,
+    }
+}
+
+fn submitted(app_id: Uuid, message: &str) -> PublishedMessage {
+    PublishedMessage {
+        app_id,
+        event: TicketSubmittedEvent {
+            message: message.to_string(),
+        }
+        .into(),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn listening_client_receives_app_events() {
+    let server = TestServer::start().await;
+    let sender = server.sender.clone();
+
+    let app_id = Uuid::new_v4();
+    let config = TicketSocketConfig {
+        server_url: server.server_url.clone(),
+        token: server.staff_token(1, app_id),
+    };
+    let (client, mut events) = socketio_client::connect::<TicketNamespace>(&config)
+        .await
+        .unwrap();
+    client.listen_to(app_id, None).await.unwrap();
+
+    // the room is joined once the server handles `listen_to`, which is not awaited by the client
+    let received = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            sender.send(submitted(Uuid::new_v4(), "other app")).unwrap();
+            sender.send(submitted(app_id, "help")).unwrap();
+
+            match tokio::time::timeout(Duration::from_millis(100), events.recv()).await {
+                Ok(received) => break received.unwrap(),
+                Err(_) => continue,
+            }
+        }
+    })
+    .await
+    .expect("no event was received");
+
+    let (received_app_id, event) = received;
+    assert_eq!(received_app_id, app_id);
+    assert!(matches!(
+        event,
+        TicketUpdatedEvent::TicketSubmitted(submitted) if submitted.message == "help"
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staff_replies_reach_the_collector_as_the_staff_member() {
+    let server = TestServer::start().await;
+    let (app_id, ticket_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let client = server.connect(server.staff_token(7, app_id)).await;
+
+    let message = client
+        .reply(
+            app_id,
+            ticket_id,
+            "have you tried turning it off".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(message.ticket_id, ticket_id);
+    assert_eq!(message.body, "have you tried turning it off");
+
+    let received = server.collector.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0].path,
+        format!("/staff/apps/{app_id}/tickets/{ticket_id}/messages")
+    );
+    assert_eq!(received[0].body["body"], "have you tried turning it off");
+    // the collector checks the memberships itself, the socket's apps are not passed on
+    assert!(matches!(
+        &received[0].caller,
+        Some(JwtData {
+            accessor: JwtAccessor::DiscordStaffMember { user_id: 7, authorized_apps, role: UserRole::Staff },
+        }) if authorized_apps.is_empty()
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn collector_refusals_are_relayed_in_the_ack() {
+    let server = TestServer::start().await;
+    let (app_id, ticket_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let client = server.connect(server.staff_token(7, app_id)).await;
+
+    let refusal = TicketsError::from(AuthorizationError::CannotAccessApp);
+    let expected = NetworkError::from(&refusal);
+    server.collector.reply_with(refusal);
+
+    let err = client
+        .set_status(app_id, ticket_id, TicketStatus::Closed)
+        .await
+        .unwrap_err();
+    // relayed as the collector sent them, not prefixed again
+    assert_eq!(err.code(), expected.code);
+    assert_eq!(NetworkError::from(&err).reason, expected.reason);
+
+    let received = server.collector.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0].path,
+        format!("/staff/apps/{app_id}/tickets/{ticket_id}/status")
+    );
+    assert_eq!(received[0].body["status"], "closed");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staff_are_refused_once_their_token_expires() {
+    let server = TestServer::start().await;
+    let (app_id, ticket_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let token = server.token_valid_for(staff_member(7, app_id), Duration::from_secs(2));
+    let client = server.connect(token).await;
+
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let expected = AuthorizationError::LoginExpired.code();
+    let err = client
+        .reply(app_id, ticket_id, "hello".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), expected);
+    let err = client.typing(app_id, ticket_id).await.unwrap_err();
+    assert_eq!(err.code(), expected);
+
+    assert!(server.collector.received().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_staff_act_on_tickets() {
+    let server = TestServer::start().await;
+    let (app_id, ticket_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let client = server
+        .connect(server.token(JwtAccessor::DiscordSystem))
+        .await;
+
+    let expected = AuthorizationError::ChannelCannotAccessResource.code();
+    let err = client
+        .reply(app_id, ticket_id, "hello".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), expected);
+    let err = client.typing(app_id, ticket_id).await.unwrap_err();
+    assert_eq!(err.code(), expected);
+
+    assert!(server.collector.received().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn typing_reaches_the_other_listeners() {
+    let server = TestServer::start().await;
+    let (app_id, ticket_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let typist = server.connect(server.staff_token(7, app_id)).await;
+
+    let err = typist.typing(app_id, ticket_id).await.unwrap_err();
+    assert_eq!(
+        err.code(),
+        TicketsError::from(AuthorizationError::CannotAccessApp).code()
+    );
+
+    let (sender, mut typing) = tokio::sync::mpsc::unbounded_channel();
+    let watcher = ClientBuilder::new(server.server_url.as_str())
+        .namespace(TICKETS_NAMESPACE)
+        .auth(
+            serde_json::to_value(SocketAuthData {
+                token: server.staff_token(8, app_id),
+            })
+            .unwrap(),
+        )
+        .on(STAFF_TYPING_EVENT, move |payload, _client| {
+            let sender = sender.clone();
+            Box::pin(async move {
+                if let Payload::String(value) = payload {
+                    let _ = sender.send(serde_json::from_str::<(Uuid, StaffTyping)>(&value));
+                }
+            })
+        })
+        .connect()
+        .await
+        .unwrap();
+
+    typist.listen_to(app_id, None).await.unwrap();
+    watcher.listen_to(app_id, None).await.unwrap();
+
+    // both rooms are joined once the server handles `listen_to`, which is not awaited by the client
+    let (typing_app_id, staff_typing) = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let _ = typist.typing(app_id, ticket_id).await;
+
+            match tokio::time::timeout(Duration::from_millis(100), typing.recv()).await {
+                Ok(received) => break received.unwrap().unwrap(),
+                Err(_) => continue,
+            }
+        }
+    })
+    .await
+    .expect("no typing event was received");
+
+    assert_eq!(typing_app_id, app_id);
+    assert_eq!(staff_typing.ticket_id, ticket_id);
+    assert_eq!(staff_typing.user_id, 7);
+    // the ack succeeds once the typist listens
+    typist.typing(app_id, ticket_id).await.unwrap();
+}